@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{ed25519_program, instruction::Instruction};
+
+use crate::instructions::staking::{
+    create_versioned_oracle_message,
+    LATEST_ORACLE_MESSAGE_VERSION,
+    USD_DECIMALS,
+};
+
+/// Length of the Ed25519Program header: count, padding and seven u16 offsets
+pub const ED25519_HEADER_LEN: u16 = 16;
+
+/// Instruction index meaning "this instruction" in Ed25519Program offsets
+const ED25519_CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Serializes single-signature Ed25519Program instruction data in the layout
+/// the on-chain verifiers parse: header, signature(64), pubkey(32), message
+pub fn ed25519_instruction_data(signature: &[u8; 64], public_key: &Pubkey, message: &[u8]) -> Vec<u8> {
+    let signature_offset = ED25519_HEADER_LEN;
+    let public_key_offset = signature_offset + 64;
+    let message_offset = public_key_offset + 32;
+
+    let mut data = vec![1u8, 0]; // one signature, padding
+    for value in [
+        signature_offset,
+        ED25519_CURRENT_INSTRUCTION,
+        public_key_offset,
+        ED25519_CURRENT_INSTRUCTION,
+        message_offset,
+        message.len() as u16,
+        ED25519_CURRENT_INSTRUCTION,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(signature);
+    data.extend_from_slice(public_key.as_ref());
+    data.extend_from_slice(message);
+    data
+}
+
+/// Wraps ed25519_instruction_data in an Ed25519Program instruction
+pub fn build_ed25519_ix(signature: &[u8; 64], public_key: &Pubkey, message: &[u8]) -> Instruction {
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data: ed25519_instruction_data(signature, public_key, message),
+    }
+}
+
+/// Builds the oracle price verification instruction for initialize_warden and
+/// query_warden_tier, signed in the latest oracle message format
+///
+/// Place it at index 0 and pass an OraclePrice with LATEST_ORACLE_MESSAGE_VERSION,
+/// USD_DECIMALS, `price`, `timestamp` and `signature` in the program instruction.
+pub fn build_oracle_ed25519_ix(
+    price: u64,
+    timestamp: i64,
+    signature: &[u8; 64],
+    oracle_pubkey: &Pubkey,
+) -> Instruction {
+    build_versioned_oracle_ed25519_ix(LATEST_ORACLE_MESSAGE_VERSION, price, timestamp, signature, oracle_pubkey)
+        .expect("latest oracle message version is always buildable")
+}
+
+/// Builds the oracle price verification instruction for a specific message version,
+/// for signers still on an older format
+pub fn build_versioned_oracle_ed25519_ix(
+    message_version: u8,
+    price: u64,
+    timestamp: i64,
+    signature: &[u8; 64],
+    oracle_pubkey: &Pubkey,
+) -> Result<Instruction> {
+    let message = create_versioned_oracle_message(message_version, price, USD_DECIMALS as u8, timestamp)?;
+    Ok(build_ed25519_ix(signature, oracle_pubkey, &message))
+}
+
+/// Builds the seeker and warden verification instructions for a bandwidth proof
+///
+/// `message` is create_proof_message for submit_bandwidth_proof or
+/// create_quick_session_proof_message for quick_session. Place the returned
+/// instructions at indices 0 and 1, ahead of the program instruction.
+pub fn build_proof_ed25519_ixs(
+    message: &[u8],
+    seeker_signature: &[u8; 64],
+    seeker_pubkey: &Pubkey,
+    warden_signature: &[u8; 64],
+    warden_pubkey: &Pubkey,
+) -> [Instruction; 2] {
+    [
+        build_ed25519_ix(seeker_signature, seeker_pubkey, message),
+        build_ed25519_ix(warden_signature, warden_pubkey, message),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::bandwidth::{
+        create_proof_message,
+        create_quick_session_proof_message,
+        instructions_sysvar_data,
+        verify_dual_signatures,
+        BandwidthError,
+    };
+    use crate::instructions::staking::{verify_oracle_price, OraclePrice, ORACLE_MESSAGE_V1};
+    use anchor_lang::solana_program::sysvar::instructions::ID as INSTRUCTIONS_SYSVAR_ID;
+
+    /// Runs `check` against an instructions sysvar holding `instructions`
+    fn with_sysvar<T>(instructions: &[Instruction], check: impl FnOnce(&AccountInfo) -> T) -> T {
+        let serialized: Vec<_> = instructions.iter().map(|ix| (ix.program_id, ix.data.clone())).collect();
+        let mut lamports = 0u64;
+        let mut data = instructions_sysvar_data(&serialized);
+        let owner = Pubkey::default();
+        let sysvar = AccountInfo::new(
+            &INSTRUCTIONS_SYSVAR_ID, false, false, &mut lamports, &mut data, &owner, false, 0,
+        );
+        check(&sysvar)
+    }
+
+    #[test]
+    fn test_oracle_ix_round_trips_through_verify_oracle_price() {
+        let config = crate::state::test_protocol_config();
+        let (price, timestamp, signature) = (150_000_000u64, 1_700_000_000i64, [5u8; 64]);
+        let quote = |version: u8, price: u64| OraclePrice { version, price, decimals: USD_DECIMALS as u8, timestamp, signature };
+
+        let latest = build_oracle_ed25519_ix(price, timestamp, &signature, &config.oracle_authority);
+        assert_eq!(latest.program_id, ed25519_program::ID);
+        assert!(latest.accounts.is_empty());
+        with_sysvar(std::slice::from_ref(&latest), |sysvar| {
+            verify_oracle_price(sysvar, &config, &quote(LATEST_ORACLE_MESSAGE_VERSION, price), timestamp)
+        })
+        .unwrap();
+
+        // A different price or version than the one signed doesn't verify
+        with_sysvar(&[latest], |sysvar| {
+            assert!(verify_oracle_price(sysvar, &config, &quote(LATEST_ORACLE_MESSAGE_VERSION, price + 1), timestamp).is_err());
+            assert!(verify_oracle_price(sysvar, &config, &quote(ORACLE_MESSAGE_V1, price), timestamp).is_err());
+        });
+
+        let v1 = build_versioned_oracle_ed25519_ix(ORACLE_MESSAGE_V1, price, timestamp, &signature, &config.oracle_authority).unwrap();
+        with_sysvar(&[v1], |sysvar| {
+            verify_oracle_price(sysvar, &config, &quote(ORACLE_MESSAGE_V1, price), timestamp)
+        })
+        .unwrap();
+
+        assert!(build_versioned_oracle_ed25519_ix(LATEST_ORACLE_MESSAGE_VERSION + 1, price, timestamp, &signature, &config.oracle_authority).is_err());
+    }
+
+    #[test]
+    fn test_proof_ixs_round_trip_through_verify_dual_signatures() {
+        let connection = Pubkey::new_unique();
+        let (seeker, warden) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (seeker_sig, warden_sig) = ([3u8; 64], [4u8; 64]);
+
+        for message in [
+            create_proof_message(&connection, 100, 1_700_000_000),
+            create_quick_session_proof_message(&connection, 7, 100, 1_700_000_000),
+        ] {
+            let ixs = build_proof_ed25519_ixs(&message, &seeker_sig, &seeker, &warden_sig, &warden);
+            with_sysvar(&ixs, |sysvar| {
+                verify_dual_signatures(sysvar, &message, &seeker_sig, &seeker, &warden_sig, &warden)
+            })
+            .unwrap();
+
+            // Swapped order puts the warden's signature in the seeker's slot
+            let swapped = [ixs[1].clone(), ixs[0].clone()];
+            let result = with_sysvar(&swapped, |sysvar| {
+                verify_dual_signatures(sysvar, &message, &seeker_sig, &seeker, &warden_sig, &warden)
+            });
+            assert_eq!(result.unwrap_err(), BandwidthError::SignatureMismatch.into());
+        }
+    }
+}
@@ -1,8 +1,41 @@
-use anchor_lang::prelude::*;
-use anchor_spl::token::Token;
-use crate::state::{ProtocolConfig, GeoPremium, Warden};
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::token::{
+    self, spl_token::instruction::AuthorityType, Mint, MintTo, SetAuthority, Token, TokenAccount, Transfer,
+};
+use crate::state::{
+    ProtocolConfig, GeoPremium, RateUnit, SubsidyToken, Warden, Seeker, Connection,
+    TopEarners, TOP_EARNERS_CAPACITY, RegionIndex, REGION_COUNT, MINT_AUTHORITY_SEEDS,
+    mint_authority_signer_seeds, STAKE_AUTHORITY_SEEDS, PROTOCOL_CONFIG_SPACE,
+};
+use crate::instructions::payments::{
+    arkham_mint_allowance, move_lamports, record_arkham_minted, reward_rate, unused_connection_escrow,
+    MAX_PREMIUM_REWARD_BOOST_BPS, MB_PER_5GB,
+};
+use crate::instructions::staking::{LATEST_ORACLE_MESSAGE_VERSION, ORACLE_MESSAGE_V1};
+use crate::instructions::migration::{
+    decode_legacy_connection, run_migration, verify_peer_id_migration_account, warden_peer_id_prefix, MigratableAccount, MigrationParams, WARDEN_PEER_ID_OFFSET,
+};
 use crate::ArkhamErrorCode;
 
+/// Maximum wardens per distribute_subsidies call
+/// Each warden costs an account key plus 40 bytes of instruction data, so larger
+/// batches overflow the transaction size limit; split bigger distributions into
+/// chunks of at most this many wardens, one transaction each
+pub const MAX_SUBSIDY_BATCH: usize = 10;
+pub const DEFAULT_MAX_CONNECTIONS_PER_SEEKER: u8 = 5;
+pub const MAX_ADJUSTMENT_REASON_LEN: usize = 64;
+pub const DEFAULT_MIN_CONNECTIONS_FOR_FULL_REPUTATION: u64 = 10;
+pub const DEFAULT_BOOTSTRAP_REPUTATION_CAP: u32 = 8500;
+pub const DEFAULT_PREMIUM_POOL_SIZE: u16 = 100;
+pub const DEFAULT_MIN_REPUTATION_UPDATE_INTERVAL: i64 = 600; // 10 minutes
+pub const DEFAULT_MAX_UPTIME_REPORT_AGE: i64 = 3600; // 1 hour
+pub const DEFAULT_MAX_TOTAL_PROOFS: u32 = 10_000;
+pub const DEFAULT_INACTIVITY_TIMEOUT: i64 = 86_400; // 1 day
+/// Highest tier multiplier governance may set (5x), in basis points
+pub const MAX_TIER_MULTIPLIER: u16 = 50000;
+/// Highest max_fee_bps a deployment may ever choose (30%)
+pub const PROTOCOL_FEE_CEILING_BPS: u16 = 3000;
+
 /// Initializes the protocol configuration with default parameters
 /// This must be called once before any other protocol operations
 pub fn initialize_protocol_config_handler(
@@ -14,19 +47,17 @@ pub fn initialize_protocol_config_handler(
     tokens_per_5gb: u64,
     geo_premiums: Vec<GeoPremium>,
     oracle_authority: Pubkey,
+    params: InitializeProtocolConfigParams,
 ) -> Result<()> {
     let protocol_config = &mut ctx.accounts.protocol_config;
     
     // Validate parameters
     require!(protocol_fee_bps <= 10000, ArkhamErrorCode::InvalidFeeBps);
-    require!(
-        tier_thresholds[0] <= tier_thresholds[1] && tier_thresholds[1] <= tier_thresholds[2],
-        ArkhamErrorCode::InvalidTierThresholds
-    );
-    
-    for &multiplier in &tier_multipliers {
-        require!(multiplier <= 50000, ArkhamErrorCode::InvalidTierMultiplier);
-    }
+    validate_fee_bounds(params.min_fee_bps, params.max_fee_bps)?;
+    validate_protocol_fee(protocol_fee_bps, params.min_fee_bps, params.max_fee_bps)?;
+    validate_tier_thresholds(&tier_thresholds, false)?;
+
+    validate_tier_multipliers(&tier_multipliers)?;
 
     // Initialize all fields
     protocol_config.authority = ctx.accounts.authority.key();
@@ -40,6 +71,45 @@ pub fn initialize_protocol_config_handler(
     protocol_config.tokens_per_5gb = tokens_per_5gb;
     protocol_config.geo_premiums = geo_premiums;
     protocol_config.reputation_updater = ctx.accounts.authority.key(); // Default to authority
+    protocol_config.idle_escrow_yield_enabled = false; // Opt-in via update_protocol_params
+    protocol_config.premium_reward_boost_bps = 0; // No boost until configured
+    protocol_config.rate_unit = RateUnit::PerMb;
+    protocol_config.arkham_minting_renounced = false;
+    protocol_config.registration_fee = 0;
+    protocol_config.subsidy_vesting_duration = 0; // Subsidies vest immediately until configured
+    protocol_config.usdc_mint = params.usdc_mint;
+    protocol_config.usdt_mint = params.usdt_mint;
+    protocol_config.oracle_required = true; // Bootstrap deployments may relax this via update_protocol_params
+    protocol_config.fallback_prices = [0; 3];
+    protocol_config.max_connections_per_seeker = DEFAULT_MAX_CONNECTIONS_PER_SEEKER;
+    protocol_config.require_strict_tiers = false; // Equal thresholds allowed until opted in
+    protocol_config.max_arkham_reward_per_proof = 0; // Uncapped until configured
+    protocol_config.min_wardens_per_region = 0; // Enforced once the region index exists and this is set
+    protocol_config.min_connections_for_full_reputation = DEFAULT_MIN_CONNECTIONS_FOR_FULL_REPUTATION;
+    protocol_config.bootstrap_reputation_cap = DEFAULT_BOOTSTRAP_REPUTATION_CAP;
+    protocol_config.min_uptime_for_matching = 0; // Matching ignores uptime until configured
+    protocol_config.min_fee_bps = params.min_fee_bps;
+    protocol_config.max_fee_bps = params.max_fee_bps;
+    protocol_config.premium_pool_size = DEFAULT_PREMIUM_POOL_SIZE;
+    protocol_config.min_reputation_update_interval = DEFAULT_MIN_REPUTATION_UPDATE_INTERVAL;
+    protocol_config.sla_uptime_bps = 0; // No SLA refunds until configured
+    protocol_config.proof_event_every_n = 0; // Every proof is reported until throttled
+    protocol_config.proof_event_interval = 0;
+    protocol_config.anomaly_penalty = 0; // Anomaly reporting is opt-in
+    protocol_config.anomaly_bounty_bps = 0;
+    protocol_config.reward_numerator = tokens_per_5gb; // Same rate, without the per-MB truncation
+    protocol_config.reward_denominator = MB_PER_5GB;
+    protocol_config.rate_check_tolerance_bps = 0; // Rate integrity events are opt-in
+    protocol_config.max_uptime_report_age = DEFAULT_MAX_UPTIME_REPORT_AGE;
+    protocol_config.max_arkham_supply = 0; // Uncapped until configured
+    protocol_config.arkham_total_minted = 0;
+    protocol_config.min_oracle_message_version = ORACLE_MESSAGE_V1; // Unversioned signers keep working until governance retires them
+    protocol_config.max_total_proofs = DEFAULT_MAX_TOTAL_PROOFS;
+    protocol_config.inactivity_timeout = DEFAULT_INACTIVITY_TIMEOUT;
+    protocol_config.buffer_refund_enabled = false; // Buffers are returned at end_connection unless enabled
+    protocol_config.insurance_bps = 0; // No insurance accrual until governance opts in
+    protocol_config.failure_decay_window = 0; // Failures count in full until governance opts in
+    protocol_config.proof_fee_enabled = false; // Wardens keep whole proof payments until governance opts in
 
     emit!(ProtocolConfigInitialized {
         authority: ctx.accounts.authority.key(),
@@ -78,28 +148,24 @@ pub fn update_protocol_config_handler(
     
     if let Some(fee_bps) = new_protocol_fee_bps {
         require!(fee_bps <= 10000, ArkhamErrorCode::InvalidFeeBps); // Max 100%
+        validate_protocol_fee(fee_bps, protocol_config.min_fee_bps, protocol_config.max_fee_bps)?;
         protocol_config.protocol_fee_bps = fee_bps;
     }
     
     if let Some(thresholds) = new_tier_thresholds {
-        // Verify thresholds are in ascending order
-        require!(
-            thresholds[0] <= thresholds[1] && thresholds[1] <= thresholds[2],
-            ArkhamErrorCode::InvalidTierThresholds
-        );
+        validate_tier_thresholds(&thresholds, protocol_config.require_strict_tiers)?;
         protocol_config.tier_thresholds = thresholds;
     }
     
     if let Some(multipliers) = new_tier_multipliers {
-        // Verify multipliers are reasonable (max 5x = 50,000 basis points)
-        for &multiplier in &multipliers {
-            require!(multiplier <= 50000, ArkhamErrorCode::InvalidTierMultiplier);
-        }
+        validate_tier_multipliers(&multipliers)?;
         protocol_config.tier_multipliers = multipliers;
     }
     
     if let Some(tokens) = new_tokens_per_5gb {
         protocol_config.tokens_per_5gb = tokens;
+        protocol_config.reward_numerator = tokens;
+        protocol_config.reward_denominator = MB_PER_5GB;
     }
     
     if let Some(geo_premiums) = new_geo_premiums {
@@ -116,12 +182,30 @@ pub fn update_protocol_config_handler(
         for premium in &geo_premiums {
             require!(premium.premium_bps <= 50000, ArkhamErrorCode::InvalidGeoPremium);
         }
+
+        // Dropping a region zeroes the premium of every warden registered there, so
+        // regions that still have wardens only go through remove_geo_premium_regions
+        let removed_regions = removed_geo_regions(&protocol_config.geo_premiums, &geo_premiums);
+        if !removed_regions.is_empty() {
+            ensure_geo_regions_removable(
+                &removed_regions,
+                ctx.accounts.region_index.as_deref().map(|index| &index.active_wardens),
+                false,
+            )?;
+            emit!(GeoPremiumRegionsRemoved {
+                authority: ctx.accounts.authority.key(),
+                region_codes: removed_regions,
+                forced: false,
+            });
+        }
         
         protocol_config.geo_premiums = geo_premiums;
     }
 
+    // Kept for existing callers; set_reputation_updater is the canonical path.
+    // Either way the change is announced with ReputationUpdaterChanged
     if let Some(updater) = new_reputation_updater {
-        protocol_config.reputation_updater = updater;
+        emit!(replace_reputation_updater(protocol_config, ctx.accounts.authority.key(), updater));
     }
 
     if let Some(oracle) = new_oracle_authority {
@@ -140,284 +224,1288 @@ pub fn update_protocol_config_handler(
     Ok(())
 }
 
-/// Initializes the ARKHAM token mint
-/// Only callable by the protocol authority
-pub fn initialize_arkham_mint_handler(ctx: Context<InitializeArkhamMint>) -> Result<()> {
+/// Removes geo premium regions from the protocol config
+/// Only callable by the protocol authority. Regions that still have registered wardens
+/// are kept unless `force` is set, since their wardens lose the premium mid-operation
+pub fn remove_geo_premium_regions_handler(
+    ctx: Context<UpdateProtocolConfig>,
+    region_codes: Vec<u8>,
+    force: bool,
+) -> Result<()> {
     let protocol_config = &mut ctx.accounts.protocol_config;
-    let mint = &mut ctx.accounts.arkham_mint;
-    
-    // Verify the caller is the protocol authority
+
     require!(
         ctx.accounts.authority.key() == protocol_config.authority,
         ArkhamErrorCode::UnauthorizedAdminAction
     );
 
-    // Verify the mint hasn't been initialized yet
-    require!(
-        protocol_config.arkham_token_mint == Pubkey::default(),
-        ArkhamErrorCode::TokenMintAlreadyInitialized
-    );
-
-    // Mint initialization will be handled by Anchor's mint constraints
-    // The mint authority is set up in the InitializeArkhamMint context
-    // The bump is automatically handled by Anchor's init constraint
-
-    // Update the protocol config with the new mint address
-    protocol_config.arkham_token_mint = mint.key();
+    let remaining = geo_premiums_without(&protocol_config.geo_premiums, &region_codes)?;
+    ensure_geo_regions_removable(
+        &region_codes,
+        ctx.accounts.region_index.as_deref().map(|index| &index.active_wardens),
+        force,
+    )?;
+    protocol_config.geo_premiums = remaining;
 
-    emit!(ArkhamMintInitialized {
+    emit!(GeoPremiumRegionsRemoved {
         authority: ctx.accounts.authority.key(),
-        mint: mint.key(),
+        region_codes,
+        forced: force,
     });
 
     Ok(())
 }
 
-/// Distributes bootstrap subsidies to Wardens
-/// This is the mechanism to attract early participants during the first 6 months
-pub fn distribute_subsidies_handler(
-    ctx: Context<DistributeSubsidies>,
-    warden_keys: Vec<Pubkey>,
-    subsidy_amounts: Vec<u64>,
+/// Re-points the authority allowed to submit reputation updates
+/// Only callable by the protocol authority; emits ReputationUpdaterChanged so monitoring
+/// can alert on this security-sensitive change specifically
+pub fn set_reputation_updater_handler(
+    ctx: Context<UpdateProtocolConfig>,
+    new_reputation_updater: Pubkey,
 ) -> Result<()> {
-    let protocol_config = &ctx.accounts.protocol_config;
-    let treasury = &mut ctx.accounts.treasury;
-    
-    // Verify the caller is the protocol authority
+    let protocol_config = &mut ctx.accounts.protocol_config;
+
     require!(
         ctx.accounts.authority.key() == protocol_config.authority,
         ArkhamErrorCode::UnauthorizedAdminAction
     );
 
-    // Verify that the vectors have the same length
+    emit!(replace_reputation_updater(protocol_config, ctx.accounts.authority.key(), new_reputation_updater));
+
+    Ok(())
+}
+
+/// Stores the new reputation updater, returning the event recording old and new
+pub fn replace_reputation_updater(
+    config: &mut ProtocolConfig,
+    authority: Pubkey,
+    new_reputation_updater: Pubkey,
+) -> ReputationUpdaterChanged {
+    let old = std::mem::replace(&mut config.reputation_updater, new_reputation_updater);
+    ReputationUpdaterChanged {
+        authority,
+        old,
+        new: new_reputation_updater,
+    }
+}
+
+/// The reward rate numerator / denominator expressed per 5 GB, rounded down
+/// Kept in tokens_per_5gb for clients that predate the pair; saturates at u64::MAX
+pub fn tokens_per_5gb_equivalent(reward_numerator: u64, reward_denominator: u64) -> u64 {
+    let per_5gb = reward_numerator as u128 * MB_PER_5GB as u128 / reward_denominator.max(1) as u128;
+    u64::try_from(per_5gb).unwrap_or(u64::MAX)
+}
+
+/// Region codes with a premium in `current` but none in `replacement`
+pub fn removed_geo_regions(current: &[GeoPremium], replacement: &[GeoPremium]) -> Vec<u8> {
+    current
+        .iter()
+        .map(|gp| gp.region_code)
+        .filter(|code| !replacement.iter().any(|gp| gp.region_code == *code))
+        .collect()
+}
+
+/// Geo premiums left after removing `region_codes`, each of which must be configured
+pub fn geo_premiums_without(current: &[GeoPremium], region_codes: &[u8]) -> Result<Vec<GeoPremium>> {
     require!(
-        warden_keys.len() == subsidy_amounts.len(),
-        ArkhamErrorCode::InvalidSubsidyDistribution
+        region_codes.iter().all(|code| current.iter().any(|gp| gp.region_code == *code)),
+        ArkhamErrorCode::GeoPremiumRegionNotFound
     );
+    Ok(current.iter().filter(|gp| !region_codes.contains(&gp.region_code)).cloned().collect())
+}
 
-    // Verify that we're not distributing more than available in treasury
-    let total_subsidy: u64 = subsidy_amounts.iter().map(|&x| x).sum();
-    
+/// Allows removing geo premium regions only when none has registered wardens,
+/// unless forced. Without the region index the counts are unknown, so it's required
+pub fn ensure_geo_regions_removable(
+    removed_regions: &[u8],
+    active_wardens: Option<&[u16; REGION_COUNT]>,
+    force: bool,
+) -> Result<()> {
+    if force {
+        return Ok(());
+    }
+    let active_wardens = active_wardens.ok_or(ArkhamErrorCode::RegionIndexRequired)?;
     require!(
-        treasury.amount >= total_subsidy,
-        ArkhamErrorCode::InsufficientTreasuryBalance
+        removed_regions.iter().all(|&code| active_wardens[code as usize] == 0),
+        ArkhamErrorCode::GeoPremiumRegionInUse
     );
+    Ok(())
+}
 
-    // Process each subsidy distribution
-    for (i, _warden_key) in warden_keys.iter().enumerate() {
-        // Load the warden account to update pending claims
-        // In a real implementation, this would use CPI to update warden pending claims
-        // For this implementation, we're emitting an event to indicate the intended distribution
-        let _subsidy_amount = subsidy_amounts[i];
-        
-        // NOTE: In a real implementation, we'd need to load each warden account
-        // and update their pending_claims balance using CPI
-        // For this version, we're emitting an event to indicate the intended distribution
+/// Validates tier thresholds: ascending USD values
+/// Each threshold is the inclusive lower bound of its tier, so two equal
+/// thresholds make the lower of the two tiers unreachable. That is allowed
+/// by default and rejected when `strict` (config.require_strict_tiers) is set.
+pub fn validate_tier_thresholds(thresholds: &[u64; 3], strict: bool) -> Result<()> {
+    require!(
+        thresholds[0] <= thresholds[1] && thresholds[1] <= thresholds[2],
+        ArkhamErrorCode::InvalidTierThresholds
+    );
+    if strict {
+        require!(
+            thresholds[0] != thresholds[1] && thresholds[1] != thresholds[2],
+            ArkhamErrorCode::CollapsedTierThresholds
+        );
     }
+    Ok(())
+}
 
-    emit!(SubsidiesDistributed {
-        authority: ctx.accounts.authority.key(),
-        warden_count: warden_keys.len() as u32,
-        total_amount: total_subsidy,
-    });
-
+/// Validates fee bounds: min <= max <= PROTOCOL_FEE_CEILING_BPS
+/// The ceiling is fixed in the program, so even a compromised authority is held to it
+pub fn validate_fee_bounds(min_fee_bps: u16, max_fee_bps: u16) -> Result<()> {
+    require!(
+        min_fee_bps <= max_fee_bps && max_fee_bps <= PROTOCOL_FEE_CEILING_BPS,
+        ArkhamErrorCode::InvalidFeeBounds
+    );
     Ok(())
 }
 
-/// Updates a Warden's Peer ID. Only callable by the protocol authority.
-/// This is a developer/admin tool to fix registration errors.
-pub fn update_warden_peer_id_handler(ctx: Context<UpdateWardenPeerId>, new_peer_id: String) -> Result<()> {
-    let protocol_config = &ctx.accounts.protocol_config;
-    let warden = &mut ctx.accounts.warden;
+/// Resolves an update_protocol_params change to the fee bounds, returning the new
+/// (min_fee_bps, max_fee_bps). The current protocol fee must lie inside them; move
+/// it first with update_protocol_config to narrow the bounds past it.
+pub fn updated_fee_bounds(config: &ProtocolConfig, min_fee_bps: Option<u16>, max_fee_bps: Option<u16>) -> Result<(u16, u16)> {
+    let min_fee_bps = min_fee_bps.unwrap_or(config.min_fee_bps);
+    let max_fee_bps = max_fee_bps.unwrap_or(config.max_fee_bps);
+    validate_fee_bounds(min_fee_bps, max_fee_bps)?;
+    validate_protocol_fee(config.protocol_fee_bps, min_fee_bps, max_fee_bps)?;
+    Ok((min_fee_bps, max_fee_bps))
+}
 
-    // Verify the caller is the protocol authority
+/// Rejects a protocol fee outside the configured [min_fee_bps, max_fee_bps] bounds
+pub fn validate_protocol_fee(fee_bps: u16, min_fee_bps: u16, max_fee_bps: u16) -> Result<()> {
     require!(
-        ctx.accounts.authority.key() == protocol_config.authority,
-        ArkhamErrorCode::UnauthorizedAdminAction
+        (min_fee_bps..=max_fee_bps).contains(&fee_bps),
+        ArkhamErrorCode::ProtocolFeeOutOfBounds
     );
+    Ok(())
+}
 
-    // Basic validation for Peer ID format
+/// Resolves an update_protocol_params change to the approved stablecoin mints,
+/// returning the new (usdc_mint, usdt_mint). Both must be set and distinct.
+/// Stake already held in the old mints' vaults stays there; unstake it first.
+pub fn updated_stake_mints(config: &ProtocolConfig, usdc_mint: Option<Pubkey>, usdt_mint: Option<Pubkey>) -> Result<(Pubkey, Pubkey)> {
+    let usdc_mint = usdc_mint.unwrap_or(config.usdc_mint);
+    let usdt_mint = usdt_mint.unwrap_or(config.usdt_mint);
     require!(
-        new_peer_id.starts_with("12D3KooW"),
-        ArkhamErrorCode::InvalidPeerId
+        usdc_mint != Pubkey::default() && usdt_mint != Pubkey::default() && usdc_mint != usdt_mint,
+        ArkhamErrorCode::InvalidStakeMints
     );
+    Ok((usdc_mint, usdt_mint))
+}
+
+/// Validates tier multipliers: each at most 5x (50,000 basis points) and
+/// ascending like the tier thresholds, so higher tiers never earn less
+pub fn validate_tier_multipliers(multipliers: &[u16; 3]) -> Result<()> {
+    for &multiplier in multipliers {
+        // A zero multiplier prices that tier's connections at zero
+        require!(multiplier > 0, ArkhamErrorCode::ZeroTierMultiplier);
+        require!(multiplier <= MAX_TIER_MULTIPLIER, ArkhamErrorCode::InvalidTierMultiplier);
+    }
     require!(
-        new_peer_id.len() > 40 && new_peer_id.len() < 60,
-        ArkhamErrorCode::InvalidPeerId
+        multipliers[0] <= multipliers[1] && multipliers[1] <= multipliers[2],
+        ArkhamErrorCode::NonMonotonicTierMultipliers
     );
+    Ok(())
+}
 
-    let old_peer_id = warden.peer_id.clone();
-    warden.peer_id = new_peer_id.clone();
+/// Updates the protocol parameters that are not covered by update_protocol_config
+/// Only callable by the protocol authority
+pub fn update_protocol_params_handler(
+    ctx: Context<UpdateProtocolConfig>,
+    params: ProtocolParamsUpdate,
+) -> Result<()> {
+    let protocol_config = &mut ctx.accounts.protocol_config;
 
-    emit!(WardenPeerIdUpdated {
-        warden_authority: warden.authority,
-        old_peer_id,
-        new_peer_id,
-    });
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
 
-    Ok(())
-}
+    if let Some(enabled) = params.idle_escrow_yield_enabled {
+        protocol_config.idle_escrow_yield_enabled = enabled;
+    }
 
-/// Migrates a Warden account with corrupted PeerId field by fixing the Borsh string length prefix
-/// This is a one-time migration for accounts where the length prefix is incorrect but the data is intact
-pub fn migrate_warden_peer_id_handler(ctx: Context<MigrateWardenPeerId>) -> Result<()> {
-    let warden_account = &ctx.accounts.warden;
-    let authority = &ctx.accounts.authority;
+    if let Some(boost_bps) = params.premium_reward_boost_bps {
+        require!(
+            boost_bps <= MAX_PREMIUM_REWARD_BOOST_BPS,
+            ArkhamErrorCode::InvalidPremiumRewardBoost
+        );
+        protocol_config.premium_reward_boost_bps = boost_bps;
+    }
+
+    if let Some(rate_unit) = params.rate_unit.clone() {
+        protocol_config.rate_unit = rate_unit;
+    }
 
-    // Get raw account data
-    let data = warden_account.try_borrow_data()?;
+    if let Some(fee) = params.registration_fee {
+        protocol_config.registration_fee = fee;
+    }
 
-    // Verify we have enough data for a Warden account
-    require!(data.len() >= 200, ArkhamErrorCode::InvalidPeerId);
+    if let Some(duration) = params.subsidy_vesting_duration {
+        require!(duration >= 0, ArkhamErrorCode::InvalidVestingDuration);
+        protocol_config.subsidy_vesting_duration = duration;
+    }
 
-    // Verify discriminator matches Warden account type
-    // Discriminator bytes from IDL: [73, 11, 82, 46, 202, 0, 179, 133]
-    let expected_discriminator: [u8; 8] = [73, 11, 82, 46, 202, 0, 179, 133];
-    require!(
-        &data[0..8] == expected_discriminator,
-        ArkhamErrorCode::InvalidPeerId
-    );
+    if let Some(required) = params.oracle_required {
+        protocol_config.oracle_required = required;
+    }
 
-    // Extract and verify authority (bytes 8-40)
-    let stored_authority_bytes = &data[8..40];
-    let stored_authority = Pubkey::try_from(stored_authority_bytes)
-        .map_err(|_| ArkhamErrorCode::UnauthorizedWardenUpdate)?;
+    if let Some(prices) = params.fallback_prices {
+        protocol_config.fallback_prices = prices;
+    }
 
-    require!(
-        stored_authority == authority.key(),
-        ArkhamErrorCode::UnauthorizedWardenUpdate
-    );
+    if let Some(max_connections) = params.max_connections_per_seeker {
+        // A zero cap would lock every seeker out of the network
+        require!(max_connections > 0, ArkhamErrorCode::InvalidMaxConnectionsPerSeeker);
+        protocol_config.max_connections_per_seeker = max_connections;
+    }
+
+    if let Some(strict) = params.require_strict_tiers {
+        // Opting in must not leave the live thresholds in violation
+        validate_tier_thresholds(&protocol_config.tier_thresholds, strict)?;
+        protocol_config.require_strict_tiers = strict;
+    }
+
+    if let Some(max_reward) = params.max_arkham_reward_per_proof {
+        protocol_config.max_arkham_reward_per_proof = max_reward;
+    }
 
-    msg!("Verified authority: {}", stored_authority);
+    if let Some(min_wardens) = params.min_wardens_per_region {
+        protocol_config.min_wardens_per_region = min_wardens;
+    }
 
-    // PeerId field starts at offset 40 (after discriminator + authority)
-    // Structure: 4-byte length prefix (little-endian u32) + UTF-8 string bytes
-    let peer_id_offset = 40;
-    let length_bytes = &data[peer_id_offset..peer_id_offset + 4];
-    let stored_length = u32::from_le_bytes([
-        length_bytes[0],
-        length_bytes[1],
-        length_bytes[2],
-        length_bytes[3],
-    ]);
+    if let Some(min_connections) = params.min_connections_for_full_reputation {
+        protocol_config.min_connections_for_full_reputation = min_connections;
+    }
 
-    msg!("Current PeerId length prefix: {}", stored_length);
+    if let Some(cap) = params.bootstrap_reputation_cap {
+        require!(cap <= 10000, ArkhamErrorCode::InvalidBootstrapReputationCap);
+        protocol_config.bootstrap_reputation_cap = cap;
+    }
 
-    // The actual PeerId string data starts at offset 44
-    let peer_id_data_offset = peer_id_offset + 4;
+    if let Some(min_uptime) = params.min_uptime_for_matching {
+        require!(min_uptime <= 10000, ArkhamErrorCode::InvalidMinUptimeForMatching);
+        protocol_config.min_uptime_for_matching = min_uptime;
+    }
 
-    // Find the actual string length by detecting boundaries
-    // Valid PeerIDs start with "12D3KooW" and are typically 40-53 characters
-    let max_peer_id_length = 60; // Safety bound
-    let search_end = std::cmp::min(peer_id_data_offset + max_peer_id_length, data.len());
-    let peer_id_slice = &data[peer_id_data_offset..search_end];
+    if let Some(pool_size) = params.premium_pool_size {
+        require!(pool_size > 0, ArkhamErrorCode::InvalidPremiumPoolSize);
+        protocol_config.premium_pool_size = pool_size;
+    }
 
-    // Strategy 1: Find boundary by looking for StakeToken enum (should be 0, 1, or 2)
-    let mut actual_length = 0;
-    for i in 40..max_peer_id_length {
-        let test_offset = peer_id_data_offset + i;
-        if test_offset >= data.len() {
-            break;
-        }
+    if let Some(interval) = params.min_reputation_update_interval {
+        require!(interval >= 0, ArkhamErrorCode::InvalidReputationUpdateInterval);
+        protocol_config.min_reputation_update_interval = interval;
+    }
 
-        // Check if this position could be the start of StakeToken enum
-        let potential_stake_token = data[test_offset];
-
-        // StakeToken enum values: Sol = 0, Usdc = 1, Usdt = 2
-        if potential_stake_token <= 2 {
-            // Validate by checking if we have a valid PeerId up to this point
-            if i <= peer_id_slice.len() {
-                let potential_peer_id = &peer_id_slice[..i];
-                if let Ok(peer_id_str) = std::str::from_utf8(potential_peer_id) {
-                    // Valid PeerIDs must start with "12D3KooW" and be at least 40 chars
-                    if peer_id_str.starts_with("12D3KooW") && peer_id_str.len() >= 40 && peer_id_str.len() <= 60 {
-                        actual_length = i;
-                        msg!("Found PeerId boundary at offset {} (length: {})", test_offset, actual_length);
-                        break;
-                    }
-                }
-            }
-        }
+    if let Some(sla) = params.sla_uptime_bps {
+        require!(sla <= 10000, ArkhamErrorCode::InvalidSlaUptime);
+        protocol_config.sla_uptime_bps = sla;
     }
 
-    // Strategy 2: If boundary detection failed, try common PeerId lengths
-    if actual_length == 0 {
-        msg!("Boundary detection failed, trying common PeerId lengths");
-        for test_len in [44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 42, 43, 54, 55, 56] {
-            if test_len <= peer_id_slice.len() {
-                if let Ok(peer_id_str) = std::str::from_utf8(&peer_id_slice[..test_len]) {
-                    if peer_id_str.starts_with("12D3KooW") && peer_id_str.len() >= 40 {
-                        actual_length = test_len;
-                        msg!("Using common PeerId length: {}", actual_length);
-                        break;
-                    }
-                }
-            }
-        }
+    if let Some(every_n) = params.proof_event_every_n {
+        protocol_config.proof_event_every_n = every_n;
+    }
+
+    if let Some(interval) = params.proof_event_interval {
+        require!(interval >= 0, ArkhamErrorCode::InvalidProofEventInterval);
+        protocol_config.proof_event_interval = interval;
+    }
+
+    if let Some(penalty) = params.anomaly_penalty {
+        protocol_config.anomaly_penalty = penalty;
+    }
+
+    if let Some(bounty_bps) = params.anomaly_bounty_bps {
+        require!(bounty_bps <= 10000, ArkhamErrorCode::InvalidAnomalyBounty);
+        protocol_config.anomaly_bounty_bps = bounty_bps;
+    }
+
+    if params.reward_numerator.is_some() || params.reward_denominator.is_some() {
+        let (current_numerator, current_denominator) = reward_rate(protocol_config);
+        let numerator = params.reward_numerator.unwrap_or(current_numerator);
+        let denominator = params.reward_denominator.unwrap_or(current_denominator);
+        require!(denominator > 0, ArkhamErrorCode::InvalidRewardRate);
+        protocol_config.reward_numerator = numerator;
+        protocol_config.reward_denominator = denominator;
+        protocol_config.tokens_per_5gb = tokens_per_5gb_equivalent(numerator, denominator);
+    }
+
+    if let Some(tolerance_bps) = params.rate_check_tolerance_bps {
+        protocol_config.rate_check_tolerance_bps = tolerance_bps;
+    }
+
+    if let Some(max_age) = params.max_uptime_report_age {
+        require!(max_age >= 0, ArkhamErrorCode::InvalidUptimeReportAge);
+        protocol_config.max_uptime_report_age = max_age;
+    }
+
+    if let Some(max_supply) = params.max_arkham_supply {
+        // A cap below what is already minted could never be honoured
+        require!(
+            max_supply == 0 || max_supply >= protocol_config.arkham_total_minted,
+            ArkhamErrorCode::InvalidMaxArkhamSupply
+        );
+        protocol_config.max_arkham_supply = max_supply;
+    }
+
+    if let Some(min_version) = params.min_oracle_message_version {
+        // Requiring a format the program can't build would lock out every signer
+        require!(
+            (ORACLE_MESSAGE_V1..=LATEST_ORACLE_MESSAGE_VERSION).contains(&min_version),
+            ArkhamErrorCode::UnsupportedOracleMessageVersion
+        );
+        protocol_config.min_oracle_message_version = min_version;
+    }
+
+    if let Some(max_proofs) = params.max_total_proofs {
+        protocol_config.max_total_proofs = max_proofs;
+    }
+
+    if let Some(timeout) = params.inactivity_timeout {
+        require!(timeout >= 0, ArkhamErrorCode::InvalidInactivityTimeout);
+        protocol_config.inactivity_timeout = timeout;
+    }
+
+    if let Some(enabled) = params.buffer_refund_enabled {
+        protocol_config.buffer_refund_enabled = enabled;
+    }
+
+    if let Some(enabled) = params.proof_fee_enabled {
+        protocol_config.proof_fee_enabled = enabled;
+    }
+
+    if let Some(insurance_bps) = params.insurance_bps {
+        require!(insurance_bps <= 10000, ArkhamErrorCode::InvalidInsuranceBps);
+        protocol_config.insurance_bps = insurance_bps;
+    }
+
+    if let Some(window) = params.failure_decay_window {
+        require!(window >= 0, ArkhamErrorCode::InvalidFailureDecayWindow);
+        protocol_config.failure_decay_window = window;
+    }
+
+    if params.min_fee_bps.is_some() || params.max_fee_bps.is_some() {
+        let (min_fee_bps, max_fee_bps) = updated_fee_bounds(protocol_config, params.min_fee_bps, params.max_fee_bps)?;
+        protocol_config.min_fee_bps = min_fee_bps;
+        protocol_config.max_fee_bps = max_fee_bps;
+    }
+
+    if params.usdc_mint.is_some() || params.usdt_mint.is_some() {
+        let (usdc_mint, usdt_mint) = updated_stake_mints(protocol_config, params.usdc_mint, params.usdt_mint)?;
+        protocol_config.usdc_mint = usdc_mint;
+        protocol_config.usdt_mint = usdt_mint;
+    }
+
+    emit!(ProtocolParamsUpdated {
+        authority: ctx.accounts.authority.key(),
+        params,
+    });
+
+    Ok(())
+}
+
+/// Returns the key config fields as versioned return data
+/// Callers decode ProtocolConfigExport instead of tracking the account layout
+pub fn export_protocol_config_handler(ctx: Context<ExportProtocolConfig>) -> Result<ProtocolConfigExport> {
+    Ok(protocol_config_export(&ctx.accounts.protocol_config))
+}
+
+/// Builds the current-version export of `config`
+pub fn protocol_config_export(config: &ProtocolConfig) -> ProtocolConfigExport {
+    ProtocolConfigExport {
+        version: PROTOCOL_CONFIG_EXPORT_VERSION,
+        base_rate_per_mb: config.base_rate_per_mb,
+        rate_unit: config.rate_unit.clone(),
+        protocol_fee_bps: config.protocol_fee_bps,
+        min_fee_bps: config.min_fee_bps,
+        max_fee_bps: config.max_fee_bps,
+        tier_thresholds: config.tier_thresholds,
+        tier_multipliers: config.tier_multipliers,
+        tokens_per_5gb: config.tokens_per_5gb,
+        registration_fee: config.registration_fee,
+        max_arkham_reward_per_proof: config.max_arkham_reward_per_proof,
+        premium_reward_boost_bps: config.premium_reward_boost_bps,
+        oracle_authority: config.oracle_authority,
+        reputation_updater: config.reputation_updater,
+        oracle_required: config.oracle_required,
+        idle_escrow_yield_enabled: config.idle_escrow_yield_enabled,
+        require_strict_tiers: config.require_strict_tiers,
+        arkham_minting_renounced: config.arkham_minting_renounced,
+        reward_numerator: reward_rate(config).0,
+        reward_denominator: reward_rate(config).1,
     }
+}
+
+/// Initializes the top earners leaderboard
+/// Only callable by the protocol authority
+pub fn initialize_top_earners_handler(ctx: Context<InitializeTopEarners>) -> Result<()> {
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
 
-    // Validate we found a valid length
-    require!(actual_length > 0, ArkhamErrorCode::InvalidPeerId);
-    require!(actual_length >= 40, ArkhamErrorCode::InvalidPeerId);
-    require!(actual_length <= 60, ArkhamErrorCode::InvalidPeerId);
+    ctx.accounts.top_earners.entries = Vec::new();
 
-    // Extract and validate the PeerId string
-    let peer_id_bytes = &peer_id_slice[..actual_length];
-    let peer_id_str = std::str::from_utf8(peer_id_bytes)
-        .map_err(|_| ArkhamErrorCode::InvalidPeerId)?;
+    Ok(())
+}
 
-    // Final validation: must be a valid libp2p PeerId format
+/// Initializes the per-region active warden index
+/// Only callable by the protocol authority. Wardens registered before the index existed
+/// are not counted until they cancel an unstake or re-register.
+pub fn initialize_region_index_handler(ctx: Context<InitializeRegionIndex>) -> Result<()> {
+    // Verify the caller is the protocol authority
     require!(
-        peer_id_str.starts_with("12D3KooW"),
-        ArkhamErrorCode::InvalidPeerId
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
     );
+
+    ctx.accounts.region_index.active_wardens = [0; REGION_COUNT];
+
+    Ok(())
+}
+
+/// Creates the shared USDC/USDT stake vaults owned by the stake authority PDA
+/// Run once at setup (and again for a new mint if the approved mints change)
+/// so registrations only reference existing vaults instead of racing to create them
+/// Only callable by the protocol authority
+pub fn initialize_stake_vaults_handler(ctx: Context<InitializeStakeVaults>) -> Result<()> {
+    // Verify the caller is the protocol authority
     require!(
-        peer_id_str.len() >= 40 && peer_id_str.len() <= 60,
-        ArkhamErrorCode::InvalidPeerId
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
     );
 
-    msg!("Detected PeerId: {}", peer_id_str);
-    msg!("Actual length: {} bytes", actual_length);
+    Ok(())
+}
 
-    // Only update if the length prefix is incorrect
-    if stored_length != actual_length as u32 {
-        // Clone the string before dropping the borrow
-        let peer_id_clone = peer_id_str.to_string();
+/// Tops the insurance fund PDA up to rent-exempt so proof settlements can credit it
+/// small slices of the protocol fee. Run once before setting insurance_bps; a no-op
+/// once the fund is rent-exempt
+/// Only callable by the protocol authority, who pays the top-up
+pub fn initialize_insurance_fund_handler(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
 
-        drop(data); // Drop the immutable borrow before getting mutable
+    fund_to_rent_exempt(&ctx.accounts.authority, &ctx.accounts.insurance_fund, &ctx.accounts.system_program)
+}
 
-        // Get mutable reference and update the length prefix
-        let mut data_mut = warden_account.try_borrow_mut_data()?;
+/// Tops the rewards vault PDA up to rent-exempt, so the first proof payment or subsidy
+/// credited to it, and claims that leave a small balance behind, never leave it
+/// holding less than the minimum. Run once at setup; a no-op once the vault is rent-exempt
+/// Only callable by the protocol authority, who pays the top-up
+pub fn initialize_rewards_vault_handler(ctx: Context<InitializeRewardsVault>) -> Result<()> {
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
 
-        // Write the correct length as little-endian u32
-        let correct_length_bytes = (actual_length as u32).to_le_bytes();
-        data_mut[peer_id_offset..peer_id_offset + 4].copy_from_slice(&correct_length_bytes);
+    fund_to_rent_exempt(&ctx.accounts.authority, &ctx.accounts.rewards_vault, &ctx.accounts.system_program)
+}
 
-        msg!(
-            "✓ Fixed PeerId length prefix: {} -> {}",
-            stored_length,
-            actual_length
-        );
+/// Tops the escrow yield vault PDA up to rent-exempt, so the first stake_idle_escrow
+/// and partial unwinds never leave it holding less than the minimum
+/// Run once before enabling idle_escrow_yield_enabled; a no-op once the vault is rent-exempt
+/// Only callable by the protocol authority, who pays the top-up
+pub fn initialize_escrow_yield_vault_handler(ctx: Context<InitializeEscrowYieldVault>) -> Result<()> {
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
 
-        // Emit event for audit trail
-        emit!(WardenPeerIdMigrated {
-            warden_authority: authority.key(),
-            peer_id: peer_id_clone,
-            old_length: stored_length,
-            new_length: actual_length as u32,
-        });
+    fund_to_rent_exempt(&ctx.accounts.authority, &ctx.accounts.escrow_yield_vault, &ctx.accounts.system_program)
+}
 
-        msg!("Migration completed successfully");
-    } else {
-        msg!("PeerId length prefix is already correct ({}), no migration needed", stored_length);
+/// Pays `payer` into a lamport-only PDA until it holds the rent-exempt minimum
+/// for an empty account; a no-op once it does
+fn fund_to_rent_exempt<'info>(
+    payer: &Signer<'info>,
+    pda: &SystemAccount<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let top_up = Rent::get()?.minimum_balance(0).saturating_sub(pda.lamports());
+    if top_up > 0 {
+        let cpi_context = CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: payer.to_account_info(),
+                to: pda.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, top_up)?;
     }
 
     Ok(())
 }
 
+/// Moves stablecoin stake out of the legacy vaults owned by the stake_vault PDA into
+/// the stake authority's vaults, which initialize_stake_vaults must have created first
+/// Only callable by the protocol authority; a no-op for a vault that is already empty
+pub fn migrate_stake_vaults_handler(ctx: Context<MigrateStakeVaults>) -> Result<()> {
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+
+    let vault_seeds = &[b"stake_vault".as_ref(), &[ctx.bumps.stake_vault]];
+    let signer_seeds = &[&vault_seeds[..]];
+
+    let usdc_amount = ctx.accounts.legacy_usdc_vault.amount;
+    let usdt_amount = ctx.accounts.legacy_usdt_vault.amount;
+    for (from, to, amount) in [
+        (&ctx.accounts.legacy_usdc_vault, &ctx.accounts.usdc_vault, usdc_amount),
+        (&ctx.accounts.legacy_usdt_vault, &ctx.accounts.usdt_vault, usdt_amount),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let cpi_accounts = Transfer {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            authority: ctx.accounts.stake_vault.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_context, amount)?;
+    }
+
+    emit!(StakeVaultsMigrated {
+        authority: ctx.accounts.authority.key(),
+        usdc_amount,
+        usdt_amount,
+    });
+
+    Ok(())
+}
+
+/// Empties the pre-split sol_vault PDA, which held stake principal and warden
+/// earnings together, into the stake and rewards vaults, and its stablecoin
+/// vaults into the stake authority's. `to_rewards` is the part owed to wardens
+/// (their pending_claims, summed off-chain); the rest is stake principal.
+/// Only callable by the protocol authority; vaults that were never created are skipped
+pub fn sweep_legacy_sol_vault_handler(ctx: Context<SweepLegacySolVault>, to_rewards: u64) -> Result<()> {
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+
+    let vault_seeds = &[b"sol_vault".as_ref(), &[ctx.bumps.sol_vault]];
+    let signer_seeds = &[&vault_seeds[..]];
+
+    // 1. Lamports: earnings to the rewards vault, principal to the stake vault
+    let (to_rewards, to_stake) = split_legacy_sol_vault(ctx.accounts.sol_vault.lamports(), to_rewards)?;
+    for (to, amount) in [
+        (ctx.accounts.rewards_vault.to_account_info(), to_rewards),
+        (ctx.accounts.stake_vault.to_account_info(), to_stake),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.sol_vault.to_account_info(),
+                to,
+            },
+            signer_seeds,
+        );
+        system_program::transfer(cpi_context, amount)?;
+    }
+
+    // 2. Stablecoin stake
+    let mut amounts = [0u64; 2];
+    for ((from, to), moved) in [
+        (&ctx.accounts.legacy_usdc_vault, &ctx.accounts.usdc_vault),
+        (&ctx.accounts.legacy_usdt_vault, &ctx.accounts.usdt_vault),
+    ].into_iter().zip(amounts.iter_mut()) {
+        let Some(from) = from else { continue };
+        if from.amount == 0 {
+            continue;
+        }
+        let cpi_accounts = Transfer {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            authority: ctx.accounts.sol_vault.to_account_info(),
+        };
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        token::transfer(cpi_context, from.amount)?;
+        *moved = from.amount;
+    }
+
+    emit!(LegacySolVaultSwept {
+        authority: ctx.accounts.authority.key(),
+        to_rewards,
+        to_stake,
+        usdc_amount: amounts[0],
+        usdt_amount: amounts[1],
+    });
+
+    Ok(())
+}
+
+/// Splits the legacy sol_vault balance into (rewards, stake) lamports
+pub fn split_legacy_sol_vault(balance: u64, to_rewards: u64) -> Result<(u64, u64)> {
+    let to_stake = balance
+        .checked_sub(to_rewards)
+        .ok_or(ArkhamErrorCode::LegacySweepExceedsBalance)?;
+    Ok((to_rewards, to_stake))
+}
+
+/// Initializes the ARKHAM token mint
+/// Only callable by the protocol authority
+pub fn initialize_arkham_mint_handler(ctx: Context<InitializeArkhamMint>) -> Result<()> {
+    let protocol_config = &mut ctx.accounts.protocol_config;
+    let mint = &mut ctx.accounts.arkham_mint;
+    
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+
+    // Verify the mint hasn't been initialized yet
+    require!(
+        protocol_config.arkham_token_mint == Pubkey::default(),
+        ArkhamErrorCode::TokenMintAlreadyInitialized
+    );
+
+    // Mint initialization will be handled by Anchor's mint constraints
+    // The mint authority is set up in the InitializeArkhamMint context
+    // The bump is automatically handled by Anchor's init constraint
+
+    // Update the protocol config with the new mint address
+    protocol_config.arkham_token_mint = mint.key();
+
+    emit!(ArkhamMintInitialized {
+        authority: ctx.accounts.authority.key(),
+        mint: mint.key(),
+    });
+
+    Ok(())
+}
+
+/// Permanently renounces the ARKHAM mint authority, capping the token supply
+/// Only callable by the protocol authority. This cannot be undone.
+pub fn renounce_mint_authority_handler(ctx: Context<RenounceMintAuthority>) -> Result<()> {
+    // Flag first: the CPI below failing rolls the flag back with it
+    renounce_arkham_minting(&mut ctx.accounts.protocol_config, &ctx.accounts.authority.key())?;
+
+    // Clear the mint authority, signed by the mint authority PDA
+    let bump = [ctx.bumps.mint_authority];
+    let seeds = mint_authority_signer_seeds(&bump);
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = SetAuthority {
+        current_authority: ctx.accounts.mint_authority.to_account_info(),
+        account_or_mint: ctx.accounts.arkham_mint.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::set_authority(cpi_context, AuthorityType::MintTokens, None)?;
+
+    emit!(ArkhamMintAuthorityRenounced {
+        authority: ctx.accounts.authority.key(),
+        mint: ctx.accounts.arkham_mint.key(),
+        total_supply: ctx.accounts.arkham_mint.supply,
+    });
+
+    Ok(())
+}
+
+/// Checks `authority` may renounce ARKHAM minting and flags it renounced, after
+/// which claims and ARKHAM subsidy grants fail with ArkhamMintingRenounced
+pub fn renounce_arkham_minting(config: &mut ProtocolConfig, authority: &Pubkey) -> Result<()> {
+    // Verify the caller is the protocol authority
+    require!(
+        *authority == config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+
+    require!(
+        !config.arkham_minting_renounced,
+        ArkhamErrorCode::ArkhamMintingRenounced
+    );
+
+    config.arkham_minting_renounced = true;
+    Ok(())
+}
+
+/// Distributes bootstrap subsidies to Wardens
+/// This is the mechanism to attract early participants during the first 6 months.
+/// SOL subsidies move from the treasury into the rewards vault and credit `pending_claims`;
+/// ARKHAM subsidies move from the treasury ATA into the subsidy vault and credit
+/// `arkham_tokens_subsidized`. Warden accounts are passed in `remaining_accounts`
+/// in the same order as `warden_keys`.
+pub fn distribute_subsidies_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DistributeSubsidies<'info>>,
+    subsidy_token: SubsidyToken,
+    warden_keys: Vec<Pubkey>,
+    subsidy_amounts: Vec<u64>,
+) -> Result<()> {
+    let protocol_config = &ctx.accounts.protocol_config;
+    
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+
+    // Verify the batch shape up front, before any funds move
+    validate_subsidy_batch(warden_keys.len(), subsidy_amounts.len(), ctx.remaining_accounts.len())?;
+
+    // Verify that we're not distributing more than available in treasury
+    let total_subsidy = total_subsidy_amount(&subsidy_amounts)?;
+
+    // Fund the vault backing the credited balance, checking solvency for that token
+    match subsidy_token {
+        SubsidyToken::Sol => {
+            let treasury_balance = ctx.accounts.treasury_authority.lamports();
+            check_treasury_solvency(treasury_balance, total_subsidy)?;
+
+            let cpi_context = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.treasury_authority.to_account_info(),
+                    to: ctx.accounts.rewards_vault.to_account_info(),
+                },
+            );
+            system_program::transfer(cpi_context, total_subsidy)?;
+        }
+        SubsidyToken::Arkham => {
+            let treasury = ctx.accounts.treasury.as_ref()
+                .ok_or(ArkhamErrorCode::InvalidSubsidyDistribution)?;
+            let subsidy_vault = ctx.accounts.subsidy_vault.as_ref()
+                .ok_or(ArkhamErrorCode::InvalidSubsidyDistribution)?;
+            validate_treasury_token_account(treasury, protocol_config)?;
+            check_treasury_solvency(treasury.amount, total_subsidy)?;
+
+            let cpi_accounts = Transfer {
+                from: treasury.to_account_info(),
+                to: subsidy_vault.to_account_info(),
+                authority: ctx.accounts.treasury_authority.to_account_info(),
+            };
+            let cpi_context = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+            token::transfer(cpi_context, total_subsidy)?;
+        }
+    }
+
+    // Credit each warden's balance for the subsidy currency
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    for (i, warden_info) in ctx.remaining_accounts.iter().enumerate() {
+        require!(
+            warden_info.key() == warden_keys[i],
+            ArkhamErrorCode::InvalidSubsidyDistribution
+        );
+        let mut warden: Account<'info, Warden> = Account::try_from(warden_info)?;
+        credit_subsidy(
+            &mut warden,
+            &subsidy_token,
+            subsidy_amounts[i],
+            protocol_config.subsidy_vesting_duration,
+            current_timestamp,
+        )?;
+        warden.exit(&crate::ID)?;
+    }
+
+    emit!(SubsidiesDistributed {
+        authority: ctx.accounts.authority.key(),
+        subsidy_token,
+        warden_count: warden_keys.len() as u32,
+        total_amount: total_subsidy,
+    });
+
+    Ok(())
+}
+
+/// Validates a subsidy batch: matching vector and account counts, at most MAX_SUBSIDY_BATCH
+pub fn validate_subsidy_batch(keys_len: usize, amounts_len: usize, accounts_len: usize) -> Result<()> {
+    require!(
+        keys_len <= MAX_SUBSIDY_BATCH,
+        ArkhamErrorCode::SubsidyBatchTooLarge
+    );
+    require!(
+        keys_len == amounts_len && keys_len == accounts_len,
+        ArkhamErrorCode::InvalidSubsidyDistribution
+    );
+    Ok(())
+}
+
+/// Sums a batch's subsidy amounts, rejecting totals that don't fit in a u64
+pub fn total_subsidy_amount(subsidy_amounts: &[u64]) -> Result<u64> {
+    subsidy_amounts
+        .iter()
+        .try_fold(0u64, |total, &amount| total.checked_add(amount))
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow.into())
+}
+
+/// Verifies a treasury token account belongs to the config-recorded treasury
+/// and holds the ARKHAM mint, so balances are never read from an unrelated account
+pub fn validate_treasury_token_account(treasury: &TokenAccount, protocol_config: &ProtocolConfig) -> Result<()> {
+    require_keys_eq!(
+        treasury.owner,
+        protocol_config.treasury,
+        ArkhamErrorCode::InvalidTreasuryAccount
+    );
+    require_keys_eq!(
+        treasury.mint,
+        protocol_config.arkham_token_mint,
+        ArkhamErrorCode::InvalidTreasuryAccount
+    );
+    Ok(())
+}
+
+/// Rejects a distribution the treasury can't cover in the subsidy currency
+pub fn check_treasury_solvency(treasury_balance: u64, total_subsidy: u64) -> Result<()> {
+    require!(
+        treasury_balance >= total_subsidy,
+        ArkhamErrorCode::InsufficientTreasuryBalance
+    );
+    Ok(())
+}
+
+/// Credits a subsidy to the Warden balance matching its currency
+pub fn credit_subsidy(
+    warden: &mut Warden,
+    subsidy_token: &SubsidyToken,
+    amount: u64,
+    vesting_duration: i64,
+    current_timestamp: i64,
+) -> Result<()> {
+    match subsidy_token {
+        SubsidyToken::Sol => {
+            warden.pending_claims = warden.pending_claims
+                .checked_add(amount)
+                .ok_or(ArkhamErrorCode::RewardOverflow)?;
+        }
+        SubsidyToken::Arkham => credit_arkham_subsidy(warden, amount, vesting_duration, current_timestamp)?,
+    }
+    Ok(())
+}
+
+/// Mints bootstrap ARKHAM tokens into the subsidy vault for a Warden's subsidized bucket
+/// Kept separate from arkham_tokens_earned so organic rewards stay distinguishable;
+/// claimed through claim_subsidized_arkham_tokens subject to the vesting schedule
+pub fn grant_arkham_subsidy_handler(ctx: Context<GrantArkhamSubsidy>, amount: u64) -> Result<()> {
+    let protocol_config = &mut ctx.accounts.protocol_config;
+    let warden = &mut ctx.accounts.warden;
+
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+    require!(amount > 0, ArkhamErrorCode::InvalidSubsidyDistribution);
+
+    crate::instructions::payments::ensure_arkham_mintable(protocol_config)?;
+
+    // Grants are never partially filled: the whole amount must fit under the supply cap
+    require!(
+        arkham_mint_allowance(protocol_config, ctx.accounts.arkham_mint.supply, amount) == amount,
+        ArkhamErrorCode::ArkhamSupplyExhausted
+    );
+
+    // Back the grant with freshly minted tokens held in the subsidy vault
+    let bump = [ctx.bumps.mint_authority];
+    let seeds = mint_authority_signer_seeds(&bump);
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = MintTo {
+        mint: ctx.accounts.arkham_mint.to_account_info(),
+        to: ctx.accounts.subsidy_vault.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    let cpi_context = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds,
+    );
+    token::mint_to(cpi_context, amount)?;
+    record_arkham_minted(protocol_config, amount)?;
+
+    let current_timestamp = Clock::get()?.unix_timestamp;
+    credit_arkham_subsidy(warden, amount, protocol_config.subsidy_vesting_duration, current_timestamp)?;
+
+    emit!(ArkhamSubsidyGranted {
+        warden: warden.key(),
+        amount,
+        total_subsidized: warden.arkham_tokens_subsidized,
+    });
+
+    Ok(())
+}
+
+/// Adds a grant to the subsidized bucket; every grant vests over the full duration
+/// - once all earlier grants have vested, they're carried as vested and the new
+///   grant vests alone from now
+/// - while earlier grants are still vesting, vesting restarts from the amount-weighted
+///   average start, so whatever had vested stays vested
+pub fn credit_arkham_subsidy(
+    warden: &mut Warden,
+    amount: u64,
+    vesting_duration: i64,
+    current_timestamp: i64,
+) -> Result<()> {
+    let total = warden.arkham_tokens_subsidized
+        .checked_add(amount)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?;
+    let vesting = warden.arkham_tokens_subsidized.saturating_sub(warden.subsidy_vested_carry) as i128;
+    let elapsed = current_timestamp.saturating_sub(warden.subsidy_vesting_start).max(0);
+
+    if vesting_duration <= 0 || elapsed >= vesting_duration {
+        warden.subsidy_vested_carry = warden.arkham_tokens_subsidized;
+        warden.subsidy_vesting_start = current_timestamp;
+    } else {
+        let shift = (vesting * elapsed as i128)
+            .checked_div(vesting + amount as i128)
+            .unwrap_or(0);
+        warden.subsidy_vesting_start = current_timestamp - shift as i64;
+    }
+    warden.arkham_tokens_subsidized = total;
+    Ok(())
+}
+
+/// Corrects a Warden's over-credited `pending_claims`, e.g. after an accounting bug
+/// Only callable by the protocol authority. Reductions are always allowed; an increase
+/// may not exceed what the rewards vault holds above its rent-exempt minimum.
+pub fn adjust_pending_claims_handler(
+    ctx: Context<AdjustPendingClaims>,
+    new_amount: u64,
+    reason: String,
+) -> Result<()> {
+    let warden = &mut ctx.accounts.warden;
+
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+    require!(
+        reason.len() <= MAX_ADJUSTMENT_REASON_LEN,
+        ArkhamErrorCode::AdjustmentReasonTooLong
+    );
+
+    let vault_info = ctx.accounts.rewards_vault.to_account_info();
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(vault_info.data_len());
+    let vault_spendable = vault_info.lamports().saturating_sub(rent_exempt_minimum);
+    validate_claims_adjustment(warden.pending_claims, new_amount, vault_spendable)?;
+
+    let previous_amount = warden.pending_claims;
+    warden.pending_claims = new_amount;
+
+    emit!(AdminClaimsAdjusted {
+        warden: warden.key(),
+        authority: ctx.accounts.authority.key(),
+        previous_amount,
+        new_amount,
+        reason,
+    });
+
+    Ok(())
+}
+
+/// Validates a pending_claims correction: lowering is always allowed, raising is
+/// bounded by the rewards vault's spendable balance so claims stay backed
+pub fn validate_claims_adjustment(current: u64, new_amount: u64, vault_spendable: u64) -> Result<()> {
+    require!(
+        new_amount <= current || new_amount <= vault_spendable,
+        ArkhamErrorCode::ClaimsAdjustmentExceedsVault
+    );
+    Ok(())
+}
+
+/// Force-refunds a stuck connection's escrow to the Seeker and closes it
+/// Last-resort incident tool: amounts already settled by co-signed proofs stay
+/// with the Warden, the remaining escrow returns to the Seeker.
+pub fn admin_refund_connection_handler(ctx: Context<AdminRefundConnection>) -> Result<()> {
+    let protocol_config = &ctx.accounts.protocol_config;
+    let connection = &ctx.accounts.connection;
+    let seeker = &mut ctx.accounts.seeker;
+    let warden = &mut ctx.accounts.warden;
+
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+
+    let refunded = book_admin_refund(
+        seeker,
+        warden,
+        connection.amount_escrowed,
+        connection.amount_paid,
+        connection.bandwidth_consumed,
+    )?;
+
+    emit!(AdminConnectionRefunded {
+        authority: ctx.accounts.authority.key(),
+        connection: connection.key(),
+        connection_id: connection.connection_id,
+        seeker: seeker.key(),
+        warden: warden.key(),
+        amount_paid: connection.amount_paid,
+        refunded,
+    });
+
+    // Note: Connection account will be closed automatically via close constraint
+    Ok(())
+}
+
+/// Emergency refund for a Connection opened before bandwidth_proofs became a fixed ring
+/// Such connections no longer deserialize, so end_connection and admin_refund_connection
+/// can't reach them; this decodes the old layout from raw bytes, books the same refund
+/// and closes the account, its rent going to the seeker's authority.
+/// Only callable by the protocol authority
+pub fn admin_refund_legacy_connection_handler(ctx: Context<AdminRefundLegacyConnection>) -> Result<()> {
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == ctx.accounts.protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+
+    let connection_info = ctx.accounts.connection.to_account_info();
+    let legacy = decode_legacy_connection(&connection_info.try_borrow_data()?)?;
+    let seeker = &mut ctx.accounts.seeker;
+    let warden = &mut ctx.accounts.warden;
+    require!(
+        legacy.seeker == seeker.key() && legacy.warden == warden.key(),
+        ArkhamErrorCode::ConnectionAccountMismatch
+    );
+
+    let refunded = book_admin_refund(
+        seeker,
+        warden,
+        legacy.amount_escrowed,
+        legacy.amount_paid,
+        legacy.bandwidth_consumed,
+    )?;
+
+    // Close the connection by hand, as the close constraint would
+    let rent = connection_info.lamports();
+    move_lamports(&connection_info, &ctx.accounts.seeker_authority, rent)?;
+    connection_info.assign(&system_program::ID);
+    connection_info.resize(0)?;
+
+    emit!(AdminConnectionRefunded {
+        authority: ctx.accounts.authority.key(),
+        connection: connection_info.key(),
+        connection_id: 0, // legacy connections predate connection ids
+        seeker: seeker.key(),
+        warden: warden.key(),
+        amount_paid: legacy.amount_paid,
+        refunded,
+    });
+
+    Ok(())
+}
+
+/// Books an admin refund of a connection on both parties: the unsettled escrow goes
+/// back to the seeker, the settled part counts toward its totals, and both active
+/// connection counters drop (saturating, counters may have drifted during the incident)
+/// Returns the refunded lamports
+pub fn book_admin_refund(
+    seeker: &mut Seeker,
+    warden: &mut Warden,
+    amount_escrowed: u64,
+    amount_paid: u64,
+    bandwidth_consumed: u64,
+) -> Result<u64> {
+    // 1. Refund the unsettled escrow to the seeker
+    let refunded = unused_connection_escrow(amount_escrowed, amount_paid)?;
+    seeker.escrow_balance = seeker.escrow_balance
+        .checked_add(refunded)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    seeker.committed_escrow = seeker.committed_escrow.saturating_sub(refunded);
+
+    // 2. Record the settled portion against the seeker's totals
+    seeker.total_bandwidth_consumed = seeker.total_bandwidth_consumed
+        .checked_add(bandwidth_consumed)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    seeker.total_spent = seeker.total_spent
+        .checked_add(amount_paid)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+
+    // 3. Reset active connection counters
+    seeker.active_connections = seeker.active_connections.saturating_sub(1);
+    warden.active_connections = warden.active_connections.saturating_sub(1);
+
+    Ok(refunded)
+}
+
+/// Updates a Warden's Peer ID. Only callable by the protocol authority.
+/// This is a developer/admin tool to fix registration errors.
+pub fn update_warden_peer_id_handler(ctx: Context<UpdateWardenPeerId>, new_peer_id: String) -> Result<()> {
+    let protocol_config = &ctx.accounts.protocol_config;
+    let warden = &mut ctx.accounts.warden;
+
+    // Verify the caller is the protocol authority
+    require!(
+        ctx.accounts.authority.key() == protocol_config.authority,
+        ArkhamErrorCode::UnauthorizedAdminAction
+    );
+
+    // Basic validation for Peer ID format
+    require!(
+        new_peer_id.starts_with("12D3KooW"),
+        ArkhamErrorCode::InvalidPeerId
+    );
+    require!(
+        new_peer_id.len() > 40 && new_peer_id.len() < 60,
+        ArkhamErrorCode::InvalidPeerId
+    );
+
+    let old_peer_id = warden.peer_id.clone();
+    warden.peer_id = new_peer_id.clone();
+
+    emit!(WardenPeerIdUpdated {
+        warden_authority: warden.authority,
+        old_peer_id,
+        new_peer_id,
+    });
+
+    Ok(())
+}
+
+/// Migrates a Warden account with corrupted PeerId field by fixing the Borsh string length prefix
+/// This is a one-time migration for accounts where the length prefix is incorrect but the data is intact.
+/// Equivalent to migrate_account(Warden, 2); kept for existing clients.
+pub fn migrate_warden_peer_id_handler(ctx: Context<MigrateWardenPeerId>) -> Result<()> {
+    let warden_account = &ctx.accounts.warden;
+    let authority = &ctx.accounts.authority;
+
+    let mut data = warden_account.try_borrow_mut_data()?;
+    verify_peer_id_migration_account(&data, &authority.key())?;
+    let stored_length = warden_peer_id_prefix(&data).ok_or(ArkhamErrorCode::InvalidPeerId)?;
+
+    let changed = run_migration(
+        &mut data,
+        &MigratableAccount::Warden,
+        2,
+        &authority.key(),
+        &MigrationParams::default(),
+    )?;
+
+    if changed {
+        let actual_length = warden_peer_id_prefix(&data).ok_or(ArkhamErrorCode::InvalidPeerId)?;
+        let peer_id_start = WARDEN_PEER_ID_OFFSET + 4;
+        let peer_id = String::from_utf8_lossy(&data[peer_id_start..peer_id_start + actual_length as usize])
+            .into_owned();
+
+        msg!("✓ Fixed PeerId length prefix: {} -> {}", stored_length, actual_length);
+
+        // Emit event for audit trail
+        emit!(WardenPeerIdMigrated {
+            warden_authority: authority.key(),
+            peer_id,
+            old_length: stored_length,
+            new_length: actual_length,
+        });
+    } else {
+        msg!("PeerId length prefix is already correct ({}), no migration needed", stored_length);
+    }
+
+    Ok(())
+}
+
+
+/// Settings initialize_protocol_config takes beyond the original rate and tier arguments
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct InitializeProtocolConfigParams {
+    pub usdc_mint: Pubkey,
+    pub usdt_mint: Pubkey,
+    pub min_fee_bps: u16,
+    pub max_fee_bps: u16,
+}
+
+/// Optional protocol parameters for update_protocol_params; `None` leaves a value unchanged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct ProtocolParamsUpdate {
+    pub idle_escrow_yield_enabled: Option<bool>,
+    pub premium_reward_boost_bps: Option<u16>,
+    pub rate_unit: Option<RateUnit>,
+    pub registration_fee: Option<u64>,
+    pub subsidy_vesting_duration: Option<i64>,
+    pub oracle_required: Option<bool>,
+    pub fallback_prices: Option<[u64; 3]>,
+    pub max_connections_per_seeker: Option<u8>,
+    pub require_strict_tiers: Option<bool>,
+    pub max_arkham_reward_per_proof: Option<u64>,
+    pub min_wardens_per_region: Option<u16>,
+    pub min_connections_for_full_reputation: Option<u64>,
+    pub bootstrap_reputation_cap: Option<u32>,
+    pub min_uptime_for_matching: Option<u16>,
+    pub premium_pool_size: Option<u16>,
+    pub min_reputation_update_interval: Option<i64>,
+    pub sla_uptime_bps: Option<u16>,
+    pub proof_event_every_n: Option<u16>,
+    pub proof_event_interval: Option<i64>,
+    pub anomaly_penalty: Option<u64>,
+    pub anomaly_bounty_bps: Option<u16>,
+    pub reward_numerator: Option<u64>,
+    pub reward_denominator: Option<u64>,
+    pub rate_check_tolerance_bps: Option<u16>,
+    pub max_uptime_report_age: Option<i64>,
+    pub max_arkham_supply: Option<u64>,
+    pub min_oracle_message_version: Option<u8>,
+    pub max_total_proofs: Option<u32>,
+    pub inactivity_timeout: Option<i64>,
+    pub buffer_refund_enabled: Option<bool>,
+    pub insurance_bps: Option<u16>,
+    pub failure_decay_window: Option<i64>,
+    pub min_fee_bps: Option<u16>,
+    pub max_fee_bps: Option<u16>,
+    pub usdc_mint: Option<Pubkey>,
+    pub usdt_mint: Option<Pubkey>,
+    pub proof_fee_enabled: Option<bool>,
+}
+
+/// Version of the ProtocolConfigExport layout; bumped whenever fields change
+pub const PROTOCOL_CONFIG_EXPORT_VERSION: u8 = 2;
+
+/// Return data for export_protocol_config
+/// Fields are only ever appended, under a new version
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProtocolConfigExport {
+    pub version: u8,
+    pub base_rate_per_mb: u64,
+    pub rate_unit: RateUnit,
+    pub protocol_fee_bps: u16,
+    pub min_fee_bps: u16,
+    pub max_fee_bps: u16,
+    pub tier_thresholds: [u64; 3],
+    pub tier_multipliers: [u16; 3],
+    pub tokens_per_5gb: u64,
+    pub registration_fee: u64,
+    pub max_arkham_reward_per_proof: u64,
+    pub premium_reward_boost_bps: u16,
+    pub oracle_authority: Pubkey,
+    pub reputation_updater: Pubkey,
+    pub oracle_required: bool,
+    pub idle_escrow_yield_enabled: bool,
+    pub require_strict_tiers: bool,
+    pub arkham_minting_renounced: bool,
+    // v2
+    pub reward_numerator: u64,
+    pub reward_denominator: u64,
+}
+
+// Account contexts:
 
-// Account contexts:
-
 #[derive(Accounts)]
 pub struct UpdateProtocolConfig<'info> {
     #[account(
@@ -427,8 +1515,255 @@ pub struct UpdateProtocolConfig<'info> {
     )]
     pub protocol_config: Account<'info, ProtocolConfig>,
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Per-region warden counts, needed to remove a geo premium region without force
+    #[account(seeds = [b"region_index"], bump)]
+    pub region_index: Option<Box<Account<'info, RegionIndex>>>,
+}
+
+#[derive(Accounts)]
+pub struct ExportProtocolConfig<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTopEarners<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + // discriminator
+                4 + (TOP_EARNERS_CAPACITY * (32 + 8)), // entries vec (warden + total_earnings)
+        seeds = [b"top_earners"],
+        bump
+    )]
+    pub top_earners: Account<'info, TopEarners>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRegionIndex<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + // discriminator
+                (REGION_COUNT * 2), // active_wardens
+        seeds = [b"region_index"],
+        bump
+    )]
+    pub region_index: Box<Account<'info, RegionIndex>>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakeVaults<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: Stake authority PDA, owner of the stablecoin vaults; holds no data
+    #[account(seeds = [STAKE_AUTHORITY_SEEDS[0]], bump)]
+    pub stake_authority: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = stake_authority,
+    )]
+    pub usdc_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = usdt_mint,
+        associated_token::authority = stake_authority,
+    )]
+    pub usdt_vault: Account<'info, TokenAccount>,
+
+    #[account(address = protocol_config.usdc_mint)]
+    pub usdc_mint: Account<'info, Mint>,
+
+    #[account(address = protocol_config.usdt_mint)]
+    pub usdt_mint: Account<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeInsuranceFund<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Insurance fund (PDA) holding the slices of the protocol fee that back SLA refunds
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardsVault<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Rewards vault (PDA) holding warden earnings until they are claimed
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEscrowYieldVault<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Escrow yield vault (PDA) holding the idle escrow seekers park with stake_idle_escrow
+    #[account(mut, seeds = [b"escrow_yield_vault"], bump)]
+    pub escrow_yield_vault: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStakeVaults<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+
+    /// The stake vault PDA, which owned the stablecoin vaults before the stake authority
+    #[account(seeds = [b"stake_vault"], bump)]
+    pub stake_vault: SystemAccount<'info>,
+
+    /// CHECK: Stake authority PDA, owner of the destination vaults; holds no data
+    #[account(seeds = [STAKE_AUTHORITY_SEEDS[0]], bump)]
+    pub stake_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = stake_vault,
+    )]
+    pub legacy_usdc_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdt_mint,
+        associated_token::authority = stake_vault,
+    )]
+    pub legacy_usdt_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = stake_authority,
+    )]
+    pub usdc_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdt_mint,
+        associated_token::authority = stake_authority,
+    )]
+    pub usdt_vault: Account<'info, TokenAccount>,
+
+    #[account(address = protocol_config.usdc_mint)]
+    pub usdc_mint: Account<'info, Mint>,
+
+    #[account(address = protocol_config.usdt_mint)]
+    pub usdt_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SweepLegacySolVault<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+
+    /// The vault that held all SOL and stablecoin funds before the stake/rewards split
+    #[account(mut, seeds = [b"sol_vault"], bump)]
+    pub sol_vault: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: SystemAccount<'info>,
+
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    /// CHECK: Stake authority PDA, owner of the destination vaults; holds no data
+    #[account(seeds = [STAKE_AUTHORITY_SEEDS[0]], bump)]
+    pub stake_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = sol_vault,
+    )]
+    pub legacy_usdc_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdt_mint,
+        associated_token::authority = sol_vault,
+    )]
+    pub legacy_usdt_vault: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = stake_authority,
+    )]
+    pub usdc_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = usdt_mint,
+        associated_token::authority = stake_authority,
+    )]
+    pub usdt_vault: Account<'info, TokenAccount>,
+
+    #[account(address = protocol_config.usdc_mint)]
+    pub usdc_mint: Account<'info, Mint>,
+
+    #[account(address = protocol_config.usdt_mint)]
+    pub usdt_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -446,7 +1781,7 @@ pub struct InitializeArkhamMint<'info> {
 
     /// CHECK: Mint authority for the ARKHAM token - this is a PDA controlled by the program
     #[account(
-        seeds = [b"arkham", b"mint", b"authority"],
+        seeds = [MINT_AUTHORITY_SEEDS[0], MINT_AUTHORITY_SEEDS[1], MINT_AUTHORITY_SEEDS[2]],
         bump,
     )]
     pub mint_authority: AccountInfo<'info>,
@@ -465,6 +1800,36 @@ pub struct InitializeArkhamMint<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct RenounceMintAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"arkham_mint"],
+        bump,
+        address = protocol_config.arkham_token_mint,
+    )]
+    pub arkham_mint: Account<'info, Mint>,
+
+    /// CHECK: Mint authority for the ARKHAM token - PDA controlled by the program
+    #[account(
+        seeds = [MINT_AUTHORITY_SEEDS[0], MINT_AUTHORITY_SEEDS[1], MINT_AUTHORITY_SEEDS[2]],
+        bump,
+    )]
+    pub mint_authority: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct DistributeSubsidies<'info> {
     #[account(
@@ -473,23 +1838,45 @@ pub struct DistributeSubsidies<'info> {
     )]
     pub protocol_config: Account<'info, ProtocolConfig>,
 
+    /// Treasury wallet (e.g., multisig); funds SOL subsidies and owns the ARKHAM treasury ATA
+    #[account(mut, address = protocol_config.treasury)]
+    pub treasury_authority: Signer<'info>,
+
+    /// Rewards vault (PDA) that pays out `pending_claims`
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    /// ARKHAM treasury ATA, required for ARKHAM subsidies
     #[account(
         mut,
         associated_token::mint = arkham_mint,
         associated_token::authority = treasury_authority,
     )]
-    pub treasury: Account<'info, anchor_spl::token::TokenAccount>,
+    pub treasury: Option<Account<'info, TokenAccount>>,
 
-    pub arkham_mint: Account<'info, anchor_spl::token::Mint>,
+    #[account(seeds = [b"arkham_mint"], bump, address = protocol_config.arkham_token_mint)]
+    pub arkham_mint: Option<Account<'info, Mint>>,
 
-    /// CHECK: Treasury authority (e.g., multisig wallet)
-    pub treasury_authority: AccountInfo<'info>,
+    /// CHECK: Mint authority for the ARKHAM token - PDA controlled by the program
+    #[account(
+        seeds = [MINT_AUTHORITY_SEEDS[0], MINT_AUTHORITY_SEEDS[1], MINT_AUTHORITY_SEEDS[2]],
+        bump,
+    )]
+    pub mint_authority: Option<AccountInfo<'info>>,
+
+    /// Subsidy vault, required for ARKHAM subsidies
+    #[account(
+        mut,
+        associated_token::mint = arkham_mint,
+        associated_token::authority = mint_authority,
+    )]
+    pub subsidy_vault: Option<Account<'info, TokenAccount>>,
 
     #[account(mut)]
     pub authority: Signer<'info>,
 
-    // The actual warden accounts would need to be loaded dynamically
-    // This is simplified for the core implementation
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -497,18 +1884,7 @@ pub struct InitializeProtocolConfig<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + // discriminator
-                32 + // authority
-                32 + // treasury
-                32 + // arkham_token_mint
-                32 + // oracle_authority
-                8 +  // base_rate_per_mb
-                2 +  // protocol_fee_bps
-                (8 * 3) + // tier_thresholds
-                (2 * 3) + // tier_multipliers
-                8 +  // tokens_per_5gb
-                4 + (10 * (1 + 2)) + // geo_premiums vec (assume max 10 regions)
-                32, // reputation_updater
+        space = PROTOCOL_CONFIG_SPACE,
         seeds = [b"protocol_config"],
         bump
     )]
@@ -537,6 +1913,118 @@ pub struct UpdateWardenPeerId<'info> {
     pub warden: Account<'info, Warden>,
 }
 
+#[derive(Accounts)]
+pub struct AdminRefundConnection<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// The protocol authority must sign to authorize the refund.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
+        bump,
+        close = seeker_authority  // Refund rent to seeker
+    )]
+    pub connection: Box<Account<'info, Connection>>,
+
+    #[account(mut)]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    /// CHECK: Receives the connection rent; must be the seeker's authority
+    #[account(mut, address = seeker.authority)]
+    pub seeker_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AdminRefundLegacyConnection<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// The protocol authority must sign to authorize the refund.
+    pub authority: Signer<'info>,
+
+    /// CHECK: A Connection in the pre-ring-buffer layout, which no longer deserializes;
+    /// the handler decodes it from raw bytes and checks it names seeker and warden
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
+        bump,
+    )]
+    pub connection: AccountInfo<'info>,
+
+    #[account(mut)]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    /// CHECK: Receives the connection rent; must be the seeker's authority
+    #[account(mut, address = seeker.authority)]
+    pub seeker_authority: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GrantArkhamSubsidy<'info> {
+    #[account(mut, seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    #[account(
+        mut,
+        seeds = [b"arkham_mint"],
+        bump,
+        address = protocol_config.arkham_token_mint,
+    )]
+    pub arkham_mint: Account<'info, Mint>,
+
+    /// CHECK: Mint authority for the ARKHAM token - PDA controlled by the program
+    #[account(
+        seeds = [MINT_AUTHORITY_SEEDS[0], MINT_AUTHORITY_SEEDS[1], MINT_AUTHORITY_SEEDS[2]],
+        bump,
+    )]
+    pub mint_authority: AccountInfo<'info>,
+
+    /// Holds the tokens backing every Warden's subsidized bucket
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = arkham_mint,
+        associated_token::authority = mint_authority,
+    )]
+    pub subsidy_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AdjustPendingClaims<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    /// Rewards vault (PDA) whose balance bounds any increase
+    #[account(seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
 /// Account context for migrating a Warden's corrupted PeerId field
 #[derive(Accounts)]
 pub struct MigrateWardenPeerId<'info> {
@@ -560,6 +2048,22 @@ pub struct MigrateWardenPeerId<'info> {
 
 // Events:
 
+#[event]
+pub struct StakeVaultsMigrated {
+    pub authority: Pubkey,
+    pub usdc_amount: u64,
+    pub usdt_amount: u64,
+}
+
+#[event]
+pub struct LegacySolVaultSwept {
+    pub authority: Pubkey,
+    pub to_rewards: u64,
+    pub to_stake: u64,
+    pub usdc_amount: u64,
+    pub usdt_amount: u64,
+}
+
 #[event]
 pub struct ProtocolConfigUpdated {
     pub authority: Pubkey,
@@ -570,19 +2074,63 @@ pub struct ProtocolConfigUpdated {
     pub new_tokens_per_5gb: Option<u64>,
 }
 
+#[event]
+pub struct ReputationUpdaterChanged {
+    pub authority: Pubkey,
+    pub old: Pubkey,
+    pub new: Pubkey,
+}
+
+#[event]
+pub struct GeoPremiumRegionsRemoved {
+    pub authority: Pubkey,
+    pub region_codes: Vec<u8>,
+    pub forced: bool,
+}
+
+#[event]
+pub struct ProtocolParamsUpdated {
+    pub authority: Pubkey,
+    pub params: ProtocolParamsUpdate,
+}
+
 #[event]
 pub struct ArkhamMintInitialized {
     pub authority: Pubkey,
     pub mint: Pubkey,
 }
 
+#[event]
+pub struct ArkhamMintAuthorityRenounced {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub total_supply: u64,
+}
+
 #[event]
 pub struct SubsidiesDistributed {
     pub authority: Pubkey,
+    pub subsidy_token: SubsidyToken,
     pub warden_count: u32,
     pub total_amount: u64,
 }
 
+#[event]
+pub struct AdminClaimsAdjusted {
+    pub warden: Pubkey,
+    pub authority: Pubkey,
+    pub previous_amount: u64,
+    pub new_amount: u64,
+    pub reason: String,
+}
+
+#[event]
+pub struct ArkhamSubsidyGranted {
+    pub warden: Pubkey,
+    pub amount: u64,
+    pub total_subsidized: u64,
+}
+
 #[event]
 pub struct ProtocolConfigInitialized {
     pub authority: Pubkey,
@@ -590,6 +2138,17 @@ pub struct ProtocolConfigInitialized {
     pub protocol_fee_bps: u16,
 }
 
+#[event]
+pub struct AdminConnectionRefunded {
+    pub authority: Pubkey,
+    pub connection: Pubkey,
+    pub connection_id: u64,
+    pub seeker: Pubkey,
+    pub warden: Pubkey,
+    pub amount_paid: u64,
+    pub refunded: u64,
+}
+
 #[event]
 pub struct WardenPeerIdUpdated {
     pub warden_authority: Pubkey,
@@ -720,3 +2279,480 @@ pub struct MigrateProtocolConfig<'info> {
     /// CHECK: Just a public key, doesn't need to sign
     pub new_oracle_authority: AccountInfo<'info>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instructions::payments::{claimable_subsidy, ensure_arkham_mintable};
+    use crate::state::{test_protocol_config, test_warden};
+
+    /// claimable_subsidy for `warden` under `vesting_duration` at `now`
+    fn claimable(warden: &Warden, vesting_duration: i64, now: i64) -> u64 {
+        claimable_subsidy(
+            warden.arkham_tokens_subsidized,
+            warden.subsidy_vested_carry,
+            warden.arkham_subsidy_claimed,
+            warden.subsidy_vesting_start,
+            vesting_duration,
+            now,
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_earned_and_subsidized_buckets_are_independent() {
+        let mut warden = test_warden();
+        let vesting_duration = 1_000;
+
+        // Organic reward and a subsidy grant land in separate buckets
+        warden.arkham_tokens_earned = 50;
+        credit_arkham_subsidy(&mut warden, 1_000, vesting_duration, 100).unwrap();
+        assert_eq!(warden.arkham_tokens_earned, 50);
+        assert_eq!(warden.arkham_tokens_subsidized, 1_000);
+        assert_eq!(warden.subsidy_vesting_start, 100); // first grant starts the clock
+
+        // Claiming organic earnings leaves the subsidy untouched
+        warden.arkham_tokens_earned = 0;
+        assert_eq!(warden.arkham_tokens_subsidized, 1_000);
+
+        // Halfway through vesting, half the subsidy is claimable
+        assert_eq!(claimable(&warden, vesting_duration, 600), 500);
+        warden.arkham_subsidy_claimed += 500;
+        assert_eq!(warden.arkham_tokens_earned, 0);
+
+        // Nothing more until further vesting, then the remainder
+        assert_eq!(claimable(&warden, vesting_duration, 600), 0);
+        assert_eq!(claimable(&warden, vesting_duration, 5_000), 500);
+
+        // Zero duration vests immediately
+        assert_eq!(claimable(&warden, 0, 100), 500);
+    }
+
+    #[test]
+    fn test_each_subsidy_grant_vests_over_the_full_duration() {
+        let vesting_duration = 1_000;
+
+        // A grant halfway through an earlier one's vesting: nothing vested is taken
+        // back or released early, and both keep vesting at their own pace
+        let mut warden = test_warden();
+        credit_arkham_subsidy(&mut warden, 1_000, vesting_duration, 0).unwrap();
+        assert_eq!(claimable(&warden, vesting_duration, 500), 500);
+        warden.arkham_subsidy_claimed = 500;
+        credit_arkham_subsidy(&mut warden, 1_000, vesting_duration, 500).unwrap();
+        assert_eq!(warden.subsidy_vesting_start, 250); // amount-weighted average start
+        assert_eq!(claimable(&warden, vesting_duration, 500), 0);
+        assert_eq!(claimable(&warden, vesting_duration, 1_000), 1_000); // 500 + 500
+        assert_eq!(claimable(&warden, vesting_duration, 1_250), 1_500);
+
+        // A grant after everything earlier has vested doesn't inherit its schedule
+        let mut warden = test_warden();
+        credit_arkham_subsidy(&mut warden, 1_000, vesting_duration, 0).unwrap();
+        credit_arkham_subsidy(&mut warden, 1_000, vesting_duration, 5_000).unwrap();
+        assert_eq!((warden.subsidy_vested_carry, warden.subsidy_vesting_start), (1_000, 5_000));
+        assert_eq!(claimable(&warden, vesting_duration, 5_000), 1_000);
+        assert_eq!(claimable(&warden, vesting_duration, 5_500), 1_500);
+        assert_eq!(claimable(&warden, vesting_duration, 6_000), 2_000);
+    }
+
+    #[test]
+    fn test_validate_treasury_token_account() {
+        use anchor_spl::token::spl_token::state::{Account as SplTokenAccount, AccountState};
+        use anchor_lang::solana_program::program_pack::Pack;
+
+        let config = test_protocol_config();
+        let (treasury, arkham_mint) = (config.treasury, config.arkham_token_mint);
+        let token_account = |owner: Pubkey, mint: Pubkey| {
+            let account = SplTokenAccount { mint, owner, amount: 1_000, state: AccountState::Initialized, ..Default::default() };
+            let mut data = vec![0u8; SplTokenAccount::LEN];
+            SplTokenAccount::pack(account, &mut data).unwrap();
+            TokenAccount::try_deserialize(&mut &data[..]).unwrap()
+        };
+
+        assert!(validate_treasury_token_account(&token_account(treasury, arkham_mint), &config).is_ok());
+
+        // ATA of some other wallet
+        assert_eq!(
+            validate_treasury_token_account(&token_account(Pubkey::new_unique(), arkham_mint), &config).unwrap_err(),
+            ArkhamErrorCode::InvalidTreasuryAccount.into()
+        );
+
+        // Treasury's account for a different token
+        assert!(validate_treasury_token_account(&token_account(treasury, Pubkey::new_unique()), &config).is_err());
+    }
+
+    #[test]
+    fn test_validate_subsidy_batch() {
+        assert!(validate_subsidy_batch(MAX_SUBSIDY_BATCH, MAX_SUBSIDY_BATCH, MAX_SUBSIDY_BATCH).is_ok());
+        assert_eq!(
+            validate_subsidy_batch(MAX_SUBSIDY_BATCH + 1, MAX_SUBSIDY_BATCH + 1, MAX_SUBSIDY_BATCH + 1).unwrap_err(),
+            ArkhamErrorCode::SubsidyBatchTooLarge.into()
+        );
+        assert_eq!(
+            validate_subsidy_batch(3, 2, 3).unwrap_err(),
+            ArkhamErrorCode::InvalidSubsidyDistribution.into()
+        );
+    }
+
+    #[test]
+    fn test_subsidy_total_overflow_is_a_clean_error() {
+        assert_eq!(total_subsidy_amount(&[300, 700]).unwrap(), 1_000);
+        assert_eq!(total_subsidy_amount(&[]).unwrap(), 0);
+        assert_eq!(total_subsidy_amount(&[u64::MAX - 1, 1]).unwrap(), u64::MAX);
+
+        // Crafted to exceed u64::MAX: an error, not a panic or a wrapped small total
+        assert_eq!(
+            total_subsidy_amount(&[u64::MAX, 1]).unwrap_err(),
+            ArkhamErrorCode::ArithmeticOverflow.into()
+        );
+        assert_eq!(
+            total_subsidy_amount(&[u64::MAX / 2 + 1, u64::MAX / 2 + 1, 0]).unwrap_err(),
+            ArkhamErrorCode::ArithmeticOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_subsidies_credit_balance_for_their_currency() {
+        let mut warden = test_warden();
+        let amounts = [300u64, 700];
+        let total: u64 = amounts.iter().sum();
+
+        // Same shape for both currencies: solvency check, then per-warden credit
+        assert!(check_treasury_solvency(1_000, total).is_ok());
+        assert!(check_treasury_solvency(999, total).is_err());
+
+        credit_subsidy(&mut warden, &SubsidyToken::Sol, amounts[0], 0, 10).unwrap();
+        assert_eq!(warden.pending_claims, 300);
+        assert_eq!(warden.arkham_tokens_subsidized, 0);
+
+        credit_subsidy(&mut warden, &SubsidyToken::Arkham, amounts[1], 0, 20).unwrap();
+        assert_eq!(warden.pending_claims, 300);
+        assert_eq!(warden.arkham_tokens_subsidized, 700);
+        assert_eq!(warden.subsidy_vesting_start, 20);
+        assert_eq!(warden.arkham_tokens_earned, 0);
+    }
+
+    #[test]
+    fn test_subsidy_overflow_reports_reward_overflow() {
+        let mut warden = test_warden();
+        warden.pending_claims = u64::MAX;
+        warden.arkham_tokens_subsidized = u64::MAX;
+
+        for token in [SubsidyToken::Sol, SubsidyToken::Arkham] {
+            assert_eq!(
+                credit_subsidy(&mut warden, &token, 1, 0, 0).unwrap_err(),
+                ArkhamErrorCode::RewardOverflow.into()
+            );
+        }
+    }
+
+    #[test]
+    fn test_mint_authority_seeds_derive_one_address() {
+        let (derived, bump) = Pubkey::find_program_address(&MINT_AUTHORITY_SEEDS, &crate::ID);
+
+        // The address the contexts constrain must be the one the CPIs sign for
+        let signed = Pubkey::create_program_address(&mint_authority_signer_seeds(&[bump]), &crate::ID).unwrap();
+        assert_eq!(derived, signed);
+
+        // And it must not drift from the address the mint was originally created with
+        let (original, _) = Pubkey::find_program_address(&[b"arkham", b"mint", b"authority"], &crate::ID);
+        assert_eq!(derived, original);
+    }
+
+    #[test]
+    fn test_adjust_over_credited_pending_claims() {
+        let mut warden = test_warden();
+        warden.pending_claims = 5_000;
+
+        // Reducing an over-credited balance is allowed even when the vault is empty
+        assert!(validate_claims_adjustment(warden.pending_claims, 1_200, 0).is_ok());
+        assert!(validate_claims_adjustment(warden.pending_claims, 0, 0).is_ok());
+
+        // Increases are bounded by what the rewards vault can back
+        assert!(validate_claims_adjustment(warden.pending_claims, 8_000, 8_000).is_ok());
+        assert!(validate_claims_adjustment(warden.pending_claims, 8_001, 8_000).is_err());
+    }
+
+    #[test]
+    fn test_legacy_sol_vault_split() {
+        assert_eq!(split_legacy_sol_vault(10_000, 3_000).unwrap(), (3_000, 7_000));
+        assert_eq!(split_legacy_sol_vault(10_000, 0).unwrap(), (0, 10_000));
+        assert_eq!(split_legacy_sol_vault(10_000, 10_000).unwrap(), (10_000, 0));
+
+        // Claiming more earnings than the vault holds is refused, not truncated
+        assert_eq!(
+            split_legacy_sol_vault(10_000, 10_001).unwrap_err(),
+            ArkhamErrorCode::LegacySweepExceedsBalance.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_tier_thresholds() {
+        for strict in [false, true] {
+            assert!(validate_tier_thresholds(&[1_000, 5_000, 10_000], strict).is_ok());
+            assert!(validate_tier_thresholds(&[0, 1, 2], strict).is_ok());
+
+            // Descending
+            assert_eq!(
+                validate_tier_thresholds(&[5_000, 1_000, 10_000], strict).unwrap_err(),
+                ArkhamErrorCode::InvalidTierThresholds.into()
+            );
+        }
+
+        // Equal consecutive thresholds are permitted by default
+        assert!(validate_tier_thresholds(&[5_000, 5_000, 10_000], false).is_ok());
+        assert!(validate_tier_thresholds(&[10_000, 10_000, 10_000], false).is_ok());
+    }
+
+    #[test]
+    fn test_strict_tiers_reject_equal_thresholds() {
+        assert_eq!(
+            validate_tier_thresholds(&[5_000, 5_000, 10_000], true).unwrap_err(),
+            ArkhamErrorCode::CollapsedTierThresholds.into()
+        );
+        assert_eq!(
+            validate_tier_thresholds(&[1_000, 10_000, 10_000], true).unwrap_err(),
+            ArkhamErrorCode::CollapsedTierThresholds.into()
+        );
+        assert_eq!(
+            validate_tier_thresholds(&[10_000, 10_000, 10_000], true).unwrap_err(),
+            ArkhamErrorCode::CollapsedTierThresholds.into()
+        );
+    }
+
+    #[test]
+    fn test_fee_updates_respect_configured_bounds() {
+        let config = ProtocolConfig { min_fee_bps: 100, max_fee_bps: 1_000, ..test_protocol_config() };
+
+        assert!(validate_protocol_fee(100, config.min_fee_bps, config.max_fee_bps).is_ok());
+        assert!(validate_protocol_fee(1_000, config.min_fee_bps, config.max_fee_bps).is_ok());
+
+        // A 99% fee is under the 100% cap but far outside the bounds
+        assert_eq!(
+            validate_protocol_fee(9_900, config.min_fee_bps, config.max_fee_bps).unwrap_err(),
+            ArkhamErrorCode::ProtocolFeeOutOfBounds.into()
+        );
+        assert_eq!(
+            validate_protocol_fee(99, config.min_fee_bps, config.max_fee_bps).unwrap_err(),
+            ArkhamErrorCode::ProtocolFeeOutOfBounds.into()
+        );
+    }
+
+    #[test]
+    fn test_fee_bounds_update_is_validated() {
+        let config = ProtocolConfig { protocol_fee_bps: 200, min_fee_bps: 100, max_fee_bps: 1_000, ..test_protocol_config() };
+
+        // Either bound alone, or both
+        assert_eq!(updated_fee_bounds(&config, Some(150), None).unwrap(), (150, 1_000));
+        assert_eq!(updated_fee_bounds(&config, None, Some(500)).unwrap(), (100, 500));
+        assert_eq!(updated_fee_bounds(&config, Some(0), Some(PROTOCOL_FEE_CEILING_BPS)).unwrap(), (0, PROTOCOL_FEE_CEILING_BPS));
+
+        // Never past the program's ceiling or inverted
+        assert_eq!(
+            updated_fee_bounds(&config, None, Some(PROTOCOL_FEE_CEILING_BPS + 1)).unwrap_err(),
+            ArkhamErrorCode::InvalidFeeBounds.into()
+        );
+        assert_eq!(
+            updated_fee_bounds(&config, Some(1_001), None).unwrap_err(),
+            ArkhamErrorCode::InvalidFeeBounds.into()
+        );
+
+        // Bounds that would strand the current fee outside them
+        assert_eq!(
+            updated_fee_bounds(&config, Some(250), None).unwrap_err(),
+            ArkhamErrorCode::ProtocolFeeOutOfBounds.into()
+        );
+        assert_eq!(
+            updated_fee_bounds(&config, None, Some(199)).unwrap_err(),
+            ArkhamErrorCode::ProtocolFeeOutOfBounds.into()
+        );
+    }
+
+    #[test]
+    fn test_stake_mint_update_is_validated() {
+        let config = test_protocol_config();
+        let (usdc, usdt) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Either mint can be replaced on its own
+        assert_eq!(updated_stake_mints(&config, Some(usdc), None).unwrap(), (usdc, config.usdt_mint));
+        assert_eq!(updated_stake_mints(&config, None, Some(usdt)).unwrap(), (config.usdc_mint, usdt));
+
+        // A migrated config with unset mints can be repaired, but only with both
+        let unset = ProtocolConfig { usdc_mint: Pubkey::default(), usdt_mint: Pubkey::default(), ..test_protocol_config() };
+        assert_eq!(updated_stake_mints(&unset, Some(usdc), Some(usdt)).unwrap(), (usdc, usdt));
+        for (usdc_mint, usdt_mint) in [(Some(usdc), None), (Some(Pubkey::default()), Some(usdt)), (Some(usdc), Some(usdc))] {
+            assert_eq!(
+                updated_stake_mints(&unset, usdc_mint, usdt_mint).unwrap_err(),
+                ArkhamErrorCode::InvalidStakeMints.into()
+            );
+        }
+    }
+
+    #[test]
+    fn test_geo_region_removal_requires_force_when_wardens_exist() {
+        let premium = |region_code| GeoPremium { region_code, premium_bps: 2000 };
+        let current = vec![premium(0), premium(1), premium(2)];
+        let replacement = vec![premium(0), premium(2)];
+        let removed = removed_geo_regions(&current, &replacement);
+        assert_eq!(removed, vec![1]);
+
+        let mut active_wardens = [0u16; REGION_COUNT];
+        active_wardens[1] = 3;
+
+        // Wardens are registered in region 1
+        assert_eq!(
+            ensure_geo_regions_removable(&removed, Some(&active_wardens), false).unwrap_err(),
+            ArkhamErrorCode::GeoPremiumRegionInUse.into()
+        );
+        assert!(ensure_geo_regions_removable(&removed, Some(&active_wardens), true).is_ok());
+
+        // Counts unknown without the index
+        assert_eq!(
+            ensure_geo_regions_removable(&removed, None, false).unwrap_err(),
+            ArkhamErrorCode::RegionIndexRequired.into()
+        );
+
+        // An empty region goes without force
+        active_wardens[1] = 0;
+        assert!(ensure_geo_regions_removable(&removed, Some(&active_wardens), false).is_ok());
+
+        // Changing a premium isn't a removal
+        let repriced = vec![premium(0), GeoPremium { region_code: 1, premium_bps: 100 }, premium(2)];
+        assert!(removed_geo_regions(&current, &repriced).is_empty());
+
+        // remove_geo_premium_regions drops only configured regions
+        assert_eq!(geo_premiums_without(&current, &[1]).unwrap().len(), 2);
+        assert!(geo_premiums_without(&current, &[1]).unwrap().iter().all(|gp| gp.region_code != 1));
+        assert_eq!(
+            geo_premiums_without(&current, &[1, 4]).unwrap_err(),
+            ArkhamErrorCode::GeoPremiumRegionNotFound.into()
+        );
+    }
+
+    #[test]
+    fn test_protocol_config_export_return_data() {
+        use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+
+        let mut config = test_protocol_config();
+        config.rate_unit = RateUnit::PerGb;
+        config.registration_fee = 42;
+        config.max_arkham_reward_per_proof = 7;
+        config.premium_reward_boost_bps = 500;
+        config.oracle_required = false;
+        config.idle_escrow_yield_enabled = true;
+        config.require_strict_tiers = true;
+        config.arkham_minting_renounced = true;
+
+        let data = protocol_config_export(&config).try_to_vec().unwrap();
+        assert!(data.len() <= MAX_RETURN_DATA);
+
+        // What a CPI caller decodes from get_return_data
+        let export = ProtocolConfigExport::try_from_slice(&data).unwrap();
+        assert_eq!(export.version, PROTOCOL_CONFIG_EXPORT_VERSION);
+        assert_eq!(export.base_rate_per_mb, config.base_rate_per_mb);
+        assert_eq!(export.rate_unit, config.rate_unit);
+        assert_eq!(export.protocol_fee_bps, config.protocol_fee_bps);
+        assert_eq!(export.min_fee_bps, config.min_fee_bps);
+        assert_eq!(export.max_fee_bps, config.max_fee_bps);
+        assert_eq!(export.tier_thresholds, config.tier_thresholds);
+        assert_eq!(export.tier_multipliers, config.tier_multipliers);
+        assert_eq!(export.tokens_per_5gb, config.tokens_per_5gb);
+        assert_eq!(export.registration_fee, config.registration_fee);
+        assert_eq!(export.max_arkham_reward_per_proof, config.max_arkham_reward_per_proof);
+        assert_eq!(export.premium_reward_boost_bps, config.premium_reward_boost_bps);
+        assert_eq!(export.oracle_authority, config.oracle_authority);
+        assert_eq!(export.reputation_updater, config.reputation_updater);
+        assert_eq!(export.oracle_required, config.oracle_required);
+        assert_eq!(export.idle_escrow_yield_enabled, config.idle_escrow_yield_enabled);
+        assert_eq!(export.require_strict_tiers, config.require_strict_tiers);
+        assert_eq!(export.arkham_minting_renounced, config.arkham_minting_renounced);
+        assert_eq!((export.reward_numerator, export.reward_denominator), (500_000_000, 5_120));
+    }
+
+    #[test]
+    fn test_tokens_per_5gb_equivalent() {
+        assert_eq!(tokens_per_5gb_equivalent(500_000_000, MB_PER_5GB), 500_000_000);
+        assert_eq!(tokens_per_5gb_equivalent(1, 1), 5_120);
+        assert_eq!(tokens_per_5gb_equivalent(1, 3), 1_706); // 1706.67 rounds down
+        assert_eq!(tokens_per_5gb_equivalent(u64::MAX, 1), u64::MAX);
+    }
+
+    #[test]
+    fn test_validate_fee_bounds() {
+        assert!(validate_fee_bounds(0, PROTOCOL_FEE_CEILING_BPS).is_ok());
+        assert!(validate_fee_bounds(200, 200).is_ok());
+
+        // Inverted, or a ceiling above the hard limit
+        assert_eq!(validate_fee_bounds(500, 100).unwrap_err(), ArkhamErrorCode::InvalidFeeBounds.into());
+        assert_eq!(
+            validate_fee_bounds(0, PROTOCOL_FEE_CEILING_BPS + 1).unwrap_err(),
+            ArkhamErrorCode::InvalidFeeBounds.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_tier_multipliers() {
+        assert!(validate_tier_multipliers(&[10000, 12000, 15000]).is_ok());
+        assert!(validate_tier_multipliers(&[10000, 10000, 10000]).is_ok());
+
+        // Bronze above Gold inverts incentives
+        assert!(validate_tier_multipliers(&[15000, 12000, 10000]).is_err());
+        assert!(validate_tier_multipliers(&[10000, 15000, 12000]).is_err());
+
+        // Above the 5x cap
+        assert!(validate_tier_multipliers(&[10000, 12000, 50001]).is_err());
+
+        // Zero would make a tier's connections free; one basis point is the floor
+        assert_eq!(
+            validate_tier_multipliers(&[0, 12000, 15000]).unwrap_err(),
+            ArkhamErrorCode::ZeroTierMultiplier.into()
+        );
+        assert_eq!(
+            validate_tier_multipliers(&[0, 0, 0]).unwrap_err(),
+            ArkhamErrorCode::ZeroTierMultiplier.into()
+        );
+        assert!(validate_tier_multipliers(&[1, 1, 1]).is_ok());
+    }
+
+    #[test]
+    fn test_renounce_minting_is_authority_gated_and_blocks_later_mints() {
+        let mut config = test_protocol_config();
+        assert!(ensure_arkham_mintable(&config).is_ok());
+
+        // Only the protocol authority may renounce
+        assert_eq!(
+            renounce_arkham_minting(&mut config, &Pubkey::new_unique()).unwrap_err(),
+            ArkhamErrorCode::UnauthorizedAdminAction.into()
+        );
+        assert!(!config.arkham_minting_renounced);
+
+        let authority = config.authority;
+        renounce_arkham_minting(&mut config, &authority).unwrap();
+        assert!(config.arkham_minting_renounced);
+
+        // Claims and ARKHAM subsidy grants now fail cleanly, and renouncing is one-shot
+        assert_eq!(ensure_arkham_mintable(&config).unwrap_err(), ArkhamErrorCode::ArkhamMintingRenounced.into());
+        assert_eq!(
+            renounce_arkham_minting(&mut config, &authority).unwrap_err(),
+            ArkhamErrorCode::ArkhamMintingRenounced.into()
+        );
+    }
+
+    #[test]
+    fn test_set_reputation_updater_reports_old_and_new() {
+        let mut config = crate::state::test_protocol_config();
+        let (authority, original) = (config.authority, config.reputation_updater);
+        let replacement = Pubkey::new_unique();
+
+        let event = replace_reputation_updater(&mut config, authority, replacement);
+        assert_eq!(config.reputation_updater, replacement);
+        assert_eq!((event.authority, event.old, event.new), (authority, original, replacement));
+
+        // Indexers decode the same old/new pair from the emitted event data
+        let decoded = ReputationUpdaterChanged::try_from_slice(&event.try_to_vec().unwrap()).unwrap();
+        assert_eq!((decoded.old, decoded.new), (original, replacement));
+
+        // A second change reports the previous replacement as the old value
+        let rotated = Pubkey::new_unique();
+        let event = replace_reputation_updater(&mut config, authority, rotated);
+        assert_eq!((event.old, event.new), (replacement, rotated));
+    }
+}
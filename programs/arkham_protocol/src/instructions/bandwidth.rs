@@ -42,17 +42,95 @@ pub fn verify_ed25519_signature_via_sysvar(
     );
 
     // Load the Ed25519Program instruction at the specified index
+    // A missing or non-Ed25519 instruction means the client skipped verification
     let ed25519_ix = load_instruction_at_checked(
         instruction_index as usize,
         instructions_sysvar,
-    ).map_err(|_| BandwidthError::Ed25519InstructionNotFound)?;
+    ).map_err(|_| BandwidthError::MissingSignatureVerification)?;
 
-    // Verify it's actually an Ed25519Program instruction
     require!(
         ed25519_ix.program_id == ed25519_program::ID,
-        BandwidthError::InvalidEd25519Instruction
+        BandwidthError::MissingSignatureVerification
     );
 
+    verify_ed25519_instruction_data(&ed25519_ix.data, message, signature, public_key)?;
+
+    // If we get here, the Ed25519Program instruction exists and matches our data
+    // The Ed25519Program already verified the signature cryptographically
+    Ok(())
+}
+
+/// Confirms an Ed25519Program instruction sits at each of `instruction_indices`
+///
+/// Run before any signature, key or message is compared, so a transaction that
+/// omits the verification instructions fails with MissingSignatureVerification
+/// regardless of what signature bytes it carries.
+pub fn ensure_signature_verification_present(
+    instructions_sysvar: &AccountInfo,
+    instruction_indices: &[u16],
+) -> Result<()> {
+    require!(
+        instructions_sysvar.key() == INSTRUCTIONS_SYSVAR_ID,
+        BandwidthError::InvalidInstructionsSysvar
+    );
+
+    for &index in instruction_indices {
+        let present = load_instruction_at_checked(index as usize, instructions_sysvar)
+            .is_ok_and(|ix| ix.program_id == ed25519_program::ID);
+        require!(present, BandwidthError::MissingSignatureVerification);
+    }
+
+    Ok(())
+}
+
+/// Byte offset of the `message_data_size` field in the Ed25519Program instruction header
+pub const ED25519_MESSAGE_DATA_SIZE_OFFSET: usize = 12;
+
+/// Where a single-signature Ed25519Program instruction keeps its signature, public key
+/// and message when they are inline, right after the 16-byte header
+pub const ED25519_INLINE_SIGNATURE_OFFSET: u16 = 16;
+pub const ED25519_INLINE_PUBLIC_KEY_OFFSET: u16 = ED25519_INLINE_SIGNATURE_OFFSET + 64;
+pub const ED25519_INLINE_MESSAGE_OFFSET: u16 = ED25519_INLINE_PUBLIC_KEY_OFFSET + 32;
+
+/// Reads the declared `message_data_size` from Ed25519Program instruction data
+pub fn ed25519_message_data_size(data: &[u8]) -> Option<u16> {
+    ed25519_header_field(data, ED25519_MESSAGE_DATA_SIZE_OFFSET)
+}
+
+fn ed25519_header_field(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+/// Whether Ed25519Program instruction data verifies exactly one signature whose
+/// signature, public key and message all sit inline in this instruction, as
+/// client::ed25519_instruction_data lays them out
+///
+/// The precompile verifies the bytes the header points at, so anything else could
+/// have it check different bytes than the fixed offsets callers compare.
+pub fn ed25519_has_inline_layout(data: &[u8]) -> bool {
+    let field = |offset| ed25519_header_field(data, offset);
+    data.first() == Some(&1)
+        && field(2) == Some(ED25519_INLINE_SIGNATURE_OFFSET)
+        && field(4) == Some(u16::MAX)
+        && field(6) == Some(ED25519_INLINE_PUBLIC_KEY_OFFSET)
+        && field(8) == Some(u16::MAX)
+        && field(10) == Some(ED25519_INLINE_MESSAGE_OFFSET)
+        && field(14) == Some(u16::MAX)
+}
+
+/// Checks that Ed25519Program instruction data carries exactly our signature,
+/// public key and message
+///
+/// The header must point at the inline signature, public key and message, and
+/// the declared message size must equal `message.len()`; otherwise a longer
+/// signed message would match on its prefix.
+pub fn verify_ed25519_instruction_data(
+    data: &[u8],
+    message: &[u8],
+    signature: &[u8; 64],
+    public_key: &Pubkey,
+) -> Result<()> {
     // Parse the Ed25519Program instruction data
     // Format: [num_signatures: u8, padding: u8, signature_offset: u16, 
     //          signature_instruction_index: u16, public_key_offset: u16,
@@ -60,14 +138,22 @@ pub fn verify_ed25519_signature_via_sysvar(
     //          message_data_size: u16, message_instruction_index: u16,
     //          ...signature(64), ...pubkey(32), ...message]
     
-    let data = &ed25519_ix.data;
     require!(
         data.len() >= 2 + 5*2 + 64 + 32 + message.len(),
         BandwidthError::InvalidEd25519Data
     );
 
+    // The precompile must have verified the same bytes compared below
+    require!(ed25519_has_inline_layout(data), BandwidthError::UnexpectedEd25519Layout);
+
+    // The signed message must be exactly as long as ours
+    require!(
+        ed25519_message_data_size(data) == Some(message.len() as u16),
+        BandwidthError::MessageLengthMismatch
+    );
+
     // Extract signature from instruction data (starts at byte 16)
-    let sig_start = 16;
+    let sig_start = ED25519_INLINE_SIGNATURE_OFFSET as usize;
     let sig_end = sig_start + 64;
     let ix_signature = &data[sig_start..sig_end];
     
@@ -103,8 +189,6 @@ pub fn verify_ed25519_signature_via_sysvar(
         BandwidthError::MessageMismatch
     );
 
-    // If we get here, the Ed25519Program instruction exists and matches our data
-    // The Ed25519Program already verified the signature cryptographically
     Ok(())
 }
 
@@ -120,7 +204,7 @@ pub fn verify_ed25519_signature_via_sysvar(
 /// * `current_instruction_index` - The current instruction's index in the transaction
 /// 
 /// # Expected Transaction Layout
-/// ```
+/// ```text
 /// Instruction 0: Ed25519Program (verify Seeker signature)
 /// Instruction 1: Ed25519Program (verify Warden signature)
 /// Instruction 2: ArkhamProtocol::submit_bandwidth_proof (this instruction)
@@ -138,6 +222,9 @@ pub fn verify_dual_signatures(
     
     // Note: We can't use negative indices, so we need to know the current instruction index
     // For now, we'll assume instructions 0 and 1 are the Ed25519 verifications
+
+    // Both verification instructions must be present before either is inspected
+    ensure_signature_verification_present(instructions_sysvar, &[0, 1])?;
     
     verify_ed25519_signature_via_sysvar(
         instructions_sysvar,
@@ -196,17 +283,45 @@ pub fn create_proof_message(
     hash.to_bytes().to_vec()
 }
 
+/// Creates the message both parties sign for a quick_session proof
+///
+/// A quick session opens and ends its connection within the instruction, so the
+/// Connection PDA alone can't tell sessions apart. The message also commits to the
+/// session's connection_id, which the seeker's connection_nonce makes single-use.
+///
+/// # Arguments
+/// * `connection_pubkey` - The seeker/warden Connection PDA
+/// * `connection_id` - derive_connection_id for the seeker's current connection_nonce
+/// * `mb_consumed` - Amount of bandwidth in megabytes
+/// * `timestamp` - Unix timestamp of the proof
+pub fn create_quick_session_proof_message(
+    connection_pubkey: &Pubkey,
+    connection_id: u64,
+    mb_consumed: u64,
+    timestamp: i64,
+) -> Vec<u8> {
+    let mut message = Vec::new();
+    message.extend_from_slice(&connection_pubkey.to_bytes());
+    message.extend_from_slice(&connection_id.to_le_bytes());
+    message.extend_from_slice(&mb_consumed.to_le_bytes());
+    message.extend_from_slice(&timestamp.to_le_bytes());
+
+    keccak::hash(&message).to_bytes().to_vec()
+}
+
 /// Validates a bandwidth proof against expected constraints
 /// 
 /// Checks that:
 /// - Bandwidth amount is reasonable (not zero, not impossibly large)
 /// - Timestamp is recent (within last hour)
+/// - Timestamp falls inside the session (not before the connection started)
 /// - Signatures are present and correct length
 /// 
 /// # Arguments
 /// * `mb_consumed` - Amount of bandwidth claimed
 /// * `timestamp` - When the bandwidth was measured
 /// * `current_timestamp` - Current blockchain time
+/// * `session_started_at` - When the connection started
 /// * `seeker_signature` - Seeker's signature bytes
 /// * `warden_signature` - Warden's signature bytes
 /// 
@@ -216,6 +331,7 @@ pub fn validate_bandwidth_proof(
     mb_consumed: u64,
     timestamp: i64,
     current_timestamp: i64,
+    session_started_at: i64,
     seeker_signature: &[u8; 64],
     warden_signature: &[u8; 64],
 ) -> Result<()> {
@@ -240,6 +356,12 @@ pub fn validate_bandwidth_proof(
         age >= 0 && age <= MAX_PROOF_AGE,
         BandwidthError::ProofTooOld
     );
+
+    // Bandwidth measured before the connection started can't belong to this session
+    require!(
+        timestamp >= session_started_at,
+        BandwidthError::ProofOutsideSession
+    );
     
     // 3. Validate signatures are not empty (basic sanity check)
     require!(
@@ -305,6 +427,30 @@ pub fn hash_bandwidth_proof(
     hash.to_bytes()
 }
 
+/// Builds single-signature Ed25519Program instruction data with everything inline
+#[cfg(test)]
+pub(crate) fn ed25519_ix_data(signature: &[u8; 64], public_key: &Pubkey, message: &[u8]) -> Vec<u8> {
+    crate::client::ed25519_instruction_data(signature, public_key, message)
+}
+
+/// Serializes account-less instructions in the instructions sysvar layout
+#[cfg(test)]
+pub(crate) fn instructions_sysvar_data(instructions: &[(Pubkey, Vec<u8>)]) -> Vec<u8> {
+    let mut data = (instructions.len() as u16).to_le_bytes().to_vec();
+    let offsets_at = data.len();
+    data.resize(offsets_at + 2 * instructions.len(), 0);
+    for (i, (program_id, ix_data)) in instructions.iter().enumerate() {
+        let offset = (data.len() as u16).to_le_bytes();
+        data[offsets_at + 2 * i..offsets_at + 2 * i + 2].copy_from_slice(&offset);
+        data.extend_from_slice(&0u16.to_le_bytes()); // no accounts
+        data.extend_from_slice(program_id.as_ref());
+        data.extend_from_slice(&(ix_data.len() as u16).to_le_bytes());
+        data.extend_from_slice(ix_data);
+    }
+    data.extend_from_slice(&0u16.to_le_bytes()); // current instruction index
+    data
+}
+
 // Custom error codes specific to bandwidth validation
 #[error_code]
 pub enum BandwidthError {
@@ -319,6 +465,9 @@ pub enum BandwidthError {
     
     #[msg("Proof is too old and cannot be accepted")]
     ProofTooOld,
+
+    #[msg("Proof timestamp predates the connection's start")]
+    ProofOutsideSession,
     
     #[msg("Invalid or empty signature provided")]
     InvalidSignature,
@@ -347,6 +496,15 @@ pub enum BandwidthError {
     
     #[msg("Message in Ed25519 instruction doesn't match expected message")]
     MessageMismatch,
+
+    #[msg("Message size declared in Ed25519 instruction doesn't match expected message length")]
+    MessageLengthMismatch,
+
+    #[msg("Transaction is missing the Ed25519Program signature verification instructions")]
+    MissingSignatureVerification,
+
+    #[msg("Ed25519 instruction offsets don't point at its inline signature, public key and message")]
+    UnexpectedEd25519Layout,
 }
 
 #[cfg(test)]
@@ -367,6 +525,158 @@ mod tests {
         assert_ne!(message, message3);
     }
     
+    #[test]
+    fn test_validate_bandwidth_proof_session_window() {
+        let sig = [1u8; 64];
+        let started_at = 1_000_000i64;
+        let now = started_at + 600;
+
+        // Measured inside the session
+        assert!(validate_bandwidth_proof(100, started_at + 300, now, started_at, &sig, &sig).is_ok());
+        assert!(validate_bandwidth_proof(100, started_at, now, started_at, &sig, &sig).is_ok());
+
+        // Recent in absolute terms, but from before the connection started
+        assert_eq!(
+            validate_bandwidth_proof(100, started_at - 60, now, started_at, &sig, &sig).unwrap_err(),
+            BandwidthError::ProofOutsideSession.into()
+        );
+
+        // Future-dated
+        assert!(validate_bandwidth_proof(100, now + 1, now, started_at, &sig, &sig).is_err());
+    }
+
+    #[test]
+    fn test_verify_ed25519_instruction_data_message_length() {
+        let signature = [7u8; 64];
+        let public_key = Pubkey::new_unique();
+        let message = create_proof_message(&Pubkey::new_unique(), 100, 1_700_000_000);
+
+        let exact = ed25519_ix_data(&signature, &public_key, &message);
+        assert_eq!(ed25519_message_data_size(&exact), Some(32));
+        assert!(verify_ed25519_instruction_data(&exact, &message, &signature, &public_key).is_ok());
+
+        // Signed message is our hash plus trailing bytes: the prefix matches, the size doesn't
+        let mut longer_message = message.clone();
+        longer_message.extend_from_slice(b"extra");
+        let longer = ed25519_ix_data(&signature, &public_key, &longer_message);
+        assert_eq!(
+            verify_ed25519_instruction_data(&longer, &message, &signature, &public_key).unwrap_err(),
+            BandwidthError::MessageLengthMismatch.into()
+        );
+
+        // Shorter signed message
+        let shorter = ed25519_ix_data(&signature, &public_key, &message[..16]);
+        assert!(verify_ed25519_instruction_data(&shorter, &message, &signature, &public_key).is_err());
+    }
+
+    #[test]
+    fn test_verify_ed25519_instruction_data_requires_inline_layout() {
+        let signature = [7u8; 64];
+        let public_key = Pubkey::new_unique();
+        let message = create_proof_message(&Pubkey::new_unique(), 100, 1_700_000_000);
+        let exact = ed25519_ix_data(&signature, &public_key, &message);
+        assert!(ed25519_has_inline_layout(&exact));
+
+        // Each header field pointing anywhere else: signature count, then the
+        // signature, public key and message offsets and their instruction indices
+        for (offset, value) in [
+            (0usize, 2u16),
+            (2, ED25519_INLINE_SIGNATURE_OFFSET + 1),
+            (4, 0),
+            (6, ED25519_INLINE_SIGNATURE_OFFSET),
+            (8, 1),
+            (10, ED25519_INLINE_PUBLIC_KEY_OFFSET),
+            (14, 0),
+        ] {
+            let mut crafted = exact.clone();
+            if offset == 0 {
+                crafted[0] = value as u8;
+            } else {
+                crafted[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+            }
+            assert!(!ed25519_has_inline_layout(&crafted), "header byte {offset}");
+            assert_eq!(
+                verify_ed25519_instruction_data(&crafted, &message, &signature, &public_key).unwrap_err(),
+                BandwidthError::UnexpectedEd25519Layout.into(),
+                "header byte {offset}"
+            );
+        }
+    }
+
+    /// Runs verify_dual_signatures against a sysvar holding `instructions`
+    fn verify_dual_with(
+        instructions: &[(Pubkey, Vec<u8>)],
+        message: &[u8],
+        seeker_signature: &[u8; 64],
+        seeker: &Pubkey,
+        warden_signature: &[u8; 64],
+        warden: &Pubkey,
+    ) -> Result<()> {
+        let mut lamports = 0u64;
+        let mut data = instructions_sysvar_data(instructions);
+        let owner = Pubkey::default();
+        let sysvar = AccountInfo::new(
+            &INSTRUCTIONS_SYSVAR_ID, false, false, &mut lamports, &mut data, &owner, false, 0,
+        );
+        verify_dual_signatures(&sysvar, message, seeker_signature, seeker, warden_signature, warden)
+    }
+
+    #[test]
+    fn test_verify_dual_signatures_missing_ed25519_instructions() {
+        let message = create_proof_message(&Pubkey::new_unique(), 100, 1_700_000_000);
+        let (seeker, warden) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let zero = [0u8; 64];
+
+        // No instructions ahead of ours at all
+        assert_eq!(
+            verify_dual_with(&[], &message, &zero, &seeker, &zero, &warden).unwrap_err(),
+            BandwidthError::MissingSignatureVerification.into()
+        );
+
+        // Seeker verification present, warden verification missing
+        let seeker_ix = (ed25519_program::ID, ed25519_ix_data(&zero, &seeker, &message));
+        assert_eq!(
+            verify_dual_with(std::slice::from_ref(&seeker_ix), &message, &zero, &seeker, &zero, &warden).unwrap_err(),
+            BandwidthError::MissingSignatureVerification.into()
+        );
+
+        // Second slot holds some other program's instruction
+        let other_ix = (Pubkey::new_unique(), ed25519_ix_data(&zero, &warden, &message));
+        assert_eq!(
+            verify_dual_with(&[seeker_ix, other_ix], &message, &zero, &seeker, &zero, &warden).unwrap_err(),
+            BandwidthError::MissingSignatureVerification.into()
+        );
+    }
+
+    #[test]
+    fn test_verify_dual_signatures_mismatched_ed25519_instructions() {
+        let message = create_proof_message(&Pubkey::new_unique(), 100, 1_700_000_000);
+        let (seeker, warden) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (seeker_sig, warden_sig) = ([3u8; 64], [4u8; 64]);
+        let ixs = |seeker_ix_sig: &[u8; 64], warden_ix_key: &Pubkey| {
+            vec![
+                (ed25519_program::ID, ed25519_ix_data(seeker_ix_sig, &seeker, &message)),
+                (ed25519_program::ID, ed25519_ix_data(&warden_sig, warden_ix_key, &message)),
+            ]
+        };
+
+        assert!(verify_dual_with(&ixs(&seeker_sig, &warden), &message, &seeker_sig, &seeker, &warden_sig, &warden).is_ok());
+
+        // Present, but verifying a different signature
+        assert_eq!(
+            verify_dual_with(&ixs(&[9u8; 64], &warden), &message, &seeker_sig, &seeker, &warden_sig, &warden)
+                .unwrap_err(),
+            BandwidthError::SignatureMismatch.into()
+        );
+
+        // Present, but verifying against a different key
+        assert_eq!(
+            verify_dual_with(&ixs(&seeker_sig, &seeker), &message, &seeker_sig, &seeker, &warden_sig, &warden)
+                .unwrap_err(),
+            BandwidthError::PublicKeyMismatch.into()
+        );
+    }
+
     #[test]
     fn test_calculate_expected_bandwidth() {
         let proofs = vec![100, 110, 105, 95, 100];
@@ -0,0 +1,721 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_lang::Discriminator;
+use crate::state::{BandwidthProof, Connection, ProtocolConfig, Seeker, Warden, PROTOCOL_CONFIG_SPACE, SEEKER_SPACE, WARDEN_SPACE};
+use crate::instructions::admin::{
+    DEFAULT_BOOTSTRAP_REPUTATION_CAP, DEFAULT_INACTIVITY_TIMEOUT, DEFAULT_MAX_CONNECTIONS_PER_SEEKER,
+    DEFAULT_MAX_TOTAL_PROOFS, DEFAULT_MAX_UPTIME_REPORT_AGE, DEFAULT_MIN_CONNECTIONS_FOR_FULL_REPUTATION,
+    DEFAULT_MIN_REPUTATION_UPDATE_INTERVAL, DEFAULT_PREMIUM_POOL_SIZE, PROTOCOL_FEE_CEILING_BPS,
+};
+use crate::instructions::payments::MB_PER_5GB;
+use crate::instructions::staking::ORACLE_MESSAGE_V1;
+use crate::ArkhamErrorCode;
+
+/// Byte offset of `authority`, the first field of every migratable account
+pub const AUTHORITY_OFFSET: usize = 8;
+/// Byte offset of Warden.peer_id's 4-byte Borsh length prefix (after discriminator + authority)
+pub const WARDEN_PEER_ID_OFFSET: usize = 40;
+/// Byte offset of ProtocolConfig.oracle_authority (after authority, treasury, arkham_token_mint)
+pub const PROTOCOL_CONFIG_ORACLE_AUTHORITY_OFFSET: usize = 104;
+
+/// Size start_connection allocated before bandwidth_proofs became a fixed ring:
+/// a Borsh Vec of up to 10 proofs followed by the escrow fields
+pub const LEGACY_CONNECTION_SPACE: usize = 8 + 32 + 32 + 8 + 8 + 8 + 4 + (10 * (8 + 8 + 64 + 64)) + 8 + 8 + 8 + 2;
+
+const MIN_WARDEN_DATA_LEN: usize = 200;
+const MIN_PEER_ID_LEN: usize = 40;
+const MAX_PEER_ID_LEN: usize = 60;
+
+/// Account types migrate_account can rewrite
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum MigratableAccount {
+    Warden,
+    ProtocolConfig,
+    Seeker,
+}
+
+impl MigratableAccount {
+    pub fn discriminator(&self) -> &'static [u8] {
+        match self {
+            Self::Warden => Warden::DISCRIMINATOR,
+            Self::ProtocolConfig => ProtocolConfig::DISCRIMINATOR,
+            Self::Seeker => Seeker::DISCRIMINATOR,
+        }
+    }
+
+    /// The PDA an account of this type owned by `authority` lives at
+    pub fn expected_address(&self, authority: &Pubkey) -> Pubkey {
+        match self {
+            Self::Warden => Pubkey::find_program_address(&[b"warden", authority.as_ref()], &crate::ID).0,
+            Self::ProtocolConfig => Pubkey::find_program_address(&[b"protocol_config"], &crate::ID).0,
+            Self::Seeker => Pubkey::find_program_address(&[b"seeker", authority.as_ref()], &crate::ID).0,
+        }
+    }
+}
+
+/// Inputs individual migration steps may need; steps ignore fields they don't use
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
+pub struct MigrationParams {
+    pub new_oracle_authority: Option<Pubkey>,
+    pub usdc_mint: Option<Pubkey>,
+    pub usdt_mint: Option<Pubkey>,
+}
+
+/// A registered schema migration, applied to raw account bytes in place
+pub struct Migration {
+    pub account_type: MigratableAccount,
+    pub target_version: u8,
+    pub description: &'static str,
+    /// Size the account is grown to (zero-filled, rent paid by the authority) before
+    /// `apply` runs; None leaves the size alone
+    pub space: Option<usize>,
+    /// Rewrites the account data; returns whether any byte changed
+    pub apply: fn(&mut [u8], &MigrationParams) -> Result<bool>,
+}
+
+/// Every known migration. A new migration is one entry here plus its step function;
+/// each step must be idempotent so re-running it on a migrated account is a no-op.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        account_type: MigratableAccount::Warden,
+        target_version: 2,
+        description: "repair the peer_id Borsh length prefix",
+        space: None,
+        apply: warden_v2_fix_peer_id_prefix,
+    },
+    Migration {
+        account_type: MigratableAccount::Warden,
+        target_version: 3,
+        description: "grow to WARDEN_SPACE for the recent_connections ring",
+        space: Some(WARDEN_SPACE),
+        apply: resize_only,
+    },
+    Migration {
+        account_type: MigratableAccount::ProtocolConfig,
+        target_version: 2,
+        description: "set oracle_authority on configs created before it existed",
+        space: None,
+        apply: protocol_config_v2_set_oracle_authority,
+    },
+    Migration {
+        account_type: MigratableAccount::ProtocolConfig,
+        target_version: 3,
+        description: "grow to PROTOCOL_CONFIG_SPACE and write initialize_protocol_config's defaults",
+        space: Some(PROTOCOL_CONFIG_SPACE),
+        apply: protocol_config_v3_write_defaults,
+    },
+    Migration {
+        account_type: MigratableAccount::Seeker,
+        target_version: 2,
+        description: "grow to SEEKER_SPACE for connection_history and the later fields",
+        space: Some(SEEKER_SPACE),
+        apply: resize_only,
+    },
+];
+
+/// Migrates an account to `target_version` using the registered step
+/// The account is handled as raw bytes so layouts that no longer deserialize can be
+/// repaired. Only the authority stored in the account may migrate it.
+pub fn migrate_account_handler(
+    ctx: Context<MigrateAccount>,
+    account_type: MigratableAccount,
+    target_version: u8,
+    params: MigrationParams,
+) -> Result<()> {
+    let account = &ctx.accounts.account;
+    let authority = ctx.accounts.authority.key();
+
+    require_keys_eq!(
+        account.key(),
+        account_type.expected_address(&authority),
+        ArkhamErrorCode::MigrationAccountMismatch
+    );
+
+    // Grow first, so the step sees the account at its new size; verified before anyone pays
+    let migration = find_migration(&account_type, target_version)?;
+    verify_migratable_account(&account.try_borrow_data()?, &account_type, &authority)?;
+    let grown = match migration_growth(account.data_len(), migration.space) {
+        Some(space) => {
+            let top_up = Rent::get()?.minimum_balance(space).saturating_sub(account.lamports());
+            if top_up > 0 {
+                let cpi_context = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: account.to_account_info(),
+                    },
+                );
+                system_program::transfer(cpi_context, top_up)?;
+            }
+            account.resize(space)?;
+            true
+        }
+        None => false,
+    };
+
+    let mut data = account.try_borrow_mut_data()?;
+    let changed = run_migration(&mut data, &account_type, target_version, &authority, &params)? || grown;
+
+    emit!(AccountMigrated {
+        account: account.key(),
+        account_type,
+        target_version,
+        authority,
+        changed,
+    });
+
+    Ok(())
+}
+
+/// Looks up the registered migration for an account type and target version
+pub fn find_migration(account_type: &MigratableAccount, target_version: u8) -> Result<&'static Migration> {
+    MIGRATIONS
+        .iter()
+        .find(|m| &m.account_type == account_type && m.target_version == target_version)
+        .ok_or(ArkhamErrorCode::UnknownMigration.into())
+}
+
+/// The size an account of `current_len` bytes must grow to for a step of `space`, if any
+/// Steps never shrink an account
+pub fn migration_growth(current_len: usize, space: Option<usize>) -> Option<usize> {
+    space.filter(|&space| space > current_len)
+}
+
+/// Checks the discriminator and that `signer` is the authority stored at AUTHORITY_OFFSET
+pub fn verify_migratable_account(
+    data: &[u8],
+    account_type: &MigratableAccount,
+    signer: &Pubkey,
+) -> Result<()> {
+    require!(
+        data.len() >= AUTHORITY_OFFSET + 32 && data[..8] == *account_type.discriminator(),
+        ArkhamErrorCode::MigrationAccountMismatch
+    );
+
+    let stored_authority = Pubkey::try_from(&data[AUTHORITY_OFFSET..AUTHORITY_OFFSET + 32])
+        .map_err(|_| ArkhamErrorCode::MigrationAccountMismatch)?;
+    require_keys_eq!(stored_authority, *signer, ArkhamErrorCode::UnauthorizedMigration);
+
+    Ok(())
+}
+
+/// Verifies the account and applies the registered step to its raw bytes
+/// Returns whether the account changed
+pub fn run_migration(
+    data: &mut [u8],
+    account_type: &MigratableAccount,
+    target_version: u8,
+    signer: &Pubkey,
+    params: &MigrationParams,
+) -> Result<bool> {
+    let migration = find_migration(account_type, target_version)?;
+    verify_migratable_account(data, account_type, signer)?;
+
+    msg!("Migrating {:?} to v{}: {}", account_type, target_version, migration.description);
+    (migration.apply)(data, params)
+}
+
+/// migrate_warden_peer_id's account checks, reported with the errors it always returned:
+/// InvalidPeerId for something that isn't a Warden, UnauthorizedWardenUpdate for a
+/// Warden the signer doesn't own
+pub fn verify_peer_id_migration_account(data: &[u8], signer: &Pubkey) -> Result<()> {
+    require!(
+        data.len() >= MIN_WARDEN_DATA_LEN && data[..8] == *Warden::DISCRIMINATOR,
+        ArkhamErrorCode::InvalidPeerId
+    );
+
+    let stored_authority = Pubkey::try_from(&data[AUTHORITY_OFFSET..AUTHORITY_OFFSET + 32])
+        .map_err(|_| ArkhamErrorCode::UnauthorizedWardenUpdate)?;
+    require_keys_eq!(stored_authority, *signer, ArkhamErrorCode::UnauthorizedWardenUpdate);
+
+    Ok(())
+}
+
+/// The fields of a pre-ring-buffer Connection that admin_refund_legacy_connection books
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LegacyConnection {
+    pub seeker: Pubkey,
+    pub warden: Pubkey,
+    pub bandwidth_consumed: u64, // in megabytes
+    pub amount_escrowed: u64, // in lamports
+    pub amount_paid: u64, // in lamports
+}
+
+/// Connection as it was laid out before the proof ring, up to the last field the
+/// allocation always had room for
+#[derive(AnchorDeserialize)]
+struct LegacyConnectionLayout {
+    seeker: Pubkey,
+    warden: Pubkey,
+    _started_at: i64,
+    _last_proof_at: i64,
+    bandwidth_consumed: u64,
+    _bandwidth_proofs: Vec<BandwidthProof>,
+    amount_escrowed: u64,
+    amount_paid: u64,
+}
+
+/// Decodes a Connection still in the pre-ring-buffer layout, which no longer
+/// deserializes as a Connection. Current connections are larger than
+/// LEGACY_CONNECTION_SPACE and are rejected.
+pub fn decode_legacy_connection(data: &[u8]) -> Result<LegacyConnection> {
+    require!(
+        data.len() == LEGACY_CONNECTION_SPACE && data[..8] == *Connection::DISCRIMINATOR,
+        ArkhamErrorCode::MigrationAccountMismatch
+    );
+
+    let layout = LegacyConnectionLayout::deserialize(&mut &data[8..])
+        .map_err(|_| ArkhamErrorCode::MigrationAccountMismatch)?;
+    Ok(LegacyConnection {
+        seeker: layout.seeker,
+        warden: layout.warden,
+        bandwidth_consumed: layout.bandwidth_consumed,
+        amount_escrowed: layout.amount_escrowed,
+        amount_paid: layout.amount_paid,
+    })
+}
+
+/// Reads Warden.peer_id's stored length prefix
+pub fn warden_peer_id_prefix(data: &[u8]) -> Option<u32> {
+    data.get(WARDEN_PEER_ID_OFFSET..WARDEN_PEER_ID_OFFSET + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Finds the length of the libp2p PeerId ("12D3KooW...", 40-60 bytes) at the start of `bytes`
+/// First by the StakeToken variant (0, 1 or 2) that follows the string, then by trying
+/// common PeerId lengths
+pub fn detect_peer_id_length(bytes: &[u8]) -> Option<usize> {
+    let is_peer_id = |len: usize| {
+        bytes
+            .get(..len)
+            .and_then(|candidate| std::str::from_utf8(candidate).ok())
+            .is_some_and(|peer_id| {
+                peer_id.starts_with("12D3KooW")
+                    && (MIN_PEER_ID_LEN..=MAX_PEER_ID_LEN).contains(&peer_id.len())
+            })
+    };
+
+    (MIN_PEER_ID_LEN..MAX_PEER_ID_LEN)
+        .find(|&len| bytes.get(len).is_some_and(|&next| next <= 2) && is_peer_id(len))
+        .or_else(|| {
+            [44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 42, 43, 54, 55, 56]
+                .into_iter()
+                .find(|&len| is_peer_id(len))
+        })
+}
+
+/// Warden v2: rewrites a peer_id length prefix that disagrees with the stored PeerId
+fn warden_v2_fix_peer_id_prefix(data: &mut [u8], _params: &MigrationParams) -> Result<bool> {
+    require!(data.len() >= MIN_WARDEN_DATA_LEN, ArkhamErrorCode::InvalidPeerId);
+
+    let stored_length = warden_peer_id_prefix(data).ok_or(ArkhamErrorCode::InvalidPeerId)?;
+    let actual_length = detect_peer_id_length(&data[WARDEN_PEER_ID_OFFSET + 4..])
+        .ok_or(ArkhamErrorCode::InvalidPeerId)? as u32;
+
+    if stored_length == actual_length {
+        return Ok(false);
+    }
+
+    data[WARDEN_PEER_ID_OFFSET..WARDEN_PEER_ID_OFFSET + 4].copy_from_slice(&actual_length.to_le_bytes());
+    Ok(true)
+}
+
+/// ProtocolConfig v2: sets oracle_authority, which older configs never initialized
+fn protocol_config_v2_set_oracle_authority(data: &mut [u8], params: &MigrationParams) -> Result<bool> {
+    let oracle_authority = params.new_oracle_authority
+        .ok_or(ArkhamErrorCode::MissingMigrationParam)?;
+
+    let range = PROTOCOL_CONFIG_ORACLE_AUTHORITY_OFFSET..PROTOCOL_CONFIG_ORACLE_AUTHORITY_OFFSET + 32;
+    require!(data.len() >= range.end, ArkhamErrorCode::MigrationAccountMismatch);
+
+    if data[range.clone()] == oracle_authority.to_bytes() {
+        return Ok(false);
+    }
+
+    data[range].copy_from_slice(oracle_authority.as_ref());
+    Ok(true)
+}
+
+/// ProtocolConfig v3: fills the fields appended after reputation_updater with the values
+/// initialize_protocol_config writes, since zero is not "off" for all of them
+/// A zero max_connections_per_seeker marks fields never written: init sets it and
+/// updates reject zero, so configs that already have it are left alone
+fn protocol_config_v3_write_defaults(data: &mut [u8], params: &MigrationParams) -> Result<bool> {
+    let mut config = ProtocolConfig::try_deserialize(&mut &data[..])
+        .map_err(|_| ArkhamErrorCode::MigrationAccountMismatch)?;
+    if config.max_connections_per_seeker != 0 {
+        return Ok(false);
+    }
+
+    config.usdc_mint = params.usdc_mint.ok_or(ArkhamErrorCode::MissingMigrationParam)?;
+    config.usdt_mint = params.usdt_mint.ok_or(ArkhamErrorCode::MissingMigrationParam)?;
+    require!(
+        config.usdc_mint != Pubkey::default()
+            && config.usdt_mint != Pubkey::default()
+            && config.usdc_mint != config.usdt_mint,
+        ArkhamErrorCode::InvalidStakeMints
+    );
+
+    config.oracle_required = true;
+    config.max_connections_per_seeker = DEFAULT_MAX_CONNECTIONS_PER_SEEKER;
+    config.min_connections_for_full_reputation = DEFAULT_MIN_CONNECTIONS_FOR_FULL_REPUTATION;
+    config.bootstrap_reputation_cap = DEFAULT_BOOTSTRAP_REPUTATION_CAP;
+    // The existing fee stays settable; bounds narrower than it would reject every fee update
+    config.min_fee_bps = 0;
+    config.max_fee_bps = config.protocol_fee_bps.max(PROTOCOL_FEE_CEILING_BPS);
+    config.premium_pool_size = DEFAULT_PREMIUM_POOL_SIZE;
+    config.min_reputation_update_interval = DEFAULT_MIN_REPUTATION_UPDATE_INTERVAL;
+    config.reward_numerator = config.tokens_per_5gb;
+    config.reward_denominator = MB_PER_5GB;
+    config.max_uptime_report_age = DEFAULT_MAX_UPTIME_REPORT_AGE;
+    config.min_oracle_message_version = ORACLE_MESSAGE_V1;
+    config.max_total_proofs = DEFAULT_MAX_TOTAL_PROOFS;
+    config.inactivity_timeout = DEFAULT_INACTIVITY_TIMEOUT;
+
+    config.try_serialize(&mut &mut data[..])?;
+    Ok(true)
+}
+
+/// Steps whose whole migration is the resize migrate_account performs before them
+fn resize_only(_data: &mut [u8], _params: &MigrationParams) -> Result<bool> {
+    Ok(false)
+}
+
+// Account contexts:
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: Not deserialized so stale layouts can be repaired; the registry verifies
+    /// its discriminator and stored authority, and the handler its address
+    #[account(mut, owner = crate::ID)]
+    pub account: AccountInfo<'info>,
+
+    /// Must be the authority stored in the account; pays the rent of any growth
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// Events:
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub account_type: MigratableAccount,
+    pub target_version: u8,
+    pub authority: Pubkey,
+    pub changed: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{test_protocol_config, test_warden, GeoPremium, RateUnit};
+
+    /// Serializes `account` with its discriminator, padded to `space` bytes
+    fn account_bytes<T: AccountSerialize>(account: &T, space: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.resize(space, 0);
+        data
+    }
+
+    #[test]
+    fn test_discriminators_match_legacy_constants() {
+        // The value migrate_warden_peer_id used to hard-code
+        assert_eq!(Warden::DISCRIMINATOR, [73, 11, 82, 46, 202, 0, 179, 133]);
+    }
+
+    #[test]
+    fn test_hardcoded_offsets_match_canonical_layout() {
+        let mut warden = test_warden();
+        warden.peer_id = format!("12D3KooW{}", "B".repeat(44));
+        let data = account_bytes(&warden, 8 + 512);
+
+        // authority sits right after the discriminator, at bytes 8..40
+        assert_eq!(AUTHORITY_OFFSET, 8);
+        assert_eq!(&data[AUTHORITY_OFFSET..AUTHORITY_OFFSET + 32], warden.authority.as_ref());
+        assert_eq!(WARDEN_PEER_ID_OFFSET, AUTHORITY_OFFSET + 32);
+
+        // peer_id's Borsh length prefix is at byte 40, followed by the string bytes
+        assert_eq!(warden_peer_id_prefix(&data), Some(warden.peer_id.len() as u32));
+        let peer_id_start = WARDEN_PEER_ID_OFFSET + 4;
+        assert_eq!(&data[peer_id_start..peer_id_start + warden.peer_id.len()], warden.peer_id.as_bytes());
+
+        // ProtocolConfig.oracle_authority follows authority, treasury and arkham_token_mint
+        let config = crate::state::test_protocol_config();
+        let data = account_bytes(&config, 8 + 512);
+        assert_eq!(
+            &data[PROTOCOL_CONFIG_ORACLE_AUTHORITY_OFFSET..PROTOCOL_CONFIG_ORACLE_AUTHORITY_OFFSET + 32],
+            config.oracle_authority.as_ref()
+        );
+    }
+
+    #[test]
+    fn test_migrate_v1_warden_to_v2() {
+        let mut warden = test_warden();
+        let authority = warden.authority;
+        warden.peer_id = format!("12D3KooW{}", "A".repeat(44));
+        warden.stake_amount = 1_000;
+        let mut data = account_bytes(&warden, 8 + 512);
+
+        // v1: the length prefix disagrees with the stored PeerId
+        data[WARDEN_PEER_ID_OFFSET..WARDEN_PEER_ID_OFFSET + 4].copy_from_slice(&60u32.to_le_bytes());
+        assert!(Warden::try_deserialize(&mut &data[..]).map(|w| w.peer_id != warden.peer_id).unwrap_or(true));
+
+        let changed = run_migration(
+            &mut data, &MigratableAccount::Warden, 2, &authority, &MigrationParams::default(),
+        ).unwrap();
+        assert!(changed);
+
+        let migrated = Warden::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.peer_id, warden.peer_id);
+        assert_eq!(migrated.stake_amount, 1_000);
+
+        // Re-running is a no-op
+        let changed = run_migration(
+            &mut data, &MigratableAccount::Warden, 2, &authority, &MigrationParams::default(),
+        ).unwrap();
+        assert!(!changed);
+    }
+
+    #[test]
+    fn test_migrate_v1_protocol_config() {
+        let mut config = test_protocol_config();
+        let authority = config.authority;
+        config.oracle_authority = Pubkey::default();
+        let mut data = account_bytes(&config, 1024);
+
+        let new_oracle_authority = Pubkey::new_unique();
+        let params = MigrationParams { new_oracle_authority: Some(new_oracle_authority), ..Default::default() };
+        let changed = run_migration(
+            &mut data, &MigratableAccount::ProtocolConfig, 2, &authority, &params,
+        ).unwrap();
+        assert!(changed);
+
+        let migrated = ProtocolConfig::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.oracle_authority, new_oracle_authority);
+        assert_eq!(migrated.treasury, config.treasury);
+        assert_eq!(migrated.tier_thresholds, config.tier_thresholds);
+
+        // This step needs its parameter
+        assert_eq!(
+            run_migration(
+                &mut data, &MigratableAccount::ProtocolConfig, 2, &authority, &MigrationParams::default(),
+            ).unwrap_err(),
+            ArkhamErrorCode::MissingMigrationParam.into()
+        );
+    }
+
+    #[test]
+    fn test_resize_steps_grow_accounts_that_predate_appended_fields() {
+        // A config from before failure_decay_window, a seeker from before connection_nonce,
+        // and a warden created at 8 + 512, before recent_connections
+        let config = test_protocol_config();
+        let mut config_data = Vec::new();
+        config.try_serialize(&mut config_data).unwrap();
+        config_data.truncate(config_data.len() - 8);
+        let seeker = crate::state::test_seeker();
+        let mut seeker_data = Vec::new();
+        seeker.try_serialize(&mut seeker_data).unwrap();
+        seeker_data.truncate(seeker_data.len() - 8);
+        assert!(ProtocolConfig::try_deserialize(&mut &config_data[..]).is_err());
+        assert!(Seeker::try_deserialize(&mut &seeker_data[..]).is_err());
+        let warden = test_warden();
+        let mut warden_data = account_bytes(&warden, 8 + 512);
+
+        for (data, account_type, target_version, authority, space) in [
+            (&mut config_data, MigratableAccount::ProtocolConfig, 3, config.authority, PROTOCOL_CONFIG_SPACE),
+            (&mut seeker_data, MigratableAccount::Seeker, 2, seeker.authority, SEEKER_SPACE),
+            (&mut warden_data, MigratableAccount::Warden, 3, warden.authority, WARDEN_SPACE),
+        ] {
+            // As migrate_account does it: grow zero-filled, then run the step
+            let migration = find_migration(&account_type, target_version).unwrap();
+            assert_eq!(migration.space, Some(space));
+            let grown_to = migration_growth(data.len(), migration.space).unwrap();
+            data.resize(grown_to, 0);
+            run_migration(data, &account_type, target_version, &authority, &MigrationParams::default()).unwrap();
+
+            // Already at size: nothing to grow, and the step stays a no-op
+            assert_eq!(migration_growth(data.len(), migration.space), None);
+            assert!(!run_migration(data, &account_type, target_version, &authority, &MigrationParams::default()).unwrap());
+        }
+
+        let migrated = ProtocolConfig::try_deserialize(&mut &config_data[..]).unwrap();
+        assert_eq!((migrated.failure_decay_window, migrated.treasury), (0, config.treasury));
+        let migrated = Seeker::try_deserialize(&mut &seeker_data[..]).unwrap();
+        assert_eq!((migrated.connection_nonce, migrated.authority), (0, seeker.authority));
+        assert_eq!(warden_data.len(), WARDEN_SPACE);
+        assert_eq!(Warden::try_deserialize(&mut &warden_data[..]).unwrap().authority, warden.authority);
+
+        // Steps never shrink an account
+        assert_eq!(migration_growth(SEEKER_SPACE + 1, Some(SEEKER_SPACE)), None);
+    }
+
+    #[test]
+    fn test_protocol_config_v3_writes_init_defaults() {
+        // A baseline config: fields through reputation_updater, in the space the baseline
+        // initialize_protocol_config allocated (room for 10 geo premiums)
+        let mut config = test_protocol_config();
+        let authority = config.authority;
+        config.protocol_fee_bps = 4_000;
+        config.geo_premiums = vec![GeoPremium { region_code: 3, premium_bps: 500 }];
+        let mut data = Vec::new();
+        config.try_serialize(&mut data).unwrap();
+        let baseline_space = 8 + 32 * 4 + 8 + 2 + 8 * 3 + 2 * 3 + 8 + 4 + 10 * 3 + 32;
+        data.truncate(8 + 32 * 4 + 8 + 2 + 8 * 3 + 2 * 3 + 8 + 4 + 3 + 32);
+        data.resize(baseline_space, 0);
+
+        let migration = find_migration(&MigratableAccount::ProtocolConfig, 3).unwrap();
+        data.resize(migration_growth(data.len(), migration.space).unwrap(), 0);
+
+        // The mints have no sensible default, so the step needs both
+        assert_eq!(
+            run_migration(&mut data, &MigratableAccount::ProtocolConfig, 3, &authority, &MigrationParams::default())
+                .unwrap_err(),
+            ArkhamErrorCode::MissingMigrationParam.into()
+        );
+        let (usdc_mint, usdt_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let params = MigrationParams { usdc_mint: Some(usdc_mint), usdt_mint: Some(usdt_mint), ..Default::default() };
+        assert!(run_migration(&mut data, &MigratableAccount::ProtocolConfig, 3, &authority, &params).unwrap());
+
+        let migrated = ProtocolConfig::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.geo_premiums.len(), 1);
+        assert_eq!(migrated.reputation_updater, config.reputation_updater);
+        assert_eq!((migrated.usdc_mint, migrated.usdt_mint), (usdc_mint, usdt_mint));
+        assert!(migrated.oracle_required);
+        assert_eq!(migrated.fallback_prices, [0; 3]);
+        assert_eq!(migrated.max_connections_per_seeker, DEFAULT_MAX_CONNECTIONS_PER_SEEKER);
+        assert_eq!(migrated.min_connections_for_full_reputation, DEFAULT_MIN_CONNECTIONS_FOR_FULL_REPUTATION);
+        assert_eq!(migrated.bootstrap_reputation_cap, DEFAULT_BOOTSTRAP_REPUTATION_CAP);
+        assert_eq!((migrated.min_fee_bps, migrated.max_fee_bps), (0, 4_000));
+        assert_eq!(migrated.premium_pool_size, DEFAULT_PREMIUM_POOL_SIZE);
+        assert_eq!(migrated.min_reputation_update_interval, DEFAULT_MIN_REPUTATION_UPDATE_INTERVAL);
+        assert_eq!((migrated.reward_numerator, migrated.reward_denominator), (config.tokens_per_5gb, MB_PER_5GB));
+        assert_eq!(migrated.max_uptime_report_age, DEFAULT_MAX_UPTIME_REPORT_AGE);
+        assert_eq!(migrated.min_oracle_message_version, ORACLE_MESSAGE_V1);
+        assert_eq!(migrated.max_total_proofs, DEFAULT_MAX_TOTAL_PROOFS);
+        assert_eq!(migrated.inactivity_timeout, DEFAULT_INACTIVITY_TIMEOUT);
+        // Fields where zero is off stay off
+        assert_eq!(migrated.rate_unit, RateUnit::PerMb);
+        assert!(!migrated.idle_escrow_yield_enabled && !migrated.buffer_refund_enabled && !migrated.proof_fee_enabled);
+        assert_eq!((migrated.registration_fee, migrated.max_arkham_supply, migrated.insurance_bps), (0, 0, 0));
+
+        // Fee bounds follow the ceiling when the existing fee sits under it
+        let mut low_fee = migrated.clone();
+        low_fee.max_connections_per_seeker = 0;
+        low_fee.protocol_fee_bps = 200;
+        let mut low_fee_data = account_bytes(&low_fee, PROTOCOL_CONFIG_SPACE);
+        assert!(run_migration(&mut low_fee_data, &MigratableAccount::ProtocolConfig, 3, &authority, &params).unwrap());
+        assert_eq!(ProtocolConfig::try_deserialize(&mut &low_fee_data[..]).unwrap().max_fee_bps, PROTOCOL_FEE_CEILING_BPS);
+
+        // Re-running leaves values governance has since changed alone
+        let mut tuned = migrated;
+        tuned.inactivity_timeout = 0;
+        let mut data = account_bytes(&tuned, PROTOCOL_CONFIG_SPACE);
+        assert!(!run_migration(&mut data, &MigratableAccount::ProtocolConfig, 3, &authority, &MigrationParams::default()).unwrap());
+        assert_eq!(ProtocolConfig::try_deserialize(&mut &data[..]).unwrap().inactivity_timeout, 0);
+    }
+
+    #[test]
+    fn test_decode_legacy_connection() {
+        let (seeker, warden) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let proof = BandwidthProof { timestamp: 70, mb_consumed: 40, seeker_signature: [1; 64], warden_signature: [2; 64] };
+
+        // As the baseline start_connection allocated and serialized it: two proofs in the Vec
+        let mut data = Connection::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(seeker.as_ref());
+        data.extend_from_slice(warden.as_ref());
+        for value in [10i64, 70] {
+            data.extend_from_slice(&value.to_le_bytes()); // started_at, last_proof_at
+        }
+        data.extend_from_slice(&80u64.to_le_bytes()); // bandwidth_consumed
+        vec![proof.clone(), proof].serialize(&mut data).unwrap();
+        for value in [500_000u64, 80_000, 1_000] {
+            data.extend_from_slice(&value.to_le_bytes()); // amount_escrowed, amount_paid, rate_per_mb
+        }
+        data.extend_from_slice(&10_000u16.to_le_bytes()); // warden_multiplier
+        data.extend_from_slice(&0u64.to_le_bytes()); // tokens_per_5gb
+        data.resize(LEGACY_CONNECTION_SPACE, 0);
+        assert!(Connection::try_deserialize(&mut &data[..]).is_err());
+
+        let legacy = decode_legacy_connection(&data).unwrap();
+        assert_eq!(
+            legacy,
+            LegacyConnection { seeker, warden, bandwidth_consumed: 80, amount_escrowed: 500_000, amount_paid: 80_000 }
+        );
+
+        // admin_refund_legacy_connection releases the stranded escrow and counters
+        let mut seeker_state = Seeker { active_connections: 1, committed_escrow: 420_000, ..crate::state::test_seeker() };
+        let mut warden_state = Warden { active_connections: 1, ..test_warden() };
+        let refunded = crate::instructions::admin::book_admin_refund(
+            &mut seeker_state, &mut warden_state, legacy.amount_escrowed, legacy.amount_paid, legacy.bandwidth_consumed,
+        ).unwrap();
+        assert_eq!(refunded, 420_000);
+        assert_eq!((seeker_state.escrow_balance, seeker_state.committed_escrow), (420_000, 0));
+        assert_eq!((seeker_state.total_spent, seeker_state.total_bandwidth_consumed), (80_000, 80));
+        assert_eq!((seeker_state.active_connections, warden_state.active_connections), (0, 0));
+
+        // A connection in the current layout goes through admin_refund_connection instead
+        let mut current = Vec::new();
+        crate::state::test_connection().try_serialize(&mut current).unwrap();
+        assert!(current.len() > LEGACY_CONNECTION_SPACE);
+        assert_eq!(decode_legacy_connection(&current).unwrap_err(), ArkhamErrorCode::MigrationAccountMismatch.into());
+        let mut not_a_connection = data.clone();
+        not_a_connection[..8].copy_from_slice(Warden::DISCRIMINATOR);
+        assert_eq!(decode_legacy_connection(&not_a_connection).unwrap_err(), ArkhamErrorCode::MigrationAccountMismatch.into());
+    }
+
+    #[test]
+    fn test_peer_id_migration_keeps_its_legacy_errors() {
+        let mut warden = test_warden();
+        warden.peer_id = format!("12D3KooW{}", "A".repeat(44));
+        let data = account_bytes(&warden, 8 + 512);
+
+        assert!(verify_peer_id_migration_account(&data, &warden.authority).is_ok());
+        assert_eq!(
+            verify_peer_id_migration_account(&data, &Pubkey::new_unique()).unwrap_err(),
+            ArkhamErrorCode::UnauthorizedWardenUpdate.into()
+        );
+
+        // Not a warden, or too short to be one
+        let config_data = account_bytes(&test_protocol_config(), 1024);
+        let authority = test_protocol_config().authority;
+        assert_eq!(
+            verify_peer_id_migration_account(&config_data, &authority).unwrap_err(),
+            ArkhamErrorCode::InvalidPeerId.into()
+        );
+        assert_eq!(
+            verify_peer_id_migration_account(&data[..100], &warden.authority).unwrap_err(),
+            ArkhamErrorCode::InvalidPeerId.into()
+        );
+    }
+
+    #[test]
+    fn test_migration_guards() {
+        let warden = test_warden();
+        let mut data = account_bytes(&warden, 8 + 512);
+        let params = MigrationParams::default();
+
+        // Only the stored authority may migrate
+        assert_eq!(
+            run_migration(&mut data, &MigratableAccount::Warden, 2, &Pubkey::new_unique(), &params)
+                .unwrap_err(),
+            ArkhamErrorCode::UnauthorizedMigration.into()
+        );
+
+        // The discriminator must match the declared account type
+        assert_eq!(
+            run_migration(&mut data, &MigratableAccount::ProtocolConfig, 2, &warden.authority, &params)
+                .unwrap_err(),
+            ArkhamErrorCode::MigrationAccountMismatch.into()
+        );
+
+        // Unregistered versions are rejected
+        assert_eq!(
+            run_migration(&mut data, &MigratableAccount::Warden, 9, &warden.authority, &params)
+                .unwrap_err(),
+            ArkhamErrorCode::UnknownMigration.into()
+        );
+    }
+}
@@ -3,9 +3,11 @@ pub mod payments;
 pub mod bandwidth;
 pub mod reputation;
 pub mod admin;
+pub mod migration;
 
 pub use staking::*;
 pub use payments::*;
 pub use bandwidth::*;
 pub use reputation::*;
-pub use admin::*;
\ No newline at end of file
+pub use admin::*;
+pub use migration::*;
\ No newline at end of file
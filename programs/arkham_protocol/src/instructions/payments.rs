@@ -1,10 +1,17 @@
 use anchor_lang::{prelude::*, system_program};
 use anchor_spl::token::{self, Mint, Token, TokenAccount, MintTo};
 use anchor_lang::solana_program::sysvar::instructions::ID as INSTRUCTIONS_SYSVAR_ID;
-use crate::state::{Seeker, Warden, Connection, ProtocolConfig, BandwidthProof};
+use crate::state::{
+    Seeker, Warden, Connection, ProtocolConfig, BandwidthProof, PendingProofEvent, RateUnit,
+    TopEarners, EarnerEntry, TOP_EARNERS_CAPACITY, SessionRecord, SEEKER_HISTORY_CAPACITY,
+    MAX_ALLOWED_WARDENS, SEEKER_SPACE, PROOF_HISTORY_CAPACITY, RegionIndex, MINT_AUTHORITY_SEEDS, mint_authority_signer_seeds,
+    WardenSessionRecord, SessionOutcome, WARDEN_HISTORY_CAPACITY, WARDEN_SPACE,
+};
 use crate::ArkhamErrorCode;
 
 const ESCROW_BUFFER_BPS: u16 = 1000; // 10% buffer
+pub const MAX_PREMIUM_REWARD_BOOST_BPS: u16 = 10000; // Premium wardens earn at most 2x ARKHAM
+pub const MB_PER_5GB: u64 = 5120; // Denominator of the legacy tokens_per_5gb reward rate
 
 /// Deposits SOL into a Seeker's escrow account
 pub fn deposit_escrow_handler(
@@ -38,7 +45,7 @@ pub fn deposit_escrow_handler(
     // Now that the transfer is done, we can mutably borrow seeker to update its balance.
     seeker.escrow_balance = seeker.escrow_balance
         .checked_add(amount)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
 
     emit!(EscrowDeposited {
         authority: seeker.authority,
@@ -49,611 +56,4716 @@ pub fn deposit_escrow_handler(
     Ok(())
 }
 
-/// Starts a new VPN connection between a Seeker and Warden
-pub fn start_connection_handler(
-    ctx: Context<StartConnection>,
-    estimated_mb: u64,
-) -> Result<()> {
-    let config = &ctx.accounts.protocol_config;
-    let warden = &mut ctx.accounts.warden;
-    let seeker = &mut ctx.accounts.seeker;
-    let connection = &mut ctx.accounts.connection;
-    let clock = Clock::get()?;
+/// Withdraws SOL from a Seeker's spendable escrow back to its authority
+///
+/// Dust policy: the Seeker account never drops below its rent-exempt minimum
+/// (plus any escrow locked in active connections). Withdrawing the entire
+/// balance of an idle Seeker closes the account instead, returning the rent
+/// deposit too, so no sub-rent remainder is ever left behind to be purged.
+pub fn withdraw_escrow_handler(ctx: Context<WithdrawEscrow>, amount: u64) -> Result<()> {
+    let seeker_info = ctx.accounts.seeker.to_account_info();
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(seeker_info.data_len());
 
-    // 1. Calculate effective rate per MB
-    let base_rate = config.base_rate_per_mb;
-    
-    // Get geographic premium for this warden's region
-    let geo_premium_bps = config.geo_premiums
-        .iter()
-        .find(|gp| gp.region_code == warden.region_code)
-        .map(|gp| gp.premium_bps)
-        .unwrap_or(0);
+    let withdrawal = plan_escrow_withdrawal(
+        seeker_info.lamports(),
+        rent_exempt_minimum,
+        ctx.accounts.seeker.escrow_balance,
+        ctx.accounts.seeker.committed_escrow,
+        ctx.accounts.seeker.staked_escrow,
+        ctx.accounts.seeker.active_connections,
+        amount,
+    )?;
 
-    // Get tier multiplier
-    let tier_multiplier = match warden.tier {
-        crate::state::Tier::Bronze => config.tier_multipliers[0],
-        crate::state::Tier::Silver => config.tier_multipliers[1],
-        crate::state::Tier::Gold => config.tier_multipliers[2],
-    };
+    if withdrawal.close {
+        ctx.accounts.seeker.close(ctx.accounts.authority.to_account_info())?;
+    } else {
+        move_lamports(&seeker_info, &ctx.accounts.authority.to_account_info(), withdrawal.lamports)?;
+        ctx.accounts.seeker.escrow_balance = ctx.accounts.seeker.escrow_balance
+            .checked_sub(amount)
+            .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    }
 
-    // Calculate: rate = base * (1 + geo_premium) * tier_multiplier
-    // All in basis points for precision
-    let rate_with_geo = (base_rate as u128)
-        .checked_mul((10000 + geo_premium_bps) as u128)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
-        .checked_div(10000)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u64;
+    emit!(EscrowWithdrawn {
+        authority: ctx.accounts.authority.key(),
+        amount,
+        lamports_returned: withdrawal.lamports,
+        closed: withdrawal.close,
+    });
 
-    let rate_per_mb = (rate_with_geo as u128)
-        .checked_mul(tier_multiplier as u128)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
-        .checked_div(10000)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u64;
+    Ok(())
+}
 
-    // 2. Calculate total escrow needed (with 10% buffer)
-    let base_escrow = (estimated_mb as u128)
-        .checked_mul(rate_per_mb as u128)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u64;
-    
-    let escrow_needed = (base_escrow as u128)
-        .checked_mul((10000 + ESCROW_BUFFER_BPS) as u128)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
-        .checked_div(10000)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u64;
+/// Outcome of an escrow withdrawal under the dust policy
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EscrowWithdrawal {
+    pub lamports: u64, // paid to the authority, including the rent deposit when closing
+    pub close: bool,
+}
+
+/// Applies the withdraw_escrow dust policy
+/// A full withdrawal by a Seeker with nothing locked or parked closes the account;
+/// anything else must leave the rent-exempt minimum and committed escrow behind
+pub fn plan_escrow_withdrawal(
+    account_lamports: u64,
+    rent_exempt_minimum: u64,
+    escrow_balance: u64,
+    committed_escrow: u64,
+    staked_escrow: u64,
+    active_connections: u8,
+    amount: u64,
+) -> Result<EscrowWithdrawal> {
+    require!(amount > 0, ArkhamErrorCode::InvalidEscrowAmount);
+    require!(amount <= escrow_balance, ArkhamErrorCode::InsufficientEscrow);
+
+    let idle = active_connections == 0 && committed_escrow == 0 && staked_escrow == 0;
+    if amount == escrow_balance && idle {
+        return Ok(EscrowWithdrawal { lamports: account_lamports, close: true });
+    }
+
+    let floor = rent_exempt_minimum
+        .checked_add(committed_escrow)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    let remaining = account_lamports
+        .checked_sub(amount)
+        .ok_or(ArkhamErrorCode::EscrowWithdrawalBelowRentExemption)?;
+    require!(remaining >= floor, ArkhamErrorCode::EscrowWithdrawalBelowRentExemption);
+
+    Ok(EscrowWithdrawal { lamports: amount, close: false })
+}
+
+/// Parks part of a Seeker's idle escrow in the protocol's escrow yield vault
+/// Deployments route the vault's lamports into their configured yield venue off-chain
+pub fn stake_idle_escrow_handler(ctx: Context<StakeIdleEscrow>, amount: u64) -> Result<()> {
+    let seeker = &mut ctx.accounts.seeker;
 
-    // 3. Verify sufficient escrow balance
     require!(
-        seeker.escrow_balance >= escrow_needed,
-        ArkhamErrorCode::InsufficientEscrow
-    );
-
-    // 4. Initialize Connection account
-    connection.seeker = seeker.key();
-    connection.warden = warden.key();
-    connection.started_at = clock.unix_timestamp;
-    connection.last_proof_at = clock.unix_timestamp;
-    connection.bandwidth_consumed = 0;
-    connection.bandwidth_proofs = Vec::new();
-    connection.amount_escrowed = escrow_needed;
-    connection.amount_paid = 0;
-    connection.rate_per_mb = rate_per_mb;
-    connection.warden_multiplier = tier_multiplier;
-
-    // 5. Move funds from seeker escrow to connection escrow
-    seeker.escrow_balance = seeker.escrow_balance
-        .checked_sub(escrow_needed)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+        ctx.accounts.protocol_config.idle_escrow_yield_enabled,
+        ArkhamErrorCode::IdleEscrowYieldDisabled
+    );
 
-    // 6. Update active connection counters
-    seeker.active_connections = seeker.active_connections
-        .checked_add(1)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-    
-    warden.active_connections = warden.active_connections
-        .checked_add(1)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    let (escrow_balance, staked_escrow) =
+        park_idle_escrow(seeker.escrow_balance, seeker.staked_escrow, amount)?;
 
-    emit!(ConnectionStarted {
-        seeker: seeker.key(),
-        warden: warden.key(),
-        estimated_mb,
-        rate_per_mb,
-        escrow_amount: escrow_needed,
+    move_lamports(
+        &seeker.to_account_info(),
+        &ctx.accounts.escrow_yield_vault.to_account_info(),
+        amount,
+    )?;
+
+    seeker.escrow_balance = escrow_balance;
+    seeker.staked_escrow = staked_escrow;
+
+    emit!(IdleEscrowStaked {
+        authority: seeker.authority,
+        amount,
+        staked_escrow,
     });
 
     Ok(())
 }
 
-/// Submits a bandwidth proof and processes micropayment
-pub fn submit_bandwidth_proof_handler(
-    ctx: Context<SubmitBandwidthProof>,
-    mb_consumed: u64,
-    timestamp: i64,
-    seeker_signature: [u8; 64],
-    warden_signature: [u8; 64],
-) -> Result<()> {
-    // Get the connection key before we mutably borrow the connection
-    let connection_key = ctx.accounts.connection.key();
-    let warden_key = ctx.accounts.warden.key();
-    let seeker_key = ctx.accounts.seeker.key();
-    
-    let connection = &mut ctx.accounts.connection;
-    let warden = &mut ctx.accounts.warden;
-    let seeker = &ctx.accounts.seeker;
-    let config = &ctx.accounts.protocol_config;
-    let clock = Clock::get()?;
+/// Returns parked escrow from the yield vault to the Seeker's spendable escrow
+/// Seekers call this before start_connection when their escrow_balance is short
+pub fn unwind_staked_escrow_handler(ctx: Context<UnwindStakedEscrow>, amount: u64) -> Result<()> {
+    let seeker = &mut ctx.accounts.seeker;
 
-    // 1. Validate the proof using bandwidth module helpers
-    crate::instructions::bandwidth::validate_bandwidth_proof(
-        mb_consumed,
-        timestamp,
-        clock.unix_timestamp,
-        &seeker_signature,
-        &warden_signature,
-    )?;
+    let (escrow_balance, staked_escrow) =
+        unwind_idle_escrow(seeker.escrow_balance, seeker.staked_escrow, amount)?;
 
-    // 2. Create the deterministic message and verify Ed25519 signatures
-    let proof_message = crate::instructions::bandwidth::create_proof_message(
-        &connection_key,
-        mb_consumed,
-        timestamp,
-    );
-    
-    // REAL Ed25519 VERIFICATION using instruction introspection
-    crate::instructions::bandwidth::verify_dual_signatures(
-        &ctx.accounts.instructions_sysvar,
-        &proof_message,
-        &seeker_signature,
-        &seeker.authority,
-        &warden_signature,
-        &warden.authority,
-    )?;
+    let vault_seeds = &[b"escrow_yield_vault".as_ref(), &[ctx.bumps.escrow_yield_vault]];
+    let signer_seeds = &[&vault_seeds[..]];
 
-    // 3. Check for duplicate proofs (prevent replay attacks)
-    let proof_hash = crate::instructions::bandwidth::hash_bandwidth_proof(
-        &connection_key,
-        mb_consumed,
-        clock.unix_timestamp,
-        &seeker_signature,
-        &warden_signature,
+    let cpi_context = CpiContext::new_with_signer(
+        ctx.accounts.system_program.to_account_info(),
+        system_program::Transfer {
+            from: ctx.accounts.escrow_yield_vault.to_account_info(),
+            to: seeker.to_account_info(),
+        },
+        signer_seeds,
     );
-    
-    // Check if this proof hash already exists in our history
-    for existing_proof in &connection.bandwidth_proofs {
-        let existing_hash = crate::instructions::bandwidth::hash_bandwidth_proof(
-            &connection_key,
-            existing_proof.mb_consumed,
-            existing_proof.timestamp,
-            &existing_proof.seeker_signature,
-            &existing_proof.warden_signature,
-        );
-        
-        require!(
-            proof_hash != existing_hash,
-            crate::instructions::bandwidth::BandwidthError::InvalidSignature
-        );
-    }
+    system_program::transfer(cpi_context, amount)?;
 
-    // 4. Anomaly detection (optional - flag suspicious claims)
-    if connection.bandwidth_proofs.len() >= 3 {
-        let historical: Vec<u64> = connection.bandwidth_proofs
-            .iter()
-            .map(|p| p.mb_consumed)
-            .collect();
-        
-        let expected = crate::instructions::bandwidth::calculate_expected_bandwidth(&historical, 5);
-        
-        if crate::instructions::bandwidth::detect_bandwidth_anomaly(mb_consumed, expected, 3.0) {
-            msg!("Warning: Anomalous bandwidth detected. Expected: {}, Claimed: {}", expected, mb_consumed);
-            // Continue processing but log the warning for reputation system
-        }
-    }
+    seeker.escrow_balance = escrow_balance;
+    seeker.staked_escrow = staked_escrow;
 
-    // 5. Calculate payment amount
-    let payment_amount = (mb_consumed as u128)
-        .checked_mul(connection.rate_per_mb as u128)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u64;
+    emit!(StakedEscrowUnwound {
+        authority: seeker.authority,
+        amount,
+        staked_escrow,
+    });
 
-    // 6. Verify payment doesn't exceed available escrow
-    let new_total_paid = connection.amount_paid
-        .checked_add(payment_amount)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-    
-    require!(
-        new_total_paid <= connection.amount_escrowed,
-        ArkhamErrorCode::InsufficientConnectionEscrow
-    );
+    Ok(())
+}
 
-    // 7. Transfer payment to warden's pending claims
-    warden.pending_claims = warden.pending_claims
-        .checked_add(payment_amount)
+/// Moves lamports out of a program-owned account (e.g. a Seeker's escrow) into `to`
+/// The program may debit accounts it owns directly, without a system transfer CPI
+pub fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    **from.try_borrow_mut_lamports()? = from
+        .lamports()
+        .checked_sub(amount)
         .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-
-    // 8. Update connection bandwidth and payment tracking
-    connection.bandwidth_consumed = connection.bandwidth_consumed
-        .checked_add(mb_consumed)
+    **to.try_borrow_mut_lamports()? = to
+        .lamports()
+        .checked_add(amount)
         .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-    
-    connection.amount_paid = new_total_paid;
+    Ok(())
+}
 
-    // 9. Update warden statistics
-    warden.total_bandwidth_served = warden.total_bandwidth_served
-        .checked_add(mb_consumed)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-    
-    warden.total_earnings = warden.total_earnings
-        .checked_add(payment_amount)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+/// Moves `amount` from spendable escrow into the parked bucket
+/// Returns the new (escrow_balance, staked_escrow)
+pub fn park_idle_escrow(escrow_balance: u64, staked_escrow: u64, amount: u64) -> Result<(u64, u64)> {
+    require!(amount > 0, ArkhamErrorCode::InvalidEscrowAmount);
+    require!(escrow_balance >= amount, ArkhamErrorCode::InsufficientEscrow);
 
-    // 10. Calculate and add ARKHAM token allocation
-    let tokens_per_mb = config.tokens_per_5gb / 5120;
-    let arkham_earned = (mb_consumed as u128)
-        .checked_mul(tokens_per_mb as u128)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u64;
-    
-    warden.arkham_tokens_earned = warden.arkham_tokens_earned
-        .checked_add(arkham_earned)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    let escrow_balance = escrow_balance
+        .checked_sub(amount)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    let staked_escrow = staked_escrow
+        .checked_add(amount)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
 
-    // 11. Add proof to bandwidth_proofs vector (limit to last 10)
-    let proof = BandwidthProof {
-        timestamp: clock.unix_timestamp,
-        mb_consumed,
-        seeker_signature,
-        warden_signature,
-    };
+    Ok((escrow_balance, staked_escrow))
+}
 
-    if connection.bandwidth_proofs.len() >= 10 {
-        connection.bandwidth_proofs.remove(0);
-    }
-    connection.bandwidth_proofs.push(proof);
+/// Moves `amount` from the parked bucket back into spendable escrow
+/// Returns the new (escrow_balance, staked_escrow)
+pub fn unwind_idle_escrow(escrow_balance: u64, staked_escrow: u64, amount: u64) -> Result<(u64, u64)> {
+    require!(amount > 0, ArkhamErrorCode::InvalidEscrowAmount);
+    require!(staked_escrow >= amount, ArkhamErrorCode::InsufficientStakedEscrow);
 
-    // 12. Update last proof timestamp
-    connection.last_proof_at = clock.unix_timestamp;
+    let staked_escrow = staked_escrow
+        .checked_sub(amount)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    let escrow_balance = escrow_balance
+        .checked_add(amount)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
 
-    // 13. Update warden's last active timestamp
-    warden.last_active = clock.unix_timestamp;
+    Ok((escrow_balance, staked_escrow))
+}
 
-    emit!(BandwidthProofSubmitted {
-        connection: connection_key,
-        mb_consumed,
-        payment_amount,
-        arkham_earned,
+/// Sets or clears the wardens a Seeker is willing to connect through
+pub fn set_allowed_wardens_handler(
+    ctx: Context<SetAllowedWardens>,
+    allowed_wardens: Option<Vec<Pubkey>>,
+) -> Result<()> {
+    let seeker = &mut ctx.accounts.seeker;
+
+    if let Some(wardens) = &allowed_wardens {
+        require!(
+            wardens.len() <= MAX_ALLOWED_WARDENS,
+            ArkhamErrorCode::TooManyAllowedWardens
+        );
+    }
+
+    seeker.allowed_wardens = allowed_wardens;
+
+    emit!(AllowedWardensUpdated {
+        authority: seeker.authority,
+        allowed_wardens: seeker.allowed_wardens.clone(),
     });
 
     Ok(())
 }
 
-/// Ends a VPN connection and settles final amounts
-pub fn end_connection_handler(ctx: Context<EndConnection>) -> Result<()> {
-    let connection = &ctx.accounts.connection;
-    let warden = &mut ctx.accounts.warden;
+/// Registers the account that will hold the Seeker's private escrow
+/// Private deposits aren't live yet, so this is the only way the field is set;
+/// a different account can only be registered after the current one is cleared
+pub fn set_private_escrow_handler(ctx: Context<SetPrivateEscrow>, private_escrow: Pubkey) -> Result<()> {
     let seeker = &mut ctx.accounts.seeker;
+    assign_private_escrow(&mut seeker.private_escrow, private_escrow)?;
 
-    // 1. Calculate unused escrow
-    let unused_escrow = connection.amount_escrowed
-        .checked_sub(connection.amount_paid)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    emit!(PrivateEscrowUpdated {
+        authority: seeker.authority,
+        private_escrow: seeker.private_escrow,
+    });
 
-    // 2. Refund unused escrow to seeker
-    if unused_escrow > 0 {
-        seeker.escrow_balance = seeker.escrow_balance
-            .checked_add(unused_escrow)
-            .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-    }
+    Ok(())
+}
 
-    // 3. Update warden reputation (increment successful connections)
-    warden.successful_connections = warden.successful_connections
-        .checked_add(1)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+/// Unregisters the Seeker's private escrow account; `private_escrow` must name it
+/// No private balance is tracked yet, so there is nothing left to withdraw first
+pub fn clear_private_escrow_handler(ctx: Context<SetPrivateEscrow>, private_escrow: Pubkey) -> Result<()> {
+    let seeker = &mut ctx.accounts.seeker;
+    ensure_private_escrow_matches(&seeker.private_escrow, &private_escrow)?;
+    seeker.private_escrow = None;
 
-    // 4. Decrement active connection counters
-    seeker.active_connections = seeker.active_connections
-        .checked_sub(1)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-    
-    warden.active_connections = warden.active_connections
-        .checked_sub(1)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    emit!(PrivateEscrowUpdated {
+        authority: seeker.authority,
+        private_escrow: None,
+    });
 
-    // 5. Update seeker's total consumption and spending
-    seeker.total_bandwidth_consumed = seeker.total_bandwidth_consumed
-        .checked_add(connection.bandwidth_consumed)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-    
-    seeker.total_spent = seeker.total_spent
-        .checked_add(connection.amount_paid)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
 
-    emit!(ConnectionEnded {
-        seeker: seeker.key(),
-        warden: warden.key(),
-        bandwidth_consumed: connection.bandwidth_consumed,
-        total_paid: connection.amount_paid,
-        refunded: unused_escrow,
-    });
+/// Returns the Seeker's registered private escrow account, if any, as return data
+pub fn query_private_escrow_handler(ctx: Context<QueryPrivateEscrow>) -> Result<Option<Pubkey>> {
+    Ok(ctx.accounts.seeker.private_escrow)
+}
 
-    // Note: Connection account will be closed automatically via close constraint
+/// Sets `current` to `private_escrow` unless another account is already registered
+/// Re-registering the same account is a no-op
+pub fn assign_private_escrow(current: &mut Option<Pubkey>, private_escrow: Pubkey) -> Result<()> {
+    require!(
+        !matches!(current, Some(existing) if *existing != private_escrow),
+        ArkhamErrorCode::PrivateEscrowAlreadySet
+    );
+    *current = Some(private_escrow);
     Ok(())
 }
 
-/// Claims accumulated earnings for a Warden
-pub fn claim_earnings_handler(
-    ctx: Context<ClaimEarnings>,
-    use_private: bool,
-) -> Result<()> {
-    let warden = &mut ctx.accounts.warden;
-
-    // 1. Verify there are earnings to claim
-    require!(
-        warden.pending_claims > 0,
-        ArkhamErrorCode::NothingToClaim
-    );
+/// Rejects a private escrow operation naming an account other than the registered one
+pub fn ensure_private_escrow_matches(current: &Option<Pubkey>, supplied: &Pubkey) -> Result<()> {
+    let registered = current.ok_or(ArkhamErrorCode::PrivateEscrowNotSet)?;
+    require!(registered == *supplied, ArkhamErrorCode::PrivateEscrowMismatch);
+    Ok(())
+}
 
-    let amount = warden.pending_claims;
+/// Returns whether a seeker's allowlist permits connecting to `warden`
+/// An unset or empty allowlist places no restriction
+pub fn is_warden_allowed(allowed_wardens: &Option<Vec<Pubkey>>, warden: &Pubkey) -> bool {
+    match allowed_wardens {
+        Some(wardens) if !wardens.is_empty() => wardens.contains(warden),
+        _ => true,
+    }
+}
 
-    if use_private {
-        // TODO: Implement Elusiv CPI for private withdrawals
-        return err!(ArkhamErrorCode::PrivatePaymentsNotImplemented);
-    } else {
-        // Public claim: Transfer from protocol vault to warden's authority
-        let vault_seeds = &[b"sol_vault".as_ref(), &[ctx.bumps.sol_vault]];
-        let signer_seeds = &[&vault_seeds[..]];
+/// Clears a lapsed premium subscription so off-chain systems get a clean signal
+/// Permissionless keeper call; a no-op when premium is unset or still active
+pub fn expire_premium_handler(ctx: Context<ExpirePremium>) -> Result<()> {
+    let seeker = &mut ctx.accounts.seeker;
+    let clock = Clock::get()?;
 
-        let cpi_context = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            system_program::Transfer {
-                from: ctx.accounts.sol_vault.to_account_info(),
-                to: ctx.accounts.authority.to_account_info(),
-            },
-            signer_seeds,
-        );
-        system_program::transfer(cpi_context, amount)?;
+    if let Some(expired_at) = clear_expired_premium(&mut seeker.premium_expires_at, clock.unix_timestamp) {
+        emit!(PremiumExpired {
+            seeker: seeker.key(),
+            authority: seeker.authority,
+            expired_at,
+        });
     }
 
-    // 2. Reset pending claims
-    warden.pending_claims = 0;
+    Ok(())
+}
 
-    emit!(EarningsClaimed {
-        authority: warden.authority,
-        amount,
-        use_private,
-    });
+/// Whether a subscription expiring at `premium_expires_at` is active at `current_timestamp`
+/// Matches clear_expired_premium: a subscription lapses only once its expiry is in the past
+pub fn is_premium_active(premium_expires_at: Option<i64>, current_timestamp: i64) -> bool {
+    matches!(premium_expires_at, Some(expires_at) if expires_at >= current_timestamp)
+}
 
+/// Rejects a seeker without active premium when the Warden only accepts premium seekers
+pub fn ensure_seeker_admitted_by_warden(
+    premium_seekers_only: bool,
+    premium_expires_at: Option<i64>,
+    current_timestamp: i64,
+) -> Result<()> {
+    require!(
+        !premium_seekers_only || is_premium_active(premium_expires_at, current_timestamp),
+        ArkhamErrorCode::PremiumSeekersOnly
+    );
     Ok(())
 }
 
-/// Claims earned ARKHAM tokens
-pub fn claim_arkham_tokens_handler(ctx: Context<ClaimArkhamTokens>) -> Result<()> {
-    let warden = &mut ctx.accounts.warden;
-    let config = &ctx.accounts.protocol_config;
-    let amount = warden.arkham_tokens_earned;
+/// Clears `premium_expires_at` if it lies in the past, returning the cleared expiry
+pub fn clear_expired_premium(premium_expires_at: &mut Option<i64>, current_timestamp: i64) -> Option<i64> {
+    match *premium_expires_at {
+        Some(expires_at) if expires_at < current_timestamp => premium_expires_at.take(),
+        _ => None,
+    }
+}
 
-    // 1. Verify there are tokens to claim
+/// Starts a new VPN connection between a Seeker and Warden
+pub fn start_connection_handler(
+    ctx: Context<StartConnection>,
+    estimated_mb: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(ctx.accounts.seeker.to_account_info().data_len());
+
+    let connection = open_connection(
+        &ctx.accounts.protocol_config,
+        &mut ctx.accounts.warden,
+        &mut ctx.accounts.seeker,
+        ctx.accounts.region_index.as_deref().map(|region_index| &**region_index),
+        estimated_mb,
+        rent_exempt_minimum,
+        clock.unix_timestamp,
+    )?;
+    ctx.accounts.connection.set_inner(connection);
+
+    Ok(())
+}
+
+/// Validates and prices a new session, locks its escrow and bumps both parties'
+/// active connection counters. Shared by start_connection and quick_session.
+/// `started_at` is when the session began: now for start_connection, the proof
+/// timestamp for quick_session. Returns the Connection state to store; emits ConnectionStarted.
+fn open_connection(
+    config: &ProtocolConfig,
+    warden: &mut Account<Warden>,
+    seeker: &mut Account<Seeker>,
+    region_index: Option<&RegionIndex>,
+    estimated_mb: u64,
+    rent_exempt_minimum: u64,
+    started_at: i64,
+) -> Result<Connection> {
+    // 0. Reject self-dealing: one key must not sit on both sides of a connection
     require!(
-        amount > 0,
-        ArkhamErrorCode::NothingToClaim
+        seeker.key() != warden.key() && seeker.authority != warden.authority,
+        ArkhamErrorCode::SelfDealingNotAllowed
     );
 
-    // 2. Verify ARKHAM mint is initialized
+    // 0a. Wardens leaving the network don't take new connections
+    ensure_warden_accepting_connections(warden)?;
+    ensure_warden_available(&warden.availability, started_at)?;
+
+    // 0b. Cap concurrent sessions so a seeker's escrow isn't fragmented across many
+    ensure_seeker_below_capacity(seeker.active_connections, config.max_connections_per_seeker)?;
+
+    // 0c. Avoid routing through thinly served regions, once configured
+    if config.min_wardens_per_region > 0 {
+        let region_index = region_index.ok_or(ArkhamErrorCode::RegionIndexRequired)?;
+        ensure_region_has_min_wardens(
+            region_index.active_wardens[warden.region_code as usize],
+            config.min_wardens_per_region,
+        )?;
+    }
+
+    // 0d. Don't match wardens whose uptime is poor, however good their overall reputation
+    ensure_warden_meets_min_uptime(warden.uptime_percentage, config.min_uptime_for_matching)?;
+
+    // 0e. Respect the seeker's warden allowlist, if set
     require!(
-        config.arkham_token_mint != Pubkey::default(),
-        ArkhamErrorCode::TokenMintNotInitialized
+        is_warden_allowed(&seeker.allowed_wardens, &warden.key()),
+        ArkhamErrorCode::WardenNotAllowed
     );
 
-    // 3. Mint tokens to warden's token account using PDA authority
-    let authority_bump = ctx.bumps.mint_authority;
-    
-    let seeds = &[
-        b"arkham".as_ref(),
-        b"mint".as_ref(),
-        b"authority".as_ref(),
-        &[authority_bump]
-    ];
-    let signer_seeds = &[&seeds[..]];
+    // 0f. Premium-only wardens reserve their bandwidth for premium subscribers
+    ensure_seeker_admitted_by_warden(
+        warden.premium_seekers_only,
+        seeker.premium_expires_at,
+        started_at,
+    )?;
 
-    let cpi_accounts = MintTo {
-        mint: ctx.accounts.arkham_mint.to_account_info(),
-        to: ctx.accounts.warden_arkham_token_account.to_account_info(),
-        authority: ctx.accounts.mint_authority.to_account_info(),
+    // 1-2. Price the session: effective rate per MB and total escrow (with 10% buffer)
+    // estimate_session_cost quotes seekers exactly these figures ahead of time
+    let estimate = session_cost_estimate(config, warden, estimated_mb)?;
+    let rate_per_mb = estimate.rate_per_mb;
+    let escrow_needed = estimate.total_escrow;
+    let tier_multiplier = warden_tier_multiplier(config, warden);
+
+    // 3. The Seeker account itself holds the escrowed lamports; refuse to lock escrow
+    // the escrow_balance counter claims but the account doesn't actually hold
+    let seeker_info = seeker.to_account_info();
+    ensure_escrow_balance_backed(seeker_info.lamports(), rent_exempt_minimum, seeker.escrow_balance)?;
+
+    // 3a. Lock the escrow against the seeker's current balance. The check and the
+    // decrement happen together, so a second connection started in the same block
+    // sees the already-reduced balance
+    seeker.escrow_balance = lock_connection_escrow(seeker.escrow_balance, escrow_needed)?;
+
+    // 3b. Verify the seeker account's lamports back every active connection's escrow
+    let committed_escrow = commit_connection_escrow(
+        seeker_info.lamports(),
+        rent_exempt_minimum,
+        seeker.committed_escrow,
+        escrow_needed,
+    )?;
+
+    // 4. Record the locked escrow against the seeker's committed total
+    seeker.committed_escrow = committed_escrow;
+
+    // 5. Identify the session for indexers; the PDA is reused by the next session with this warden
+    let connection_id = next_connection_id(&seeker.key(), &mut seeker.connection_nonce)?;
+
+    // 6. Initialize Connection state
+    let (reward_numerator, reward_denominator) = reward_rate(config);
+    let connection = Connection {
+        seeker: seeker.key(),
+        warden: warden.key(),
+        started_at,
+        last_proof_at: started_at,
+        bandwidth_consumed: 0,
+        bandwidth_proofs: [BandwidthProof::EMPTY; PROOF_HISTORY_CAPACITY],
+        proofs_head: 0,
+        proofs_count: 0,
+        amount_escrowed: escrow_needed,
+        escrow_buffer: estimate.buffer,
+        amount_paid: 0,
+        rate_per_mb,
+        warden_multiplier: tier_multiplier,
+        tokens_per_5gb: crate::instructions::admin::tokens_per_5gb_equivalent(reward_numerator, reward_denominator),
+        delegated_max_mb: 0,
+        delegated_mb_settled: 0,
+        max_spend: 0,
+        uptime_at_start: warden.uptime_percentage,
+        sla_refunded: false,
+        pending_proof_event: PendingProofEvent {
+            last_emitted_at: started_at,
+            ..PendingProofEvent::default()
+        },
+        connection_id,
+        total_proofs: 0,
+        reward_numerator,
+        reward_denominator,
     };
 
-    let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
-    token::mint_to(cpi_context, amount)?;
+    // 7. Update active connection counters
+    seeker.active_connections = seeker.active_connections
+        .checked_add(1)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    
+    warden.active_connections = warden.active_connections
+        .checked_add(1)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
 
-    // 4. Reset earned tokens counter
-    warden.arkham_tokens_earned = 0;
+    emit!(connection_started_event(&connection, estimated_mb));
 
-    emit!(TokensClaimed {
-        authority: warden.authority,
-        amount,
-    });
+    Ok(connection)
+}
 
-    Ok(())
+/// ConnectionStarted for a freshly opened `connection`
+pub fn connection_started_event(connection: &Connection, estimated_mb: u64) -> ConnectionStarted {
+    ConnectionStarted {
+        connection_id: connection.connection_id,
+        seeker: connection.seeker,
+        warden: connection.warden,
+        estimated_mb,
+        rate_per_mb: connection.rate_per_mb,
+        escrow_amount: connection.amount_escrowed,
+    }
 }
 
-// Account contexts:
+/// BandwidthProofSubmitted reporting `batch`, the proofs settled on `connection`
+/// since its last report
+pub fn proof_batch_event(
+    connection_key: Pubkey,
+    connection: &Connection,
+    batch: &PendingProofEvent,
+) -> BandwidthProofSubmitted {
+    BandwidthProofSubmitted {
+        connection: connection_key,
+        connection_id: connection.connection_id,
+        proof_count: batch.proofs,
+        mb_consumed: batch.mb_consumed,
+        payment_amount: batch.payment_amount,
+        arkham_earned: batch.arkham_earned,
+    }
+}
 
-#[derive(Accounts)]
-pub struct DepositEscrow<'info> {
-    #[account(
-        init_if_needed,
-        payer = authority,
-        space = 8 + 32 + 8 + (1 + 32) + 8 + 8 + 1 + (1 + 8), // 107 bytes for Seeker struct
-        seeds = [b"seeker", authority.key().as_ref()],
-        bump
-    )]
-    pub seeker: Account<'info, Seeker>,
+/// ConnectionEnded for `connection`, with the unpaid escrow it refunds split
+/// into its buffer and unused-bandwidth parts
+pub fn connection_ended_event(connection: &Connection) -> Result<ConnectionEnded> {
+    let refunded = unused_connection_escrow(connection.amount_escrowed, connection.amount_paid)?;
+    let (buffer_refunded, unused_bandwidth_refunded) =
+        split_connection_refund(connection.amount_escrowed, connection.amount_paid, connection.escrow_buffer);
+    Ok(ConnectionEnded {
+        connection_id: connection.connection_id,
+        seeker: connection.seeker,
+        warden: connection.warden,
+        bandwidth_consumed: connection.bandwidth_consumed,
+        total_paid: connection.amount_paid,
+        refunded,
+        buffer_refunded,
+        unused_bandwidth_refunded,
+    })
+}
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+/// Stable identifier for the `nonce`-th connection started by `seeker`
+/// The first 8 bytes, little-endian, of sha256("connection_id" || seeker || nonce)
+pub fn derive_connection_id(seeker: &Pubkey, nonce: u64) -> u64 {
+    let hash = anchor_lang::solana_program::hash::hashv(&[
+        b"connection_id",
+        seeker.as_ref(),
+        &nonce.to_le_bytes(),
+    ]);
+    u64::from_le_bytes(hash.to_bytes()[..8].try_into().unwrap())
+}
 
-    pub system_program: Program<'info, System>,
+/// Assigns the connection_id for the seeker's next connection and advances its nonce
+pub fn next_connection_id(seeker: &Pubkey, connection_nonce: &mut u64) -> Result<u64> {
+    let connection_id = derive_connection_id(seeker, *connection_nonce);
+    *connection_nonce = connection_nonce
+        .checked_add(1)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    Ok(connection_id)
 }
 
-#[derive(Accounts)]
-pub struct StartConnection<'info> {
-    #[account(
-        init,
-        payer = seeker_authority,
-        space = 8 + 32 + 32 + 8 + 8 + 8 + 4 + (10 * (8 + 8 + 64 + 64)) + 8 + 8 + 8 + 2,
-        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
-        bump
-    )]
-    pub connection: Account<'info, Connection>,
+/// Rejects new connections to a Warden that is draining ahead of unstaking
+pub fn ensure_warden_accepting_connections(warden: &Warden) -> Result<()> {
+    require!(!warden.draining, ArkhamErrorCode::WardenDraining);
+    require!(!warden.suspended, ArkhamErrorCode::WardenSuspended);
+    Ok(())
+}
 
-    #[account(mut)]
-    pub seeker: Account<'info, Seeker>,
+/// Rejects new connections outside a Warden's weekly availability (None = always available)
+pub fn ensure_warden_available(availability: &Option<[u32; 7]>, current_timestamp: i64) -> Result<()> {
+    if let Some(schedule) = availability {
+        let (weekday, hour) = utc_weekday_and_hour(current_timestamp);
+        require!(schedule[weekday] & (1 << hour) != 0, ArkhamErrorCode::WardenUnavailable);
+    }
+    Ok(())
+}
 
-    #[account(mut)]
-    pub warden: Account<'info, Warden>,
+/// UTC weekday (0 = Monday) and hour of a unix timestamp; 1970-01-01 was a Thursday
+pub fn utc_weekday_and_hour(unix_timestamp: i64) -> (usize, u32) {
+    let days = unix_timestamp.div_euclid(86_400);
+    let hour = unix_timestamp.rem_euclid(86_400) / 3_600;
+    ((days + 3).rem_euclid(7) as usize, hour as u32)
+}
 
-    #[account(mut)]
-    pub seeker_authority: Signer<'info>,
+/// Rejects connections into a region with fewer than `min_wardens` active Wardens
+pub fn ensure_region_has_min_wardens(active_wardens: u16, min_wardens: u16) -> Result<()> {
+    require!(
+        active_wardens >= min_wardens,
+        ArkhamErrorCode::RegionBelowMinimumWardens
+    );
+    Ok(())
+}
 
-    pub protocol_config: Account<'info, ProtocolConfig>,
-    
-    pub system_program: Program<'info, System>,
+/// Rejects a Warden whose reported uptime is below `min_uptime` (0 disables the check)
+pub fn ensure_warden_meets_min_uptime(uptime_percentage: u16, min_uptime: u16) -> Result<()> {
+    require!(
+        uptime_percentage >= min_uptime,
+        ArkhamErrorCode::WardenUptimeTooLow
+    );
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct SubmitBandwidthProof<'info> {
-    #[account(
-        mut,
-        seeds = [b"connection", connection.seeker.as_ref(), connection.warden.as_ref()],
-        bump,
-        has_one = warden,
-        has_one = seeker
-    )]
-    pub connection: Account<'info, Connection>,
+/// Rejects a new connection once the Seeker holds `max_connections` active ones
+pub fn ensure_seeker_below_capacity(active_connections: u8, max_connections: u8) -> Result<()> {
+    require!(
+        active_connections < max_connections,
+        ArkhamErrorCode::SeekerAtCapacity
+    );
+    Ok(())
+}
 
-    #[account(mut)]
-    pub warden: Account<'info, Warden>,
+/// Quotes the cost of a session of `estimated_mb` against a Warden, as return data
+/// The figures are exactly what start_connection would lock right now
+pub fn estimate_session_cost_handler(
+    ctx: Context<EstimateSessionCost>,
+    estimated_mb: u64,
+) -> Result<SessionCostEstimate> {
+    session_cost_estimate(&ctx.accounts.protocol_config, &ctx.accounts.warden, estimated_mb)
+}
 
-    #[account(mut)]
-    pub seeker: Account<'info, Seeker>,
+/// Prices a session of `estimated_mb` against `warden` under the current config
+/// Shared by start_connection and estimate_session_cost so the quote can't drift
+pub fn session_cost_estimate(
+    config: &ProtocolConfig,
+    warden: &Warden,
+    estimated_mb: u64,
+) -> Result<SessionCostEstimate> {
+    let rate_per_mb = current_rate_per_mb(config, warden)?;
 
-    pub protocol_config: Account<'info, ProtocolConfig>,
+    // A zero rate would let wardens farm ARKHAM rewards for free bandwidth
+    require!(rate_per_mb > 0, ArkhamErrorCode::ZeroRateNotAllowed);
 
-    /// CHECK: Instructions sysvar for Ed25519 verification
-    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
-    pub instructions_sysvar: AccountInfo<'info>,
+    let base_cost = calculate_payment_amount(estimated_mb, rate_per_mb)?;
+    let total_escrow = calculate_escrow_needed(estimated_mb, rate_per_mb)?;
+    let expected_fee = ((base_cost as u128) * config.protocol_fee_bps as u128 / 10000) as u64;
 
-    /// Either seeker or warden can submit proofs
-    pub submitter: Signer<'info>,
+    Ok(SessionCostEstimate {
+        rate_per_mb,
+        base_cost,
+        buffer: total_escrow.saturating_sub(base_cost),
+        expected_fee,
+        total_escrow,
+    })
 }
 
-#[derive(Accounts)]
-pub struct EndConnection<'info> {
-    #[account(
-        mut,
-        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
-        bump,
-        close = seeker_authority  // Refund rent to seeker
-    )]
-    pub connection: Account<'info, Connection>,
+/// The rate per MB a new connection to `warden` would lock under the current config
+pub fn current_rate_per_mb(config: &ProtocolConfig, warden: &Warden) -> Result<u64> {
+    let base_rate = normalize_rate_per_mb(config.base_rate_per_mb, &config.rate_unit);
 
-    #[account(mut)]
-    pub seeker: Account<'info, Seeker>,
+    // Geographic premium for this warden's region
+    let geo_premium_bps = config.geo_premiums
+        .iter()
+        .find(|gp| gp.region_code == warden.region_code)
+        .map(|gp| gp.premium_bps)
+        .unwrap_or(0);
 
-    #[account(mut)]
-    pub warden: Account<'info, Warden>,
+    calculate_rate_per_mb(base_rate, geo_premium_bps, warden_tier_multiplier(config, warden))
+}
 
-    #[account(mut)]
-    pub seeker_authority: Signer<'info>,
+/// How far `stored` is from `expected`, in basis points of `expected`, when that exceeds
+/// `tolerance_bps`; None when within tolerance. A nonzero rate against an expected zero
+/// rate always diverges, reported as u64::MAX.
+pub fn rate_divergence_beyond_tolerance(stored: u64, expected: u64, tolerance_bps: u16) -> Option<u64> {
+    let divergence_bps = if expected == 0 {
+        if stored == 0 { 0 } else { u64::MAX }
+    } else {
+        let diff = stored.abs_diff(expected) as u128;
+        u64::try_from(diff * 10000 / expected as u128).unwrap_or(u64::MAX)
+    };
+
+    (divergence_bps > tolerance_bps as u64).then_some(divergence_bps)
 }
 
-#[derive(Accounts)]
-pub struct ClaimEarnings<'info> {
-    #[account(
-        mut,
-        seeds = [b"warden", authority.key().as_ref()],
-        bump,
-        has_one = authority
-    )]
-    pub warden: Account<'info, Warden>,
+/// The configured rate multiplier for the Warden's tier
+pub fn warden_tier_multiplier(config: &ProtocolConfig, warden: &Warden) -> u16 {
+    match warden.tier {
+        crate::state::Tier::Bronze => config.tier_multipliers[0],
+        crate::state::Tier::Silver => config.tier_multipliers[1],
+        crate::state::Tier::Gold => config.tier_multipliers[2],
+    }
+}
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+/// Calculates the effective rate per MB: base * (1 + geo_premium) * tier_multiplier
+/// All in basis points for precision
+pub fn calculate_rate_per_mb(base_rate: u64, geo_premium_bps: u16, tier_multiplier: u16) -> Result<u64> {
+    let rate_with_geo = (base_rate as u128)
+        .checked_mul(10000 + geo_premium_bps as u128)
+        .ok_or(ArkhamErrorCode::RateOverflow)?
+        .checked_div(10000)
+        .ok_or(ArkhamErrorCode::RateOverflow)?;
 
-    #[account(mut, seeds = [b"sol_vault"], bump)]
-    pub sol_vault: SystemAccount<'info>,
+    let rate_per_mb = rate_with_geo
+        .checked_mul(tier_multiplier as u128)
+        .ok_or(ArkhamErrorCode::RateOverflow)?
+        .checked_div(10000)
+        .ok_or(ArkhamErrorCode::RateOverflow)?;
 
-    pub system_program: Program<'info, System>,
+    u64::try_from(rate_per_mb).map_err(|_| ArkhamErrorCode::RateOverflow.into())
 }
 
-#[derive(Accounts)]
-pub struct ClaimArkhamTokens<'info> {
-    #[account(
-        mut,
-        seeds = [b"warden", authority.key().as_ref()],
-        bump,
-        has_one = authority
-    )]
-    pub warden: Account<'info, Warden>,
+/// Calculates the payment owed for `mb_consumed` at the connection's locked rate
+pub fn calculate_payment_amount(mb_consumed: u64, rate_per_mb: u64) -> Result<u64> {
+    let payment_amount = (mb_consumed as u128)
+        .checked_mul(rate_per_mb as u128)
+        .ok_or(ArkhamErrorCode::RateOverflow)?;
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    u64::try_from(payment_amount).map_err(|_| ArkhamErrorCode::RateOverflow.into())
+}
 
-    #[account(
-        seeds = [b"protocol_config"],
-        bump,
-    )]
-    pub protocol_config: Account<'info, ProtocolConfig>,
+/// Calculates the escrow to lock for an estimated bandwidth, including the 10% buffer
+pub fn calculate_escrow_needed(estimated_mb: u64, rate_per_mb: u64) -> Result<u64> {
+    let base_escrow = (estimated_mb as u128)
+        .checked_mul(rate_per_mb as u128)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
 
-    #[account(
-        mut,
-        seeds = [b"arkham_mint"],
-        bump,
-    )]
-    pub arkham_mint: Account<'info, Mint>,
+    let escrow_needed = base_escrow
+        .checked_mul((10000 + ESCROW_BUFFER_BPS) as u128)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?
+        .checked_div(10000)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
 
-    #[account(
-        init_if_needed,
-        payer = authority,
-        associated_token::mint = arkham_mint,
-        associated_token::authority = authority,
-    )]
-    pub warden_arkham_token_account: Account<'info, TokenAccount>,
+    u64::try_from(escrow_needed).map_err(|_| ArkhamErrorCode::EscrowOverflow.into())
+}
 
-    /// CHECK: Mint authority for the ARKHAM token - PDA controlled by the program
-    #[account(
-        seeds = [b"arkham", b"mint", b"authority"],
-        bump,
-    )]
-    pub mint_authority: AccountInfo<'info>,
+/// Checks the Seeker account's lamports above rent-exemption cover its escrow_balance
+/// counter, so a counter that has drifted above the real balance can't fund a connection
+pub fn ensure_escrow_balance_backed(seeker_lamports: u64, rent_exempt_minimum: u64, escrow_balance: u64) -> Result<()> {
+    require!(
+        escrow_balance <= seeker_lamports.saturating_sub(rent_exempt_minimum),
+        ArkhamErrorCode::EscrowUndercollateralized
+    );
+    Ok(())
+}
 
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
-    pub system_program: Program<'info, System>,
+/// Takes `escrow_needed` out of the seeker's spendable escrow for a new connection
+/// Returns the remaining balance, or InsufficientEscrow without touching anything
+pub fn lock_connection_escrow(escrow_balance: u64, escrow_needed: u64) -> Result<u64> {
+    require!(escrow_balance >= escrow_needed, ArkhamErrorCode::InsufficientEscrow);
+
+    let remaining = escrow_balance
+        .checked_sub(escrow_needed)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+
+    // Post-condition: the lock moved exactly escrow_needed and never wrapped
+    require!(
+        remaining + escrow_needed == escrow_balance,
+        ArkhamErrorCode::EscrowOverflow
+    );
+
+    Ok(remaining)
 }
 
-// Events:
+/// Adds a new connection's escrow to the seeker's committed total, ensuring the
+/// total never exceeds the lamports actually held above rent-exemption
+/// Returns the new committed escrow total
+pub fn commit_connection_escrow(
+    seeker_lamports: u64,
+    rent_exempt_minimum: u64,
+    committed_escrow: u64,
+    escrow_needed: u64,
+) -> Result<u64> {
+    let committed_escrow = committed_escrow
+        .checked_add(escrow_needed)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    let backing = seeker_lamports.saturating_sub(rent_exempt_minimum);
 
-#[event]
-pub struct EscrowDeposited {
-    pub authority: Pubkey,
-    pub amount: u64,
-    pub use_private: bool,
+    require!(
+        committed_escrow <= backing,
+        ArkhamErrorCode::EscrowUndercollateralized
+    );
+
+    Ok(committed_escrow)
 }
 
-#[event]
-pub struct ConnectionStarted {
-    pub seeker: Pubkey,
-    pub warden: Pubkey,
-    pub estimated_mb: u64,
-    pub rate_per_mb: u64,
-    pub escrow_amount: u64,
+/// Converts the configured base rate into the per-MB rate used for settlement
+pub fn normalize_rate_per_mb(base_rate: u64, rate_unit: &RateUnit) -> u64 {
+    match rate_unit {
+        RateUnit::PerMb => base_rate,
+        RateUnit::PerGb => base_rate / 1024,
+    }
 }
 
-#[event]
-pub struct BandwidthProofSubmitted {
-    pub connection: Pubkey,
-    pub mb_consumed: u64,
-    pub payment_amount: u64,
-    pub arkham_earned: u64,
+/// Lowers a connection's locked escrow to what has been paid plus the escrow
+/// needed for the remaining estimated bandwidth, returning the excess to the Seeker
+pub fn reduce_connection_escrow_handler(
+    ctx: Context<ReduceConnectionEscrow>,
+    remaining_mb: u64,
+) -> Result<()> {
+    let connection = &mut ctx.accounts.connection;
+    let seeker = &mut ctx.accounts.seeker;
+
+    let (new_amount_escrowed, freed) = reduce_escrow_to_remaining(
+        connection.amount_escrowed,
+        connection.amount_paid,
+        remaining_mb,
+        connection.rate_per_mb,
+    )?;
+
+    connection.amount_escrowed = new_amount_escrowed;
+    // The reduced escrow is re-buffered for remaining_mb; don't count more buffer than it holds
+    connection.escrow_buffer = connection.escrow_buffer
+        .min(new_amount_escrowed.saturating_sub(connection.amount_paid));
+    seeker.escrow_balance = seeker.escrow_balance
+        .checked_add(freed)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    seeker.committed_escrow = seeker.committed_escrow.saturating_sub(freed);
+
+    emit!(ConnectionEscrowReduced {
+        connection: connection.key(),
+        connection_id: connection.connection_id,
+        seeker: seeker.key(),
+        new_amount_escrowed,
+        freed,
+    });
+
+    Ok(())
 }
 
-#[event]
-pub struct ConnectionEnded {
-    pub seeker: Pubkey,
-    pub warden: Pubkey,
-    pub bandwidth_consumed: u64,
-    pub total_paid: u64,
-    pub refunded: u64,
+/// Computes a reduced connection escrow of `amount_paid` plus the buffered escrow
+/// for `remaining_mb`. Never reduces below what has already been paid.
+/// Returns (new_amount_escrowed, freed)
+pub fn reduce_escrow_to_remaining(
+    amount_escrowed: u64,
+    amount_paid: u64,
+    remaining_mb: u64,
+    rate_per_mb: u64,
+) -> Result<(u64, u64)> {
+    let new_amount_escrowed = amount_paid
+        .checked_add(calculate_escrow_needed(remaining_mb, rate_per_mb)?)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+
+    require!(
+        new_amount_escrowed < amount_escrowed,
+        ArkhamErrorCode::InvalidEscrowReduction
+    );
+
+    let freed = amount_escrowed
+        .checked_sub(new_amount_escrowed)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+
+    Ok((new_amount_escrowed, freed))
 }
 
-#[event]
-pub struct EarningsClaimed {
-    pub authority: Pubkey,
-    pub amount: u64,
-    pub use_private: bool,
+/// Submits a bandwidth proof and processes micropayment
+pub fn submit_bandwidth_proof_handler(
+    ctx: Context<SubmitBandwidthProof>,
+    mb_consumed: u64,
+    timestamp: i64,
+    seeker_signature: [u8; 64],
+    warden_signature: [u8; 64],
+) -> Result<()> {
+    process_bandwidth_proof(ctx, mb_consumed, timestamp, Some(seeker_signature), warden_signature)
 }
 
-#[event]
-pub struct TokensClaimed {
-    pub authority: Pubkey,
-    pub amount: u64,
-}
\ No newline at end of file
+/// Lets the Seeker pre-authorize the Warden to settle up to `max_mb` on this connection
+/// with only the Warden's live signature. Replaces any earlier delegation; 0 revokes it.
+/// MB already settled under the delegation count against the new cap.
+pub fn authorize_warden_settlement_handler(
+    ctx: Context<AuthorizeWardenSettlement>,
+    max_mb: u64,
+) -> Result<()> {
+    let connection = &mut ctx.accounts.connection;
+    connection.delegated_max_mb = max_mb;
+
+    emit!(WardenSettlementAuthorized {
+        connection: connection.key(),
+        connection_id: connection.connection_id,
+        seeker: connection.seeker,
+        warden: connection.warden,
+        max_mb,
+        already_settled_mb: connection.delegated_mb_settled,
+    });
+
+    Ok(())
+}
+
+/// Lets the Seeker cap the total lamports this Warden may collect on the connection,
+/// below the escrow, so valid co-signatures can't extract beyond the Seeker's trust
+/// in this Warden. 0 removes the cap. A cap at or below amount_paid stops further settlement.
+pub fn set_warden_spend_limit_handler(
+    ctx: Context<SetWardenSpendLimit>,
+    max_spend: u64,
+) -> Result<()> {
+    let connection = &mut ctx.accounts.connection;
+    connection.max_spend = max_spend;
+
+    emit!(WardenSpendLimitSet {
+        connection: connection.key(),
+        connection_id: connection.connection_id,
+        seeker: connection.seeker,
+        warden: connection.warden,
+        max_spend,
+        amount_paid: connection.amount_paid,
+    });
+
+    Ok(())
+}
+
+/// Refunds the Seeker part of what it paid on a connection during which the Warden
+/// was reported below the SLA uptime. The refund comes out of the Warden's pending
+/// claims, at most once per connection, and is credited back to the Seeker's escrow
+pub fn sla_refund_handler(ctx: Context<SlaRefund>) -> Result<()> {
+    let config = &ctx.accounts.protocol_config;
+    let connection = &mut ctx.accounts.connection;
+    let warden = &mut ctx.accounts.warden;
+    let seeker = &mut ctx.accounts.seeker;
+
+    // 1. Check the claim and size the refund from the breach report. The warden's unclaimed
+    // earnings cover it first; the insurance fund, when supplied, backstops what they can't
+    let insurance_available = match &ctx.accounts.insurance_fund {
+        Some(fund) => insurance_fund_available(fund.lamports(), Rent::get()?.minimum_balance(0)),
+        None => 0,
+    };
+    let (from_warden, from_insurance) = plan_sla_refund(
+        connection,
+        seeker,
+        &ctx.accounts.authority.key(),
+        warden,
+        config.sla_uptime_bps,
+        insurance_available,
+    )?;
+    let refund = from_warden + from_insurance;
+
+    // 2. Return the lamports from the rewards vault (and insurance fund) to the seeker's escrow
+    if from_warden > 0 {
+        let vault_seeds = &[b"rewards_vault".as_ref(), &[ctx.bumps.rewards_vault]];
+        let signer_seeds = &[&vault_seeds[..]];
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.rewards_vault.to_account_info(),
+                to: seeker.to_account_info(),
+            },
+            signer_seeds,
+        );
+        system_program::transfer(cpi_context, from_warden)?;
+    }
+    if let (Some(insurance_fund), Some(bump)) = (&ctx.accounts.insurance_fund, ctx.bumps.insurance_fund) {
+        if from_insurance > 0 {
+            let fund_seeds = &[b"insurance_fund".as_ref(), &[bump]];
+            let signer_seeds = &[&fund_seeds[..]];
+            let cpi_context = CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: insurance_fund.to_account_info(),
+                    to: seeker.to_account_info(),
+                },
+                signer_seeds,
+            );
+            system_program::transfer(cpi_context, from_insurance)?;
+        }
+    }
+
+    warden.pending_claims = warden.pending_claims
+        .checked_sub(from_warden)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?;
+    seeker.escrow_balance = seeker.escrow_balance
+        .checked_add(refund)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    connection.sla_refunded = true;
+
+    emit!(SlaRefunded {
+        connection: connection.key(),
+        connection_id: connection.connection_id,
+        seeker: seeker.key(),
+        warden: warden.key(),
+        uptime_at_start: connection.uptime_at_start,
+        reported_uptime: warden.last_sla_breach_uptime,
+        refund,
+        from_insurance,
+    });
+
+    Ok(())
+}
+
+/// Checks an SLA refund claim and returns (from the warden's pending claims, from the
+/// insurance fund). Only the Seeker's authority may claim, once per connection, and
+/// only for a breach reported during the session.
+pub fn plan_sla_refund(
+    connection: &Connection,
+    seeker: &Seeker,
+    claimant: &Pubkey,
+    warden: &Warden,
+    sla_uptime_bps: u16,
+    insurance_available: u64,
+) -> Result<(u64, u64)> {
+    require_keys_eq!(*claimant, seeker.authority, ArkhamErrorCode::UnauthorizedSlaRefundClaim);
+    require!(!connection.sla_refunded, ArkhamErrorCode::SlaRefundAlreadyClaimed);
+
+    let owed = calculate_sla_refund(
+        connection,
+        warden.last_sla_breach_at,
+        warden.last_sla_breach_uptime,
+        sla_uptime_bps,
+    )?;
+    let (from_warden, from_insurance) = split_sla_refund(owed, warden.pending_claims, insurance_available);
+    require!(from_warden + from_insurance > 0, ArkhamErrorCode::NoSlaRefundDue);
+
+    Ok((from_warden, from_insurance))
+}
+
+/// Splits an owed refund into (from the warden's pending claims, from the insurance fund)
+/// The warden pays what it can; the fund covers the shortfall up to its balance
+pub fn split_sla_refund(owed: u64, warden_pending_claims: u64, insurance_available: u64) -> (u64, u64) {
+    let from_warden = owed.min(warden_pending_claims);
+    let from_insurance = (owed - from_warden).min(insurance_available);
+    (from_warden, from_insurance)
+}
+
+/// Portion of a connection's `amount_paid` owed back for a Warden SLA breach
+/// Proportional to the shortfall: uptime 10% under the SLA refunds 10% of the payment.
+/// A Warden already below the SLA when the session started owes only for the drop from
+/// that uptime, which the Seeker saw when choosing it. Returns 0 when the SLA is off or
+/// the breach was reported outside the session (before it started or after its last proof).
+pub fn calculate_sla_refund(
+    connection: &Connection,
+    last_breach_at: i64,
+    breach_uptime: u16,
+    sla_uptime_bps: u16,
+) -> Result<u64> {
+    let reference_uptime = sla_uptime_bps.min(connection.uptime_at_start);
+    let during_session = (connection.started_at..=connection.last_proof_at).contains(&last_breach_at);
+    if sla_uptime_bps == 0 || !during_session || breach_uptime >= reference_uptime {
+        return Ok(0);
+    }
+
+    let shortfall = (reference_uptime - breach_uptime) as u128;
+    let refund = (connection.amount_paid as u128)
+        .checked_mul(shortfall)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?
+        / reference_uptime as u128;
+
+    u64::try_from(refund).map_err(|_| ArkhamErrorCode::EscrowOverflow.into())
+}
+
+/// Submits a warden-only bandwidth proof under the Seeker's stored settlement delegation
+/// Only the Warden's Ed25519 signature is verified (expected at instruction index 0)
+pub fn submit_delegated_bandwidth_proof_handler(
+    ctx: Context<SubmitBandwidthProof>,
+    mb_consumed: u64,
+    timestamp: i64,
+    warden_signature: [u8; 64],
+) -> Result<()> {
+    process_bandwidth_proof(ctx, mb_consumed, timestamp, None, warden_signature)
+}
+
+/// Charges `mb_consumed` against a settlement delegation
+/// Returns the new delegated_mb_settled
+pub fn consume_settlement_delegation(
+    delegated_max_mb: u64,
+    delegated_mb_settled: u64,
+    mb_consumed: u64,
+) -> Result<u64> {
+    require!(delegated_max_mb > 0, ArkhamErrorCode::SettlementNotDelegated);
+
+    let settled = delegated_mb_settled
+        .checked_add(mb_consumed)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    require!(
+        settled <= delegated_max_mb,
+        ArkhamErrorCode::DelegatedSettlementCapExceeded
+    );
+
+    Ok(settled)
+}
+
+/// Rejects seeker/warden accounts other than the ones the connection records
+pub fn ensure_connection_parties(connection: &Connection, seeker: &Pubkey, warden: &Pubkey) -> Result<()> {
+    require!(
+        connection.seeker == *seeker && connection.warden == *warden,
+        ArkhamErrorCode::ConnectionAccountMismatch
+    );
+    Ok(())
+}
+
+/// Rejects a stored warden_multiplier above what validate_tier_multipliers allows,
+/// so a corrupted connection can't settle at an inflated rate
+pub fn ensure_connection_multiplier_in_bounds(warden_multiplier: u16) -> Result<()> {
+    require!(
+        warden_multiplier <= crate::instructions::admin::MAX_TIER_MULTIPLIER,
+        ArkhamErrorCode::InvalidTierMultiplier
+    );
+    Ok(())
+}
+
+/// Rejects proofs against a connection whose escrow has been paid out in full
+/// Such a connection can only be ended; InsufficientConnectionEscrow is kept for
+/// a proof that overshoots escrow still left on the connection
+pub fn ensure_connection_not_fully_settled(amount_paid: u64, amount_escrowed: u64) -> Result<()> {
+    require!(amount_paid < amount_escrowed, ArkhamErrorCode::ConnectionFullySettled);
+    Ok(())
+}
+
+/// Rejects a proof once a connection has settled max_total_proofs (0 = unlimited)
+/// The seeker ends the connection and starts a new one to keep settling
+pub fn ensure_below_proof_limit(total_proofs: u32, max_total_proofs: u32) -> Result<()> {
+    require!(
+        max_total_proofs == 0 || total_proofs < max_total_proofs,
+        ArkhamErrorCode::ProofLimitReached
+    );
+    Ok(())
+}
+
+/// Checks a connection's running total against both the escrow and the Seeker's
+/// per-warden spend limit (0 = no limit beyond the escrow)
+pub fn ensure_within_spend_limits(new_total_paid: u64, amount_escrowed: u64, max_spend: u64) -> Result<()> {
+    require!(
+        new_total_paid <= amount_escrowed,
+        ArkhamErrorCode::InsufficientConnectionEscrow
+    );
+    require!(
+        max_spend == 0 || new_total_paid <= max_spend,
+        ArkhamErrorCode::WardenSpendLimitExceeded
+    );
+    Ok(())
+}
+
+/// Shared settlement for co-signed and delegated proofs
+/// `seeker_signature` is None for a proof settled under the Seeker's stored delegation
+fn process_bandwidth_proof(
+    ctx: Context<SubmitBandwidthProof>,
+    mb_consumed: u64,
+    timestamp: i64,
+    seeker_signature: Option<[u8; 64]>,
+    warden_signature: [u8; 64],
+) -> Result<()> {
+    // Get the connection key before we mutably borrow the connection
+    let connection_key = ctx.accounts.connection.key();
+    let warden_key = ctx.accounts.warden.key();
+    
+    let connection = &mut ctx.accounts.connection;
+    let warden = &mut ctx.accounts.warden;
+    let seeker = &mut ctx.accounts.seeker;
+    let config = &ctx.accounts.protocol_config;
+    let clock = Clock::get()?;
+
+    // The passed seeker and warden must be the connection's own parties
+    ensure_connection_parties(connection, &seeker.key(), &warden_key)?;
+
+    // The multiplier was copied at start; one past the config max means a corrupted account
+    ensure_connection_multiplier_in_bounds(connection.warden_multiplier)?;
+
+    // 0. The Ed25519 verification instructions must be present before any content is checked,
+    // so all-zero signatures without them surface as MissingSignatureVerification
+    let verification_indices: &[u16] = if seeker_signature.is_some() { &[0, 1] } else { &[0] };
+    crate::instructions::bandwidth::ensure_signature_verification_present(
+        &ctx.accounts.instructions_sysvar,
+        verification_indices,
+    )?;
+
+    // 0a. Bound a single connection's lifetime settlement work
+    ensure_below_proof_limit(connection.total_proofs, config.max_total_proofs)?;
+
+    // 1. Validate the proof using bandwidth module helpers
+    // A delegated proof has no live seeker signature; the stored delegation stands in for it
+    crate::instructions::bandwidth::validate_bandwidth_proof(
+        mb_consumed,
+        timestamp,
+        clock.unix_timestamp,
+        connection.started_at,
+        seeker_signature.as_ref().unwrap_or(&warden_signature),
+        &warden_signature,
+    )?;
+
+    // 2. Create the deterministic message and verify Ed25519 signatures
+    let proof_message = crate::instructions::bandwidth::create_proof_message(
+        &connection_key,
+        mb_consumed,
+        timestamp,
+    );
+    
+    // REAL Ed25519 VERIFICATION using instruction introspection
+    match seeker_signature.as_ref() {
+        Some(seeker_signature) => crate::instructions::bandwidth::verify_dual_signatures(
+            &ctx.accounts.instructions_sysvar,
+            &proof_message,
+            seeker_signature,
+            &seeker.authority,
+            &warden_signature,
+            &warden.authority,
+        )?,
+        None => {
+            connection.delegated_mb_settled = consume_settlement_delegation(
+                connection.delegated_max_mb,
+                connection.delegated_mb_settled,
+                mb_consumed,
+            )?;
+            crate::instructions::bandwidth::verify_ed25519_signature_via_sysvar(
+                &ctx.accounts.instructions_sysvar,
+                &proof_message,
+                &warden_signature,
+                &warden.authority,
+                0, // Only Ed25519 instruction
+            )?;
+        }
+    }
+    let seeker_signature = seeker_signature.unwrap_or([0; 64]);
+
+    let insurance_fund = ctx.accounts.insurance_fund.as_ref().map(|fund| fund.to_account_info());
+    settle_bandwidth_proof(
+        ProofSettlement {
+            connection_key,
+            connection,
+            warden,
+            seeker,
+            config,
+            rewards_vault: &ctx.accounts.rewards_vault.to_account_info(),
+            treasury: &ctx.accounts.treasury,
+            insurance_fund: insurance_fund.as_ref(),
+            top_earners: ctx.accounts.top_earners.as_deref_mut(),
+        },
+        mb_consumed,
+        seeker_signature,
+        warden_signature,
+        clock.unix_timestamp,
+    )
+}
+
+/// Accounts a verified proof is settled against
+struct ProofSettlement<'a, 'info> {
+    connection_key: Pubkey,
+    connection: &'a mut Connection,
+    warden: &'a mut Account<'info, Warden>,
+    seeker: &'a mut Account<'info, Seeker>,
+    config: &'a ProtocolConfig,
+    rewards_vault: &'a AccountInfo<'info>,
+    treasury: &'a AccountInfo<'info>,
+    insurance_fund: Option<&'a AccountInfo<'info>>, // required while insurance_bps is set
+    top_earners: Option<&'a mut TopEarners>,
+}
+
+/// Settles a proof whose signatures have already been verified: replay and anomaly
+/// checks, payment out of escrow, warden statistics, ARKHAM allocation and the proof
+/// event. Shared by submitted, delegated and quick-session proofs.
+fn settle_bandwidth_proof(
+    accounts: ProofSettlement,
+    mb_consumed: u64,
+    seeker_signature: [u8; 64],
+    warden_signature: [u8; 64],
+    current_timestamp: i64,
+) -> Result<()> {
+    let ProofSettlement { connection_key, connection, warden, seeker, config, .. } = accounts;
+    let warden_key = warden.key();
+
+    // 3. Check for duplicate proofs (prevent replay attacks)
+    let proof_hash = crate::instructions::bandwidth::hash_bandwidth_proof(
+        &connection_key,
+        mb_consumed,
+        current_timestamp,
+        &seeker_signature,
+        &warden_signature,
+    );
+    
+    // Check if this proof hash already exists in our history
+    let recent_proofs: Vec<BandwidthProof> = bandwidth_proofs_chronological(
+        &connection.bandwidth_proofs,
+        connection.proofs_head,
+        connection.proofs_count,
+    ).cloned().collect();
+    for existing_proof in &recent_proofs {
+        let existing_hash = crate::instructions::bandwidth::hash_bandwidth_proof(
+            &connection_key,
+            existing_proof.mb_consumed,
+            existing_proof.timestamp,
+            &existing_proof.seeker_signature,
+            &existing_proof.warden_signature,
+        );
+        
+        require!(
+            proof_hash != existing_hash,
+            crate::instructions::bandwidth::BandwidthError::InvalidSignature
+        );
+    }
+
+    // 4. Anomaly detection (optional - flag suspicious claims)
+    if recent_proofs.len() >= 3 {
+        let historical: Vec<u64> = recent_proofs
+            .iter()
+            .map(|p| p.mb_consumed)
+            .collect();
+        
+        let expected = crate::instructions::bandwidth::calculate_expected_bandwidth(&historical, 5);
+        
+        if crate::instructions::bandwidth::detect_bandwidth_anomaly(mb_consumed, expected, 3.0) {
+            msg!("Warning: Anomalous bandwidth detected. Expected: {}, Claimed: {}", expected, mb_consumed);
+            // Continue processing but log the warning for reputation system
+        }
+    }
+
+    // 5. Calculate payment amount at the rate locked for this session
+    let payment_amount = calculate_payment_amount(mb_consumed, connection.rate_per_mb)?;
+
+    // 5a. Integrity check (optional - flag a locked rate far from what start_connection would
+    // charge now). Governance changes also move the live rate, so this never fails the proof.
+    if config.rate_check_tolerance_bps > 0 {
+        if let Ok(expected_rate_per_mb) = current_rate_per_mb(config, warden) {
+            if let Some(divergence_bps) = rate_divergence_beyond_tolerance(
+                connection.rate_per_mb,
+                expected_rate_per_mb,
+                config.rate_check_tolerance_bps,
+            ) {
+                emit!(ConnectionRateDivergence {
+                    connection: connection_key,
+                    connection_id: connection.connection_id,
+                    warden: warden_key,
+                    stored_rate_per_mb: connection.rate_per_mb,
+                    expected_rate_per_mb,
+                    divergence_bps,
+                });
+            }
+        }
+    }
+
+    // 6. Verify payment doesn't exceed available escrow or the seeker's limit for this warden
+    // A connection whose escrow is already paid out is reported as such, not as a shortfall
+    ensure_connection_not_fully_settled(connection.amount_paid, connection.amount_escrowed)?;
+    let new_total_paid = connection.amount_paid
+        .checked_add(payment_amount)
+        .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    
+    ensure_within_spend_limits(new_total_paid, connection.amount_escrowed, connection.max_spend)?;
+
+    // 7. Move the payment out of the seeker's escrow: once proof fees are enabled, the
+    // protocol fee to the treasury, less the insurance fund's slice of it, and the rest
+    // into the rewards vault, credited to the warden's pending claims
+    let split = split_proof_payment(payment_amount, proof_fee_bps(config), config.insurance_bps)?;
+    let warden_share = split.warden_share;
+    move_lamports(
+        &seeker.to_account_info(),
+        accounts.rewards_vault,
+        warden_share,
+    )?;
+    if split.treasury_fee > 0 {
+        move_lamports(&seeker.to_account_info(), accounts.treasury, split.treasury_fee)?;
+    }
+    if split.insurance > 0 {
+        let insurance_fund = accounts.insurance_fund.ok_or(ArkhamErrorCode::InsuranceFundRequired)?;
+        move_lamports(&seeker.to_account_info(), insurance_fund, split.insurance)?;
+    }
+    warden.pending_claims = warden.pending_claims
+        .checked_add(warden_share)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?;
+    // The paid lamports have left the seeker account, so they no longer back the commitment
+    seeker.committed_escrow = seeker.committed_escrow.saturating_sub(payment_amount);
+
+    // 8. Update connection bandwidth and payment tracking
+    connection.bandwidth_consumed = connection.bandwidth_consumed
+        .checked_add(mb_consumed)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    
+    connection.amount_paid = new_total_paid;
+
+    // 9. Update warden statistics
+    warden.total_bandwidth_served = warden.total_bandwidth_served
+        .checked_add(mb_consumed)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    
+    warden.total_earnings = warden.total_earnings
+        .checked_add(warden_share)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?;
+
+    // 10. Calculate and add ARKHAM token allocation at the rate locked for this session
+    // Premium-ranked wardens earn a boosted allocation; unpaid bandwidth earns no ARKHAM
+    let (reward_numerator, reward_denominator) = connection_reward_rate(connection);
+    let arkham_earned = if payment_amount > 0 {
+        compute_arkham_reward(
+            mb_consumed,
+            reward_numerator,
+            reward_denominator,
+            config.premium_reward_boost_bps,
+            warden.premium_pool_rank.is_some(),
+            config.max_arkham_reward_per_proof,
+        )?
+    } else {
+        0
+    };
+
+    warden.arkham_tokens_earned = warden.arkham_tokens_earned
+        .checked_add(arkham_earned)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?;
+
+    // 11. Add proof to the bandwidth_proofs ring buffer (keeps the last 10)
+    let proof = BandwidthProof {
+        timestamp: current_timestamp,
+        mb_consumed,
+        seeker_signature,
+        warden_signature,
+    };
+
+    let (mut head, mut count) = (connection.proofs_head, connection.proofs_count);
+    push_bandwidth_proof(&mut connection.bandwidth_proofs, &mut head, &mut count, proof);
+    connection.proofs_head = head;
+    connection.proofs_count = count;
+    connection.total_proofs = connection.total_proofs
+        .checked_add(1)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+
+    // 11a. Optionally hand back buffer escrow the observed usage no longer calls for
+    if config.buffer_refund_enabled {
+        let recent_mb: Vec<u64> = bandwidth_proofs_chronological(
+            &connection.bandwidth_proofs,
+            connection.proofs_head,
+            connection.proofs_count,
+        ).map(|p| p.mb_consumed).collect();
+        let released = releasable_escrow_buffer(
+            connection.amount_escrowed,
+            connection.amount_paid,
+            connection.escrow_buffer,
+            projected_proof_payment(&recent_mb, connection.rate_per_mb)?,
+        );
+        if released > 0 {
+            connection.amount_escrowed -= released;
+            connection.escrow_buffer = connection.escrow_buffer.saturating_sub(released);
+            seeker.escrow_balance = seeker.escrow_balance
+                .checked_add(released)
+                .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+            seeker.committed_escrow = seeker.committed_escrow.saturating_sub(released);
+
+            emit!(EscrowBufferReleased {
+                connection: connection_key,
+                connection_id: connection.connection_id,
+                seeker: seeker.key(),
+                released,
+                remaining_buffer: connection.escrow_buffer,
+            });
+        }
+    }
+
+    // 12. Update last proof timestamp
+    connection.last_proof_at = current_timestamp;
+
+    // 13. Update warden's last active timestamp
+    warden.last_active = current_timestamp;
+
+    // 14. Opportunistically update the earnings leaderboard
+    if let Some(top_earners) = accounts.top_earners {
+        update_top_earners(&mut top_earners.entries, warden_key, warden.total_earnings);
+    }
+
+    // 15. Report the proof, batched with earlier ones when events are throttled
+    let batch = accumulate_proof_event(
+        &mut connection.pending_proof_event,
+        mb_consumed,
+        payment_amount,
+        arkham_earned,
+        current_timestamp,
+        config.proof_event_every_n,
+        config.proof_event_interval,
+    )?;
+    if let Some(batch) = batch {
+        emit!(proof_batch_event(connection_key, connection, &batch));
+    }
+
+    Ok(())
+}
+
+/// Where a proof payment goes, all in lamports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofPaymentSplit {
+    pub warden_share: u64, // credited to the warden's pending claims
+    pub treasury_fee: u64, // protocol fee less the insurance slice
+    pub insurance: u64, // insurance_bps of the protocol fee
+}
+
+/// Fee taken from each proof payment: protocol_fee_bps once proof_fee_enabled is set, else none
+pub fn proof_fee_bps(config: &ProtocolConfig) -> u16 {
+    if config.proof_fee_enabled {
+        config.protocol_fee_bps
+    } else {
+        0
+    }
+}
+
+/// Splits a proof payment into the warden's share and the protocol fee on it
+/// - the fee is `protocol_fee_bps` of the payment, rounded down
+/// - `insurance_bps` of the fee goes to the insurance fund, the rest to the treasury
+pub fn split_proof_payment(payment_amount: u64, protocol_fee_bps: u16, insurance_bps: u16) -> Result<ProofPaymentSplit> {
+    let fee = (payment_amount as u128)
+        .checked_mul(protocol_fee_bps as u128)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
+        / 10_000;
+    let insurance = fee * insurance_bps as u128 / 10_000;
+    // Both are at most payment_amount, so they fit u64
+    let (fee, insurance) = (fee as u64, insurance as u64);
+    Ok(ProofPaymentSplit {
+        warden_share: payment_amount.checked_sub(fee).ok_or(ArkhamErrorCode::ArithmeticOverflow)?,
+        treasury_fee: fee - insurance,
+        insurance,
+    })
+}
+
+/// Lamports the insurance fund can pay out: everything above its rent-exempt minimum
+pub fn insurance_fund_available(fund_lamports: u64, rent_exempt_minimum: u64) -> u64 {
+    fund_lamports.saturating_sub(rent_exempt_minimum)
+}
+
+/// Expected payment for the next proof: the average of the retained proofs, rounded up
+pub fn projected_proof_payment(recent_mb: &[u64], rate_per_mb: u64) -> Result<u64> {
+    if recent_mb.is_empty() {
+        return Ok(0);
+    }
+    let total_mb = recent_mb
+        .iter()
+        .try_fold(0u64, |total, &mb| total.checked_add(mb))
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    calculate_payment_amount(total_mb.div_ceil(recent_mb.len() as u64), rate_per_mb)
+}
+
+/// Buffer escrow a connection can return early: whatever is left of the buffer beyond
+/// one projected proof. Escrow for the committed (estimated) bandwidth is never released,
+/// and neither is buffer already paid out.
+pub fn releasable_escrow_buffer(
+    amount_escrowed: u64,
+    amount_paid: u64,
+    escrow_buffer: u64,
+    projected_payment: u64,
+) -> u64 {
+    let committed = amount_escrowed.saturating_sub(escrow_buffer);
+    let unused_buffer = amount_escrowed.saturating_sub(amount_paid.max(committed));
+    unused_buffer.saturating_sub(projected_payment)
+}
+
+/// Adds a settled proof to the connection's unreported totals
+/// Returns the batch to emit once `every_n` proofs or `interval` seconds have
+/// accumulated, resetting the totals; with both off every proof is its own batch.
+/// Only the events are throttled: the proof has already been fully accounted for.
+pub fn accumulate_proof_event(
+    pending: &mut PendingProofEvent,
+    mb_consumed: u64,
+    payment_amount: u64,
+    arkham_earned: u64,
+    current_timestamp: i64,
+    every_n: u16,
+    interval: i64,
+) -> Result<Option<PendingProofEvent>> {
+    pending.proofs = pending.proofs.saturating_add(1);
+    pending.mb_consumed = pending.mb_consumed
+        .checked_add(mb_consumed)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    pending.payment_amount = pending.payment_amount
+        .checked_add(payment_amount)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    pending.arkham_earned = pending.arkham_earned
+        .checked_add(arkham_earned)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+
+    let throttled = every_n > 1 || interval > 0;
+    let due = !throttled
+        || (every_n > 1 && pending.proofs >= every_n)
+        || (interval > 0 && current_timestamp.saturating_sub(pending.last_emitted_at) >= interval);
+    if !due {
+        return Ok(None);
+    }
+
+    let batch = std::mem::replace(
+        pending,
+        PendingProofEvent { last_emitted_at: current_timestamp, ..PendingProofEvent::default() },
+    );
+    Ok(Some(batch))
+}
+
+/// Proofs settled since the connection's last BandwidthProofSubmitted, if any
+/// close_connection reports them so throttling never leaves a proof unreported
+pub fn unreported_proof_batch(pending: &PendingProofEvent) -> Option<&PendingProofEvent> {
+    (pending.proofs > 0).then_some(pending)
+}
+
+/// The ARKHAM reward rate as a (numerator, denominator) pair of tokens per MB
+/// Configs written before the pair existed have a zero denominator and keep
+/// their tokens_per_5gb meaning
+pub fn reward_rate(config: &ProtocolConfig) -> (u64, u64) {
+    if config.reward_denominator == 0 {
+        (config.tokens_per_5gb, MB_PER_5GB)
+    } else {
+        (config.reward_numerator, config.reward_denominator)
+    }
+}
+
+/// The ARKHAM reward rate a connection locked at start, as in reward_rate
+/// Connections opened before the pair existed fall back to their tokens_per_5gb
+pub fn connection_reward_rate(connection: &Connection) -> (u64, u64) {
+    if connection.reward_denominator == 0 {
+        (connection.tokens_per_5gb, MB_PER_5GB)
+    } else {
+        (connection.reward_numerator, connection.reward_denominator)
+    }
+}
+
+/// Computes the ARKHAM reward for one proof:
+/// mb * reward_numerator * (10000 + boost) / (reward_denominator * 10000)
+/// - the whole product is divided once, so the result is rounded down only at the end
+/// - the boost (capped at MAX_PREMIUM_REWARD_BOOST_BPS) applies only to ranked wardens
+/// - the result is capped at `max_reward_per_proof` (0 = uncapped)
+///
+/// All intermediate math is u128; a result that still doesn't fit u64 after the cap
+/// fails with RewardOverflow instead of wrapping or saturating.
+pub fn compute_arkham_reward(
+    mb_consumed: u64,
+    reward_numerator: u64,
+    reward_denominator: u64,
+    boost_bps: u16,
+    is_ranked: bool,
+    max_reward_per_proof: u64,
+) -> Result<u64> {
+    require!(reward_denominator > 0, ArkhamErrorCode::InvalidRewardRate);
+
+    let boost_bps = if is_ranked { boost_bps.min(MAX_PREMIUM_REWARD_BOOST_BPS) } else { 0 };
+    let boosted = (mb_consumed as u128)
+        .checked_mul(reward_numerator as u128)
+        .and_then(|v| v.checked_mul(10000 + boost_bps as u128))
+        .ok_or(ArkhamErrorCode::RewardOverflow)?
+        / (reward_denominator as u128 * 10000);
+
+    let capped = if max_reward_per_proof > 0 {
+        boosted.min(max_reward_per_proof as u128)
+    } else {
+        boosted
+    };
+
+    u64::try_from(capped).map_err(|_| ArkhamErrorCode::RewardOverflow.into())
+}
+
+/// Places a warden's running earnings on the leaderboard if they rank in the top N
+/// Entries stay sorted highest first; the lowest entry is evicted when full. O(N).
+pub fn update_top_earners(entries: &mut Vec<EarnerEntry>, warden: Pubkey, total_earnings: u64) {
+    if let Some(index) = entries.iter().position(|e| e.warden == warden) {
+        entries.remove(index);
+    } else if entries.len() >= TOP_EARNERS_CAPACITY
+        && entries.last().is_some_and(|lowest| total_earnings <= lowest.total_earnings)
+    {
+        return;
+    }
+
+    let position = entries
+        .iter()
+        .position(|e| total_earnings > e.total_earnings)
+        .unwrap_or(entries.len());
+    entries.insert(position, EarnerEntry { warden, total_earnings });
+    entries.truncate(TOP_EARNERS_CAPACITY);
+}
+
+/// Opens a connection, settles one co-signed proof and ends it in a single instruction
+/// Meant for small fixed-size sessions: escrow is sized to the proof, and every check of
+/// start_connection, submit_bandwidth_proof and end_connection applies. The proof is
+/// signed over create_quick_session_proof_message so it can't be replayed into another
+/// session between the same pair.
+pub fn quick_session_handler(
+    ctx: Context<QuickSession>,
+    mb_consumed: u64,
+    timestamp: i64,
+    seeker_signature: [u8; 64],
+    warden_signature: [u8; 64],
+) -> Result<ConnectionSummary> {
+    let connection_key = ctx.accounts.connection.key();
+    let clock = Clock::get()?;
+    let rent_exempt_minimum = Rent::get()?.minimum_balance(ctx.accounts.seeker.to_account_info().data_len());
+
+    // 0. The Ed25519 verification instructions must be present before any content is checked
+    crate::instructions::bandwidth::ensure_signature_verification_present(
+        &ctx.accounts.instructions_sysvar,
+        &[0, 1],
+    )?;
+
+    // 1. Validate the proof; the session's bandwidth was measured no later than its timestamp
+    crate::instructions::bandwidth::validate_bandwidth_proof(
+        mb_consumed,
+        timestamp,
+        clock.unix_timestamp,
+        timestamp,
+        &seeker_signature,
+        &warden_signature,
+    )?;
+
+    // 2. Open the connection with escrow for exactly this proof, started when it was measured
+    let mut connection = open_connection(
+        &ctx.accounts.protocol_config,
+        &mut ctx.accounts.warden,
+        &mut ctx.accounts.seeker,
+        ctx.accounts.region_index.as_deref().map(|region_index| &**region_index),
+        mb_consumed,
+        rent_exempt_minimum,
+        timestamp,
+    )?;
+
+    // 3. Both parties signed this session's proof
+    let proof_message = crate::instructions::bandwidth::create_quick_session_proof_message(
+        &connection_key,
+        connection.connection_id,
+        mb_consumed,
+        timestamp,
+    );
+    crate::instructions::bandwidth::verify_dual_signatures(
+        &ctx.accounts.instructions_sysvar,
+        &proof_message,
+        &seeker_signature,
+        &ctx.accounts.seeker.authority,
+        &warden_signature,
+        &ctx.accounts.warden.authority,
+    )?;
+
+    // 4. Settle the proof exactly as submit_bandwidth_proof would
+    let insurance_fund = ctx.accounts.insurance_fund.as_ref().map(|fund| fund.to_account_info());
+    settle_bandwidth_proof(
+        ProofSettlement {
+            connection_key,
+            connection: &mut connection,
+            warden: &mut ctx.accounts.warden,
+            seeker: &mut ctx.accounts.seeker,
+            config: &ctx.accounts.protocol_config,
+            rewards_vault: &ctx.accounts.rewards_vault.to_account_info(),
+            treasury: &ctx.accounts.treasury,
+            insurance_fund: insurance_fund.as_ref(),
+            top_earners: ctx.accounts.top_earners.as_deref_mut(),
+        },
+        mb_consumed,
+        seeker_signature,
+        warden_signature,
+        clock.unix_timestamp,
+    )?;
+
+    // 5. End it, refunding the unused buffer
+    close_connection(connection_key, &connection, &mut ctx.accounts.warden, &mut ctx.accounts.seeker)
+}
+
+/// Ends a VPN connection and settles final amounts
+/// Returns a ConnectionSummary so composing programs can read the final figures via CPI
+pub fn end_connection_handler(ctx: Context<EndConnection>) -> Result<ConnectionSummary> {
+    // Note: Connection account will be closed automatically via close constraint
+    close_connection(
+        ctx.accounts.connection.key(),
+        &ctx.accounts.connection,
+        &mut ctx.accounts.warden,
+        &mut ctx.accounts.seeker,
+    )
+}
+
+/// Refunds a session's unused escrow and records its outcome against both parties
+/// Shared by end_connection and quick_session; emits ConnectionEnded, preceded by any
+/// proofs the event throttle still held back
+fn close_connection(
+    connection_key: Pubkey,
+    connection: &Connection,
+    warden: &mut Account<Warden>,
+    seeker: &mut Account<Seeker>,
+) -> Result<ConnectionSummary> {
+    // 1. Calculate unused escrow
+    let ended = connection_ended_event(connection)?;
+    let unused_escrow = ended.refunded;
+
+    // 2. Refund unused escrow to seeker and release the connection's commitment
+    if unused_escrow > 0 {
+        seeker.escrow_balance = seeker.escrow_balance
+            .checked_add(unused_escrow)
+            .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+    }
+    // Only the unpaid part is still committed; proofs released the rest as they were paid.
+    // Saturating: connections opened before commitments were tracked never added to it
+    seeker.committed_escrow = seeker.committed_escrow.saturating_sub(unused_escrow);
+
+    // 3. Update warden reputation (only sessions that served bandwidth count as successes)
+    let successful = record_session_outcome(warden, connection.bandwidth_consumed)?;
+
+    // 4. Decrement active connection counters
+    seeker.active_connections = seeker.active_connections
+        .checked_sub(1)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    
+    warden.active_connections = warden.active_connections
+        .checked_sub(1)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+
+    // 5. Update seeker's total consumption and spending
+    seeker.total_bandwidth_consumed = seeker.total_bandwidth_consumed
+        .checked_add(connection.bandwidth_consumed)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    
+    seeker.total_spent = seeker.total_spent
+        .checked_add(connection.amount_paid)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+
+    // 6. Record the session in the seeker's connection history
+    let record = SessionRecord {
+        warden: warden.key(),
+        started_at: connection.started_at,
+        bandwidth_consumed: connection.bandwidth_consumed,
+        amount_paid: connection.amount_paid,
+    };
+    let mut history_head = seeker.history_head;
+    push_session_record(&mut seeker.connection_history, &mut history_head, record);
+    seeker.history_head = history_head;
+
+    // 7. And in the warden's, for dispute context. Wardens created at 8 + 512 have no
+    // room for the ring until their authority grows them with migrate_account
+    if warden.to_account_info().data_len() >= WARDEN_SPACE {
+        let record = WardenSessionRecord {
+            seeker: seeker.key(),
+            started_at: connection.started_at,
+            bandwidth_served: connection.bandwidth_consumed,
+            amount_earned: connection.amount_paid,
+            outcome: if successful { SessionOutcome::Served } else { SessionOutcome::Unserved },
+        };
+        let mut recent_head = warden.recent_connections_head;
+        push_warden_session_record(&mut warden.recent_connections, &mut recent_head, record);
+        warden.recent_connections_head = recent_head;
+    }
+
+    // 8. Report proofs still batched by the event throttle before the session ends
+    if let Some(batch) = unreported_proof_batch(&connection.pending_proof_event) {
+        emit!(proof_batch_event(connection_key, connection, batch));
+    }
+
+    emit!(ended);
+
+    Ok(ConnectionSummary {
+        bandwidth_consumed: connection.bandwidth_consumed,
+        total_paid: connection.amount_paid,
+        refunded: unused_escrow,
+        successful,
+        connection_id: connection.connection_id,
+    })
+}
+
+/// Attributes a settled session to the Warden's connection statistics
+/// A session is a success when it served bandwidth (disputes don't exist yet). A
+/// zero-bandwidth session is neither: the Seeker may simply have disconnected, and
+/// failures are reported through update_reputation. Returns whether it succeeded.
+pub fn record_session_outcome(warden: &mut Warden, bandwidth_consumed: u64) -> Result<bool> {
+    let successful = bandwidth_consumed > 0;
+    if successful {
+        warden.successful_connections = warden.successful_connections
+            .checked_add(1)
+            .ok_or(ArkhamErrorCode::ReputationOverflow)?;
+    }
+    Ok(successful)
+}
+
+/// Writes a proof into a connection's ring buffer, evicting the oldest once full
+pub fn push_bandwidth_proof(
+    proofs: &mut [BandwidthProof; PROOF_HISTORY_CAPACITY],
+    head: &mut u8,
+    count: &mut u8,
+    proof: BandwidthProof,
+) {
+    proofs[*head as usize] = proof;
+    *head = ((*head as usize + 1) % PROOF_HISTORY_CAPACITY) as u8;
+    if (*count as usize) < PROOF_HISTORY_CAPACITY {
+        *count += 1;
+    }
+}
+
+/// Iterates a connection's stored proofs from oldest to newest
+pub fn bandwidth_proofs_chronological(
+    proofs: &[BandwidthProof; PROOF_HISTORY_CAPACITY],
+    head: u8,
+    count: u8,
+) -> impl Iterator<Item = &BandwidthProof> {
+    let start = (head as usize + PROOF_HISTORY_CAPACITY - count as usize) % PROOF_HISTORY_CAPACITY;
+    (0..count as usize).map(move |i| &proofs[(start + i) % PROOF_HISTORY_CAPACITY])
+}
+
+/// Pushes a session onto a seeker's bounded history, overwriting the oldest once full
+pub fn push_session_record(history: &mut Vec<SessionRecord>, head: &mut u8, record: SessionRecord) {
+    if history.len() < SEEKER_HISTORY_CAPACITY {
+        history.push(record);
+    } else {
+        history[*head as usize] = record;
+        *head = ((*head as usize + 1) % SEEKER_HISTORY_CAPACITY) as u8;
+    }
+}
+
+/// Appends a session to a warden's recent connections, overwriting the oldest once full
+pub fn push_warden_session_record(
+    history: &mut Vec<WardenSessionRecord>,
+    head: &mut u8,
+    record: WardenSessionRecord,
+) {
+    if history.len() < WARDEN_HISTORY_CAPACITY {
+        history.push(record);
+    } else {
+        history[*head as usize] = record;
+        *head = ((*head as usize + 1) % WARDEN_HISTORY_CAPACITY) as u8;
+    }
+}
+
+/// Returns a warden's recent connections from oldest to newest
+pub fn warden_history_chronological(history: &[WardenSessionRecord], head: u8) -> Vec<WardenSessionRecord> {
+    let split = if history.len() < WARDEN_HISTORY_CAPACITY { 0 } else { head as usize };
+    history[split..].iter().chain(history[..split].iter()).cloned().collect()
+}
+
+/// Returns a seeker's session history from oldest to newest
+pub fn session_history_chronological(history: &[SessionRecord], head: u8) -> Vec<SessionRecord> {
+    let split = if history.len() < SEEKER_HISTORY_CAPACITY { 0 } else { head as usize };
+    history[split..].iter().chain(history[..split].iter()).cloned().collect()
+}
+
+/// Closes several fully-settled connections for a Warden in one call
+/// `remaining_accounts` holds (connection, seeker) pairs. Each connection must have
+/// paid out its entire escrow; its rent is refunded into the seeker's escrow balance.
+pub fn close_settled_connections_batch_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CloseSettledConnectionsBatch<'info>>,
+) -> Result<()> {
+    let warden = &mut ctx.accounts.warden;
+    let warden_key = warden.key();
+    let remaining_accounts = ctx.remaining_accounts;
+
+    require!(
+        !remaining_accounts.is_empty() && remaining_accounts.len().is_multiple_of(2),
+        ArkhamErrorCode::InvalidBatchAccounts
+    );
+
+    let mut closed_count: u32 = 0;
+    for pair in remaining_accounts.chunks(2) {
+        let connection: Box<Account<'info, Connection>> = Box::new(Account::try_from(&pair[0])?);
+        let mut seeker: Account<'info, Seeker> = Account::try_from(&pair[1])?;
+
+        // 1-3. Verify the pair, require it fully settled, and book it as end_connection would
+        let seeker_key = seeker.key();
+        settle_batch_pair(&connection.key(), &connection, &seeker_key, &mut seeker, &warden_key, warden)?;
+
+        // 4. Close the connection, refunding its rent into the seeker's escrow
+        let rent_refund = connection.to_account_info().lamports();
+        connection.close(seeker.to_account_info())?;
+        seeker.escrow_balance = seeker.escrow_balance
+            .checked_add(rent_refund)
+            .ok_or(ArkhamErrorCode::EscrowOverflow)?;
+        seeker.exit(&crate::ID)?;
+
+        closed_count += 1;
+    }
+
+    emit!(SettledConnectionsClosed {
+        warden: warden_key,
+        closed_count,
+    });
+
+    Ok(())
+}
+
+/// Checks one (connection, seeker) pair of a batch close and applies end_connection's
+/// bookkeeping to both parties; the caller closes the connection account afterwards
+/// - the connection must be the pair's PDA under `warden_key` and name both parties
+/// - only connections whose escrow is fully paid out qualify
+pub fn settle_batch_pair(
+    connection_key: &Pubkey,
+    connection: &Connection,
+    seeker_key: &Pubkey,
+    seeker: &mut Seeker,
+    warden_key: &Pubkey,
+    warden: &mut Warden,
+) -> Result<()> {
+    // 1. Verify the pair belongs together and to this warden
+    let (expected_connection, _) = Pubkey::find_program_address(
+        &[b"connection", seeker_key.as_ref(), warden_key.as_ref()],
+        &crate::ID,
+    );
+    require!(
+        *connection_key == expected_connection
+            && connection.warden == *warden_key
+            && connection.seeker == *seeker_key,
+        ArkhamErrorCode::ConnectionAccountMismatch
+    );
+
+    // 2. Only fully-settled connections can be closed here
+    require!(
+        unused_connection_escrow(connection.amount_escrowed, connection.amount_paid)? == 0,
+        ArkhamErrorCode::ConnectionNotSettled
+    );
+
+    // 3. Same bookkeeping as end_connection; settlement already released the commitment
+    seeker.active_connections = seeker.active_connections.saturating_sub(1);
+    seeker.total_bandwidth_consumed = seeker.total_bandwidth_consumed
+        .checked_add(connection.bandwidth_consumed)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    seeker.total_spent = seeker.total_spent
+        .checked_add(connection.amount_paid)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+
+    warden.active_connections = warden.active_connections.saturating_sub(1);
+    record_session_outcome(warden, connection.bandwidth_consumed)?;
+
+    Ok(())
+}
+
+/// Returns the escrow locked on a connection that was not paid out to the Warden
+pub fn unused_connection_escrow(amount_escrowed: u64, amount_paid: u64) -> Result<u64> {
+    amount_escrowed
+        .checked_sub(amount_paid)
+        .ok_or(ArkhamErrorCode::EscrowOverflow.into())
+}
+
+/// Splits a connection's refund into (buffer_refunded, unused_bandwidth_refunded)
+/// Payments draw on the escrow for the estimated bandwidth first, so buffer is only
+/// consumed once the estimate was exceeded; the two parts sum to the unused escrow
+pub fn split_connection_refund(amount_escrowed: u64, amount_paid: u64, escrow_buffer: u64) -> (u64, u64) {
+    let committed = amount_escrowed.saturating_sub(escrow_buffer);
+    let unused_bandwidth_refunded = committed.saturating_sub(amount_paid);
+    let buffer_refunded = amount_escrowed.saturating_sub(amount_paid.max(committed));
+    (buffer_refunded, unused_bandwidth_refunded)
+}
+
+/// Emits a checkpoint of a Warden's running earnings totals for accounting exports
+/// Permissionless and read-only: indexers diff consecutive snapshots into statements
+pub fn snapshot_warden_earnings_handler(ctx: Context<SnapshotWardenEarnings>) -> Result<()> {
+    let clock = Clock::get()?;
+    emit!(warden_earnings_snapshot(&ctx.accounts.warden, clock.unix_timestamp));
+    Ok(())
+}
+
+/// Builds the WardenEarningsSnapshot event for `warden` at `timestamp`
+pub fn warden_earnings_snapshot(warden: &Warden, timestamp: i64) -> WardenEarningsSnapshot {
+    WardenEarningsSnapshot {
+        warden: warden.authority,
+        timestamp,
+        total_earnings: warden.total_earnings,
+        total_bandwidth_served: warden.total_bandwidth_served,
+        arkham_tokens_earned: warden.arkham_tokens_earned,
+        pending_claims: warden.pending_claims,
+        total_slashed: warden.total_slashed,
+        slash_count: warden.slash_count,
+    }
+}
+
+/// Claims accumulated earnings for a Warden
+pub fn claim_earnings_handler(
+    ctx: Context<ClaimEarnings>,
+    use_private: bool,
+) -> Result<()> {
+    let warden = &mut ctx.accounts.warden;
+
+    // 1. Verify there are earnings to claim
+    require!(
+        warden.pending_claims > 0,
+        ArkhamErrorCode::NothingToClaim
+    );
+
+    let amount = warden.pending_claims;
+
+    if use_private {
+        // TODO: Implement Elusiv CPI for private withdrawals
+        return err!(ArkhamErrorCode::PrivatePaymentsNotImplemented);
+    } else {
+        // Public claim: Transfer from the rewards vault to warden's authority
+        // Stake principal lives in the stake vault and is never touched here
+        let vault_seeds = &[b"rewards_vault".as_ref(), &[ctx.bumps.rewards_vault]];
+        let signer_seeds = &[&vault_seeds[..]];
+
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.rewards_vault.to_account_info(),
+                to: ctx.accounts.authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        system_program::transfer(cpi_context, amount)?;
+    }
+
+    // 2. Reset pending claims
+    warden.pending_claims = 0;
+
+    emit!(EarningsClaimed {
+        authority: warden.authority,
+        amount,
+        use_private,
+    });
+
+    Ok(())
+}
+
+/// Claims earned ARKHAM tokens
+pub fn claim_arkham_tokens_handler(ctx: Context<ClaimArkhamTokens>) -> Result<()> {
+    let warden = &mut ctx.accounts.warden;
+    let config = &mut ctx.accounts.protocol_config;
+
+    // 1. Verify there are tokens to claim
+    require!(
+        warden.arkham_tokens_earned > 0,
+        ArkhamErrorCode::NothingToClaim
+    );
+
+    // 1a. Near the supply cap the claim is partially filled; the rest stays earned
+    let amount = arkham_mint_allowance(config, ctx.accounts.arkham_mint.supply, warden.arkham_tokens_earned);
+    require!(amount > 0, ArkhamErrorCode::ArkhamSupplyExhausted);
+
+    // 2. Verify ARKHAM mint is initialized and still mintable
+    ensure_arkham_mintable(config)?;
+
+    // 3. Mint tokens to warden's token account using PDA authority
+    let bump = [ctx.bumps.mint_authority];
+    let seeds = mint_authority_signer_seeds(&bump);
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = MintTo {
+        mint: ctx.accounts.arkham_mint.to_account_info(),
+        to: ctx.accounts.warden_arkham_token_account.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::mint_to(cpi_context, amount)?;
+    record_arkham_minted(config, amount)?;
+
+    // 4. Deduct the minted tokens; anything over the cap remains claimable
+    warden.arkham_tokens_earned = warden.arkham_tokens_earned
+        .checked_sub(amount)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+
+    emit!(TokensClaimed {
+        authority: warden.authority,
+        amount,
+        unfilled: warden.arkham_tokens_earned,
+    });
+
+    Ok(())
+}
+
+/// Reports how much ARKHAM may still be minted under max_arkham_supply, as return data
+/// Lets clients warn before a claim that would only be partially filled
+pub fn query_arkham_minting_headroom_handler(
+    ctx: Context<QueryArkhamMintingHeadroom>,
+) -> Result<ArkhamMintingHeadroom> {
+    let mint_supply = ctx.accounts.arkham_mint.as_ref().map_or(0, |mint| mint.supply);
+    Ok(arkham_minting_headroom(&ctx.accounts.protocol_config, mint_supply))
+}
+
+/// Minted total, cap and remaining headroom; headroom is u64::MAX less the
+/// minted total when the supply is uncapped
+///
+/// The minted total is the larger of the tracked total and the mint's current
+/// supply, so tokens minted before tracking began still count against the cap.
+pub fn arkham_minting_headroom(config: &ProtocolConfig, mint_supply: u64) -> ArkhamMintingHeadroom {
+    let cap = if config.max_arkham_supply == 0 { u64::MAX } else { config.max_arkham_supply };
+    let total_minted = config.arkham_total_minted.max(mint_supply);
+    ArkhamMintingHeadroom {
+        total_minted,
+        max_supply: config.max_arkham_supply,
+        remaining_headroom: cap.saturating_sub(total_minted),
+    }
+}
+
+/// How much of `requested` may be minted without exceeding max_arkham_supply
+pub fn arkham_mint_allowance(config: &ProtocolConfig, mint_supply: u64, requested: u64) -> u64 {
+    requested.min(arkham_minting_headroom(config, mint_supply).remaining_headroom)
+}
+
+/// Checks the ARKHAM mint exists and its authority hasn't been renounced
+pub fn ensure_arkham_mintable(config: &ProtocolConfig) -> Result<()> {
+    require!(
+        config.arkham_token_mint != Pubkey::default(),
+        ArkhamErrorCode::TokenMintNotInitialized
+    );
+    require!(
+        !config.arkham_minting_renounced,
+        ArkhamErrorCode::ArkhamMintingRenounced
+    );
+    Ok(())
+}
+
+/// Adds `amount` to the minted total tracked against max_arkham_supply
+pub fn record_arkham_minted(config: &mut ProtocolConfig, amount: u64) -> Result<()> {
+    config.arkham_total_minted = config.arkham_total_minted
+        .checked_add(amount)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?;
+    Ok(())
+}
+
+/// Claims vested subsidized ARKHAM tokens from the subsidy vault
+/// Independent of claim_arkham_tokens: organic earnings are never touched here
+pub fn claim_subsidized_arkham_tokens_handler(ctx: Context<ClaimSubsidizedArkhamTokens>) -> Result<()> {
+    let warden = &mut ctx.accounts.warden;
+    let config = &ctx.accounts.protocol_config;
+    let clock = Clock::get()?;
+
+    // 1. Compute the vested, not yet claimed amount
+    let amount = claimable_subsidy(
+        warden.arkham_tokens_subsidized,
+        warden.subsidy_vested_carry,
+        warden.arkham_subsidy_claimed,
+        warden.subsidy_vesting_start,
+        config.subsidy_vesting_duration,
+        clock.unix_timestamp,
+    )?;
+    require!(
+        amount > 0,
+        ArkhamErrorCode::NothingToClaim
+    );
+
+    // 2. Verify ARKHAM mint is initialized
+    require!(
+        config.arkham_token_mint != Pubkey::default(),
+        ArkhamErrorCode::TokenMintNotInitialized
+    );
+
+    // 3. Pay out of the subsidy vault using PDA authority
+    // Subsidies are funded when granted, so this works even after minting is renounced
+    let bump = [ctx.bumps.mint_authority];
+    let seeds = mint_authority_signer_seeds(&bump);
+    let signer_seeds = &[&seeds[..]];
+
+    let cpi_accounts = token::Transfer {
+        from: ctx.accounts.subsidy_vault.to_account_info(),
+        to: ctx.accounts.warden_arkham_token_account.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+    token::transfer(cpi_context, amount)?;
+
+    // 4. Record the claim against the subsidized bucket
+    warden.arkham_subsidy_claimed = warden.arkham_subsidy_claimed
+        .checked_add(amount)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?;
+
+    emit!(SubsidizedTokensClaimed {
+        authority: warden.authority,
+        amount,
+        total_claimed: warden.arkham_subsidy_claimed,
+    });
+
+    Ok(())
+}
+
+/// Returns the vested portion of subsidized ARKHAM that has not been claimed yet
+/// `vested_carry` is vested outright; the rest vests linearly from `vesting_start`
+/// over `vesting_duration`, and a zero duration vests immediately
+pub fn claimable_subsidy(
+    total_subsidized: u64,
+    vested_carry: u64,
+    already_claimed: u64,
+    vesting_start: i64,
+    vesting_duration: i64,
+    current_timestamp: i64,
+) -> Result<u64> {
+    let vesting = total_subsidized.saturating_sub(vested_carry);
+    let elapsed = current_timestamp.saturating_sub(vesting_start).max(0);
+    let vested = if vesting_duration <= 0 || elapsed >= vesting_duration {
+        vesting
+    } else {
+        ((vesting as u128)
+            .checked_mul(elapsed as u128)
+            .ok_or(ArkhamErrorCode::RewardOverflow)?
+            / vesting_duration as u128) as u64
+    };
+    Ok((vested_carry + vested).saturating_sub(already_claimed))
+}
+
+// Account contexts:
+
+#[derive(Accounts)]
+pub struct DepositEscrow<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SEEKER_SPACE,
+        seeds = [b"seeker", authority.key().as_ref()],
+        bump
+    )]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"seeker", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAllowedWardens<'info> {
+    #[account(
+        mut,
+        seeds = [b"seeker", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub seeker: Account<'info, Seeker>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPrivateEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"seeker", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub seeker: Account<'info, Seeker>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QueryPrivateEscrow<'info> {
+    pub seeker: Account<'info, Seeker>,
+}
+
+#[derive(Accounts)]
+pub struct ExpirePremium<'info> {
+    #[account(
+        mut,
+        seeds = [b"seeker", seeker.authority.as_ref()],
+        bump,
+    )]
+    pub seeker: Account<'info, Seeker>,
+}
+
+#[derive(Accounts)]
+pub struct StakeIdleEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"seeker", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// The protocol's escrow yield vault (PDA)
+    #[account(mut, seeds = [b"escrow_yield_vault"], bump)]
+    pub escrow_yield_vault: SystemAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnwindStakedEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"seeker", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The protocol's escrow yield vault (PDA)
+    #[account(mut, seeds = [b"escrow_yield_vault"], bump)]
+    pub escrow_yield_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct StartConnection<'info> {
+    #[account(
+        init,
+        payer = seeker_authority,
+        space = 8 + 32 + 32 + 8 + 8 + 8 + (PROOF_HISTORY_CAPACITY * (8 + 8 + 64 + 64)) + 1 + 1 + 8 + 8 + 8 + 2 + 8 + 8 + 8 + 8 + 2 + 1 + PendingProofEvent::SPACE + 8 + 4 + 8 + 8 + 8,
+        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
+        bump
+    )]
+    pub connection: Box<Account<'info, Connection>>,
+
+    #[account(mut)]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub seeker_authority: Signer<'info>,
+
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Per-region active warden counts; required while min_wardens_per_region is set
+    #[account(seeds = [b"region_index"], bump)]
+    pub region_index: Option<Box<Account<'info, RegionIndex>>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct QueryArkhamMintingHeadroom<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Omitted before the ARKHAM mint is initialized
+    #[account(seeds = [b"arkham_mint"], bump)]
+    pub arkham_mint: Option<Account<'info, Mint>>,
+}
+
+#[derive(Accounts)]
+pub struct EstimateSessionCost<'info> {
+    pub warden: Account<'info, Warden>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct ReduceConnectionEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"connection", seeker.key().as_ref(), connection.warden.as_ref()],
+        bump,
+        has_one = seeker
+    )]
+    pub connection: Box<Account<'info, Connection>>,
+
+    #[account(
+        mut,
+        seeds = [b"seeker", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub seeker: Account<'info, Seeker>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitBandwidthProof<'info> {
+    #[account(
+        mut,
+        seeds = [b"connection", connection.seeker.as_ref(), connection.warden.as_ref()],
+        bump,
+        has_one = warden,
+        has_one = seeker
+    )]
+    pub connection: Box<Account<'info, Connection>>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub seeker: Account<'info, Seeker>,
+
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Instructions sysvar for Ed25519 verification
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Rewards vault (PDA) that receives each proof's payment from the seeker's escrow
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    /// CHECK: Treasury receiving the protocol fee on each proof, must match the protocol config
+    #[account(mut, address = protocol_config.treasury)]
+    pub treasury: AccountInfo<'info>,
+
+    /// Insurance fund (PDA) receiving its slice of the protocol fee; required while insurance_bps is set
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Option<SystemAccount<'info>>,
+
+    /// Either seeker or warden can submit proofs
+    pub submitter: Signer<'info>,
+
+    /// Optional earnings leaderboard, updated when provided
+    #[account(mut, seeds = [b"top_earners"], bump)]
+    pub top_earners: Option<Account<'info, TopEarners>>,
+}
+
+#[derive(Accounts)]
+pub struct AuthorizeWardenSettlement<'info> {
+    #[account(
+        mut,
+        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
+        bump,
+        has_one = warden,
+        has_one = seeker
+    )]
+    pub connection: Box<Account<'info, Connection>>,
+
+    pub warden: Account<'info, Warden>,
+
+    #[account(has_one = authority)]
+    pub seeker: Account<'info, Seeker>,
+
+    /// The seeker's authority, granting the delegation
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetWardenSpendLimit<'info> {
+    #[account(
+        mut,
+        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
+        bump,
+        has_one = warden,
+        has_one = seeker
+    )]
+    pub connection: Box<Account<'info, Connection>>,
+
+    pub warden: Account<'info, Warden>,
+
+    #[account(has_one = authority)]
+    pub seeker: Account<'info, Seeker>,
+
+    /// The seeker's authority, setting the limit
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SlaRefund<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
+        bump,
+        has_one = warden,
+        has_one = seeker
+    )]
+    pub connection: Box<Account<'info, Connection>>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub seeker: Account<'info, Seeker>,
+
+    /// The seeker's authority, claiming the refund (checked by plan_sla_refund)
+    pub authority: Signer<'info>,
+
+    /// Rewards vault (PDA) holding the warden's unclaimed earnings
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    /// Insurance fund (PDA); when supplied, covers what the warden's earnings can't
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Option<SystemAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EndConnection<'info> {
+    #[account(
+        mut,
+        seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()],
+        bump,
+        close = seeker_authority  // Refund rent to seeker
+    )]
+    pub connection: Box<Account<'info, Connection>>,
+
+    #[account(mut)]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub seeker_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct QuickSession<'info> {
+    /// The pair's Connection PDA; it must not hold an open connection
+    #[account(seeds = [b"connection", seeker.key().as_ref(), warden.key().as_ref()], bump)]
+    pub connection: SystemAccount<'info>,
+
+    #[account(mut, constraint = seeker.authority == seeker_authority.key())]
+    pub seeker: Account<'info, Seeker>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    /// The seeker's authority, spending its escrow on this session
+    pub seeker_authority: Signer<'info>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Per-region active warden counts; required while min_wardens_per_region is set
+    #[account(seeds = [b"region_index"], bump)]
+    pub region_index: Option<Box<Account<'info, RegionIndex>>>,
+
+    /// CHECK: Instructions sysvar for Ed25519 verification
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Rewards vault (PDA) that receives the proof's payment from the seeker's escrow
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    /// CHECK: Treasury receiving the protocol fee on the proof, must match the protocol config
+    #[account(mut, address = protocol_config.treasury)]
+    pub treasury: AccountInfo<'info>,
+
+    /// Insurance fund (PDA) receiving its slice of the protocol fee; required while insurance_bps is set
+    #[account(mut, seeds = [b"insurance_fund"], bump)]
+    pub insurance_fund: Option<SystemAccount<'info>>,
+
+    /// Optional earnings leaderboard, updated when provided
+    #[account(mut, seeds = [b"top_earners"], bump)]
+    pub top_earners: Option<Account<'info, TopEarners>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSettledConnectionsBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"warden", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub warden: Account<'info, Warden>,
+
+    pub authority: Signer<'info>,
+    // remaining_accounts: (connection, seeker) pairs, both writable
+}
+
+#[derive(Accounts)]
+pub struct SnapshotWardenEarnings<'info> {
+    pub warden: Account<'info, Warden>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimEarnings<'info> {
+    #[account(
+        mut,
+        seeds = [b"warden", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Rewards vault (PDA) holding earned lamports; stake principal is not reachable here
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimArkhamTokens<'info> {
+    #[account(
+        mut,
+        seeds = [b"warden", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"arkham_mint"],
+        bump,
+    )]
+    pub arkham_mint: Account<'info, Mint>,
+
+    /// Created on a first-time claim, paid for by the authority
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = arkham_mint,
+        associated_token::authority = authority,
+    )]
+    pub warden_arkham_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Mint authority for the ARKHAM token - PDA controlled by the program
+    #[account(
+        seeds = [MINT_AUTHORITY_SEEDS[0], MINT_AUTHORITY_SEEDS[1], MINT_AUTHORITY_SEEDS[2]],
+        bump,
+    )]
+    pub mint_authority: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSubsidizedArkhamTokens<'info> {
+    #[account(
+        mut,
+        seeds = [b"warden", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        seeds = [b"arkham_mint"],
+        bump,
+    )]
+    pub arkham_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = arkham_mint,
+        associated_token::authority = authority,
+    )]
+    pub warden_arkham_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Mint authority for the ARKHAM token - PDA controlled by the program
+    #[account(
+        seeds = [MINT_AUTHORITY_SEEDS[0], MINT_AUTHORITY_SEEDS[1], MINT_AUTHORITY_SEEDS[2]],
+        bump,
+    )]
+    pub mint_authority: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = arkham_mint,
+        associated_token::authority = mint_authority,
+    )]
+    pub subsidy_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Return data for query_arkham_minting_headroom
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ArkhamMintingHeadroom {
+    pub total_minted: u64,
+    pub max_supply: u64, // 0 = uncapped
+    pub remaining_headroom: u64,
+}
+
+/// Return data for estimate_session_cost, all in lamports except the rate
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SessionCostEstimate {
+    pub rate_per_mb: u64, // lamports per MB
+    pub base_cost: u64, // estimated_mb at rate_per_mb
+    pub buffer: u64, // escrow locked on top of base_cost, refunded if unused
+    pub expected_fee: u64, // protocol_fee_bps share of base_cost
+    pub total_escrow: u64, // what start_connection locks: base_cost + buffer
+}
+
+/// Return data for end_connection
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionSummary {
+    pub bandwidth_consumed: u64, // in megabytes
+    pub total_paid: u64, // in lamports
+    pub refunded: u64, // in lamports
+    pub successful: bool,
+    pub connection_id: u64,
+}
+
+// Events:
+
+#[event]
+pub struct EscrowDeposited {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub use_private: bool,
+}
+
+#[event]
+pub struct EscrowWithdrawn {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub lamports_returned: u64,
+    pub closed: bool,
+}
+
+#[event]
+pub struct AllowedWardensUpdated {
+    pub authority: Pubkey,
+    pub allowed_wardens: Option<Vec<Pubkey>>,
+}
+
+#[event]
+pub struct PremiumExpired {
+    pub seeker: Pubkey,
+    pub authority: Pubkey,
+    pub expired_at: i64,
+}
+
+#[event]
+pub struct IdleEscrowStaked {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub staked_escrow: u64,
+}
+
+#[event]
+pub struct StakedEscrowUnwound {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub staked_escrow: u64,
+}
+
+#[event]
+pub struct PrivateEscrowUpdated {
+    pub authority: Pubkey,
+    pub private_escrow: Option<Pubkey>,
+}
+
+#[event]
+pub struct ConnectionStarted {
+    pub connection_id: u64,
+    pub seeker: Pubkey,
+    pub warden: Pubkey,
+    pub estimated_mb: u64,
+    pub rate_per_mb: u64,
+    pub escrow_amount: u64,
+}
+
+#[event]
+pub struct EscrowBufferReleased {
+    pub connection: Pubkey,
+    pub connection_id: u64,
+    pub seeker: Pubkey,
+    pub released: u64, // lamports returned to the seeker's escrow_balance
+    pub remaining_buffer: u64,
+}
+
+#[event]
+pub struct ConnectionEscrowReduced {
+    pub connection: Pubkey,
+    pub connection_id: u64,
+    pub seeker: Pubkey,
+    pub new_amount_escrowed: u64,
+    pub freed: u64,
+}
+
+#[event]
+pub struct BandwidthProofSubmitted {
+    pub connection: Pubkey,
+    pub connection_id: u64,
+    pub proof_count: u16, // proofs covered; more than 1 when proof events are throttled
+    pub mb_consumed: u64,
+    pub payment_amount: u64,
+    pub arkham_earned: u64,
+}
+
+/// A settled proof's locked rate differs from the live recomputation by more than
+/// rate_check_tolerance_bps; informational, the proof was still paid at the locked rate
+#[event]
+pub struct ConnectionRateDivergence {
+    pub connection: Pubkey,
+    pub connection_id: u64,
+    pub warden: Pubkey,
+    pub stored_rate_per_mb: u64,
+    pub expected_rate_per_mb: u64,
+    pub divergence_bps: u64,
+}
+
+#[event]
+pub struct WardenSettlementAuthorized {
+    pub connection: Pubkey,
+    pub connection_id: u64,
+    pub seeker: Pubkey,
+    pub warden: Pubkey,
+    pub max_mb: u64,
+    pub already_settled_mb: u64,
+}
+
+#[event]
+pub struct WardenSpendLimitSet {
+    pub connection: Pubkey,
+    pub connection_id: u64,
+    pub seeker: Pubkey,
+    pub warden: Pubkey,
+    pub max_spend: u64,
+    pub amount_paid: u64,
+}
+
+#[event]
+pub struct ConnectionEnded {
+    pub connection_id: u64,
+    pub seeker: Pubkey,
+    pub warden: Pubkey,
+    pub bandwidth_consumed: u64,
+    pub total_paid: u64,
+    pub refunded: u64,
+    pub buffer_refunded: u64, // part of refunded that was never-used buffer
+    pub unused_bandwidth_refunded: u64, // part of refunded for estimated bandwidth not consumed
+}
+
+#[event]
+pub struct SettledConnectionsClosed {
+    pub warden: Pubkey,
+    pub closed_count: u32,
+}
+
+#[event]
+pub struct EarningsClaimed {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub use_private: bool,
+}
+
+#[event]
+pub struct WardenEarningsSnapshot {
+    pub warden: Pubkey,
+    pub timestamp: i64,
+    pub total_earnings: u64, // in lamports
+    pub total_bandwidth_served: u64, // in megabytes
+    pub arkham_tokens_earned: u64,
+    pub pending_claims: u64, // in lamports
+    pub total_slashed: u64,
+    pub slash_count: u32,
+}
+
+#[event]
+pub struct SlaRefunded {
+    pub connection: Pubkey,
+    pub connection_id: u64,
+    pub seeker: Pubkey,
+    pub warden: Pubkey,
+    pub uptime_at_start: u16, // basis points
+    pub reported_uptime: u16, // basis points, the sub-SLA report
+    pub refund: u64, // in lamports
+    pub from_insurance: u64, // part of the refund the insurance fund covered
+}
+
+#[event]
+pub struct TokensClaimed {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub unfilled: u64, // earned tokens left unclaimed because the supply cap was reached
+}
+
+#[event]
+pub struct SubsidizedTokensClaimed {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_park_and_unwind_idle_escrow() {
+        // Park 600 of 1000 lamports of idle escrow
+        let (escrow, staked) = park_idle_escrow(1_000, 0, 600).unwrap();
+        assert_eq!((escrow, staked), (400, 600));
+
+        // A connection needing 900 lamports must unwind first
+        assert!(escrow < 900);
+        let (escrow, staked) = unwind_idle_escrow(escrow, staked, 500).unwrap();
+        assert_eq!((escrow, staked), (900, 100));
+
+        // Total escrow is conserved across park/unwind
+        assert_eq!(escrow + staked, 1_000);
+    }
+
+    #[test]
+    fn test_rapid_connection_starts_cannot_overdraw_escrow() {
+        let mut escrow_balance = 1_000;
+        let escrow_needed = calculate_escrow_needed(5, 100).unwrap();
+        assert_eq!(escrow_needed, 550);
+
+        // The first start locks its escrow
+        escrow_balance = lock_connection_escrow(escrow_balance, escrow_needed).unwrap();
+        assert_eq!(escrow_balance, 450);
+
+        // The second sees the reduced balance and fails without underflowing
+        assert_eq!(
+            lock_connection_escrow(escrow_balance, escrow_needed).unwrap_err(),
+            ArkhamErrorCode::InsufficientEscrow.into()
+        );
+        assert_eq!(escrow_balance, 450);
+
+        // Exactly draining the balance is fine
+        assert_eq!(lock_connection_escrow(escrow_balance, 450).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_draining_warden_rejects_new_connections() {
+        let mut warden = crate::state::test_warden();
+        assert!(ensure_warden_accepting_connections(&warden).is_ok());
+
+        // unstake_warden
+        warden.unstake_requested_at = Some(1_000);
+        warden.draining = true;
+        assert_eq!(
+            ensure_warden_accepting_connections(&warden).unwrap_err(),
+            ArkhamErrorCode::WardenDraining.into()
+        );
+
+        // cancel_unstake
+        warden.unstake_requested_at = None;
+        warden.draining = false;
+        assert!(ensure_warden_accepting_connections(&warden).is_ok());
+    }
+
+    #[test]
+    fn test_availability_schedule_gates_new_connections() {
+        // Monday 2024-01-01 00:00 UTC
+        let monday = 1_704_067_200i64;
+        assert_eq!(utc_weekday_and_hour(monday), (0, 0));
+        assert_eq!(utc_weekday_and_hour(monday + 6 * 86_400 + 23 * 3_600), (6, 23));
+
+        // Always available by default
+        let mut warden = crate::state::test_warden();
+        assert!(ensure_warden_available(&warden.availability, monday + 12 * 3_600).is_ok());
+
+        // Overnight residential bandwidth: 22:00-06:00 every day
+        let overnight = 0b1100_0000_0000_0000_0011_1111u32;
+        crate::instructions::staking::validate_availability_schedule(&[overnight; 7]).unwrap();
+        warden.availability = Some([overnight; 7]);
+
+        for hour in [22i64, 23, 24, 29] {
+            assert!(ensure_warden_available(&warden.availability, monday + hour * 3_600).is_ok());
+        }
+        for hour in [6i64, 12, 21] {
+            assert_eq!(
+                ensure_warden_available(&warden.availability, monday + hour * 3_600).unwrap_err(),
+                ArkhamErrorCode::WardenUnavailable.into()
+            );
+        }
+
+        // Weekends only: a weekday at any hour is outside the window
+        let mut weekends = [0u32; 7];
+        weekends[5] = 0x00FF_FFFF;
+        weekends[6] = 0x00FF_FFFF;
+        warden.availability = Some(weekends);
+        assert!(ensure_warden_available(&warden.availability, monday + 5 * 86_400 + 3_600).is_ok());
+        assert!(ensure_warden_available(&warden.availability, monday + 2 * 86_400).is_err());
+
+        // Schedules must name real hours and at least one of them
+        let mut invalid = [0u32; 7];
+        assert!(crate::instructions::staking::validate_availability_schedule(&invalid).is_err());
+        invalid[0] = 1 << 24;
+        assert_eq!(
+            crate::instructions::staking::validate_availability_schedule(&invalid).unwrap_err(),
+            ArkhamErrorCode::InvalidAvailabilitySchedule.into()
+        );
+    }
+
+    #[test]
+    fn test_record_session_outcome() {
+        let mut warden = crate::state::test_warden();
+
+        // A session that served nothing is not a success
+        assert!(!record_session_outcome(&mut warden, 0).unwrap());
+        assert_eq!(warden.successful_connections, 0);
+        assert_eq!(warden.failed_connections, 0);
+
+        assert!(record_session_outcome(&mut warden, 250).unwrap());
+        assert_eq!(warden.successful_connections, 1);
+    }
+
+    #[test]
+    fn test_connection_summary_return_data() {
+        use anchor_lang::solana_program::program::MAX_RETURN_DATA;
+
+        let summary = ConnectionSummary {
+            bandwidth_consumed: u64::MAX,
+            total_paid: u64::MAX,
+            refunded: u64::MAX,
+            successful: true,
+            connection_id: u64::MAX,
+        };
+        let data = summary.try_to_vec().unwrap();
+        assert!(data.len() <= MAX_RETURN_DATA);
+
+        // What a CPI caller decodes from get_return_data
+        assert_eq!(ConnectionSummary::try_from_slice(&data).unwrap(), summary);
+    }
+
+    #[test]
+    fn test_clear_expired_premium() {
+        // Lapsed subscription is cleared and reported
+        let mut premium = Some(1_000);
+        assert_eq!(clear_expired_premium(&mut premium, 1_001), Some(1_000));
+        assert_eq!(premium, None);
+
+        // Active subscription is left alone
+        let mut premium = Some(1_000);
+        assert_eq!(clear_expired_premium(&mut premium, 1_000), None);
+        assert_eq!(premium, Some(1_000));
+
+        // Nothing to expire
+        let mut premium = None;
+        assert_eq!(clear_expired_premium(&mut premium, 1_000), None);
+    }
+
+    #[test]
+    fn test_premium_seekers_only_warden() {
+        let now = 1_000_000;
+        let mut warden = crate::state::test_warden();
+        warden.premium_seekers_only = true;
+
+        // Non-premium seekers are turned away: never subscribed, or lapsed
+        for expires_at in [None, Some(now - 1)] {
+            assert_eq!(
+                ensure_seeker_admitted_by_warden(warden.premium_seekers_only, expires_at, now).unwrap_err(),
+                ArkhamErrorCode::PremiumSeekersOnly.into()
+            );
+        }
+
+        // Premium seekers connect, up to and including the expiry second
+        assert!(ensure_seeker_admitted_by_warden(warden.premium_seekers_only, Some(now + 86_400), now).is_ok());
+        assert!(ensure_seeker_admitted_by_warden(warden.premium_seekers_only, Some(now), now).is_ok());
+
+        // Wardens that haven't opted in take everyone
+        warden.premium_seekers_only = false;
+        assert!(ensure_seeker_admitted_by_warden(warden.premium_seekers_only, None, now).is_ok());
+    }
+
+    #[test]
+    fn test_private_escrow_lifecycle() {
+        let mut private_escrow = None;
+        let account = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        // Nothing registered yet
+        assert_eq!(
+            ensure_private_escrow_matches(&private_escrow, &account).unwrap_err(),
+            ArkhamErrorCode::PrivateEscrowNotSet.into()
+        );
+
+        // Set, and set again idempotently
+        assign_private_escrow(&mut private_escrow, account).unwrap();
+        assign_private_escrow(&mut private_escrow, account).unwrap();
+        assert_eq!(private_escrow, Some(account));
+        assert!(ensure_private_escrow_matches(&private_escrow, &account).is_ok());
+
+        // A mismatched account is rejected, both for use and for replacement
+        assert_eq!(
+            ensure_private_escrow_matches(&private_escrow, &other).unwrap_err(),
+            ArkhamErrorCode::PrivateEscrowMismatch.into()
+        );
+        assert_eq!(
+            assign_private_escrow(&mut private_escrow, other).unwrap_err(),
+            ArkhamErrorCode::PrivateEscrowAlreadySet.into()
+        );
+        assert_eq!(private_escrow, Some(account));
+
+        // Cleared (as clear_private_escrow does once the account is confirmed), then re-registered
+        ensure_private_escrow_matches(&private_escrow, &account).unwrap();
+        private_escrow = None;
+        assign_private_escrow(&mut private_escrow, other).unwrap();
+        assert_eq!(private_escrow, Some(other));
+    }
+
+    #[test]
+    fn test_is_warden_allowed() {
+        let vetted = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        assert!(is_warden_allowed(&None, &other));
+        assert!(is_warden_allowed(&Some(vec![]), &other));
+        assert!(is_warden_allowed(&Some(vec![vetted]), &vetted));
+        assert!(!is_warden_allowed(&Some(vec![vetted]), &other));
+    }
+
+    #[test]
+    fn test_reduce_escrow_to_remaining() {
+        // Started at 1000 MB @ 10 lamports (11,000 locked), 200 MB already paid
+        let escrowed = calculate_escrow_needed(1_000, 10).unwrap();
+        assert_eq!(escrowed, 11_000);
+
+        // Seeker now expects only 100 MB more: 2,000 paid + 1,100 buffered
+        let (new_escrowed, freed) = reduce_escrow_to_remaining(escrowed, 2_000, 100, 10).unwrap();
+        assert_eq!(new_escrowed, 3_100);
+        assert_eq!(freed, 7_900);
+
+        // Cannot "reduce" to an equal or larger escrow
+        assert!(reduce_escrow_to_remaining(escrowed, 2_000, 900, 10).is_err());
+        // Paid amounts are never released
+        assert!(reduce_escrow_to_remaining(2_000, 2_000, 0, 10).is_err());
+    }
+
+    #[test]
+    fn test_commit_connection_escrow() {
+        let rent = 1_000;
+        let lamports = rent + 3_000;
+
+        // Three connections of 1000 fit exactly within the real balance
+        let committed = commit_connection_escrow(lamports, rent, 0, 1_000).unwrap();
+        let committed = commit_connection_escrow(lamports, rent, committed, 1_000).unwrap();
+        let committed = commit_connection_escrow(lamports, rent, committed, 1_000).unwrap();
+        assert_eq!(committed, 3_000);
+
+        // A fourth would over-commit the seeker's lamports
+        assert!(commit_connection_escrow(lamports, rent, committed, 1).is_err());
+    }
+
+    #[test]
+    fn test_phantom_escrow_balance_is_refused() {
+        let rent = 1_000;
+
+        // Counter matches what the account holds
+        assert!(ensure_escrow_balance_backed(rent + 5_000, rent, 5_000).is_ok());
+
+        // Counter claims more than the account holds above rent
+        assert_eq!(
+            ensure_escrow_balance_backed(rent + 5_000, rent, 5_001).unwrap_err(),
+            ArkhamErrorCode::EscrowUndercollateralized.into()
+        );
+
+        // Rent is never counted as escrow
+        assert!(ensure_escrow_balance_backed(rent, rent, 1).is_err());
+    }
+
+    #[test]
+    fn test_connection_id_correlates_start_proof_and_end_events() {
+        let mut config = crate::state::test_protocol_config();
+        // Hold proofs back so the last batch is reported at close
+        config.proof_event_every_n = 10;
+        let warden = crate::state::test_warden();
+        let needed = session_cost_estimate(&config, &warden, 100).unwrap().total_escrow;
+        let seeker = Seeker { escrow_balance: 2 * needed, ..crate::state::test_seeker() };
+
+        with_party_accounts(&seeker, &warden, 2 * needed, |seeker, warden, vault, treasury| {
+            let seeker_key = seeker.key();
+
+            // start_connection assigns the id and advances the seeker's nonce
+            let mut connection = open_connection(&config, warden, seeker, None, 100, 0, 0).unwrap();
+            let first_id = connection.connection_id;
+            assert_eq!(first_id, derive_connection_id(&seeker_key, 0));
+            assert_eq!(seeker.connection_nonce, 1);
+
+            // Each event an indexer sees for the session carries the same id
+            let started = connection_started_event(&connection, 100);
+            settle_proof(&config, &mut connection, warden, seeker, (vault, treasury), 10, 60).unwrap();
+            let batch = unreported_proof_batch(&connection.pending_proof_event).unwrap();
+            let proof = proof_batch_event(Pubkey::new_unique(), &connection, batch);
+            let ended = connection_ended_event(&connection).unwrap();
+            let summary = close_connection(Pubkey::new_unique(), &connection, warden, seeker).unwrap();
+            assert_eq!(
+                (started.connection_id, proof.connection_id, ended.connection_id, summary.connection_id),
+                (first_id, first_id, first_id, first_id)
+            );
+            assert_eq!((started.seeker, ended.seeker), (seeker_key, seeker_key));
+            assert_eq!(ended.refunded, summary.refunded);
+
+            // The next session between the same pair reuses the PDA but not the id
+            let second = open_connection(&config, warden, seeker, None, 100, 0, 120).unwrap();
+            assert_ne!(second.connection_id, first_id);
+            assert_eq!(second.connection_id, derive_connection_id(&seeker_key, 1));
+            assert_eq!(seeker.connection_nonce, 2);
+        });
+
+        let seeker = Pubkey::new_unique();
+        // Another seeker at the same nonce gets its own id
+        assert_ne!(derive_connection_id(&Pubkey::new_unique(), 0), derive_connection_id(&seeker, 0));
+
+        let mut exhausted = u64::MAX;
+        assert_eq!(
+            next_connection_id(&seeker, &mut exhausted).unwrap_err(),
+            ArkhamErrorCode::ArithmeticOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_arkham_minting_headroom_after_claims() {
+        let mut config = crate::state::test_protocol_config();
+        config.max_arkham_supply = 1_000;
+
+        let before = arkham_minting_headroom(&config, 0);
+        assert_eq!(before, ArkhamMintingHeadroom { total_minted: 0, max_supply: 1_000, remaining_headroom: 1_000 });
+
+        // A full claim of 300 reduces the headroom by exactly 300
+        let minted = arkham_mint_allowance(&config, 0, 300);
+        record_arkham_minted(&mut config, minted).unwrap();
+        assert_eq!(minted, 300);
+        assert_eq!(arkham_minting_headroom(&config, 0).remaining_headroom, 700);
+        assert_eq!(arkham_minting_headroom(&config, 0).total_minted, 300);
+
+        // A claim of 900 is partially filled up to the cap
+        let minted = arkham_mint_allowance(&config, 0, 900);
+        record_arkham_minted(&mut config, minted).unwrap();
+        assert_eq!(minted, 700);
+        assert_eq!(arkham_minting_headroom(&config, 0).remaining_headroom, 0);
+        assert_eq!(arkham_mint_allowance(&config, 0, 1), 0);
+
+        // Uncapped supplies report the u64 range that remains
+        config.max_arkham_supply = 0;
+        assert_eq!(arkham_minting_headroom(&config, 0).remaining_headroom, u64::MAX - 1_000);
+        assert_eq!(arkham_mint_allowance(&config, 0, 5_000), 5_000);
+
+        // Supply minted before tracking began counts against the cap too
+        config.max_arkham_supply = 1_500;
+        config.arkham_total_minted = 0;
+        assert_eq!(
+            arkham_minting_headroom(&config, 1_200),
+            ArkhamMintingHeadroom { total_minted: 1_200, max_supply: 1_500, remaining_headroom: 300 }
+        );
+        assert_eq!(arkham_mint_allowance(&config, 1_200, 500), 300);
+        assert_eq!(arkham_mint_allowance(&config, 1_500, 1), 0);
+
+        let data = arkham_minting_headroom(&config, 0).try_to_vec().unwrap();
+        assert!(data.len() <= anchor_lang::solana_program::program::MAX_RETURN_DATA);
+    }
+
+    #[test]
+    fn test_session_cost_estimate_matches_locked_escrow() {
+        let mut config = crate::state::test_protocol_config();
+        config.geo_premiums = vec![crate::state::GeoPremium { region_code: 3, premium_bps: 2_000 }];
+        let mut warden = crate::state::test_warden();
+        warden.region_code = 3;
+        warden.tier = crate::state::Tier::Gold;
+
+        let estimate = session_cost_estimate(&config, &warden, 500).unwrap();
+
+        // 1000 base, +20% geo premium, 1.5x Gold multiplier
+        assert_eq!(estimate.rate_per_mb, 1_800);
+        assert_eq!(estimate.base_cost, 900_000);
+        assert_eq!(estimate.buffer, 90_000);
+        assert_eq!(estimate.expected_fee, 18_000); // 2% protocol fee
+        assert_eq!(estimate.total_escrow, estimate.base_cost + estimate.buffer);
+
+        // What start_connection locks for the same session
+        assert_eq!(estimate.total_escrow, calculate_escrow_needed(500, estimate.rate_per_mb).unwrap());
+        assert_eq!(lock_connection_escrow(estimate.total_escrow, estimate.total_escrow).unwrap(), 0);
+
+        // Zero-rate sessions are refused at quote time too
+        config.base_rate_per_mb = 0;
+        assert_eq!(
+            session_cost_estimate(&config, &warden, 500).unwrap_err(),
+            ArkhamErrorCode::ZeroRateNotAllowed.into()
+        );
+    }
+
+    #[test]
+    fn test_buffer_released_incrementally_as_usage_proves_lower() {
+        let config = crate::state::test_protocol_config();
+        let warden = crate::state::test_warden();
+        let estimate = session_cost_estimate(&config, &warden, 1_000).unwrap();
+        assert_eq!((estimate.base_cost, estimate.buffer), (1_000_000, 100_000));
+
+        let (mut amount_escrowed, mut escrow_buffer, mut amount_paid) =
+            (estimate.total_escrow, estimate.buffer, 0u64);
+        let mut recent_mb = vec![];
+        let mut released_per_proof = vec![];
+
+        // Each proof consumes less than the last, so less buffer is worth holding
+        for mb in [40u64, 20, 15] {
+            amount_paid += calculate_payment_amount(mb, estimate.rate_per_mb).unwrap();
+            recent_mb.push(mb);
+            let projected = projected_proof_payment(&recent_mb, estimate.rate_per_mb).unwrap();
+            let released = releasable_escrow_buffer(amount_escrowed, amount_paid, escrow_buffer, projected);
+            amount_escrowed -= released;
+            escrow_buffer -= released;
+            released_per_proof.push(released);
+
+            // Escrow for the committed estimate is never handed back
+            assert_eq!(amount_escrowed - escrow_buffer, estimate.base_cost);
+            assert_eq!(escrow_buffer, projected);
+        }
+        assert_eq!(released_per_proof, vec![60_000, 10_000, 5_000]);
+
+        // Buffer already paid out isn't released, and one projected proof is always kept
+        assert_eq!(releasable_escrow_buffer(1_100_000, 1_080_000, 100_000, 10_000), 10_000);
+        assert_eq!(releasable_escrow_buffer(1_100_000, 1_080_000, 100_000, 30_000), 0);
+        assert_eq!(projected_proof_payment(&[], 1_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_insurance_fund_accrues_from_fees_and_backs_sla_refunds() {
+        let mut config = crate::state::test_protocol_config();
+        assert_eq!(config.protocol_fee_bps, 200);
+        config.insurance_bps = 5_000; // half of the 2% fee
+
+        // Proof fees are opt-in: until enabled the warden is paid in full and nothing accrues
+        assert_eq!(proof_fee_bps(&config), 0);
+        assert_eq!(
+            split_proof_payment(100_000, proof_fee_bps(&config), config.insurance_bps).unwrap(),
+            ProofPaymentSplit { warden_share: 100_000, treasury_fee: 0, insurance: 0 }
+        );
+        config.proof_fee_enabled = true;
+
+        // Several fee-paying sessions feed the fund out of the collected fee; the warden
+        // is credited the payment less the fee, the treasury the fee less the slice
+        let rent_exempt_minimum = 890_880u64;
+        let mut fund_lamports = rent_exempt_minimum;
+        let (mut warden_pending_claims, mut treasury) = (0u64, 0u64);
+        for payment in [100_000u64, 250_000, 50_000] {
+            let split = split_proof_payment(payment, proof_fee_bps(&config), config.insurance_bps).unwrap();
+            assert_eq!(split.warden_share + split.treasury_fee + split.insurance, payment);
+            fund_lamports += split.insurance;
+            treasury += split.treasury_fee;
+            warden_pending_claims += split.warden_share;
+        }
+        let insurance_available = insurance_fund_available(fund_lamports, rent_exempt_minimum);
+        assert_eq!(insurance_available, 1_000 + 2_500 + 500);
+        assert_eq!(treasury, 4_000);
+        assert_eq!(warden_pending_claims, 400_000 - 8_000);
+
+        // Off by default: the whole fee goes to the treasury
+        assert_eq!(
+            split_proof_payment(100_000, 200, 0).unwrap(),
+            ProofPaymentSplit { warden_share: 98_000, treasury_fee: 2_000, insurance: 0 }
+        );
+        // The rent-exempt reserve is never offered for refunds
+        assert_eq!(insurance_fund_available(rent_exempt_minimum - 1, rent_exempt_minimum), 0);
+
+        // A warden that already claimed its earnings can cover only part of a valid refund
+        let connection = Connection { amount_paid: 400_000, last_proof_at: 20, ..crate::state::test_connection() };
+        let owed = calculate_sla_refund(&connection, 10, 4_500, 9_000).unwrap();
+        assert_eq!(owed, 200_000);
+        assert_eq!(split_sla_refund(owed, 197_000, insurance_available), (197_000, 3_000));
+
+        // The fund never pays out more than it holds, nor anything the warden can cover
+        assert_eq!(split_sla_refund(owed, 150_000, insurance_available), (150_000, 4_000));
+        assert_eq!(split_sla_refund(owed, 250_000, insurance_available), (200_000, 0));
+        assert_eq!(split_sla_refund(owed, 0, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_rate_divergence_beyond_tolerance() {
+        // Within 5%: not flagged, including exactly at the tolerance
+        assert_eq!(rate_divergence_beyond_tolerance(1_000, 1_000, 500), None);
+        assert_eq!(rate_divergence_beyond_tolerance(1_050, 1_000, 500), None);
+        assert_eq!(rate_divergence_beyond_tolerance(950, 1_000, 500), None);
+
+        // Beyond it, in either direction
+        assert_eq!(rate_divergence_beyond_tolerance(1_051, 1_000, 500), Some(510));
+        assert_eq!(rate_divergence_beyond_tolerance(900, 1_000, 500), Some(1_000));
+        assert_eq!(rate_divergence_beyond_tolerance(u64::MAX, 1, 500), Some(u64::MAX));
+
+        // Expected zero rate
+        assert_eq!(rate_divergence_beyond_tolerance(0, 0, 500), None);
+        assert_eq!(rate_divergence_beyond_tolerance(1, 0, 500), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_stored_rate_cross_checked_against_config() {
+        let config = crate::state::test_protocol_config();
+        let mut warden = crate::state::test_warden();
+        warden.tier = crate::state::Tier::Silver;
+
+        // What start_connection locked: 1000 base * 1.2 Silver
+        let locked = session_cost_estimate(&config, &warden, 100).unwrap().rate_per_mb;
+        let expected = current_rate_per_mb(&config, &warden).unwrap();
+        assert_eq!(locked, 1_200);
+        assert_eq!(rate_divergence_beyond_tolerance(locked, expected, 100), None);
+
+        // A tampered connection charging 1.5x is flagged
+        assert_eq!(rate_divergence_beyond_tolerance(1_800, expected, 100), Some(5_000));
+
+        // A 0.5% drift stays under a 1% tolerance but not under a 0.1% one
+        assert_eq!(rate_divergence_beyond_tolerance(1_206, expected, 100), None);
+        assert_eq!(rate_divergence_beyond_tolerance(1_206, expected, 10), Some(50));
+    }
+
+    #[test]
+    fn test_calculate_rate_per_mb() {
+        // 1000 base, +20% geo premium, 1.5x tier multiplier
+        assert_eq!(calculate_rate_per_mb(1_000, 2_000, 15_000).unwrap(), 1_800);
+
+        // A zero base rate yields a zero rate regardless of premiums
+        assert_eq!(calculate_rate_per_mb(0, 50_000, 50_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_zero_rate_connection_rejected_and_unpaid_bandwidth_earns_no_arkham() {
+        let mut config = crate::state::test_protocol_config();
+        let escrow = 10_000_000_000u64;
+        let seeker = Seeker { escrow_balance: escrow, ..crate::state::test_seeker() };
+        let warden = crate::state::test_warden();
+
+        with_party_accounts(&seeker, &warden, escrow, |seeker, warden, vault, treasury| {
+            // A misconfigured zero base rate refuses the session and locks nothing
+            config.base_rate_per_mb = 0;
+            assert_eq!(
+                open_connection(&config, warden, seeker, None, 1_000, 0, 0).map(|_| ()).unwrap_err(),
+                ArkhamErrorCode::ZeroRateNotAllowed.into()
+            );
+            assert_eq!((seeker.escrow_balance, seeker.committed_escrow, seeker.active_connections), (escrow, 0, 0));
+
+            // A session already locked at zero (opened before the check) is still
+            // credited its bandwidth but accrues no ARKHAM for it
+            config.base_rate_per_mb = 1_000;
+            let mut connection = open_connection(&config, warden, seeker, None, 1_000, 0, 0).unwrap();
+            connection.rate_per_mb = 0;
+            settle_proof(&config, &mut connection, warden, seeker, (vault, treasury), 100, 60).unwrap();
+            assert_eq!(warden.total_bandwidth_served, 100);
+            assert_eq!((warden.pending_claims, warden.arkham_tokens_earned), (0, 0));
+
+            // Paid bandwidth accrues as usual
+            connection.rate_per_mb = 1_000;
+            settle_proof(&config, &mut connection, warden, seeker, (vault, treasury), 100, 120).unwrap();
+            assert!(warden.arkham_tokens_earned > 0);
+        });
+    }
+
+    #[test]
+    fn test_rate_and_escrow_overflows_report_their_subsystem() {
+        assert_eq!(
+            calculate_rate_per_mb(u64::MAX, 50_000, 50_000).unwrap_err(),
+            ArkhamErrorCode::RateOverflow.into()
+        );
+        assert_eq!(
+            calculate_payment_amount(u64::MAX, 2).unwrap_err(),
+            ArkhamErrorCode::RateOverflow.into()
+        );
+
+        // The 10% buffer pushes an otherwise representable escrow past u64
+        assert_eq!(
+            calculate_escrow_needed(u64::MAX, 1).unwrap_err(),
+            ArkhamErrorCode::EscrowOverflow.into()
+        );
+        assert_eq!(
+            commit_connection_escrow(u64::MAX, 0, u64::MAX, 1).unwrap_err(),
+            ArkhamErrorCode::EscrowOverflow.into()
+        );
+        assert_eq!(
+            park_idle_escrow(1, u64::MAX, 1).unwrap_err(),
+            ArkhamErrorCode::EscrowOverflow.into()
+        );
+        assert_eq!(
+            unused_connection_escrow(100, 101).unwrap_err(),
+            ArkhamErrorCode::EscrowOverflow.into()
+        );
+
+        let mut warden = crate::state::test_warden();
+        warden.successful_connections = u64::MAX;
+        assert_eq!(
+            record_session_outcome(&mut warden, 1).unwrap_err(),
+            ArkhamErrorCode::ReputationOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_normalize_rate_per_mb() {
+        assert_eq!(normalize_rate_per_mb(1_000, &RateUnit::PerMb), 1_000);
+
+        // 1,024,000 lamports per GB settles at 1,000 lamports per MB
+        assert_eq!(normalize_rate_per_mb(1_024_000, &RateUnit::PerGb), 1_000);
+        assert_eq!(normalize_rate_per_mb(1_023, &RateUnit::PerGb), 0);
+    }
+
+    #[test]
+    fn test_arkham_reward_uses_snapshot_rate() {
+        let mut config = crate::state::test_protocol_config();
+        let escrow = 10_000_000_000u64;
+        let seeker = Seeker { escrow_balance: escrow, ..crate::state::test_seeker() };
+        let warden = crate::state::test_warden();
+
+        with_party_accounts(&seeker, &warden, escrow, |seeker, warden, vault, treasury| {
+            let mut connection = open_connection(&config, warden, seeker, None, 1_000, 0, 0).unwrap();
+            let (locked_numerator, locked_denominator) = (config.reward_numerator, config.reward_denominator);
+
+            // Governance doubles the live rate mid-session; the session keeps its snapshot
+            config.reward_numerator *= 2;
+            settle_proof(&config, &mut connection, warden, seeker, (vault, treasury), 100, 60).unwrap();
+            let at_snapshot = compute_arkham_reward(100, locked_numerator, locked_denominator, 0, false, 0).unwrap();
+            assert_eq!(warden.arkham_tokens_earned, at_snapshot);
+
+            // A session started after the change earns at the new rate
+            let mut connection = open_connection(&config, warden, seeker, None, 1_000, 0, 120).unwrap();
+            settle_proof(&config, &mut connection, warden, seeker, (vault, treasury), 100, 180).unwrap();
+            assert_eq!(warden.arkham_tokens_earned, at_snapshot * 3);
+        });
+    }
+
+    #[test]
+    fn test_paid_escrow_leaves_the_commitment() {
+        let config = crate::state::test_protocol_config();
+        let warden = crate::state::test_warden();
+        let needed = session_cost_estimate(&config, &warden, 1_000).unwrap().total_escrow;
+        // Exactly enough for two sessions
+        let seeker = Seeker { escrow_balance: 2 * needed, ..crate::state::test_seeker() };
+
+        with_party_accounts(&seeker, &warden, 2 * needed, |seeker, warden, vault, treasury| {
+            let mut first = open_connection(&config, warden, seeker, None, 1_000, 0, 0).unwrap();
+            settle_proof(&config, &mut first, warden, seeker, (vault, treasury), 1_000, 60).unwrap();
+            let paid = first.amount_paid;
+            assert_eq!(seeker.committed_escrow, needed - paid);
+
+            // The paid lamports left the account; the second session fits in what remains
+            let mut second = open_connection(&config, warden, seeker, None, 1_000, 0, 120).unwrap();
+            assert_eq!(seeker.committed_escrow, 2 * needed - paid);
+            assert_eq!(seeker.to_account_info().lamports(), 2 * needed - paid);
+
+            // Closing releases only the unpaid part of each session
+            close_connection(Pubkey::new_unique(), &first, warden, seeker).unwrap();
+            assert_eq!(seeker.committed_escrow, needed);
+            settle_proof(&config, &mut second, warden, seeker, (vault, treasury), 500, 180).unwrap();
+            close_connection(Pubkey::new_unique(), &second, warden, seeker).unwrap();
+            assert_eq!((seeker.committed_escrow, seeker.active_connections), (0, 0));
+            assert_eq!(seeker.escrow_balance, seeker.to_account_info().lamports());
+        });
+    }
+
+    #[test]
+    fn test_premium_reward_boost() {
+        // Identical bandwidth: ranked warden earns 25% more with a 2500 bps boost
+        let unranked = compute_arkham_reward(1_000, 5_120, MB_PER_5GB, 2500, false, 0).unwrap();
+        let ranked = compute_arkham_reward(1_000, 5_120, MB_PER_5GB, 2500, true, 0).unwrap();
+        assert_eq!(unranked, 1_000);
+        assert_eq!(ranked, 1_250);
+
+        // Boost is capped at MAX_PREMIUM_REWARD_BOOST_BPS
+        assert_eq!(compute_arkham_reward(1_000, 5_120, MB_PER_5GB, u16::MAX, true, 0).unwrap(), 2_000);
+
+        // Boosting the largest allocation reports overflow instead of wrapping
+        assert_eq!(
+            compute_arkham_reward(u64::MAX, 5_120, MB_PER_5GB, 1, true, 0).unwrap_err(),
+            ArkhamErrorCode::RewardOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_arkham_reward_rounding() {
+        // The per-MB rate is not truncated first: 10,239 tokens per 5GB over 100 MB is 199.98 -> 199
+        assert_eq!(compute_arkham_reward(100, 10_239, MB_PER_5GB, 0, false, 0).unwrap(), 199);
+        // Rates below one token per MB still accrue: 5,119 tokens per 5GB used to earn nothing
+        assert_eq!(compute_arkham_reward(5_120, 5_119, MB_PER_5GB, 0, false, 0).unwrap(), 5_119);
+        // Boosted amounts round down: 3 * 1.0001 = 3.0003 -> 3
+        assert_eq!(compute_arkham_reward(3, 5_120, MB_PER_5GB, 1, true, 0).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_arkham_reward_at_extremes() {
+        let max_rate = u64::MAX;
+
+        // Largest proof at the largest rate still fits once capped
+        let cap = 1_000_000_000_000;
+        assert_eq!(
+            compute_arkham_reward(10_000, max_rate, MB_PER_5GB, MAX_PREMIUM_REWARD_BOOST_BPS, true, cap).unwrap(),
+            cap
+        );
+
+        // Uncapped, the same proof overflows u64 and is reported, not wrapped
+        assert_eq!(
+            compute_arkham_reward(10_000, max_rate, MB_PER_5GB, MAX_PREMIUM_REWARD_BOOST_BPS, true, 0).unwrap_err(),
+            ArkhamErrorCode::RewardOverflow.into()
+        );
+
+        // u64::MAX MB at the maximum boost overflows even u128 intermediates
+        assert_eq!(
+            compute_arkham_reward(u64::MAX, max_rate, MB_PER_5GB, MAX_PREMIUM_REWARD_BOOST_BPS, true, cap).unwrap_err(),
+            ArkhamErrorCode::RewardOverflow.into()
+        );
+
+        // A single MB at the maximum boost is exactly doubled
+        assert_eq!(
+            compute_arkham_reward(1, max_rate, MB_PER_5GB, MAX_PREMIUM_REWARD_BOOST_BPS, true, 0).unwrap(),
+            (max_rate as u128 * 2 / MB_PER_5GB as u128) as u64
+        );
+    }
+
+    #[test]
+    fn test_arkham_reward_across_reward_rates() {
+        // (numerator, denominator, MB, expected)
+        let cases: [(u64, u64, u64, u64); 7] = [
+            (1, 1, 1_000, 1_000), // 1 token per MB
+            (1, 1_000, 999, 0), // 0.001 per MB, not yet a whole token
+            (1, 1_000, 1_000, 1),
+            (3, 7, 1_000, 428), // 428.57 rounds down once, at the end
+            (1_000_000, 1, 5, 5_000_000), // fine-grained base units per MB
+            (500_000_000, 5_120, 5_120, 500_000_000), // tokens_per_5gb expressed as a pair
+            (0, 1, 1_000, 0), // rewards switched off
+        ];
+        for (numerator, denominator, mb, expected) in cases {
+            assert_eq!(
+                compute_arkham_reward(mb, numerator, denominator, 0, false, 0).unwrap(),
+                expected,
+                "{numerator}/{denominator} over {mb} MB"
+            );
+        }
+
+        // Equivalent fractions pay the same
+        assert_eq!(
+            compute_arkham_reward(777, 2, 3, 0, false, 0).unwrap(),
+            compute_arkham_reward(777, 2_000, 3_000, 0, false, 0).unwrap()
+        );
+
+        // The boost applies to the unrounded amount: 3/7 * 1,000 MB * 1.25 = 535.7 -> 535
+        assert_eq!(compute_arkham_reward(1_000, 3, 7, 2500, true, 0).unwrap(), 535);
+
+        assert_eq!(
+            compute_arkham_reward(1_000, 1, 0, 0, false, 0).unwrap_err(),
+            ArkhamErrorCode::InvalidRewardRate.into()
+        );
+    }
+
+    #[test]
+    fn test_reward_rate_migrates_legacy_configs() {
+        let mut config = crate::state::test_protocol_config();
+        config.reward_numerator = 3;
+        config.reward_denominator = 7;
+        assert_eq!(reward_rate(&config), (3, 7));
+
+        // A config written before the pair existed keeps its tokens_per_5gb meaning
+        config.reward_numerator = 0;
+        config.reward_denominator = 0;
+        config.tokens_per_5gb = 10_240;
+        assert_eq!(reward_rate(&config), (10_240, MB_PER_5GB));
+        let (numerator, denominator) = reward_rate(&config);
+        assert_eq!(compute_arkham_reward(100, numerator, denominator, 0, false, 0).unwrap(), 200);
+    }
+
+    #[test]
+    fn test_connection_reward_rate_keeps_the_legacy_slot() {
+        let mut connection = Connection {
+            tokens_per_5gb: 10_240,
+            reward_numerator: 3,
+            reward_denominator: 7,
+            ..crate::state::test_connection()
+        };
+        assert_eq!(connection_reward_rate(&connection), (3, 7));
+
+        // tokens_per_5gb still sits right after warden_multiplier, where connections
+        // opened before the pair stored it, and the pair comes last
+        let data = connection.try_to_vec().unwrap();
+        let legacy_offset = 32 + 32 + 8 + 8 + 8 + PROOF_HISTORY_CAPACITY * (8 + 8 + 64 + 64) + 1 + 1 + 8 + 8 + 8 + 2;
+        assert_eq!(data[legacy_offset..legacy_offset + 8], 10_240u64.to_le_bytes());
+        assert_eq!(data[data.len() - 16..data.len() - 8], 3u64.to_le_bytes());
+        assert_eq!(data[data.len() - 8..], 7u64.to_le_bytes());
+
+        // Those connections read a zero pair and keep their tokens_per_5gb rate
+        connection.reward_numerator = 0;
+        connection.reward_denominator = 0;
+        assert_eq!(connection_reward_rate(&connection), (10_240, MB_PER_5GB));
+    }
+
+    #[test]
+    fn test_arkham_reward_per_proof_cap() {
+        // Below the cap the reward is untouched; above it, clamped
+        assert_eq!(compute_arkham_reward(100, 5_120, MB_PER_5GB, 0, false, 500).unwrap(), 100);
+        assert_eq!(compute_arkham_reward(1_000, 5_120, MB_PER_5GB, 0, false, 500).unwrap(), 500);
+        assert_eq!(compute_arkham_reward(1_000, 5_120, MB_PER_5GB, 2500, true, 1_200).unwrap(), 1_200);
+    }
+
+    #[test]
+    fn test_min_uptime_for_matching() {
+        let config = ProtocolConfig { min_uptime_for_matching: 9_000, ..crate::state::test_protocol_config() };
+
+        // Same reputation, different uptime
+        let mut reliable = crate::state::test_warden();
+        reliable.reputation_score = 9_000;
+        reliable.uptime_percentage = 9_500;
+        let mut flaky = crate::state::test_warden();
+        flaky.reputation_score = 9_000;
+        flaky.uptime_percentage = 6_000;
+
+        assert!(ensure_warden_meets_min_uptime(reliable.uptime_percentage, config.min_uptime_for_matching).is_ok());
+        assert_eq!(
+            ensure_warden_meets_min_uptime(flaky.uptime_percentage, config.min_uptime_for_matching).unwrap_err(),
+            ArkhamErrorCode::WardenUptimeTooLow.into()
+        );
+
+        // Exactly at the minimum is matchable; 0 disables the check
+        assert!(ensure_warden_meets_min_uptime(9_000, 9_000).is_ok());
+        assert!(ensure_warden_meets_min_uptime(0, 0).is_ok());
+    }
+
+    #[test]
+    fn test_region_minimum_warden_count() {
+        let mut warden = crate::state::test_warden();
+        warden.region_code = 42;
+        let mut region_index = RegionIndex { active_wardens: [0; crate::state::REGION_COUNT] };
+        let min_wardens = 2;
+
+        // A one-warden region is rejected
+        crate::instructions::staking::join_region(&mut warden, Some(&mut region_index)).unwrap();
+        assert_eq!(region_index.active_wardens[42], 1);
+        assert_eq!(
+            ensure_region_has_min_wardens(region_index.active_wardens[42], min_wardens).unwrap_err(),
+            ArkhamErrorCode::RegionBelowMinimumWardens.into()
+        );
+
+        // A second warden joins and the region meets the minimum
+        let mut second = crate::state::test_warden();
+        second.region_code = 42;
+        crate::instructions::staking::join_region(&mut second, Some(&mut region_index)).unwrap();
+        assert!(ensure_region_has_min_wardens(region_index.active_wardens[42], min_wardens).is_ok());
+
+        // Joining twice doesn't double count; leaving drops the region below the minimum
+        crate::instructions::staking::join_region(&mut second, Some(&mut region_index)).unwrap();
+        assert_eq!(region_index.active_wardens[42], 2);
+        crate::instructions::staking::leave_region(&mut second, Some(&mut region_index)).unwrap();
+        assert!(ensure_region_has_min_wardens(region_index.active_wardens[42], min_wardens).is_err());
+
+        // A counted warden can't leave without the index
+        assert_eq!(
+            crate::instructions::staking::leave_region(&mut warden, None).unwrap_err(),
+            ArkhamErrorCode::RegionIndexRequired.into()
+        );
+    }
+
+    #[test]
+    fn test_withdraw_escrow_down_to_dust_stays_rent_exempt() {
+        let rent = Rent::default().minimum_balance(512);
+        let escrow_balance = 1_000_000u64;
+        let lamports = rent + escrow_balance;
+
+        // Leaving a single lamport of escrow: the account keeps its rent deposit
+        let withdrawal = plan_escrow_withdrawal(lamports, rent, escrow_balance, 0, 0, 0, escrow_balance - 1).unwrap();
+        assert_eq!(withdrawal, EscrowWithdrawal { lamports: escrow_balance - 1, close: false });
+        assert!(lamports - withdrawal.lamports >= rent);
+
+        // A drifted account holding less than rent + escrow can't be drained below rent
+        assert_eq!(
+            plan_escrow_withdrawal(lamports - 10, rent, escrow_balance, 0, 0, 1, escrow_balance - 1).unwrap_err(),
+            ArkhamErrorCode::EscrowWithdrawalBelowRentExemption.into()
+        );
+
+        // Escrow locked in an active connection stays behind as well
+        let committed = 400_000u64;
+        let lamports = rent + escrow_balance + committed;
+        let withdrawal =
+            plan_escrow_withdrawal(lamports, rent, escrow_balance, committed, 0, 1, escrow_balance).unwrap();
+        assert!(!withdrawal.close);
+        assert_eq!(lamports - withdrawal.lamports, rent + committed);
+
+        // Beyond the spendable balance
+        assert_eq!(
+            plan_escrow_withdrawal(lamports, rent, escrow_balance, committed, 0, 1, escrow_balance + 1).unwrap_err(),
+            ArkhamErrorCode::InsufficientEscrow.into()
+        );
+    }
+
+    #[test]
+    fn test_full_withdrawal_closes_idle_seeker() {
+        let rent = Rent::default().minimum_balance(512);
+        let escrow_balance = 1_000_000u64;
+        let lamports = rent + escrow_balance;
+
+        // Everything, rent deposit included, goes back and the account is closed
+        let withdrawal = plan_escrow_withdrawal(lamports, rent, escrow_balance, 0, 0, 0, escrow_balance).unwrap();
+        assert_eq!(withdrawal, EscrowWithdrawal { lamports, close: true });
+
+        // Parked escrow keeps the account open; only the spendable balance leaves
+        let withdrawal = plan_escrow_withdrawal(lamports, rent, escrow_balance, 0, 5, 0, escrow_balance).unwrap();
+        assert_eq!(withdrawal, EscrowWithdrawal { lamports: escrow_balance, close: false });
+    }
+
+    #[test]
+    fn test_earnings_snapshot_after_two_settled_connections() {
+        let config = crate::state::test_protocol_config();
+        let escrow = 10_000_000_000u64;
+        let seeker = Seeker { escrow_balance: escrow, ..crate::state::test_seeker() };
+        let warden = crate::state::test_warden();
+
+        with_party_accounts(&seeker, &warden, escrow, |seeker, warden, vault, treasury| {
+            // Two sessions settle through the proof and end_connection paths
+            let mut paid = 0;
+            for (mb, started_at) in [(500u64, 0i64), (300, 1_000)] {
+                let mut connection = open_connection(&config, warden, seeker, None, mb, 0, started_at).unwrap();
+                settle_proof(&config, &mut connection, warden, seeker, (vault, treasury), mb, started_at + 60).unwrap();
+
+                // A proof beyond the session's escrow is refused and leaves the totals alone
+                let before = warden_earnings_snapshot(warden, started_at + 120);
+                assert_eq!(
+                    settle_proof(&config, &mut connection, warden, seeker, (vault, treasury), 10 * mb, started_at + 120).unwrap_err(),
+                    ArkhamErrorCode::InsufficientConnectionEscrow.into()
+                );
+                let after = warden_earnings_snapshot(warden, started_at + 120);
+                assert_eq!(
+                    (after.total_earnings, after.total_bandwidth_served, after.arkham_tokens_earned, after.pending_claims),
+                    (before.total_earnings, before.total_bandwidth_served, before.arkham_tokens_earned, before.pending_claims)
+                );
+
+                paid += close_connection(Pubkey::new_unique(), &connection, warden, seeker).unwrap().total_paid;
+            }
+
+            let snapshot = warden_earnings_snapshot(warden, 1_700_000_000);
+            let arkham = compute_arkham_reward(500, config.reward_numerator, config.reward_denominator, 0, false, 0).unwrap()
+                + compute_arkham_reward(300, config.reward_numerator, config.reward_denominator, 0, false, 0).unwrap();
+            assert_eq!(snapshot.warden, warden.authority);
+            assert_eq!(snapshot.timestamp, 1_700_000_000);
+            assert_eq!(snapshot.total_bandwidth_served, 800);
+            // Proof fees are off by default, so the warden earned every lamport paid
+            assert_eq!(treasury.lamports(), 0);
+            assert_eq!((snapshot.total_earnings, snapshot.pending_claims), (paid, paid));
+            assert_eq!(snapshot.arkham_tokens_earned, arkham);
+            assert_eq!(warden.successful_connections, 2);
+        });
+    }
+
+    #[test]
+    fn test_seeker_connection_cap() {
+        let max_connections = crate::state::test_protocol_config().max_connections_per_seeker;
+
+        // Fill the seeker up to the limit, one connection at a time
+        let mut active_connections = 0u8;
+        while active_connections < max_connections {
+            assert!(ensure_seeker_below_capacity(active_connections, max_connections).is_ok());
+            active_connections += 1;
+        }
+
+        // The next connection is rejected
+        assert!(ensure_seeker_below_capacity(active_connections, max_connections).is_err());
+        assert!(ensure_seeker_below_capacity(u8::MAX, max_connections).is_err());
+    }
+
+    #[test]
+    fn test_delegated_settlement_within_cap() {
+        // Seeker authorized 1,000 MB; the warden settles two proofs inside it
+        let settled = consume_settlement_delegation(1_000, 0, 400).unwrap();
+        assert_eq!(settled, 400);
+        let settled = consume_settlement_delegation(1_000, settled, 600).unwrap();
+        assert_eq!(settled, 1_000);
+    }
+
+    #[test]
+    fn test_delegated_settlement_rejected_beyond_cap() {
+        assert_eq!(
+            consume_settlement_delegation(1_000, 800, 201).unwrap_err(),
+            ArkhamErrorCode::DelegatedSettlementCapExceeded.into()
+        );
+        assert_eq!(
+            consume_settlement_delegation(1_000, 0, u64::MAX).unwrap_err(),
+            ArkhamErrorCode::DelegatedSettlementCapExceeded.into()
+        );
+
+        // Without a delegation the warden can't settle alone at all
+        assert_eq!(
+            consume_settlement_delegation(0, 0, 1).unwrap_err(),
+            ArkhamErrorCode::SettlementNotDelegated.into()
+        );
+    }
+
+    #[test]
+    fn test_fully_settled_connection_is_distinct_from_escrow_shortfall() {
+        let (rate_per_mb, amount_escrowed) = (1_000u64, 100_000u64);
+
+        // Mid-session: 40 MB left, so a 50 MB proof is a genuine shortfall
+        let amount_paid = 60 * rate_per_mb;
+        assert!(ensure_connection_not_fully_settled(amount_paid, amount_escrowed).is_ok());
+        assert_eq!(
+            ensure_within_spend_limits(amount_paid + 50 * rate_per_mb, amount_escrowed, 0).unwrap_err(),
+            ArkhamErrorCode::InsufficientConnectionEscrow.into()
+        );
+
+        // Once the escrow is consumed exactly, any further proof is rejected as settled
+        let amount_paid = amount_escrowed;
+        assert_eq!(
+            ensure_connection_not_fully_settled(amount_paid, amount_escrowed).unwrap_err(),
+            ArkhamErrorCode::ConnectionFullySettled.into()
+        );
+    }
+
+    #[test]
+    fn test_sla_refund() {
+        let sla = 9_900u16;
+        let mut connection = Connection {
+            amount_paid: 1_000_000,
+            started_at: 1_000,
+            last_proof_at: 3_000,
+            ..crate::state::test_connection()
+        };
+
+        // Reported 10% under the SLA during the session: 10% of the payment comes back
+        assert_eq!(calculate_sla_refund(&connection, 2_000, 8_910, sla).unwrap(), 100_000);
+
+        // A compliant report owes nothing
+        assert_eq!(calculate_sla_refund(&connection, 2_000, 9_900, sla).unwrap(), 0);
+
+        // A breach from before the connection started, or after its last proof, doesn't count
+        assert_eq!(calculate_sla_refund(&connection, 999, 5_000, sla).unwrap(), 0);
+        assert_eq!(calculate_sla_refund(&connection, 3_001, 5_000, sla).unwrap(), 0);
+        assert_eq!(calculate_sla_refund(&connection, 3_000, 8_910, sla).unwrap(), 100_000);
+
+        // Nor does anything while the SLA is off
+        assert_eq!(calculate_sla_refund(&connection, 2_000, 5_000, 0).unwrap(), 0);
+
+        // A warden already at 90% when chosen owes only for the drop below that
+        connection.uptime_at_start = 9_000;
+        assert_eq!(calculate_sla_refund(&connection, 2_000, 9_000, sla).unwrap(), 0);
+        assert_eq!(calculate_sla_refund(&connection, 2_000, 8_100, sla).unwrap(), 100_000);
+    }
+
+    #[test]
+    fn test_sla_refund_claim_checks() {
+        let seeker = crate::state::test_seeker();
+        let warden = Warden {
+            pending_claims: 1_000_000,
+            last_sla_breach_at: 2_000,
+            last_sla_breach_uptime: 8_910,
+            ..crate::state::test_warden()
+        };
+        let mut connection = Connection {
+            amount_paid: 1_000_000,
+            started_at: 1_000,
+            last_proof_at: 3_000,
+            ..crate::state::test_connection()
+        };
+        let claim = |connection: &Connection, claimant: &Pubkey, warden: &Warden| {
+            plan_sla_refund(connection, &seeker, claimant, warden, 9_900, 0)
+        };
+
+        assert_eq!(claim(&connection, &seeker.authority, &warden).unwrap(), (100_000, 0));
+
+        // Only the seeker's authority may claim
+        assert_eq!(
+            claim(&connection, &Pubkey::new_unique(), &warden).unwrap_err(),
+            ArkhamErrorCode::UnauthorizedSlaRefundClaim.into()
+        );
+
+        // A breach reported after the session's last proof isn't this session's
+        let later = Warden { last_sla_breach_at: 3_001, ..warden.clone() };
+        assert_eq!(
+            claim(&connection, &seeker.authority, &later).unwrap_err(),
+            ArkhamErrorCode::NoSlaRefundDue.into()
+        );
+
+        // Once per connection
+        connection.sla_refunded = true;
+        assert_eq!(
+            claim(&connection, &seeker.authority, &warden).unwrap_err(),
+            ArkhamErrorCode::SlaRefundAlreadyClaimed.into()
+        );
+    }
+
+    #[test]
+    fn test_throttled_proof_events_account_for_every_proof() {
+        let proofs: Vec<(u64, u64, u64)> = (1..=10).map(|i| (i, i * 1_000, i * 10)).collect();
+        let totals = proofs.iter().fold((0, 0, 0), |acc, p| (acc.0 + p.0, acc.1 + p.1, acc.2 + p.2));
+
+        // Unthrottled: one event per proof
+        let mut pending = PendingProofEvent::default();
+        for &(mb, paid, arkham) in &proofs {
+            let batch = accumulate_proof_event(&mut pending, mb, paid, arkham, 0, 0, 0).unwrap().unwrap();
+            assert_eq!((batch.proofs, batch.mb_consumed), (1, mb));
+        }
+
+        // Every 4 proofs: two events, two proofs still pending, nothing lost
+        let mut pending = PendingProofEvent::default();
+        let mut emitted = Vec::new();
+        for (i, &(mb, paid, arkham)) in proofs.iter().enumerate() {
+            if let Some(batch) = accumulate_proof_event(&mut pending, mb, paid, arkham, i as i64, 4, 0).unwrap() {
+                emitted.push(batch);
+            }
+        }
+        assert_eq!(emitted.iter().map(|b| b.proofs).collect::<Vec<_>>(), vec![4, 4]);
+
+        // Closing the connection reports the last two, so every proof reaches the log
+        let at_close = unreported_proof_batch(&pending).unwrap();
+        assert_eq!(at_close.proofs, 2);
+        let reported = emitted.iter().chain(std::iter::once(at_close)).fold((0, 0, 0), |acc, b| {
+            (acc.0 + b.mb_consumed, acc.1 + b.payment_amount, acc.2 + b.arkham_earned)
+        });
+        assert_eq!(reported, totals);
+
+        // Time-based: proofs a second apart are reported every 5 seconds
+        let mut pending = PendingProofEvent::default();
+        let emitted = (1..=10)
+            .filter_map(|t| accumulate_proof_event(&mut pending, 1, 1, 1, t, 0, 5).unwrap())
+            .map(|batch| batch.proofs)
+            .collect::<Vec<_>>();
+        assert_eq!(emitted, vec![5, 5]);
+        assert!(unreported_proof_batch(&pending).is_none());
+    }
+
+    #[test]
+    fn test_settlement_stops_at_warden_spend_limit() {
+        let (rate_per_mb, amount_escrowed, max_spend) = (1_000u64, 1_000_000u64, 300_000u64);
+
+        // Co-signed proofs of 100 MB each settle until the seeker's cap, not the escrow
+        let mut amount_paid = 0u64;
+        for _ in 0..3 {
+            let new_total_paid = amount_paid + 100 * rate_per_mb;
+            ensure_within_spend_limits(new_total_paid, amount_escrowed, max_spend).unwrap();
+            amount_paid = new_total_paid;
+        }
+        assert_eq!(amount_paid, max_spend);
+
+        // Escrow still covers the next proof, but the warden's limit doesn't
+        let next = amount_paid + rate_per_mb;
+        assert!(next <= amount_escrowed);
+        assert_eq!(
+            ensure_within_spend_limits(next, amount_escrowed, max_spend).unwrap_err(),
+            ArkhamErrorCode::WardenSpendLimitExceeded.into()
+        );
+
+        // Without a limit only the escrow applies
+        assert!(ensure_within_spend_limits(amount_escrowed, amount_escrowed, 0).is_ok());
+        assert_eq!(
+            ensure_within_spend_limits(amount_escrowed + 1, amount_escrowed, 0).unwrap_err(),
+            ArkhamErrorCode::InsufficientConnectionEscrow.into()
+        );
+
+        // A limit above the escrow never loosens it
+        assert_eq!(
+            ensure_within_spend_limits(amount_escrowed + 1, amount_escrowed, u64::MAX).unwrap_err(),
+            ArkhamErrorCode::InsufficientConnectionEscrow.into()
+        );
+    }
+
+    #[test]
+    fn test_unused_connection_escrow() {
+        // Partially-paid connection refunds the remainder
+        assert_eq!(unused_connection_escrow(1_100, 400).unwrap(), 700);
+        assert_eq!(unused_connection_escrow(1_100, 1_100).unwrap(), 0);
+
+        // Paid beyond escrow indicates corrupted state
+        assert!(unused_connection_escrow(100, 101).is_err());
+    }
+
+    #[test]
+    fn test_connection_refund_splits_buffer_from_unused_bandwidth() {
+        // 1,000 escrowed for the estimate plus a 100 buffer
+        let (escrowed, buffer) = (1_100u64, 100u64);
+        for (paid, expected) in [
+            (0u64, (100u64, 1_000u64)), // nothing served: all of both
+            (400, (100, 600)),          // under the estimate: buffer untouched
+            (1_000, (100, 0)),          // exactly the estimate
+            (1_050, (50, 0)),           // estimate exceeded into the buffer
+            (1_100, (0, 0)),            // fully settled
+        ] {
+            let (buffer_refunded, unused_bandwidth_refunded) = split_connection_refund(escrowed, paid, buffer);
+            assert_eq!((buffer_refunded, unused_bandwidth_refunded), expected);
+            assert_eq!(
+                buffer_refunded + unused_bandwidth_refunded,
+                unused_connection_escrow(escrowed, paid).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_top_earners() {
+        let wardens: Vec<Pubkey> = (0..TOP_EARNERS_CAPACITY).map(|_| Pubkey::new_unique()).collect();
+        let mut entries = Vec::new();
+        for (i, warden) in wardens.iter().enumerate() {
+            update_top_earners(&mut entries, *warden, 100 + i as u64);
+        }
+        assert_eq!(entries.len(), TOP_EARNERS_CAPACITY);
+        assert_eq!(entries.last().unwrap().warden, wardens[0]);
+
+        // A newcomer below the lowest entry is ignored
+        let newcomer = Pubkey::new_unique();
+        update_top_earners(&mut entries, newcomer, 50);
+        assert!(entries.iter().all(|e| e.warden != newcomer));
+
+        // Earning past the lowest promotes the newcomer and evicts the lowest
+        update_top_earners(&mut entries, newcomer, 1_000);
+        assert_eq!(entries.len(), TOP_EARNERS_CAPACITY);
+        assert_eq!(entries[0].warden, newcomer);
+        assert!(entries.iter().all(|e| e.warden != wardens[0]));
+
+        // An existing entry is updated in place rather than duplicated
+        update_top_earners(&mut entries, wardens[1], 2_000);
+        assert_eq!(entries[0].warden, wardens[1]);
+        assert_eq!(entries.iter().filter(|e| e.warden == wardens[1]).count(), 1);
+        assert!(entries.windows(2).all(|w| w[0].total_earnings >= w[1].total_earnings));
+    }
+
+    #[test]
+    fn test_session_history_wraps() {
+        let record = |started_at: i64| SessionRecord {
+            warden: Pubkey::default(),
+            started_at,
+            bandwidth_consumed: 10,
+            amount_paid: 100,
+        };
+
+        let mut history = Vec::new();
+        let mut head = 0u8;
+        for started_at in 0..SEEKER_HISTORY_CAPACITY as i64 {
+            push_session_record(&mut history, &mut head, record(started_at));
+        }
+        assert_eq!(head, 0);
+
+        // Two more sessions overwrite the two oldest
+        push_session_record(&mut history, &mut head, record(100));
+        push_session_record(&mut history, &mut head, record(101));
+        assert_eq!(history.len(), SEEKER_HISTORY_CAPACITY);
+        assert_eq!(head, 2);
+
+        let started: Vec<i64> = session_history_chronological(&history, head)
+            .iter()
+            .map(|r| r.started_at)
+            .collect();
+        assert_eq!(started, vec![2, 3, 4, 100, 101]);
+
+        // A seeker at its largest (full history and allowlist) fits SEEKER_SPACE exactly
+        let seeker = Seeker {
+            private_escrow: Some(Pubkey::new_unique()),
+            premium_expires_at: Some(0),
+            connection_history: history,
+            allowed_wardens: Some(vec![Pubkey::new_unique(); MAX_ALLOWED_WARDENS]),
+            ..crate::state::test_seeker()
+        };
+        let mut bytes = Vec::new();
+        seeker.try_serialize(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), SEEKER_SPACE);
+    }
+
+    /// Runs `f` against program-owned Seeker and Warden accounts holding `seeker`
+    /// and `warden`, the seeker account funded with `seeker_lamports` and the warden
+    /// sized at WARDEN_SPACE, plus an empty rewards vault and treasury
+    fn with_party_accounts<T>(
+        seeker: &Seeker,
+        warden: &Warden,
+        seeker_lamports: u64,
+        f: impl for<'info> FnOnce(
+            &mut Account<'info, Seeker>,
+            &mut Account<'info, Warden>,
+            &AccountInfo<'info>,
+            &AccountInfo<'info>,
+        ) -> T,
+    ) -> T {
+        let (seeker_key, warden_key, vault_key, treasury_key) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let owner = crate::ID;
+        let (mut seeker_data, mut warden_data, mut vault_data, mut treasury_data) =
+            (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+        seeker.try_serialize(&mut seeker_data).unwrap();
+        warden.try_serialize(&mut warden_data).unwrap();
+        warden_data.resize(WARDEN_SPACE, 0);
+        let (mut seeker_lamports, mut warden_lamports, mut vault_lamports, mut treasury_lamports) =
+            (seeker_lamports, 0u64, 0u64, 0u64);
+        let seeker_info =
+            AccountInfo::new(&seeker_key, false, true, &mut seeker_lamports, &mut seeker_data, &owner, false, 0);
+        let warden_info =
+            AccountInfo::new(&warden_key, false, true, &mut warden_lamports, &mut warden_data, &owner, false, 0);
+        let vault_info =
+            AccountInfo::new(&vault_key, false, true, &mut vault_lamports, &mut vault_data, &owner, false, 0);
+        let treasury_info =
+            AccountInfo::new(&treasury_key, false, true, &mut treasury_lamports, &mut treasury_data, &owner, false, 0);
+        let mut seeker: Account<Seeker> = Account::try_from(&seeker_info).unwrap();
+        let mut warden: Account<Warden> = Account::try_from(&warden_info).unwrap();
+        f(&mut seeker, &mut warden, &vault_info, &treasury_info)
+    }
+
+    /// Settles an `mb_consumed` proof on `connection` at `timestamp`, as if its
+    /// signatures had already been verified
+    fn settle_proof<'info>(
+        config: &ProtocolConfig,
+        connection: &mut Connection,
+        warden: &mut Account<'info, Warden>,
+        seeker: &mut Account<'info, Seeker>,
+        (rewards_vault, treasury): (&AccountInfo<'info>, &AccountInfo<'info>),
+        mb_consumed: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        settle_bandwidth_proof(
+            ProofSettlement {
+                connection_key: Pubkey::new_unique(),
+                connection,
+                warden,
+                seeker,
+                config,
+                rewards_vault,
+                treasury,
+                insurance_fund: None,
+                top_earners: None,
+            },
+            mb_consumed,
+            [1; 64],
+            [2; 64],
+            timestamp,
+        )
+    }
+
+    #[test]
+    fn test_start_connection_rejects_seeker_sharing_warden_authority() {
+        let config = crate::state::test_protocol_config();
+        let warden = crate::state::test_warden();
+        let escrow = 10_000_000_000u64;
+        let mut seeker = Seeker {
+            authority: warden.authority,
+            escrow_balance: escrow,
+            ..crate::state::test_seeker()
+        };
+
+        // Distinct seeker and warden accounts, but one wallet behind both
+        let result = with_party_accounts(&seeker, &warden, escrow, |seeker, warden, _, _| {
+            open_connection(&config, warden, seeker, None, 100, 0, 0).map(|_| ())
+        });
+        assert_eq!(result.unwrap_err(), ArkhamErrorCode::SelfDealingNotAllowed.into());
+
+        seeker.authority = Pubkey::new_unique();
+        with_party_accounts(&seeker, &warden, escrow, |seeker, warden, _, _| {
+            open_connection(&config, warden, seeker, None, 100, 0, 0)
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_batch_close_takes_only_settled_pairs_of_this_warden() {
+        let (seeker_key, warden_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (connection_key, _) = Pubkey::find_program_address(
+            &[b"connection", seeker_key.as_ref(), warden_key.as_ref()],
+            &crate::ID,
+        );
+        let mut connection = crate::state::test_connection();
+        connection.seeker = seeker_key;
+        connection.warden = warden_key;
+        connection.bandwidth_consumed = 100;
+        connection.amount_escrowed = 110_000;
+        connection.amount_paid = 100_000;
+
+        // Another open session holds 50_000; this one's unpaid 10_000 is still committed
+        let mut seeker = Seeker {
+            active_connections: 2,
+            committed_escrow: 50_000 + connection.amount_escrowed - connection.amount_paid,
+            ..crate::state::test_seeker()
+        };
+        let mut warden = crate::state::test_warden();
+        warden.active_connections = 1;
+        let (seeker_before, warden_before) = (seeker.clone(), warden.clone());
+
+        // Escrow still unpaid: the pair is refused and neither party is touched
+        assert_eq!(
+            settle_batch_pair(&connection_key, &connection, &seeker_key, &mut seeker, &warden_key, &mut warden).unwrap_err(),
+            ArkhamErrorCode::ConnectionNotSettled.into()
+        );
+        assert_eq!((seeker.active_connections, seeker.committed_escrow), (2, seeker_before.committed_escrow));
+        assert_eq!(warden.active_connections, warden_before.active_connections);
+
+        // A pair that isn't this warden's, or whose accounts don't belong together
+        // (the final proof paid out the rest and released its commitment)
+        connection.amount_paid = connection.amount_escrowed;
+        seeker.committed_escrow -= 10_000;
+        let other = Pubkey::new_unique();
+        for (connection_key, seeker_key, warden_key) in [
+            (Pubkey::new_unique(), seeker_key, warden_key), // not the pair's PDA
+            (connection_key, other, warden_key),            // another seeker's account
+            (connection_key, seeker_key, other),            // another warden's batch
+        ] {
+            assert_eq!(
+                settle_batch_pair(&connection_key, &connection, &seeker_key, &mut seeker, &warden_key, &mut warden).unwrap_err(),
+                ArkhamErrorCode::ConnectionAccountMismatch.into()
+            );
+        }
+
+        // Fully settled: booked exactly as end_connection would, the other session's commitment kept
+        settle_batch_pair(&connection_key, &connection, &seeker_key, &mut seeker, &warden_key, &mut warden).unwrap();
+        assert_eq!((seeker.active_connections, seeker.committed_escrow), (1, 50_000));
+        assert_eq!((seeker.total_bandwidth_consumed, seeker.total_spent), (100, 110_000));
+        assert_eq!((warden.active_connections, warden.successful_connections), (0, 1));
+    }
+
+    #[test]
+    fn test_warden_recent_connections_record_outcomes_and_wrap() {
+        let sessions = WARDEN_HISTORY_CAPACITY as u8 + 2;
+        let mut seeker_state = crate::state::test_seeker();
+        seeker_state.active_connections = sessions + 1;
+        let mut warden_state = crate::state::test_warden();
+        warden_state.active_connections = sessions;
+
+        with_party_accounts(&seeker_state, &warden_state, 0, |seeker, warden, _, _| {
+            let seeker_key = seeker.key();
+
+            // Sessions alternate between serving 10 MB and serving nothing
+            for started_at in 0..sessions as i64 {
+                let mut connection = crate::state::test_connection();
+                connection.started_at = started_at;
+                connection.bandwidth_consumed = if started_at % 2 == 0 { 10 } else { 0 };
+                connection.amount_paid = connection.bandwidth_consumed * 1_000;
+                connection.amount_escrowed = connection.amount_paid;
+                close_connection(Pubkey::new_unique(), &connection, warden, seeker).unwrap();
+            }
+
+            // Only the latest WARDEN_HISTORY_CAPACITY sessions remain, oldest first
+            assert_eq!(warden.recent_connections.len(), WARDEN_HISTORY_CAPACITY);
+            let recent = warden_history_chronological(&warden.recent_connections, warden.recent_connections_head);
+            let started: Vec<i64> = recent.iter().map(|r| r.started_at).collect();
+            assert_eq!(started, (2..sessions as i64).collect::<Vec<_>>());
+
+            for record in &recent {
+                assert_eq!(record.seeker, seeker_key);
+                let served = record.started_at % 2 == 0;
+                assert_eq!(record.outcome, if served { SessionOutcome::Served } else { SessionOutcome::Unserved });
+                assert_eq!(record.bandwidth_served, if served { 10 } else { 0 });
+                assert_eq!(record.amount_earned, record.bandwidth_served * 1_000);
+            }
+            assert_eq!(warden.successful_connections, 4);
+
+            // A full ring serializes within WARDEN_SPACE
+            let mut bytes = Vec::new();
+            warden.try_serialize(&mut bytes).unwrap();
+            assert!(bytes.len() <= WARDEN_SPACE);
+
+            // A warden still at 8 + 512 ends its sessions without recording them
+            let legacy_state = Warden { active_connections: 1, ..crate::state::test_warden() };
+            let (legacy_key, owner, mut legacy_lamports) = (Pubkey::new_unique(), crate::ID, 0u64);
+            let mut legacy_data = Vec::new();
+            legacy_state.try_serialize(&mut legacy_data).unwrap();
+            legacy_data.resize(8 + 512, 0);
+            let legacy_info =
+                AccountInfo::new(&legacy_key, false, true, &mut legacy_lamports, &mut legacy_data, &owner, false, 0);
+            let mut legacy: Account<Warden> = Account::try_from(&legacy_info).unwrap();
+            close_connection(Pubkey::new_unique(), &crate::state::test_connection(), &mut legacy, seeker).unwrap();
+            assert!(legacy.recent_connections.is_empty());
+            assert_eq!(legacy.active_connections, 0);
+        });
+    }
+
+    #[test]
+    fn test_bandwidth_proof_ring_buffer() {
+        let proof = |timestamp: i64| BandwidthProof {
+            timestamp,
+            mb_consumed: timestamp as u64,
+            seeker_signature: [1; 64],
+            warden_signature: [2; 64],
+        };
+        let timestamps = |proofs: &[BandwidthProof; PROOF_HISTORY_CAPACITY], head, count| {
+            bandwidth_proofs_chronological(proofs, head, count)
+                .map(|p| p.timestamp)
+                .collect::<Vec<i64>>()
+        };
+
+        let mut proofs = [BandwidthProof::EMPTY; PROOF_HISTORY_CAPACITY];
+        let (mut head, mut count) = (0u8, 0u8);
+        assert!(timestamps(&proofs, head, count).is_empty());
+
+        for timestamp in 1..=3 {
+            push_bandwidth_proof(&mut proofs, &mut head, &mut count, proof(timestamp));
+        }
+        assert_eq!(timestamps(&proofs, head, count), vec![1, 2, 3]);
+
+        // Fill past capacity: the oldest proofs are evicted, order stays chronological
+        for timestamp in 4..=(PROOF_HISTORY_CAPACITY as i64 + 2) {
+            push_bandwidth_proof(&mut proofs, &mut head, &mut count, proof(timestamp));
+        }
+        assert_eq!(count as usize, PROOF_HISTORY_CAPACITY);
+        assert_eq!(head, 2);
+        let expected: Vec<i64> = (3..=(PROOF_HISTORY_CAPACITY as i64 + 2)).collect();
+        assert_eq!(timestamps(&proofs, head, count), expected);
+    }
+
+    #[test]
+    fn test_proof_rejects_substituted_seeker_account() {
+        let connection = crate::state::test_connection();
+        assert!(ensure_connection_parties(&connection, &connection.seeker, &connection.warden).is_ok());
+
+        // A different seeker account, even one the warden also serves, isn't this connection's
+        assert_eq!(
+            ensure_connection_parties(&connection, &Pubkey::new_unique(), &connection.warden).unwrap_err(),
+            ArkhamErrorCode::ConnectionAccountMismatch.into()
+        );
+        assert_eq!(
+            ensure_connection_parties(&connection, &connection.seeker, &Pubkey::new_unique()).unwrap_err(),
+            ArkhamErrorCode::ConnectionAccountMismatch.into()
+        );
+        // Swapped parties don't pass either
+        assert!(ensure_connection_parties(&connection, &connection.warden, &connection.seeker).is_err());
+    }
+
+    #[test]
+    fn test_proof_rejects_out_of_range_stored_multiplier() {
+        let mut connection = crate::state::test_connection();
+        assert!(ensure_connection_multiplier_in_bounds(connection.warden_multiplier).is_ok());
+
+        // The config max itself still settles
+        connection.warden_multiplier = crate::instructions::admin::MAX_TIER_MULTIPLIER;
+        assert!(ensure_connection_multiplier_in_bounds(connection.warden_multiplier).is_ok());
+
+        // Anything above it would overpay, so settlement is refused
+        connection.warden_multiplier = crate::instructions::admin::MAX_TIER_MULTIPLIER + 1;
+        assert_eq!(
+            ensure_connection_multiplier_in_bounds(connection.warden_multiplier).unwrap_err(),
+            ArkhamErrorCode::InvalidTierMultiplier.into()
+        );
+    }
+
+    #[test]
+    fn test_proof_limit_bounds_connection_lifetime() {
+        let max_total_proofs = (PROOF_HISTORY_CAPACITY + 5) as u32;
+        let mut proofs = [BandwidthProof::EMPTY; PROOF_HISTORY_CAPACITY];
+        let (mut head, mut count, mut total_proofs) = (0u8, 0u8, 0u32);
+
+        // Every proof up to the limit settles, including past the retention window
+        for timestamp in 1..=max_total_proofs as i64 {
+            ensure_below_proof_limit(total_proofs, max_total_proofs).unwrap();
+            push_bandwidth_proof(&mut proofs, &mut head, &mut count, BandwidthProof {
+                timestamp,
+                ..BandwidthProof::EMPTY
+            });
+            total_proofs += 1;
+        }
+        assert_eq!(total_proofs, max_total_proofs);
+
+        // The proof that would exceed it is rejected
+        assert_eq!(
+            ensure_below_proof_limit(total_proofs, max_total_proofs).unwrap_err(),
+            ArkhamErrorCode::ProofLimitReached.into()
+        );
+
+        // 0 leaves the lifetime unbounded
+        assert!(ensure_below_proof_limit(u32::MAX - 1, 0).is_ok());
+    }
+
+    #[test]
+    fn test_idle_escrow_bounds() {
+        assert!(park_idle_escrow(100, 0, 101).is_err());
+        assert!(park_idle_escrow(100, 0, 0).is_err());
+        assert!(unwind_idle_escrow(0, 50, 51).is_err());
+    }
+
+    #[test]
+    fn test_stake_and_rewards_vaults_are_distinct() {
+        let (stake_vault, _) = Pubkey::find_program_address(&[b"stake_vault"], &crate::ID);
+        let (rewards_vault, _) = Pubkey::find_program_address(&[b"rewards_vault"], &crate::ID);
+        assert_ne!(stake_vault, rewards_vault);
+    }
+
+    #[test]
+    fn test_claim_earnings_never_touches_stake_vault() {
+        use anchor_lang::ToAccountMetas;
+
+        let (stake_vault, _) = Pubkey::find_program_address(&[b"stake_vault"], &crate::ID);
+        let (rewards_vault, _) = Pubkey::find_program_address(&[b"rewards_vault"], &crate::ID);
+        let metas = crate::accounts::ClaimEarnings {
+            warden: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            rewards_vault,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None);
+
+        // Earnings are paid from the rewards vault; the stake vault is not even an input
+        assert!(metas.iter().any(|m| m.pubkey == rewards_vault && m.is_writable));
+        assert!(metas.iter().all(|m| m.pubkey != stake_vault));
+    }
+
+    #[test]
+    fn test_first_claim_can_create_arkham_token_account() {
+        use anchor_lang::ToAccountMetas;
+        use anchor_spl::associated_token::get_associated_token_address;
+
+        // A warden claiming for the first time has no ATA yet; the claim itself creates it
+        let authority = Pubkey::new_unique();
+        let (warden, _) = Pubkey::find_program_address(&[b"warden", authority.as_ref()], &crate::ID);
+        let (arkham_mint, _) = Pubkey::find_program_address(&[b"arkham_mint"], &crate::ID);
+        let (mint_authority, _) = Pubkey::find_program_address(&MINT_AUTHORITY_SEEDS, &crate::ID);
+        let warden_arkham_token_account = get_associated_token_address(&authority, &arkham_mint);
+        let metas = crate::accounts::ClaimArkhamTokens {
+            warden,
+            authority,
+            protocol_config: Pubkey::new_unique(),
+            arkham_mint,
+            warden_arkham_token_account,
+            mint_authority,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None);
+
+        // The authority pays for the account, and the programs needed to create it are present
+        assert!(metas.iter().any(|m| m.pubkey == authority && m.is_signer && m.is_writable));
+        assert!(metas.iter().any(|m| m.pubkey == warden_arkham_token_account && m.is_writable));
+        assert!(metas.iter().any(|m| m.pubkey == anchor_spl::associated_token::ID));
+        assert!(metas.iter().any(|m| m.pubkey == anchor_lang::system_program::ID));
+    }
+
+    #[test]
+    fn test_proof_payment_funds_rewards_vault_only() {
+        let owner = crate::ID;
+        let (seeker_key, rewards_key, stake_key) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut seeker_lamports, mut rewards_lamports, mut stake_lamports) =
+            (10_000u64, 0u64, 5_000u64);
+        let (mut seeker_data, mut rewards_data, mut stake_data) = (vec![], vec![], vec![]);
+        let seeker = AccountInfo::new(
+            &seeker_key, false, true, &mut seeker_lamports, &mut seeker_data, &owner, false, 0,
+        );
+        let rewards = AccountInfo::new(
+            &rewards_key, false, true, &mut rewards_lamports, &mut rewards_data, &owner, false, 0,
+        );
+        let stake = AccountInfo::new(
+            &stake_key, false, true, &mut stake_lamports, &mut stake_data, &owner, false, 0,
+        );
+
+        move_lamports(&seeker, &rewards, 3_000).unwrap();
+        assert_eq!(seeker.lamports(), 7_000);
+        assert_eq!(rewards.lamports(), 3_000);
+        assert_eq!(stake.lamports(), 5_000);
+
+        // Escrow can't be overdrawn into the rewards vault
+        assert!(move_lamports(&seeker, &rewards, 7_001).is_err());
+        assert_eq!(stake.lamports(), 5_000);
+    }
+
+    #[test]
+    fn test_quick_session_matches_multi_step_path() {
+        use crate::instructions::bandwidth::{
+            create_proof_message, create_quick_session_proof_message, ed25519_ix_data,
+            instructions_sysvar_data, verify_dual_signatures,
+        };
+
+        // With proof fees on, so both paths must also agree on the treasury's cut
+        let config = ProtocolConfig { proof_fee_enabled: true, ..crate::state::test_protocol_config() };
+        let (mb_consumed, started_at, proof_at) = (100u64, 1_700_000_000i64, 1_700_000_060i64);
+        let (rent_exempt_minimum, escrow) = (1_000_000u64, 500_000u64);
+        let (seeker_signature, warden_signature) = ([1u8; 64], [2u8; 64]);
+
+        // Two identical seeker/warden pairs plus a rewards vault and treasury each:
+        // [seeker, warden, vault, treasury] runs start/submit/end, the second set runs quick_session
+        let keys: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        let seeker_state = Seeker {
+            escrow_balance: escrow,
+            ..crate::state::test_seeker()
+        };
+        let mut data: Vec<Vec<u8>> = (0..8)
+            .map(|i| {
+                let mut bytes = Vec::new();
+                match i % 4 {
+                    0 => seeker_state.try_serialize(&mut bytes).unwrap(),
+                    1 => crate::state::test_warden().try_serialize(&mut bytes).unwrap(),
+                    _ => {}
+                }
+                bytes.resize(bytes.len() + 256, 0);
+                bytes
+            })
+            .collect();
+        let mut lamports = [rent_exempt_minimum + escrow, 0, 0, 0, rent_exempt_minimum + escrow, 0, 0, 0];
+        let owner = crate::ID;
+        let infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(data.iter_mut())
+            .zip(lamports.iter_mut())
+            .map(|((key, data), lamports)| AccountInfo::new(key, false, true, lamports, data, &owner, false, 0))
+            .collect();
+        let (mut seeker_a, mut warden_a): (Account<Seeker>, Account<Warden>) =
+            (Account::try_from(&infos[0]).unwrap(), Account::try_from(&infos[1]).unwrap());
+        let (mut seeker_b, mut warden_b): (Account<Seeker>, Account<Warden>) =
+            (Account::try_from(&infos[4]).unwrap(), Account::try_from(&infos[5]).unwrap());
+        let (connection_a, connection_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Multi-step: start, settle one proof a minute later, end
+        let mut connection = open_connection(
+            &config, &mut warden_a, &mut seeker_a, None, mb_consumed, rent_exempt_minimum, started_at,
+        ).unwrap();
+        settle_bandwidth_proof(
+            ProofSettlement {
+                connection_key: connection_a,
+                connection: &mut connection,
+                warden: &mut warden_a,
+                seeker: &mut seeker_a,
+                config: &config,
+                rewards_vault: &infos[2],
+                treasury: &infos[3],
+                insurance_fund: None,
+                top_earners: None,
+            },
+            mb_consumed,
+            seeker_signature,
+            warden_signature,
+            proof_at,
+        ).unwrap();
+        let multi_step = close_connection(connection_a, &connection, &mut warden_a, &mut seeker_a).unwrap();
+
+        // Quick session: the same proof, signed over the session-bound message
+        let mut connection = open_connection(
+            &config, &mut warden_b, &mut seeker_b, None, mb_consumed, rent_exempt_minimum, proof_at,
+        ).unwrap();
+        assert_eq!(connection.started_at, proof_at);
+        let message = create_quick_session_proof_message(
+            &connection_b, connection.connection_id, mb_consumed, proof_at,
+        );
+        let mut sysvar_lamports = 0u64;
+        let mut sysvar_data = instructions_sysvar_data(&[
+            (anchor_lang::solana_program::ed25519_program::ID,
+                ed25519_ix_data(&seeker_signature, &seeker_b.authority, &message)),
+            (anchor_lang::solana_program::ed25519_program::ID,
+                ed25519_ix_data(&warden_signature, &warden_b.authority, &message)),
+        ]);
+        let sysvar_owner = Pubkey::default();
+        let sysvar = AccountInfo::new(
+            &INSTRUCTIONS_SYSVAR_ID, false, false, &mut sysvar_lamports, &mut sysvar_data, &sysvar_owner, false, 0,
+        );
+        let verify = |message: &[u8]| verify_dual_signatures(
+            &sysvar, message, &seeker_signature, &seeker_b.authority, &warden_signature, &warden_b.authority,
+        );
+        assert!(verify(&message).is_ok());
+        // A proof signed for a regular connection on the same PDA doesn't carry over
+        assert!(verify(&create_proof_message(&connection_b, mb_consumed, proof_at)).is_err());
+        settle_bandwidth_proof(
+            ProofSettlement {
+                connection_key: connection_b,
+                connection: &mut connection,
+                warden: &mut warden_b,
+                seeker: &mut seeker_b,
+                config: &config,
+                rewards_vault: &infos[6],
+                treasury: &infos[7],
+                insurance_fund: None,
+                top_earners: None,
+            },
+            mb_consumed,
+            seeker_signature,
+            warden_signature,
+            proof_at,
+        ).unwrap();
+        let quick = close_connection(connection_b, &connection, &mut warden_b, &mut seeker_b).unwrap();
+
+        // Same figures, only the session id differs
+        assert_eq!(quick, ConnectionSummary { connection_id: quick.connection_id, ..multi_step.clone() });
+        assert_eq!(multi_step.total_paid, calculate_payment_amount(mb_consumed, connection.rate_per_mb).unwrap());
+        assert!(multi_step.refunded > 0);
+
+        // Final balances match, the protocol fee on the payment going to the treasury
+        let protocol_fee = multi_step.total_paid * config.protocol_fee_bps as u64 / 10_000;
+        assert!(protocol_fee > 0);
+        for (seeker, warden) in [(&seeker_a, &warden_a), (&seeker_b, &warden_b)] {
+            assert_eq!(seeker.escrow_balance, escrow - multi_step.total_paid);
+            assert_eq!(seeker.committed_escrow, 0);
+            assert_eq!(seeker.active_connections, 0);
+            assert_eq!(seeker.total_spent, multi_step.total_paid);
+            assert_eq!(seeker.total_bandwidth_consumed, mb_consumed);
+            assert_eq!(seeker.connection_nonce, 1);
+            assert_eq!(warden.pending_claims, multi_step.total_paid - protocol_fee);
+            assert_eq!(warden.active_connections, 0);
+            assert_eq!(warden.successful_connections, 1);
+        }
+        assert_eq!(warden_a.arkham_tokens_earned, warden_b.arkham_tokens_earned);
+        assert_eq!(infos[0].lamports(), infos[4].lamports());
+        for (vault, treasury) in [(&infos[2], &infos[3]), (&infos[6], &infos[7])] {
+            assert_eq!(vault.lamports(), multi_step.total_paid - protocol_fee);
+            assert_eq!(treasury.lamports(), protocol_fee);
+        }
+    }
+}
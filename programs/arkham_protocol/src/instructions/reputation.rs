@@ -1,13 +1,24 @@
-use anchor_lang::prelude::*;
-use crate::state::{Warden, ProtocolConfig};
+use anchor_lang::{prelude::*, system_program};
+use anchor_lang::solana_program::sysvar::instructions::ID as INSTRUCTIONS_SYSVAR_ID;
+use crate::instructions::bandwidth::{
+    create_proof_message, ensure_signature_verification_present, verify_ed25519_signature_via_sysvar,
+};
+use crate::instructions::staking::record_slash;
+use crate::state::{AnomalyReport, Warden, ProtocolConfig, RECENT_FAILURE_CAPACITY};
 use crate::ArkhamErrorCode;
 
+/// Weight of a failure older than failure_decay_window, in basis points of a recent one
+pub const AGED_FAILURE_WEIGHT_BPS: u128 = 5000;
+
 /// Updates a Warden's reputation score based on performance metrics
-/// This instruction should typically be called by an off-chain cron job
+/// This instruction should typically be called by an off-chain cron job.
+/// Sessions settled through end_connection already record their success on-chain;
+/// `connection_success` is for outcomes only observed off-chain (e.g. failed handshakes).
 pub fn update_reputation_handler(
     ctx: Context<UpdateReputation>,
     connection_success: bool,
     uptime_report: u16, // Uptime as basis points (0-10000)
+    report_timestamp: i64, // When the uptime was measured
 ) -> Result<()> {
     let warden = &mut ctx.accounts.warden;
     let config = &ctx.accounts.protocol_config;
@@ -19,22 +30,44 @@ pub fn update_reputation_handler(
         ArkhamErrorCode::UnauthorizedReputationUpdate
     );
 
+    // The seed-providing authority must be the one the warden account records
+    ensure_warden_authority(warden, &ctx.accounts.warden_authority.key())?;
+
+    // Space reports out so a misbehaving updater can't move scores in rapid bursts
+    ensure_reputation_update_due(
+        warden.last_reputation_update,
+        clock.unix_timestamp,
+        config.min_reputation_update_interval,
+    )?;
+    warden.last_reputation_update = clock.unix_timestamp;
+
     // Update connection statistics
     if connection_success {
         warden.successful_connections = warden.successful_connections
             .checked_add(1)
-            .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+            .ok_or(ArkhamErrorCode::ReputationOverflow)?;
     } else {
-        warden.failed_connections = warden.failed_connections
-            .checked_add(1)
-            .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+        record_failed_connection(warden, clock.unix_timestamp)?;
     }
 
-    // Update uptime percentage
+    // Update uptime percentage, from a measurement recent enough to still describe the warden
+    validate_uptime_report(uptime_report)?;
+    ensure_uptime_report_fresh(report_timestamp, clock.unix_timestamp, config.max_uptime_report_age)?;
     warden.uptime_percentage = uptime_report;
 
-    // Calculate new reputation score using weighted formula
-    let new_reputation = calculate_reputation_score(warden, clock.unix_timestamp)?;
+    // Remember sub-SLA reports so seekers connected at the time can claim a refund
+    if config.sla_uptime_bps > 0 && uptime_report < config.sla_uptime_bps {
+        warden.last_sla_breach_at = clock.unix_timestamp;
+        warden.last_sla_breach_uptime = uptime_report;
+    }
+
+    // Calculate new reputation score using weighted formula, capped while bootstrapping
+    let components = calculate_reputation_score(warden, clock.unix_timestamp, config.failure_decay_window)?;
+    let new_reputation = apply_bootstrap_reputation_cap(
+        components.score(),
+        warden.successful_connections,
+        config,
+    );
 
     // Update the reputation score
     warden.reputation_score = new_reputation;
@@ -56,26 +89,196 @@ pub fn update_reputation_handler(
         connection_success,
     });
 
+    emit!(reputation_breakdown(warden.authority, &components, new_reputation));
+
+    Ok(())
+}
+
+/// Suspends a Warden that has been idle longer than inactivity_timeout, or lifts the
+/// suspension once it is active again (its proofs and reputation reports refresh last_active)
+/// Permissionless, so keepers rather than the reputation updater drive suspensions
+pub fn check_warden_activity_handler(ctx: Context<CheckWardenActivity>) -> Result<()> {
+    let warden = &mut ctx.accounts.warden;
+    let config = &ctx.accounts.protocol_config;
+    let clock = Clock::get()?;
+
+    match apply_inactivity_check(warden, clock.unix_timestamp, config.inactivity_timeout) {
+        Some(true) => emit!(WardenSuspended {
+            warden: warden.authority,
+            last_active: warden.last_active,
+            inactive_for: clock.unix_timestamp.saturating_sub(warden.last_active),
+        }),
+        Some(false) => emit!(WardenReinstated {
+            warden: warden.authority,
+            last_active: warden.last_active,
+        }),
+        None => {}
+    }
+
+    Ok(())
+}
+
+/// Sets `suspended` from how long the Warden has been idle (a timeout of 0 suspends no one)
+/// Returns the new state when it changed
+pub fn apply_inactivity_check(warden: &mut Warden, current_timestamp: i64, inactivity_timeout: i64) -> Option<bool> {
+    let inactive = inactivity_timeout > 0
+        && current_timestamp.saturating_sub(warden.last_active) > inactivity_timeout;
+    if inactive == warden.suspended {
+        return None;
+    }
+    warden.suspended = inactive;
+    Some(inactive)
+}
+
+/// Rejects a Warden account whose stored authority isn't `warden_authority`
+pub fn ensure_warden_authority(warden: &Warden, warden_authority: &Pubkey) -> Result<()> {
+    require!(warden.authority == *warden_authority, ArkhamErrorCode::WardenAuthorityMismatch);
+    Ok(())
+}
+
+/// Rejects a reputation update less than `min_interval` seconds after the last one
+pub fn ensure_reputation_update_due(last_update: i64, current_timestamp: i64, min_interval: i64) -> Result<()> {
+    require!(
+        current_timestamp.saturating_sub(last_update) >= min_interval,
+        ArkhamErrorCode::ReputationUpdateTooFrequent
+    );
+    Ok(())
+}
+
+/// Rejects an uptime measured more than `max_age` seconds ago (0 disables the check)
+/// or dated after `current_timestamp`
+pub fn ensure_uptime_report_fresh(report_timestamp: i64, current_timestamp: i64, max_age: i64) -> Result<()> {
+    require!(report_timestamp <= current_timestamp, ArkhamErrorCode::StaleUptimeReport);
+    require!(
+        max_age == 0 || current_timestamp.saturating_sub(report_timestamp) <= max_age,
+        ArkhamErrorCode::StaleUptimeReport
+    );
     Ok(())
 }
 
-/// Calculates the reputation score using a weighted formula:
+/// Rejects uptime reports outside 0-10000 basis points
+pub fn validate_uptime_report(uptime_report: u16) -> Result<()> {
+    require!(uptime_report <= 10000, ArkhamErrorCode::InvalidUptimeReport);
+    Ok(())
+}
+
+/// Caps the reputation of a warden that hasn't yet completed
+/// `min_connections_for_full_reputation` successful connections
+/// A fresh warden's perfect success rate is vacuous, so it shouldn't outrank
+/// proven wardens for the premium pool until it has a track record
+pub fn apply_bootstrap_reputation_cap(
+    score: u32,
+    successful_connections: u64,
+    config: &ProtocolConfig,
+) -> u32 {
+    if successful_connections < config.min_connections_for_full_reputation {
+        score.min(config.bootstrap_reputation_cap)
+    } else {
+        score
+    }
+}
+
+/// Weighted reputation contributions, in basis points of the final score
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReputationComponents {
+    pub success: u32,
+    pub uptime: u32,
+    pub bandwidth: u32,
+    pub recency: u32,
+}
+
+impl ReputationComponents {
+    /// Sum of the contributions, capped at 10000 (100%)
+    pub fn score(&self) -> u32 {
+        self.success
+            .saturating_add(self.uptime)
+            .saturating_add(self.bandwidth)
+            .saturating_add(self.recency)
+            .min(10000)
+    }
+}
+
+/// Counts a failed connection and stamps it into the warden's recent-failure ring
+pub fn record_failed_connection(warden: &mut Warden, current_timestamp: i64) -> Result<()> {
+    warden.failed_connections = warden.failed_connections
+        .checked_add(1)
+        .ok_or(ArkhamErrorCode::ReputationOverflow)?;
+
+    let head = warden.recent_failures_head as usize % RECENT_FAILURE_CAPACITY;
+    warden.recent_failures[head] = current_timestamp;
+    warden.recent_failures_head = ((head + 1) % RECENT_FAILURE_CAPACITY) as u8;
+    Ok(())
+}
+
+/// failed_connections weighted by age, in basis points of one recent failure
+///
+/// With a zero window every failure weighs 10000. Otherwise a failure older than the
+/// window weighs AGED_FAILURE_WEIGHT_BPS. Failures that have left the ring are older
+/// than every tracked one, so they count as aged once the oldest tracked failure is,
+/// or when the ring never filled and they predate failure tracking.
+pub fn weighted_failures_bps(warden: &Warden, current_timestamp: i64, failure_decay_window: i64) -> u128 {
+    let failed = warden.failed_connections as u128;
+    if failure_decay_window == 0 {
+        return failed * 10000;
+    }
+
+    let is_aged = |failed_at: i64| current_timestamp.saturating_sub(failed_at) > failure_decay_window;
+    let tracked: Vec<i64> = warden.recent_failures.iter().copied().filter(|&at| at != 0).collect();
+    let untracked = failed.saturating_sub(tracked.len() as u128);
+    let untracked_aged = tracked.len() < RECENT_FAILURE_CAPACITY
+        || tracked.iter().min().is_some_and(|&oldest| is_aged(oldest));
+
+    let aged = (tracked.iter().filter(|&&at| is_aged(at)).count() as u128)
+        .saturating_add(if untracked_aged { untracked } else { 0 })
+        .min(failed);
+    (failed - aged) * 10000 + aged * AGED_FAILURE_WEIGHT_BPS
+}
+
+/// Builds the ReputationBreakdown event for a computed score
+/// `stored_score` is what was written to the warden, after any bootstrap cap
+pub fn reputation_breakdown(
+    warden: Pubkey,
+    components: &ReputationComponents,
+    stored_score: u32,
+) -> ReputationBreakdown {
+    ReputationBreakdown {
+        warden,
+        success_contribution: components.success,
+        uptime_contribution: components.uptime,
+        bandwidth_contribution: components.bandwidth,
+        recency_contribution: components.recency,
+        stored_score,
+    }
+}
+
+/// Calculates the reputation components using a weighted formula:
 /// - Connection success rate: 40% weight
 /// - Uptime percentage: 30% weight  
 /// - Recent bandwidth contribution: 20% weight
 /// - Time since last active: 10% weight (decays over time)
-fn calculate_reputation_score(warden: &Warden, current_timestamp: i64) -> Result<u32> {
-    // 1. Connection success rate (40% weight)
-    let total_connections = warden.successful_connections
+fn calculate_reputation_score(
+    warden: &Warden,
+    current_timestamp: i64,
+    failure_decay_window: i64,
+) -> Result<ReputationComponents> {
+    // 1. Connection success rate (40% weight), with failures weighted by age
+    warden.successful_connections
         .checked_add(warden.failed_connections)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
-    
-    let success_rate = if total_connections > 0 {
-        let success_bps = (warden.successful_connections as u128)
+        .ok_or(ArkhamErrorCode::ReputationOverflow)?;
+
+    let successes_bps = (warden.successful_connections as u128)
+        .checked_mul(10000)
+        .ok_or(ArkhamErrorCode::ReputationOverflow)?;
+    let total_bps = successes_bps
+        .checked_add(weighted_failures_bps(warden, current_timestamp, failure_decay_window))
+        .ok_or(ArkhamErrorCode::ReputationOverflow)?;
+
+    let success_rate = if total_bps > 0 {
+        let success_bps = successes_bps
             .checked_mul(10000)
-            .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
-            .checked_div(total_connections as u128)
-            .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u32;
+            .ok_or(ArkhamErrorCode::ReputationOverflow)?
+            .checked_div(total_bps)
+            .ok_or(ArkhamErrorCode::ReputationOverflow)? as u32;
         success_bps.min(10000) // Cap at 100%
     } else {
         10000 // New wardens start with perfect score
@@ -83,16 +286,16 @@ fn calculate_reputation_score(warden: &Warden, current_timestamp: i64) -> Result
     
     let success_contribution = (success_rate as u128)
         .checked_mul(40)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
+        .ok_or(ArkhamErrorCode::ReputationOverflow)?
         .checked_div(100)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u32;
+        .ok_or(ArkhamErrorCode::ReputationOverflow)? as u32;
 
     // 2. Uptime percentage (30% weight)
     let uptime_contribution = (warden.uptime_percentage as u128)
         .checked_mul(30)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
+        .ok_or(ArkhamErrorCode::ReputationOverflow)?
         .checked_div(100)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u32;
+        .ok_or(ArkhamErrorCode::ReputationOverflow)? as u32;
 
     // 3. Recent bandwidth contribution (20% weight)
     // Calculate bandwidth served in the last 7 days
@@ -107,7 +310,7 @@ fn calculate_reputation_score(warden: &Warden, current_timestamp: i64) -> Result
         10000u32.saturating_sub(
             (days_since_active as u32)
                 .checked_mul(10000)
-                .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
+                .ok_or(ArkhamErrorCode::ReputationOverflow)?
                 .checked_div(max_days as u32)
                 .unwrap_or(10000)
         )
@@ -117,9 +320,9 @@ fn calculate_reputation_score(warden: &Warden, current_timestamp: i64) -> Result
     
     let bandwidth_contribution = (activity_score as u128)
         .checked_mul(20)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
+        .ok_or(ArkhamErrorCode::ReputationOverflow)?
         .checked_div(100)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u32;
+        .ok_or(ArkhamErrorCode::ReputationOverflow)? as u32;
 
     // 4. Recency bonus/penalty (10% weight)
     // Decay reputation for inactivity
@@ -140,28 +343,24 @@ fn calculate_reputation_score(warden: &Warden, current_timestamp: i64) -> Result
     let recency_contribution = 10000u32.saturating_sub(recency_penalty);
     let recency_contribution = (recency_contribution as u128)
         .checked_mul(10)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
+        .ok_or(ArkhamErrorCode::ReputationOverflow)?
         .checked_div(100)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)? as u32;
-
-    // Sum all contributions
-    let total_contribution = success_contribution
-        .checked_add(uptime_contribution)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
-        .checked_add(bandwidth_contribution)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?
-        .checked_add(recency_contribution)
-        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+        .ok_or(ArkhamErrorCode::ReputationOverflow)? as u32;
 
-    // Cap at 10000 (100%)
-    Ok(total_contribution.min(10000))
+    Ok(ReputationComponents {
+        success: success_contribution,
+        uptime: uptime_contribution,
+        bandwidth: bandwidth_contribution,
+        recency: recency_contribution,
+    })
 }
 
 /// Updates premium pool rankings by calculating all wardens' reputation scores
 /// This is typically called off-chain as a batch operation since it requires scanning all accounts
+/// The ranked Warden accounts are passed in `remaining_accounts`, in the same order as `top_wardens`
 pub fn update_premium_pool_rankings_handler(
     ctx: Context<UpdatePremiumPoolRankings>,
-    top_wardens: Vec<Pubkey>, // Top 100 warden pubkeys in reputation order
+    top_wardens: Vec<Pubkey>, // Up to premium_pool_size warden pubkeys in reputation order
 ) -> Result<()> {
     // Verify the caller is authorized to update rankings
     let config = &ctx.accounts.protocol_config;
@@ -170,6 +369,9 @@ pub fn update_premium_pool_rankings_handler(
         ArkhamErrorCode::UnauthorizedReputationUpdate
     );
 
+    let supplied: Vec<Pubkey> = ctx.remaining_accounts.iter().map(|account| account.key()).collect();
+    validate_premium_rankings(&top_wardens, &supplied, config.premium_pool_size)?;
+
     // This would typically iterate through a list of wardens and assign ranks
     // In practice, this might be computed off-chain and only the rankings stored
     // For now, we'll emit an event to signal that rankings have been updated
@@ -181,6 +383,205 @@ pub fn update_premium_pool_rankings_handler(
     Ok(())
 }
 
+/// Checks a premium ranking fits the pool, names each warden once, and lines up
+/// one-to-one with the warden accounts supplied alongside it
+pub fn validate_premium_rankings(top_wardens: &[Pubkey], supplied: &[Pubkey], pool_size: u16) -> Result<()> {
+    require!(
+        top_wardens.len() <= pool_size as usize,
+        ArkhamErrorCode::PremiumPoolTooLarge
+    );
+    require!(top_wardens == supplied, ArkhamErrorCode::PremiumRankingAccountsMismatch);
+
+    let mut sorted = top_wardens.to_vec();
+    sorted.sort_unstable();
+    require!(
+        sorted.windows(2).all(|pair| pair[0] != pair[1]),
+        ArkhamErrorCode::DuplicatePremiumWarden
+    );
+
+    Ok(())
+}
+
+/// Records a bandwidth proof that submit_bandwidth_proof rejected
+/// A failed transaction rolls back any state it touched, so the off-chain
+/// submitter that observed the rejection reports it here for fraud analytics
+pub fn report_rejected_proof_handler(
+    ctx: Context<ReportRejectedProof>,
+    connection: Pubkey,
+    reason: ProofRejectionReason,
+) -> Result<()> {
+    let event = log_rejected_proof(
+        &ctx.accounts.protocol_config,
+        &ctx.accounts.authority.key(),
+        &mut ctx.accounts.warden,
+        connection,
+        reason,
+    )?;
+    emit!(event);
+
+    Ok(())
+}
+
+/// Checks `reporter` is the reputation updater, then increments the warden's
+/// rejected proof counter and returns the ProofRejected event to emit
+pub fn log_rejected_proof(
+    config: &ProtocolConfig,
+    reporter: &Pubkey,
+    warden: &mut Warden,
+    connection: Pubkey,
+    reason: ProofRejectionReason,
+) -> Result<ProofRejected> {
+    // Verify the caller is the authorized updater
+    require!(
+        *reporter == config.reputation_updater,
+        ArkhamErrorCode::UnauthorizedReputationUpdate
+    );
+
+    warden.rejected_proof_count = warden.rejected_proof_count
+        .checked_add(1)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+
+    Ok(ProofRejected {
+        warden: warden.authority,
+        connection,
+        reason,
+        rejected_proof_count: warden.rejected_proof_count,
+    })
+}
+
+/// Why a bandwidth proof was rejected
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum ProofRejectionReason {
+    Duplicate,
+    InsufficientEscrow,
+    InvalidSignature,
+    Anomalous,
+}
+
+/// Two proofs the Warden signed for the same connection and timestamp
+/// An honest Warden signs exactly one amount per proof timestamp
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AnomalyEvidence {
+    pub seeker: Pubkey, // the connection's Seeker account, for the connection PDA
+    pub timestamp: i64,
+    pub first_mb: u64,
+    pub first_signature: [u8; 64],
+    pub second_mb: u64,
+    pub second_signature: [u8; 64],
+}
+
+/// Penalizes a Warden proven to have signed conflicting bandwidth proofs and pays
+/// the reporter a bounty out of the penalty; the remainder goes to the treasury.
+/// Anyone may report. The Warden's signatures are verified through Ed25519Program
+/// instructions at indices 0 (first proof) and 1 (second proof).
+pub fn report_anomaly_handler(ctx: Context<ReportAnomaly>, evidence: AnomalyEvidence) -> Result<()> {
+    let config = &ctx.accounts.protocol_config;
+    require!(config.anomaly_penalty > 0, ArkhamErrorCode::AnomalyReportingDisabled);
+
+    let warden = &mut ctx.accounts.warden;
+    let (connection, _) = Pubkey::find_program_address(
+        &[b"connection", evidence.seeker.as_ref(), warden.key().as_ref()],
+        &crate::ID,
+    );
+
+    // 1. Both proofs must be the warden's, for this connection
+    verify_anomaly_evidence(&ctx.accounts.instructions_sysvar, &connection, &warden.authority, &evidence)?;
+
+    // 2. Penalize the warden's unclaimed earnings, split the penalty, and record the slash
+    let (penalty, bounty) = slash_for_anomaly(warden, config.anomaly_penalty, config.anomaly_bounty_bps)?;
+
+    let vault_seeds = &[b"rewards_vault".as_ref(), &[ctx.bumps.rewards_vault]];
+    let signer_seeds = &[&vault_seeds[..]];
+    for (to, amount) in [
+        (ctx.accounts.reporter.to_account_info(), bounty),
+        (ctx.accounts.treasury.to_account_info(), penalty - bounty),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        let cpi_context = CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer { from: ctx.accounts.rewards_vault.to_account_info(), to },
+            signer_seeds,
+        );
+        system_program::transfer(cpi_context, amount)?;
+    }
+
+    // 3. Record the report so the same evidence can't be paid twice
+    let report = &mut ctx.accounts.anomaly_report;
+    report.warden = warden.key();
+    report.connection = connection;
+    report.timestamp = evidence.timestamp;
+    report.reporter = ctx.accounts.reporter.key();
+    report.penalty = penalty;
+    report.bounty = bounty;
+
+    emit!(AnomalyReported {
+        warden: warden.authority,
+        connection,
+        reporter: report.reporter,
+        timestamp: evidence.timestamp,
+        penalty,
+        bounty,
+    });
+
+    Ok(())
+}
+
+/// Checks the evidence is two different amounts the Warden signed for one connection
+/// and timestamp, each backed by an Ed25519Program instruction (indices 0 and 1)
+pub fn verify_anomaly_evidence(
+    instructions_sysvar: &AccountInfo,
+    connection: &Pubkey,
+    warden_authority: &Pubkey,
+    evidence: &AnomalyEvidence,
+) -> Result<()> {
+    require!(evidence.first_mb != evidence.second_mb, ArkhamErrorCode::InvalidAnomalyEvidence);
+
+    ensure_signature_verification_present(instructions_sysvar, &[0, 1])?;
+    let proofs = [
+        (evidence.first_mb, &evidence.first_signature),
+        (evidence.second_mb, &evidence.second_signature),
+    ];
+    for (index, (mb_consumed, signature)) in proofs.into_iter().enumerate() {
+        let message = create_proof_message(connection, mb_consumed, evidence.timestamp);
+        verify_ed25519_signature_via_sysvar(
+            instructions_sysvar,
+            &message,
+            signature,
+            warden_authority,
+            index as u16,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Takes the anomaly penalty out of the Warden's pending claims and adds it to its
+/// slashing history; returns (penalty, bounty) for the handler to pay out
+pub fn slash_for_anomaly(warden: &mut Warden, anomaly_penalty: u64, bounty_bps: u16) -> Result<(u64, u64)> {
+    let (penalty, bounty) = anomaly_penalty_split(warden.pending_claims, anomaly_penalty, bounty_bps)?;
+
+    warden.pending_claims = warden.pending_claims
+        .checked_sub(penalty)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?;
+    record_slash(warden, penalty)?;
+
+    Ok((penalty, bounty))
+}
+
+/// Returns (penalty, bounty): the configured penalty capped at the Warden's pending
+/// claims, and the reporter's `bounty_bps` share of it
+pub fn anomaly_penalty_split(pending_claims: u64, anomaly_penalty: u64, bounty_bps: u16) -> Result<(u64, u64)> {
+    let penalty = anomaly_penalty.min(pending_claims);
+    let bounty = (penalty as u128)
+        .checked_mul(bounty_bps as u128)
+        .ok_or(ArkhamErrorCode::RewardOverflow)?
+        / 10000;
+
+    Ok((penalty, bounty as u64))
+}
+
 // Account contexts:
 
 #[derive(Accounts)]
@@ -205,6 +606,80 @@ pub struct UpdateReputation<'info> {
     pub authority: Signer<'info>, // The authorized reputation updater
 }
 
+#[derive(Accounts)]
+pub struct ReportRejectedProof<'info> {
+    #[account(
+        mut,
+        seeds = [b"warden", warden_authority.key().as_ref()],
+        bump,
+    )]
+    pub warden: Account<'info, Warden>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    pub warden_authority: SystemAccount<'info>, // The warden's authority (for PDA derivation)
+
+    #[account(mut)]
+    pub authority: Signer<'info>, // The authorized reputation updater
+}
+
+#[derive(Accounts)]
+#[instruction(evidence: AnomalyEvidence)]
+pub struct ReportAnomaly<'info> {
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    /// One report per connection and proof timestamp; init fails on a repeat
+    #[account(
+        init,
+        payer = reporter,
+        space = 8 + 32 + 32 + 8 + 32 + 8 + 8,
+        seeds = [
+            b"anomaly_report",
+            warden.key().as_ref(),
+            evidence.seeker.as_ref(),
+            &evidence.timestamp.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub anomaly_report: Account<'info, AnomalyReport>,
+
+    /// Rewards vault (PDA) holding the warden's unclaimed earnings
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    #[account(mut, address = protocol_config.treasury)]
+    pub treasury: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+
+    /// CHECK: Instructions sysvar for Ed25519 verification
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CheckWardenActivity<'info> {
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    #[account(
+        seeds = [b"protocol_config"],
+        bump,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
 #[derive(Accounts)]
 pub struct UpdatePremiumPoolRankings<'info> {
     #[account(
@@ -227,8 +702,393 @@ pub struct ReputationUpdated {
     pub connection_success: bool,
 }
 
+/// Weighted components behind a reputation update, for diagnosing low scores
+/// `stored_score` equals their sum unless the bootstrap cap lowered it
+#[event]
+pub struct ReputationBreakdown {
+    pub warden: Pubkey,
+    pub success_contribution: u32,
+    pub uptime_contribution: u32,
+    pub bandwidth_contribution: u32,
+    pub recency_contribution: u32,
+    pub stored_score: u32,
+}
+
+#[event]
+pub struct WardenSuspended {
+    pub warden: Pubkey,
+    pub last_active: i64,
+    pub inactive_for: i64, // seconds since last_active
+}
+
+#[event]
+pub struct WardenReinstated {
+    pub warden: Pubkey,
+    pub last_active: i64,
+}
+
 #[event]
 pub struct PremiumPoolRankingsUpdated {
     pub updater: Pubkey,
     pub top_wardens_count: u32,
-}
\ No newline at end of file
+}
+
+#[event]
+pub struct AnomalyReported {
+    pub warden: Pubkey,
+    pub connection: Pubkey,
+    pub reporter: Pubkey,
+    pub timestamp: i64,
+    pub penalty: u64, // in lamports
+    pub bounty: u64, // in lamports, paid to the reporter
+}
+
+#[event]
+pub struct ProofRejected {
+    pub warden: Pubkey,
+    pub connection: Pubkey,
+    pub reason: ProofRejectionReason,
+    pub rejected_proof_count: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_uptime_report() {
+        assert!(validate_uptime_report(0).is_ok());
+        assert!(validate_uptime_report(10000).is_ok());
+        assert!(validate_uptime_report(10001).is_err());
+        assert!(validate_uptime_report(12000).is_err());
+    }
+
+    #[test]
+    fn test_uptime_report_freshness() {
+        let now = 1_700_000_000;
+        let max_age = 3600;
+
+        // Measured just now, or right up to the edge of the window
+        assert!(ensure_uptime_report_fresh(now, now, max_age).is_ok());
+        assert!(ensure_uptime_report_fresh(now - max_age, now, max_age).is_ok());
+
+        // Applied by a cron that ran late
+        assert_eq!(
+            ensure_uptime_report_fresh(now - max_age - 1, now, max_age).unwrap_err(),
+            ArkhamErrorCode::StaleUptimeReport.into()
+        );
+
+        // Dated in the future
+        assert_eq!(
+            ensure_uptime_report_fresh(now + 1, now, max_age).unwrap_err(),
+            ArkhamErrorCode::StaleUptimeReport.into()
+        );
+
+        // No window configured: any past measurement is accepted
+        assert!(ensure_uptime_report_fresh(0, now, 0).is_ok());
+    }
+
+    #[test]
+    fn test_reputation_update_interval() {
+        let interval = 600;
+
+        // A fresh warden's first update is always due
+        assert!(ensure_reputation_update_due(0, 1_700_000_000, interval).is_ok());
+
+        // A second update within the interval is rejected
+        let last_update = 1_700_000_000;
+        assert_eq!(
+            ensure_reputation_update_due(last_update, last_update + interval - 1, interval).unwrap_err(),
+            ArkhamErrorCode::ReputationUpdateTooFrequent.into()
+        );
+
+        // Accepted once the interval has elapsed
+        assert!(ensure_reputation_update_due(last_update, last_update + interval, interval).is_ok());
+
+        // A zero interval disables the cooldown
+        assert!(ensure_reputation_update_due(last_update, last_update, 0).is_ok());
+    }
+
+    #[test]
+    fn test_anomaly_penalty_split() {
+        // 20% of a 1000-lamport penalty goes to the reporter
+        assert_eq!(anomaly_penalty_split(5_000, 1_000, 2_000).unwrap(), (1_000, 200));
+
+        // The penalty never exceeds what the warden has left to claim
+        assert_eq!(anomaly_penalty_split(400, 1_000, 2_000).unwrap(), (400, 80));
+        assert_eq!(anomaly_penalty_split(0, 1_000, 2_000).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn test_anomaly_slashes_accumulate_in_the_warden_history() {
+        let mut warden = Warden { pending_claims: 1_500, ..crate::state::test_warden() };
+
+        assert_eq!(slash_for_anomaly(&mut warden, 1_000, 2_000).unwrap(), (1_000, 200));
+        assert_eq!((warden.pending_claims, warden.total_slashed, warden.slash_count), (500, 1_000, 1));
+
+        // The second is capped at what's left, and still counts as a slash
+        assert_eq!(slash_for_anomaly(&mut warden, 1_000, 2_000).unwrap(), (500, 100));
+        assert_eq!((warden.pending_claims, warden.total_slashed, warden.slash_count), (0, 1_500, 2));
+
+        let snapshot = crate::instructions::payments::warden_earnings_snapshot(&warden, 0);
+        assert_eq!((snapshot.total_slashed, snapshot.slash_count), (1_500, 2));
+    }
+
+    #[test]
+    fn test_verify_anomaly_evidence() {
+        use crate::instructions::bandwidth::{ed25519_ix_data, instructions_sysvar_data, BandwidthError};
+        use anchor_lang::solana_program::ed25519_program;
+
+        let (connection, warden) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let evidence = AnomalyEvidence {
+            seeker: Pubkey::new_unique(),
+            timestamp: 1_700_000_000,
+            first_mb: 100,
+            first_signature: [1; 64],
+            second_mb: 900,
+            second_signature: [2; 64],
+        };
+        let signed = |evidence: &AnomalyEvidence, signer: &Pubkey| {
+            vec![
+                (ed25519_program::ID, ed25519_ix_data(
+                    &evidence.first_signature,
+                    signer,
+                    &create_proof_message(&connection, evidence.first_mb, evidence.timestamp),
+                )),
+                (ed25519_program::ID, ed25519_ix_data(
+                    &evidence.second_signature,
+                    signer,
+                    &create_proof_message(&connection, evidence.second_mb, evidence.timestamp),
+                )),
+            ]
+        };
+        let verify = |instructions: &[(Pubkey, Vec<u8>)], evidence: &AnomalyEvidence| {
+            let mut lamports = 0u64;
+            let mut data = instructions_sysvar_data(instructions);
+            let owner = Pubkey::default();
+            let sysvar = AccountInfo::new(
+                &INSTRUCTIONS_SYSVAR_ID, false, false, &mut lamports, &mut data, &owner, false, 0,
+            );
+            verify_anomaly_evidence(&sysvar, &connection, &warden, evidence)
+        };
+
+        // Two conflicting amounts, both signed by the warden
+        assert!(verify(&signed(&evidence, &warden), &evidence).is_ok());
+
+        // Signed by someone else
+        assert_eq!(
+            verify(&signed(&evidence, &Pubkey::new_unique()), &evidence).unwrap_err(),
+            BandwidthError::PublicKeyMismatch.into()
+        );
+
+        // Unverifiable: no Ed25519 instructions in the transaction
+        assert_eq!(
+            verify(&[], &evidence).unwrap_err(),
+            BandwidthError::MissingSignatureVerification.into()
+        );
+
+        // The same proof twice is not a conflict
+        let duplicate = AnomalyEvidence { second_mb: evidence.first_mb, ..evidence.clone() };
+        assert_eq!(
+            verify(&signed(&duplicate, &warden), &duplicate).unwrap_err(),
+            ArkhamErrorCode::InvalidAnomalyEvidence.into()
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_reputation_cap() {
+        let mut config = crate::state::test_protocol_config();
+        config.min_connections_for_full_reputation = 10;
+        config.bootstrap_reputation_cap = 8500;
+
+        // A brand-new warden computes a perfect score but is held at the ceiling
+        let mut warden = crate::state::test_warden();
+        let score = calculate_reputation_score(&warden, 0, 0).unwrap().score();
+        assert_eq!(score, 10000);
+        assert_eq!(apply_bootstrap_reputation_cap(score, warden.successful_connections, &config), 8500);
+
+        // Scores already below the ceiling are untouched
+        assert_eq!(apply_bootstrap_reputation_cap(7000, 0, &config), 7000);
+
+        // Still bootstrapping one connection short of the threshold
+        warden.successful_connections = 9;
+        assert_eq!(apply_bootstrap_reputation_cap(10000, warden.successful_connections, &config), 8500);
+
+        // Graduates once enough connections have completed
+        warden.successful_connections = 10;
+        let score = calculate_reputation_score(&warden, 0, 0).unwrap().score();
+        assert_eq!(apply_bootstrap_reputation_cap(score, warden.successful_connections, &config), 10000);
+
+        // A zero threshold disables the grace period entirely
+        config.min_connections_for_full_reputation = 0;
+        assert_eq!(apply_bootstrap_reputation_cap(10000, 0, &config), 10000);
+    }
+
+    #[test]
+    fn test_connection_count_overflow_reports_reputation_overflow() {
+        let mut warden = crate::state::test_warden();
+        warden.successful_connections = u64::MAX;
+        warden.failed_connections = 1;
+
+        assert_eq!(
+            calculate_reputation_score(&warden, 0, 0).unwrap_err(),
+            ArkhamErrorCode::ReputationOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_premium_rankings() {
+        let wardens: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        assert!(validate_premium_rankings(&wardens, &wardens, 3).is_ok());
+
+        // More wardens than the configured pool holds
+        assert_eq!(
+            validate_premium_rankings(&wardens, &wardens, 2).unwrap_err(),
+            ArkhamErrorCode::PremiumPoolTooLarge.into()
+        );
+
+        // The list must line up with the supplied accounts
+        assert_eq!(
+            validate_premium_rankings(&wardens, &wardens[..2], 3).unwrap_err(),
+            ArkhamErrorCode::PremiumRankingAccountsMismatch.into()
+        );
+
+        // The same warden may not be ranked twice
+        let duplicated = vec![wardens[0], wardens[1], wardens[0]];
+        assert_eq!(
+            validate_premium_rankings(&duplicated, &duplicated, 3).unwrap_err(),
+            ArkhamErrorCode::DuplicatePremiumWarden.into()
+        );
+    }
+
+    #[test]
+    fn test_update_reputation_rejects_mismatched_warden_authority() {
+        let warden = crate::state::test_warden();
+        assert!(ensure_warden_authority(&warden, &warden.authority).is_ok());
+        assert_eq!(
+            ensure_warden_authority(&warden, &Pubkey::new_unique()).unwrap_err(),
+            ArkhamErrorCode::WardenAuthorityMismatch.into()
+        );
+    }
+
+    #[test]
+    fn test_rejected_proof_report_requires_updater_and_counts() {
+        let config = crate::state::test_protocol_config();
+        let mut warden = crate::state::test_warden();
+        let connection = Pubkey::new_unique();
+
+        // Anyone but the reputation updater is refused and nothing is recorded
+        assert_eq!(
+            log_rejected_proof(&config, &config.authority, &mut warden, connection, ProofRejectionReason::Duplicate)
+                .map(|_| ())
+                .unwrap_err(),
+            ArkhamErrorCode::UnauthorizedReputationUpdate.into()
+        );
+        assert_eq!(warden.rejected_proof_count, 0);
+
+        // Each accepted report bumps the counter and carries it in the event
+        let updater = config.reputation_updater;
+        let first = log_rejected_proof(&config, &updater, &mut warden, connection, ProofRejectionReason::Duplicate).unwrap();
+        let second = log_rejected_proof(&config, &updater, &mut warden, connection, ProofRejectionReason::InvalidSignature).unwrap();
+        assert_eq!(warden.rejected_proof_count, 2);
+        assert_eq!((first.warden, first.connection, first.reason), (warden.authority, connection, ProofRejectionReason::Duplicate));
+        assert_eq!((first.rejected_proof_count, second.rejected_proof_count), (1, 2));
+        assert_eq!(second.reason, ProofRejectionReason::InvalidSignature);
+    }
+
+    #[test]
+    fn test_inactivity_check_suspends_only_idle_wardens() {
+        let timeout = 86_400;
+        let now = 10 * timeout;
+
+        // Idle for two days: suspended, and no longer matchable
+        let mut idle = crate::state::test_warden();
+        idle.last_active = now - 2 * timeout;
+        assert_eq!(apply_inactivity_check(&mut idle, now, timeout), Some(true));
+        assert!(idle.suspended);
+        assert_eq!(
+            crate::instructions::payments::ensure_warden_accepting_connections(&idle).unwrap_err(),
+            ArkhamErrorCode::WardenSuspended.into()
+        );
+        // A second keeper call changes nothing
+        assert_eq!(apply_inactivity_check(&mut idle, now, timeout), None);
+
+        // Active an hour ago: untouched
+        let mut active = crate::state::test_warden();
+        active.last_active = now - 3_600;
+        assert_eq!(apply_inactivity_check(&mut active, now, timeout), None);
+        assert!(!active.suspended);
+        assert!(crate::instructions::payments::ensure_warden_accepting_connections(&active).is_ok());
+
+        // Fresh activity lifts the suspension
+        idle.last_active = now;
+        assert_eq!(apply_inactivity_check(&mut idle, now, timeout), Some(false));
+        assert!(!idle.suspended);
+
+        // A zero timeout suspends no one
+        let mut ancient = crate::state::test_warden();
+        assert_eq!(apply_inactivity_check(&mut ancient, now, 0), None);
+    }
+
+    #[test]
+    fn test_reputation_breakdown_sums_to_stored_score() {
+        let config = crate::state::test_protocol_config();
+        let mut warden = crate::state::test_warden();
+        warden.successful_connections = 30;
+        warden.failed_connections = 10;
+        warden.uptime_percentage = 9_000;
+        warden.last_active = 0;
+        let now = 3 * 24 * 3600; // three days idle
+
+        let components = calculate_reputation_score(&warden, now, 0).unwrap();
+        assert_eq!(
+            components,
+            ReputationComponents { success: 3_000, uptime: 2_700, bandwidth: 1_143, recency: 1_000 }
+        );
+
+        let stored = apply_bootstrap_reputation_cap(components.score(), warden.successful_connections, &config);
+        let event = reputation_breakdown(warden.authority, &components, stored);
+        assert_eq!(
+            event.success_contribution
+                + event.uptime_contribution
+                + event.bandwidth_contribution
+                + event.recency_contribution,
+            event.stored_score
+        );
+    }
+
+    #[test]
+    fn test_success_rate_recovers_as_old_failures_age_out() {
+        let window = 30 * 86_400;
+        let failed_at = 1_000_000i64;
+        let mut warden = crate::state::test_warden();
+        warden.successful_connections = 30;
+        for _ in 0..10 {
+            record_failed_connection(&mut warden, failed_at).unwrap();
+        }
+        assert_eq!(warden.failed_connections, 10);
+        // The success-rate term is 40% of the score
+        let success_contribution = |warden: &Warden, now: i64, window: i64| {
+            calculate_reputation_score(warden, now, window).unwrap().success
+        };
+
+        // Within the window every failure counts: 30 of 40 succeeded (75%)
+        assert_eq!(success_contribution(&warden, failed_at + window, window), 3_000);
+
+        // Past it, all ten count half (the two evicted ones are older still): 30 of 35 (85.71%)
+        assert_eq!(success_contribution(&warden, failed_at + window + 1, window), 3_428);
+
+        // With decay off the old failures weigh in forever
+        assert_eq!(success_contribution(&warden, failed_at + 10 * window, 0), 3_000);
+
+        // A fresh failure counts in full and keeps the evicted ones from aging early
+        let now = failed_at + window + 1;
+        for _ in 0..RECENT_FAILURE_CAPACITY {
+            record_failed_connection(&mut warden, now).unwrap();
+        }
+        assert_eq!(weighted_failures_bps(&warden, now, window), 18 * 10000);
+
+        // Once those age too, the success rate recovers again
+        assert_eq!(weighted_failures_bps(&warden, now + window + 1, window), 18 * AGED_FAILURE_WEIGHT_BPS);
+    }
+}
@@ -5,10 +5,21 @@ use anchor_lang::solana_program::{
     sysvar::instructions::{load_instruction_at_checked, ID as INSTRUCTIONS_SYSVAR_ID},
     ed25519_program,
 };
-use crate::state::{Warden, StakeToken, ProtocolConfig, Tier};
+use crate::instructions::bandwidth::{ed25519_has_inline_layout, ed25519_message_data_size};
+use crate::state::{
+    Warden, StakeToken, ProtocolConfig, Tier, TopEarners, EarnerEntry, RegionIndex,
+    RECENT_FAILURE_CAPACITY, WARDEN_SPACE,
+    STAKE_AUTHORITY_SEEDS, stake_authority_signer_seeds,
+};
 use crate::ArkhamErrorCode;
 
-const USD_DECIMALS: u32 = 6;
+pub const USD_DECIMALS: u32 = 6;
+
+/// Original oracle message: keccak(price + price_decimals + timestamp), no version byte
+pub const ORACLE_MESSAGE_V1: u8 = 1;
+/// Version-prefixed oracle message: keccak(version + price + price_decimals + timestamp)
+pub const ORACLE_MESSAGE_V2: u8 = 2;
+pub const LATEST_ORACLE_MESSAGE_VERSION: u8 = ORACLE_MESSAGE_V2;
 const SOL_DECIMALS: u32 = 9;
 const USDT_DECIMALS: u32 = 6;
 
@@ -19,48 +30,78 @@ pub fn initialize_warden_handler(
     peer_id: String,
     region_code: u8,
     ip_hash: [u8; 32],
-    price: u64,           // Price in micro-units (6 decimals) of USD per token
-    timestamp: i64,       // Timestamp of the price data
-    signature: [u8; 64],  // Ed25519 signature of the price and timestamp by the oracle
+    oracle_price: OraclePrice,
 ) -> Result<()> {
     let config = &ctx.accounts.protocol_config;
     let clock = Clock::get()?;
     let current_timestamp = clock.unix_timestamp;
 
-    // Verify that the price data is recent (within 5 minutes)
-    require!(
-        current_timestamp - timestamp <= 300, // 5 minutes
-        ArkhamErrorCode::StalePrice
-    );
-
-    // Create the message that should have been signed (price + timestamp)
-    let oracle_message = create_oracle_message(price, timestamp);
+    // Verify the oracle-signed price is recent and authentic, unless the protocol
+    // is still bootstrapping without an oracle
+    let price = if config.oracle_required {
+        verify_oracle_price(
+            &ctx.accounts.instructions_sysvar,
+            config,
+            &oracle_price,
+            current_timestamp,
+        )?;
+        oracle_price.price
+    } else {
+        let fallback_price = fallback_price_for(&config.fallback_prices, &stake_token)?;
+        emit!(OracleVerificationSkipped {
+            authority: ctx.accounts.authority.key(),
+            stake_token: stake_token.clone(),
+            fallback_price,
+        });
+        fallback_price
+    };
 
-    // Verify the signature using instruction introspection
-    if let Err(error) = verify_oracle_signature_via_sysvar(
-        &ctx.accounts.instructions_sysvar,
-        &oracle_message,
-        &signature,
-        &config.oracle_authority,
-        0, // Ed25519 instruction should be at index 0
-    ) {
-        // Convert the OracleError to ArkhamErrorCode
-        return Err(error.into());
-    }
+    // Only the protocol-approved stablecoin mints back the stablecoin vaults
+    validate_approved_mint(&ctx.accounts.usdc_mint.key(), &config.usdc_mint)?;
+    validate_approved_mint(&ctx.accounts.usdt_mint.key(), &config.usdt_mint)?;
 
     // Calculate USD value of the stake using the provided price
     let stake_value_usd = calculate_stake_value_usd(&stake_token, stake_amount, price)?;
 
     // Determine the tier based on USD value
-    let tier = if stake_value_usd >= config.tier_thresholds[2] {
-        Tier::Gold
-    } else if stake_value_usd >= config.tier_thresholds[1] {
-        Tier::Silver
-    } else if stake_value_usd >= config.tier_thresholds[0] {
-        Tier::Bronze
-    } else {
-        return err!(ArkhamErrorCode::InsufficientStake);
+    let tier = determine_tier(stake_value_usd, &config.tier_thresholds)
+        .ok_or(ArkhamErrorCode::InsufficientStake)?;
+
+    // Every check that can fail runs before the first transfer below, so a rejected
+    // registration fails fast and, like any failed transaction, leaves no Warden account
+    // behind and moves no lamports or tokens
+    let registration_fee = config.registration_fee;
+    let sol_stake = match stake_token {
+        StakeToken::Sol => {
+            // The vault is a zero-data system account; a stake below its rent-exempt
+            // minimum could leave a freshly funded vault purgeable
+            validate_sol_stake_amount(stake_amount, Rent::get()?.minimum_balance(0))?;
+            stake_amount
+        }
+        StakeToken::Usdc => {
+            let source_mint = stake_source_mint(&ctx.accounts.stake_from_account)?;
+            validate_stake_source_mint(&source_mint, &ctx.accounts.usdc_mint.key())?;
+            0
+        }
+        StakeToken::Usdt => {
+            let source_mint = stake_source_mint(&ctx.accounts.stake_from_account)?;
+            validate_stake_source_mint(&source_mint, &ctx.accounts.usdt_mint.key())?;
+            0
+        }
     };
+    ensure_registration_funded(ctx.accounts.authority.lamports(), registration_fee, sol_stake)?;
+
+    // Charge the one-time registration fee into the treasury (separate from the stake)
+    if registration_fee > 0 {
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, registration_fee)?;
+    }
 
     // Transfer stake tokens to the appropriate vault
     match stake_token {
@@ -69,7 +110,7 @@ pub fn initialize_warden_handler(
                 ctx.accounts.system_program.to_account_info(),
                 system_program::Transfer {
                     from: ctx.accounts.authority.to_account_info(),
-                    to: ctx.accounts.sol_vault.to_account_info(),
+                    to: ctx.accounts.stake_vault.to_account_info(),
                 },
             );
             system_program::transfer(cpi_context, stake_amount)?;
@@ -110,7 +151,8 @@ pub fn initialize_warden_handler(
     warden.total_earnings = 0;
     warden.pending_claims = 0;
     warden.arkham_tokens_earned = 0;
-    warden.reputation_score = 10000; // Start with a perfect score
+    // Start with a perfect score, held at the bootstrap ceiling until the warden has a track record
+    warden.reputation_score = crate::instructions::reputation::apply_bootstrap_reputation_cap(10000, 0, config);
     warden.successful_connections = 0;
     warden.failed_connections = 0;
     warden.uptime_percentage = 10000; // Start at 100%
@@ -119,6 +161,28 @@ pub fn initialize_warden_handler(
     warden.ip_hash = ip_hash;
     warden.premium_pool_rank = None;
     warden.active_connections = 0;
+    warden.rejected_proof_count = 0;
+    warden.arkham_tokens_subsidized = 0;
+    warden.arkham_subsidy_claimed = 0;
+    warden.subsidy_vesting_start = 0;
+    warden.draining = false;
+    warden.counted_in_region = false;
+    warden.total_slashed = 0;
+    warden.slash_count = 0;
+    warden.last_reputation_update = 0;
+    warden.last_sla_breach_at = 0;
+    warden.last_sla_breach_uptime = 0;
+    warden.premium_seekers_only = false;
+    warden.suspended = false;
+    warden.recent_failures = [0; RECENT_FAILURE_CAPACITY];
+    warden.recent_failures_head = 0;
+    warden.recent_connections = vec![];
+    warden.recent_connections_head = 0;
+    warden.availability = None; // Always available until the warden sets a schedule
+    warden.subsidy_vested_carry = 0;
+
+    // Count the new warden in its region, when the region index is provided
+    join_region(warden, ctx.accounts.region_index.as_deref_mut().map(|index| &mut **index))?;
 
     // Emit a registration event
     emit!(WardenRegistered {
@@ -126,27 +190,338 @@ pub fn initialize_warden_handler(
         tier: warden.tier.clone(),
         stake_amount: warden.stake_amount,
         stake_token: warden.stake_token.clone(),
+        registration_fee,
+    });
+
+    Ok(())
+}
+
+/// Reports which tier a Warden's existing stake qualifies for at a fresh oracle price
+/// Read-only: nothing is written back to the Warden account
+pub fn query_warden_tier_handler(
+    ctx: Context<QueryWardenTier>,
+    oracle_price: OraclePrice,
+) -> Result<TierQualification> {
+    let config = &ctx.accounts.protocol_config;
+    let warden = &ctx.accounts.warden;
+    let clock = Clock::get()?;
+
+    verify_oracle_price(
+        &ctx.accounts.instructions_sysvar,
+        config,
+        &oracle_price,
+        clock.unix_timestamp,
+    )?;
+
+    let current_value_usd = calculate_stake_value_usd(&warden.stake_token, warden.stake_amount, oracle_price.price)?;
+
+    Ok(TierQualification {
+        current_value_usd,
+        current_tier: determine_tier(current_value_usd, &config.tier_thresholds),
+        next_tier_gap_usd: next_tier_gap_usd(current_value_usd, &config.tier_thresholds),
+    })
+}
+
+/// Returns compact summaries of the Wardens passed in `remaining_accounts`, in order,
+/// so clients can browse many Wardens with one call instead of one RPC per account
+pub fn query_warden_summaries_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, QueryWardenSummaries>,
+) -> Result<Vec<WardenSummary>> {
+    warden_summaries(ctx.remaining_accounts)
+}
+
+/// Decodes each account as a Warden and summarizes it; at most MAX_WARDEN_SUMMARIES
+/// so the packed result always fits in return data
+pub fn warden_summaries<'info>(accounts: &'info [AccountInfo<'info>]) -> Result<Vec<WardenSummary>> {
+    require!(
+        accounts.len() <= MAX_WARDEN_SUMMARIES,
+        ArkhamErrorCode::TooManyWardenSummaries
+    );
+
+    accounts
+        .iter()
+        .map(|info| {
+            let warden: Account<'info, Warden> = Account::try_from(info)?;
+            Ok(warden_summary(info.key(), &warden))
+        })
+        .collect()
+}
+
+/// The fields a client needs to choose between Wardens
+pub fn warden_summary(warden_key: Pubkey, warden: &Warden) -> WardenSummary {
+    WardenSummary {
+        warden: warden_key,
+        tier: warden.tier.clone(),
+        reputation_score: warden.reputation_score,
+        region_code: warden.region_code,
+        uptime_percentage: warden.uptime_percentage,
+        active_connections: warden.active_connections,
+    }
+}
+
+/// Recomputes a Warden's tier from its stored stake_value_usd against the current
+/// tier_thresholds, so a threshold change propagates without a fresh oracle price
+/// Permissionless: the result depends only on on-chain state
+pub fn reclassify_tier_handler(ctx: Context<ReclassifyTier>) -> Result<()> {
+    let config = &ctx.accounts.protocol_config;
+    let warden = &mut ctx.accounts.warden;
+
+    if let Some(old_tier) = apply_tier_thresholds(warden, &config.tier_thresholds) {
+        emit!(TierChanged {
+            authority: warden.authority,
+            old_tier,
+            new_tier: warden.tier.clone(),
+            stake_value_usd: warden.stake_value_usd,
+        });
+    }
+
+    Ok(())
+}
+
+/// Applies the current thresholds to a Warden's stored stake value
+/// A registered Warden never drops below Bronze: raising the Bronze threshold
+/// gates new registrations, it doesn't evict existing stakers.
+/// Returns the previous tier when it changed
+pub fn apply_tier_thresholds(warden: &mut Warden, tier_thresholds: &[u64; 3]) -> Option<Tier> {
+    let new_tier = determine_tier(warden.stake_value_usd, tier_thresholds).unwrap_or(Tier::Bronze);
+    if new_tier == warden.tier {
+        return None;
+    }
+    Some(std::mem::replace(&mut warden.tier, new_tier))
+}
+
+/// Moves a Warden's pending_claims from the rewards vault into its SOL stake and
+/// re-tiers it at a fresh oracle price, saving a claim-then-restake round trip
+pub fn compound_earnings_handler(
+    ctx: Context<CompoundEarnings>,
+    oracle_price: OraclePrice,
+) -> Result<()> {
+    let config = &ctx.accounts.protocol_config;
+    let warden = &mut ctx.accounts.warden;
+    let clock = Clock::get()?;
+
+    // Same price source as registration: the oracle, or the fallback while bootstrapping
+    let price = if config.oracle_required {
+        verify_oracle_price(
+            &ctx.accounts.instructions_sysvar,
+            config,
+            &oracle_price,
+            clock.unix_timestamp,
+        )?;
+        oracle_price.price
+    } else {
+        let fallback_price = fallback_price_for(&config.fallback_prices, &warden.stake_token)?;
+        emit!(OracleVerificationSkipped {
+            authority: warden.authority,
+            stake_token: warden.stake_token.clone(),
+            fallback_price,
+        });
+        fallback_price
+    };
+
+    let (amount, old_tier) = compound_into_stake(warden, price, &config.tier_thresholds)?;
+
+    // Earnings leave the rewards vault for the stake vault; nothing reaches the authority
+    let vault_seeds = &[b"rewards_vault".as_ref(), &[ctx.bumps.rewards_vault]];
+    let signer_seeds = &[&vault_seeds[..]];
+    let cpi_context = CpiContext::new_with_signer(
+        ctx.accounts.system_program.to_account_info(),
+        system_program::Transfer {
+            from: ctx.accounts.rewards_vault.to_account_info(),
+            to: ctx.accounts.stake_vault.to_account_info(),
+        },
+        signer_seeds,
+    );
+    system_program::transfer(cpi_context, amount)?;
+
+    emit!(EarningsCompounded {
+        authority: warden.authority,
+        amount,
+        stake_amount: warden.stake_amount,
+        stake_value_usd: warden.stake_value_usd,
     });
 
+    if let Some(old_tier) = old_tier {
+        emit!(TierChanged {
+            authority: warden.authority,
+            old_tier,
+            new_tier: warden.tier.clone(),
+            stake_value_usd: warden.stake_value_usd,
+        });
+    }
+
+    Ok(())
+}
+
+/// Adds pending_claims to a SOL stake, revalues it at `price` and re-tiers it
+/// Returns the compounded lamports and, when the tier changed, the previous tier
+pub fn compound_into_stake(warden: &mut Warden, price: u64, tier_thresholds: &[u64; 3]) -> Result<(u64, Option<Tier>)> {
+    // Token stakes would need a swap from SOL earnings, which the program doesn't do
+    require!(
+        matches!(warden.stake_token, StakeToken::Sol),
+        ArkhamErrorCode::CompoundRequiresSolStake
+    );
+    require!(warden.pending_claims > 0, ArkhamErrorCode::NothingToClaim);
+
+    let amount = warden.pending_claims;
+    warden.stake_amount = warden.stake_amount
+        .checked_add(amount)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    warden.stake_value_usd = calculate_stake_value_usd(&warden.stake_token, warden.stake_amount, price)?;
+    warden.pending_claims = 0;
+
+    Ok((amount, apply_tier_thresholds(warden, tier_thresholds)))
+}
+
+/// Verifies an oracle-signed price: recent (within 5 minutes), scaled to USD_DECIMALS,
+/// in a message format still accepted by the config, and signed by the configured
+/// oracle authority via a preceding Ed25519Program instruction
+pub fn verify_oracle_price(
+    instructions_sysvar: &AccountInfo,
+    config: &ProtocolConfig,
+    oracle_price: &OraclePrice,
+    current_timestamp: i64,
+) -> Result<()> {
+    // Verify that the price data is recent (within 5 minutes)
+    require!(
+        current_timestamp - oracle_price.timestamp <= 300, // 5 minutes
+        ArkhamErrorCode::StalePrice
+    );
+
+    // A price in any other scale would mis-tier stakes by orders of magnitude
+    validate_price_decimals(oracle_price.decimals)?;
+
+    ensure_oracle_message_version_accepted(oracle_price.version, config.min_oracle_message_version)?;
+
+    // Create the message that should have been signed, in the signer's format
+    let oracle_message = create_versioned_oracle_message(
+        oracle_price.version,
+        oracle_price.price,
+        oracle_price.decimals,
+        oracle_price.timestamp,
+    )?;
+
+    // Verify the signature using instruction introspection
+    verify_oracle_signature_via_sysvar(
+        instructions_sysvar,
+        &oracle_message,
+        &oracle_price.signature,
+        &config.oracle_authority,
+        0, // Ed25519 instruction should be at index 0
+    )
+}
+
+/// Rejects oracle prices not expressed in USD_DECIMALS (micro-USD)
+pub fn validate_price_decimals(price_decimals: u8) -> Result<()> {
+    require!(
+        price_decimals as u32 == USD_DECIMALS,
+        ArkhamErrorCode::OraclePriceDecimalsMismatch
+    );
+    Ok(())
+}
+
+/// Returns the configured fallback price for a stake token
+/// Used only while `oracle_required` is false; an unset (zero) price is rejected
+pub fn fallback_price_for(fallback_prices: &[u64; 3], stake_token: &StakeToken) -> Result<u64> {
+    let fallback_price = match stake_token {
+        StakeToken::Sol => fallback_prices[0],
+        StakeToken::Usdc => fallback_prices[1],
+        StakeToken::Usdt => fallback_prices[2],
+    };
+    require!(fallback_price > 0, ArkhamErrorCode::FallbackPriceNotSet);
+    Ok(fallback_price)
+}
+
+/// Determines the tier for a USD stake value
+/// Thresholds are inclusive lower bounds: a stake exactly at `tier_thresholds[1]`
+/// is Silver, one unit below is Bronze. `None` means below the Bronze threshold
+pub fn determine_tier(stake_value_usd: u64, tier_thresholds: &[u64; 3]) -> Option<Tier> {
+    if stake_value_usd >= tier_thresholds[2] {
+        Some(Tier::Gold)
+    } else if stake_value_usd >= tier_thresholds[1] {
+        Some(Tier::Silver)
+    } else if stake_value_usd >= tier_thresholds[0] {
+        Some(Tier::Bronze)
+    } else {
+        None
+    }
+}
+
+/// Returns the additional USD value needed to reach the next tier (0 at Gold)
+pub fn next_tier_gap_usd(stake_value_usd: u64, tier_thresholds: &[u64; 3]) -> u64 {
+    tier_thresholds
+        .iter()
+        .find(|&&threshold| stake_value_usd < threshold)
+        .map_or(0, |&threshold| threshold - stake_value_usd)
+}
+
+/// Rejects a stablecoin mint that isn't the one approved in the protocol config
+pub fn validate_approved_mint(passed_mint: &Pubkey, approved_mint: &Pubkey) -> Result<()> {
+    require_keys_eq!(*passed_mint, *approved_mint, ArkhamErrorCode::UnapprovedStakeMint);
+    Ok(())
+}
+
+/// Reads the mint of the token account a stablecoin stake is transferred from
+pub fn stake_source_mint(stake_from_account: &AccountInfo) -> Result<Pubkey> {
+    require_keys_eq!(
+        *stake_from_account.owner,
+        token::ID,
+        ArkhamErrorCode::StakeMintMismatch
+    );
+    let data = stake_from_account.try_borrow_data()?;
+    let source = TokenAccount::try_deserialize(&mut &data[..])?;
+    Ok(source.mint)
+}
+
+/// Rejects a stake whose source token account holds a different token than declared
+pub fn validate_stake_source_mint(source_mint: &Pubkey, expected_mint: &Pubkey) -> Result<()> {
+    require_keys_eq!(*source_mint, *expected_mint, ArkhamErrorCode::StakeMintMismatch);
+    Ok(())
+}
+
+/// Checks the registering authority holds the registration fee plus any SOL stake
+/// Surfaces InsufficientFundsForRegistration up front instead of a system program
+/// failure partway through the transfers
+pub fn ensure_registration_funded(authority_lamports: u64, registration_fee: u64, sol_stake: u64) -> Result<()> {
+    let required = registration_fee
+        .checked_add(sol_stake)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    require!(
+        authority_lamports >= required,
+        ArkhamErrorCode::InsufficientFundsForRegistration
+    );
+    Ok(())
+}
+
+/// Rejects SOL stakes below the stake vault's rent-exempt minimum
+pub fn validate_sol_stake_amount(stake_amount: u64, rent_exempt_minimum: u64) -> Result<()> {
+    require!(
+        stake_amount >= rent_exempt_minimum,
+        ArkhamErrorCode::StakeBelowRentExemption
+    );
     Ok(())
 }
 
 /// Creates a deterministic message for oracle price signing
 /// 
-/// The oracle signs: price (8 bytes LE) + timestamp (8 bytes LE)
-/// This creates a 16-byte message that is then hashed for signing
+/// The oracle signs: price (8 bytes LE) + price_decimals (1 byte) + timestamp (8 bytes LE)
+/// This creates a 17-byte message that is then hashed for signing
 /// 
 /// # Arguments
 /// * `price` - Price in micro-units (6 decimals)
+/// * `price_decimals` - Decimal exponent of `price`, so a differently scaled feed can't pass
 /// * `timestamp` - Unix timestamp of the price data
 /// 
 /// # Returns
 /// * `Vec<u8>` - The deterministic message bytes to be signed (32 bytes after hashing)
-pub fn create_oracle_message(price: u64, timestamp: i64) -> Vec<u8> {
+pub fn create_oracle_message(price: u64, price_decimals: u8, timestamp: i64) -> Vec<u8> {
     let mut message = Vec::new();
     
     // Add price (8 bytes, little-endian)
     message.extend_from_slice(&price.to_le_bytes());
+
+    // Add the price's decimal exponent (1 byte)
+    message.push(price_decimals);
     
     // Add timestamp (8 bytes, little-endian)
     message.extend_from_slice(&timestamp.to_le_bytes());
@@ -158,6 +533,39 @@ pub fn create_oracle_message(price: u64, timestamp: i64) -> Vec<u8> {
     hash.to_bytes().to_vec()
 }
 
+/// Builds the oracle message for a given format version
+/// v1 is the unversioned create_oracle_message layout, kept so existing signers work;
+/// v2 onward prefix the version byte, so later formats can add fields without
+/// any two versions ever hashing the same bytes
+pub fn create_versioned_oracle_message(
+    version: u8,
+    price: u64,
+    price_decimals: u8,
+    timestamp: i64,
+) -> Result<Vec<u8>> {
+    match version {
+        ORACLE_MESSAGE_V1 => Ok(create_oracle_message(price, price_decimals, timestamp)),
+        ORACLE_MESSAGE_V2 => {
+            let mut message = vec![version];
+            message.extend_from_slice(&price.to_le_bytes());
+            message.push(price_decimals);
+            message.extend_from_slice(&timestamp.to_le_bytes());
+            Ok(keccak::hash(&message).to_bytes().to_vec())
+        }
+        _ => err!(ArkhamErrorCode::UnsupportedOracleMessageVersion),
+    }
+}
+
+/// Rejects message versions the program can't build or governance has retired
+pub fn ensure_oracle_message_version_accepted(version: u8, min_version: u8) -> Result<()> {
+    require!(
+        (ORACLE_MESSAGE_V1..=LATEST_ORACLE_MESSAGE_VERSION).contains(&version),
+        ArkhamErrorCode::UnsupportedOracleMessageVersion
+    );
+    require!(version >= min_version, ArkhamErrorCode::OracleMessageVersionRetired);
+    Ok(())
+}
+
 /// Verifies oracle Ed25519 signature by checking that an Ed25519Program instruction
 /// was included in the same transaction.
 /// 
@@ -171,7 +579,7 @@ pub fn create_oracle_message(price: u64, timestamp: i64) -> Vec<u8> {
 /// 
 /// # Arguments
 /// * `instructions_sysvar` - The Instructions sysvar account
-/// * `message` - The message that was signed (hashed price + timestamp)
+/// * `message` - The message that was signed (hashed price + decimals + timestamp)
 /// * `signature` - The 64-byte Ed25519 signature from the oracle
 /// * `oracle_pubkey` - The oracle's public key (from protocol config)
 /// * `instruction_index` - Which instruction index to check (typically 0)
@@ -216,6 +624,15 @@ pub fn verify_oracle_signature_via_sysvar(
         OracleError::InvalidEd25519Data
     );
 
+    // The precompile must have verified the same bytes compared below
+    require!(ed25519_has_inline_layout(data), OracleError::InvalidEd25519Data);
+
+    // The signed message must be exactly as long as ours, not just share its prefix
+    require!(
+        ed25519_message_data_size(data) == Some(message.len() as u16),
+        OracleError::MessageMismatch
+    );
+
     // Extract signature from instruction data (starts at byte 14)
     let sig_start = 16;
     let sig_end = sig_start + 64;
@@ -247,7 +664,7 @@ pub fn verify_oracle_signature_via_sysvar(
         OracleError::PublicKeyMismatch
     );
 
-    // Verify the message matches (hashed price + timestamp)
+    // Verify the message matches (hashed price + decimals + timestamp)
     require!(
         ix_message == message,
         OracleError::MessageMismatch
@@ -311,8 +728,11 @@ pub fn unstake_warden_handler(ctx: Context<UnstakeWarden>) -> Result<()> {
         ArkhamErrorCode::ReputationTooLow
     );
 
-    // 3. Set unstake request timestamp to begin cooldown
+    // 3. Set unstake request timestamp to begin cooldown, and stop accepting
+    // new connections while draining
     warden.unstake_requested_at = Some(clock.unix_timestamp);
+    warden.draining = true;
+    leave_region(warden, ctx.accounts.region_index.as_deref_mut().map(|index| &mut **index))?;
 
     // 4. Emit event
     emit!(UnstakeRequested {
@@ -323,34 +743,165 @@ pub fn unstake_warden_handler(ctx: Context<UnstakeWarden>) -> Result<()> {
     Ok(())
 }
 
+/// Cancels a pending unstake, making the Warden matchable again
+pub fn cancel_unstake_handler(ctx: Context<UnstakeWarden>) -> Result<()> {
+    let warden = &mut ctx.accounts.warden;
+
+    require!(
+        warden.unstake_requested_at.is_some(),
+        ArkhamErrorCode::UnstakeNotRequested
+    );
+
+    warden.unstake_requested_at = None;
+    warden.draining = false;
+    join_region(warden, ctx.accounts.region_index.as_deref_mut().map(|index| &mut **index))?;
+
+    emit!(UnstakeCancelled {
+        authority: warden.authority,
+    });
+
+    Ok(())
+}
+
 /// Completes the unstaking process after the 7-day cooldown period
 pub fn claim_unstake_handler(ctx: Context<ClaimUnstake>) -> Result<()> {
-    let warden = &ctx.accounts.warden;
     let clock = Clock::get()?;
 
-    // 1. Verify unstake was requested
-    let unstake_requested_at = warden.unstake_requested_at
-        .ok_or(ArkhamErrorCode::UnstakeNotRequested)?;
+    // Wardens that unstaked already left their region; this only catches stragglers
+    leave_region(
+        &mut ctx.accounts.warden,
+        ctx.accounts.region_index.as_deref_mut().map(|index| &mut **index),
+    )?;
+    let warden = &ctx.accounts.warden;
+
+    // 1-2. Verify unstake was requested and the 7-day cooldown has elapsed
+    ensure_unstake_cooldown_complete(warden.unstake_requested_at, clock.unix_timestamp)?;
+
+    // 3. Transfer staked tokens back to authority
+    release_stake(&ctx)?;
+
+    // 4. Emit event
+    emit!(WardenUnstaked {
+        authority: warden.authority,
+        stake_amount: warden.stake_amount,
+        stake_token: warden.stake_token.clone(),
+    });
+
+    // Note: Warden account will be closed automatically via the close constraint
+    Ok(())
+}
+
+/// Returns a never-used Warden's stake immediately, skipping unstake and its cooldown
+/// For registrations that were a misconfiguration or test: the Warden must never have
+/// served bandwidth, completed a connection, or have one open.
+pub fn abort_registration_handler(ctx: Context<ClaimUnstake>) -> Result<()> {
+    let event = prepare_registration_abort(
+        &mut ctx.accounts.warden,
+        ctx.accounts.region_index.as_deref_mut().map(|index| &mut **index),
+    )?;
+
+    release_stake(&ctx)?;
+
+    emit!(event);
+
+    // Note: Warden account will be closed automatically via the close constraint
+    Ok(())
+}
+
+/// Checks a Warden may abort its registration and takes it out of its region
+/// Returns the RegistrationAborted event for the stake the handler then releases
+pub fn prepare_registration_abort(warden: &mut Warden, region_index: Option<&mut RegionIndex>) -> Result<RegistrationAborted> {
+    require!(is_never_used(warden), ArkhamErrorCode::WardenAlreadyUsed);
+
+    leave_region(warden, region_index)?;
+
+    Ok(RegistrationAborted {
+        authority: warden.authority,
+        stake_amount: warden.stake_amount,
+        stake_token: warden.stake_token.clone(),
+    })
+}
 
-    // 2. Verify 7-day cooldown has elapsed (604800 seconds = 7 days)
-    const COOLDOWN_PERIOD: i64 = 604_800;
+/// Unstake cooldown: 7 days (604800 seconds) from the unstake request
+pub const UNSTAKE_COOLDOWN_PERIOD: i64 = 604_800;
+
+/// Rejects a claim before unstake was requested or before the cooldown has elapsed
+pub fn ensure_unstake_cooldown_complete(unstake_requested_at: Option<i64>, now: i64) -> Result<()> {
+    let unstake_requested_at = unstake_requested_at
+        .ok_or(ArkhamErrorCode::UnstakeNotRequested)?;
     require!(
-        clock.unix_timestamp >= unstake_requested_at + COOLDOWN_PERIOD,
+        now >= unstake_requested_at + UNSTAKE_COOLDOWN_PERIOD,
         ArkhamErrorCode::CooldownNotComplete
     );
+    Ok(())
+}
+
+/// Adds a Warden to its region's active count, if the index was provided
+/// Idempotent: a Warden is counted at most once
+pub fn join_region(warden: &mut Warden, region_index: Option<&mut RegionIndex>) -> Result<()> {
+    let Some(region_index) = region_index else {
+        return Ok(());
+    };
+    if warden.counted_in_region {
+        return Ok(());
+    }
+
+    let count = &mut region_index.active_wardens[warden.region_code as usize];
+    *count = count.checked_add(1).ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    warden.counted_in_region = true;
+    Ok(())
+}
+
+/// Removes a counted Warden from its region's active count
+/// A counted Warden must supply the index so the count never goes stale
+pub fn leave_region(warden: &mut Warden, region_index: Option<&mut RegionIndex>) -> Result<()> {
+    if !warden.counted_in_region {
+        return Ok(());
+    }
+    let region_index = region_index.ok_or(ArkhamErrorCode::RegionIndexRequired)?;
+
+    let count = &mut region_index.active_wardens[warden.region_code as usize];
+    *count = count.checked_sub(1).ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    warden.counted_in_region = false;
+    Ok(())
+}
+
+/// Adds a slash of `amount` to the Warden's cumulative slashing record
+/// report_anomaly calls this alongside taking the penalty, so the history outlives
+/// the transient AnomalyReported event
+pub fn record_slash(warden: &mut Warden, amount: u64) -> Result<()> {
+    warden.total_slashed = warden.total_slashed
+        .checked_add(amount)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    warden.slash_count = warden.slash_count
+        .checked_add(1)
+        .ok_or(ArkhamErrorCode::ArithmeticOverflow)?;
+    Ok(())
+}
+
+/// Whether a Warden has never served anyone and so may abort its registration
+pub fn is_never_used(warden: &Warden) -> bool {
+    warden.total_bandwidth_served == 0
+        && warden.successful_connections == 0
+        && warden.active_connections == 0
+}
 
-    // 3. Transfer staked tokens back to authority based on stake_token type
+/// Transfers the Warden's stake from the stake vault back to its authority
+fn release_stake(ctx: &Context<ClaimUnstake>) -> Result<()> {
+    let warden = &ctx.accounts.warden;
+
+    // Transfer staked tokens back to authority based on stake_token type
     let stake_amount = warden.stake_amount;
     match warden.stake_token {
         StakeToken::Sol => {
             // Transfer SOL from vault back to authority
-            let vault_seeds = &[b"sol_vault".as_ref(), &[ctx.bumps.sol_vault]];
+            let vault_seeds = &[b"stake_vault".as_ref(), &[ctx.bumps.stake_vault]];
             let signer_seeds = &[&vault_seeds[..]];
             
             let cpi_context = CpiContext::new_with_signer(
                 ctx.accounts.system_program.to_account_info(),
                 system_program::Transfer {
-                    from: ctx.accounts.sol_vault.to_account_info(),
+                    from: ctx.accounts.stake_vault.to_account_info(),
                     to: ctx.accounts.authority.to_account_info(),
                 },
                 signer_seeds,
@@ -359,13 +910,14 @@ pub fn claim_unstake_handler(ctx: Context<ClaimUnstake>) -> Result<()> {
         }
         StakeToken::Usdc => {
             // Transfer USDC from vault back to authority's token account
-            let vault_seeds = &[b"sol_vault".as_ref(), &[ctx.bumps.sol_vault]];
-            let signer_seeds = &[&vault_seeds[..]];
+            let bump = [ctx.bumps.stake_authority];
+            let authority_seeds = stake_authority_signer_seeds(&bump);
+            let signer_seeds = &[&authority_seeds[..]];
 
             let cpi_accounts = Transfer {
                 from: ctx.accounts.usdc_vault.to_account_info(),
                 to: ctx.accounts.stake_to_account.to_account_info(),
-                authority: ctx.accounts.sol_vault.to_account_info(),
+                authority: ctx.accounts.stake_authority.to_account_info(),
             };
             let cpi_program = ctx.accounts.token_program.to_account_info();
             let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
@@ -373,13 +925,14 @@ pub fn claim_unstake_handler(ctx: Context<ClaimUnstake>) -> Result<()> {
         }
         StakeToken::Usdt => {
             // Transfer USDT from vault back to authority's token account
-            let vault_seeds = &[b"sol_vault".as_ref(), &[ctx.bumps.sol_vault]];
-            let signer_seeds = &[&vault_seeds[..]];
+            let bump = [ctx.bumps.stake_authority];
+            let authority_seeds = stake_authority_signer_seeds(&bump);
+            let signer_seeds = &[&authority_seeds[..]];
 
             let cpi_accounts = Transfer {
                 from: ctx.accounts.usdt_vault.to_account_info(),
                 to: ctx.accounts.stake_to_account.to_account_info(),
-                authority: ctx.accounts.sol_vault.to_account_info(),
+                authority: ctx.accounts.stake_authority.to_account_info(),
             };
             let cpi_program = ctx.accounts.token_program.to_account_info();
             let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
@@ -387,61 +940,186 @@ pub fn claim_unstake_handler(ctx: Context<ClaimUnstake>) -> Result<()> {
         }
     }
 
-    // 4. Emit event
-    emit!(WardenUnstaked {
-        authority: warden.authority,
-        stake_amount: warden.stake_amount,
-        stake_token: warden.stake_token.clone(),
-    });
-
-    // Note: Warden account will be closed automatically via the close constraint
     Ok(())
 }
 
-// Account Contexts
+/// Moves a Warden to a new authority key
+/// The Warden PDA is seeded by its authority, so the account is migrated: a new PDA
+/// is created for `new_authority` holding the same state and the old one is closed.
+/// Stake stays in the shared vaults and is released to the new authority on unstake.
+pub fn rotate_warden_authority_handler(ctx: Context<RotateWardenAuthority>) -> Result<()> {
+    let old_warden = &ctx.accounts.warden;
+    let old_warden_key = old_warden.key();
+    let new_warden_key = ctx.accounts.new_warden.key();
+    let new_authority = ctx.accounts.new_authority.key();
 
-#[derive(Accounts)]
-#[instruction(stake_token: StakeToken, stake_amount: u64, peer_id: String, region_code: u8, ip_hash: [u8; 32], price: u64, timestamp: i64, signature: [u8; 64])]
-pub struct InitializeWarden<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + 512,
-        seeds = [b"warden", authority.key().as_ref()],
-        bump
-    )]
-    pub warden: Account<'info, Warden>,
+    // 1. Connection PDAs are seeded by the Warden address; they must all be closed first
+    require!(
+        old_warden.active_connections == 0,
+        ArkhamErrorCode::HasActiveConnections
+    );
+    require!(
+        new_authority != old_warden.authority,
+        ArkhamErrorCode::InvalidAuthorityRotation
+    );
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+    // 2. Copy the Warden state under the new authority
+    let migrated = rotated_warden(old_warden, new_authority);
+    ctx.accounts.new_warden.set_inner(migrated);
 
-    #[account(seeds = [b"protocol_config"], bump)]
-    pub protocol_config: Account<'info, ProtocolConfig>,
+    // 3. Re-point the leaderboard entry, if the caller passed the leaderboard
+    if let Some(top_earners) = ctx.accounts.top_earners.as_mut() {
+        rekey_earner_entry(&mut top_earners.entries, &old_warden_key, new_warden_key);
+    }
 
-    /// CHECK: Instructions sysvar for Ed25519 verification
-    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
-    pub instructions_sysvar: AccountInfo<'info>,
+    emit!(WardenAuthorityRotated {
+        old_authority: ctx.accounts.authority.key(),
+        new_authority,
+        old_warden: old_warden_key,
+        new_warden: new_warden_key,
+    });
 
-    /// CHECK: User's source account for the stake
-    #[account(mut)]
-    pub stake_from_account: AccountInfo<'info>,
+    // Note: the old Warden account is closed via the close constraint
+    Ok(())
+}
 
-    #[account(mut, seeds = [b"sol_vault"], bump)]
-    pub sol_vault: SystemAccount<'info>,
+/// Updates the Warden's self-service settings
+/// Only the Warden's own authority may call this; unset fields are left unchanged
+pub fn update_warden_metadata_handler(
+    ctx: Context<UpdateWardenMetadata>,
+    params: WardenMetadataUpdate,
+) -> Result<()> {
+    let warden = &mut ctx.accounts.warden;
 
+    if let Some(premium_only) = params.premium_seekers_only {
+        warden.premium_seekers_only = premium_only;
+    }
+
+    if let Some(availability) = params.availability {
+        if let Some(schedule) = &availability {
+            validate_availability_schedule(schedule)?;
+        }
+        warden.availability = availability;
+    }
+
+    emit!(WardenMetadataUpdated {
+        warden: warden.key(),
+        authority: ctx.accounts.authority.key(),
+        params,
+    });
+
+    Ok(())
+}
+
+/// Checks a weekly availability schedule: seven UTC hour masks, Monday first
+/// Rejects schedules naming hours past 23 or serving no hour at all;
+/// a warden that wants to stop serving drains instead
+pub fn validate_availability_schedule(schedule: &[u32; 7]) -> Result<()> {
+    require!(
+        schedule.iter().all(|&hours| hours >> 24 == 0) && schedule.iter().any(|&hours| hours != 0),
+        ArkhamErrorCode::InvalidAvailabilitySchedule
+    );
+    Ok(())
+}
+
+/// Returns a copy of the Warden owned by `new_authority`
+pub fn rotated_warden(warden: &Warden, new_authority: Pubkey) -> Warden {
+    let mut migrated = warden.clone();
+    migrated.authority = new_authority;
+    migrated
+}
+
+/// Renames a leaderboard entry after its Warden moved to a new address
+pub fn rekey_earner_entry(entries: &mut [EarnerEntry], old_warden: &Pubkey, new_warden: Pubkey) {
+    if let Some(entry) = entries.iter_mut().find(|e| &e.warden == old_warden) {
+        entry.warden = new_warden;
+    }
+}
+
+/// Warden settings for update_warden_metadata; None leaves a field unchanged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WardenMetadataUpdate {
+    pub premium_seekers_only: Option<bool>,
+    pub availability: Option<Option<[u32; 7]>>, // Some(None) returns to always available
+}
+
+/// An oracle-signed price, as passed to initialize_warden, query_warden_tier and
+/// compound_earnings; checked by verify_oracle_price
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub version: u8, // Format the oracle signed; see create_versioned_oracle_message
+    pub price: u64, // Price in micro-units (6 decimals) of USD per token
+    pub decimals: u8, // Decimal exponent the oracle priced in; must equal USD_DECIMALS
+    pub timestamp: i64, // Timestamp of the price data
+    pub signature: [u8; 64], // Ed25519 signature of the price, decimals and timestamp by the oracle
+}
+
+// Account Contexts
+
+#[derive(Accounts)]
+pub struct UpdateWardenMetadata<'info> {
     #[account(
-        init_if_needed,
+        mut,
+        seeds = [b"warden", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub warden: Account<'info, Warden>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(stake_token: StakeToken, stake_amount: u64, peer_id: String, region_code: u8, ip_hash: [u8; 32], price: u64, timestamp: i64, signature: [u8; 64])]
+pub struct InitializeWarden<'info> {
+    #[account(
+        init,
         payer = authority,
+        space = WARDEN_SPACE,
+        seeds = [b"warden", authority.key().as_ref()],
+        bump
+    )]
+    pub warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Treasury receiving the registration fee, must match the protocol config
+    #[account(mut, address = protocol_config.treasury)]
+    pub treasury: AccountInfo<'info>,
+
+    /// CHECK: Instructions sysvar for Ed25519 verification
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// CHECK: User's source account for the stake
+    #[account(mut)]
+    pub stake_from_account: AccountInfo<'info>,
+
+    /// The protocol's stake vault (PDA), holding SOL stake principal only
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: SystemAccount<'info>,
+
+    /// CHECK: Stake authority PDA owning the stablecoin vaults; holds no data
+    #[account(seeds = [STAKE_AUTHORITY_SEEDS[0]], bump)]
+    pub stake_authority: AccountInfo<'info>,
+
+    /// Shared USDC vault, created once by initialize_stake_vaults
+    #[account(
+        mut,
         associated_token::mint = usdc_mint,
-        associated_token::authority = sol_vault,
+        associated_token::authority = stake_authority,
     )]
     pub usdc_vault: Account<'info, anchor_spl::token::TokenAccount>,
 
+    /// Shared USDT vault, created once by initialize_stake_vaults
     #[account(
-        init_if_needed,
-        payer = authority,
+        mut,
         associated_token::mint = usdt_mint,
-        associated_token::authority = sol_vault,
+        associated_token::authority = stake_authority,
     )]
     pub usdt_vault: Account<'info, anchor_spl::token::TokenAccount>,
 
@@ -450,7 +1128,96 @@ pub struct InitializeWarden<'info> {
 
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, anchor_spl::token::Token>,
-    pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+
+    /// Per-region active warden counts, updated when provided
+    #[account(mut, seeds = [b"region_index"], bump)]
+    pub region_index: Option<Box<Account<'info, RegionIndex>>>,
+}
+
+#[derive(Accounts)]
+pub struct QueryWardenSummaries {}
+
+#[derive(Accounts)]
+pub struct QueryWardenTier<'info> {
+    pub warden: Account<'info, Warden>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// CHECK: Instructions sysvar for Ed25519 verification
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReclassifyTier<'info> {
+    #[account(mut)]
+    pub warden: Account<'info, Warden>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct CompoundEarnings<'info> {
+    #[account(
+        mut,
+        seeds = [b"warden", authority.key().as_ref()],
+        bump,
+        has_one = authority
+    )]
+    pub warden: Account<'info, Warden>,
+
+    pub authority: Signer<'info>,
+
+    #[account(seeds = [b"protocol_config"], bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Rewards vault (PDA) holding the earnings being compounded
+    #[account(mut, seeds = [b"rewards_vault"], bump)]
+    pub rewards_vault: SystemAccount<'info>,
+
+    /// The protocol's stake vault (PDA), receiving the compounded earnings as principal
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: SystemAccount<'info>,
+
+    /// CHECK: Instructions sysvar for Ed25519 verification
+    #[account(address = INSTRUCTIONS_SYSVAR_ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RotateWardenAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"warden", authority.key().as_ref()],
+        bump,
+        has_one = authority,
+        close = authority
+    )]
+    pub warden: Account<'info, Warden>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = WARDEN_SPACE,
+        seeds = [b"warden", new_authority.key().as_ref()],
+        bump
+    )]
+    pub new_warden: Account<'info, Warden>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The new key must co-sign so a Warden can't be rotated to a key nobody controls
+    pub new_authority: Signer<'info>,
+
+    #[account(mut, seeds = [b"top_earners"], bump)]
+    pub top_earners: Option<Account<'info, TopEarners>>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -465,6 +1232,10 @@ pub struct UnstakeWarden<'info> {
 
     #[account(mut)]
     pub authority: Signer<'info>,
+
+    /// Per-region active warden counts; required once the warden is counted
+    #[account(mut, seeds = [b"region_index"], bump)]
+    pub region_index: Option<Box<Account<'info, RegionIndex>>>,
 }
 
 #[derive(Accounts)]
@@ -481,14 +1252,18 @@ pub struct ClaimUnstake<'info> {
     #[account(mut)]
     pub authority: Signer<'info>,
 
-    /// The protocol's SOL vault (PDA)
-    #[account(mut, seeds = [b"sol_vault"], bump)]
-    pub sol_vault: SystemAccount<'info>,
+    /// The protocol's stake vault (PDA), holding SOL stake principal only
+    #[account(mut, seeds = [b"stake_vault"], bump)]
+    pub stake_vault: SystemAccount<'info>,
 
-    #[account(mut)]
+    /// CHECK: Stake authority PDA, signs stablecoin releases; holds no data
+    #[account(seeds = [STAKE_AUTHORITY_SEEDS[0]], bump)]
+    pub stake_authority: AccountInfo<'info>,
+
+    #[account(mut, token::authority = stake_authority)]
     pub usdc_vault: Account<'info, TokenAccount>,
 
-    #[account(mut)]
+    #[account(mut, token::authority = stake_authority)]
     pub usdt_vault: Account<'info, TokenAccount>,
 
     /// CHECK: Destination token account for USDC/USDT unstaking
@@ -497,6 +1272,38 @@ pub struct ClaimUnstake<'info> {
 
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
+
+    /// Per-region active warden counts; required if the warden is still counted
+    #[account(mut, seeds = [b"region_index"], bump)]
+    pub region_index: Option<Box<Account<'info, RegionIndex>>>,
+}
+
+/// Entry in the return data of query_warden_summaries
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WardenSummary {
+    pub warden: Pubkey,
+    pub tier: Tier,
+    pub reputation_score: u32, // 0-10000
+    pub region_code: u8,
+    pub uptime_percentage: u16, // basis points
+    pub active_connections: u8,
+}
+
+impl WardenSummary {
+    pub const SPACE: usize = 32 + 1 + 4 + 1 + 2 + 1;
+}
+
+/// Most summaries a single query_warden_summaries call returns: the packed Vec,
+/// with its 4-byte length prefix, must fit in MAX_RETURN_DATA
+pub const MAX_WARDEN_SUMMARIES: usize =
+    (anchor_lang::solana_program::program::MAX_RETURN_DATA - 4) / WardenSummary::SPACE;
+
+/// Return data for query_warden_tier
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TierQualification {
+    pub current_value_usd: u64,
+    pub current_tier: Option<Tier>,
+    pub next_tier_gap_usd: u64,
 }
 
 // Events
@@ -507,6 +1314,45 @@ pub struct WardenRegistered {
     pub tier: Tier,
     pub stake_amount: u64,
     pub stake_token: StakeToken,
+    pub registration_fee: u64,
+}
+
+#[event]
+pub struct TierChanged {
+    pub authority: Pubkey,
+    pub old_tier: Tier,
+    pub new_tier: Tier,
+    pub stake_value_usd: u64,
+}
+
+#[event]
+pub struct EarningsCompounded {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub stake_amount: u64,
+    pub stake_value_usd: u64,
+}
+
+#[event]
+pub struct OracleVerificationSkipped {
+    pub authority: Pubkey,
+    pub stake_token: StakeToken,
+    pub fallback_price: u64,
+}
+
+#[event]
+pub struct WardenMetadataUpdated {
+    pub warden: Pubkey,
+    pub authority: Pubkey,
+    pub params: WardenMetadataUpdate,
+}
+
+#[event]
+pub struct WardenAuthorityRotated {
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub old_warden: Pubkey,
+    pub new_warden: Pubkey,
 }
 
 #[event]
@@ -515,6 +1361,11 @@ pub struct UnstakeRequested {
     pub requested_at: i64,
 }
 
+#[event]
+pub struct UnstakeCancelled {
+    pub authority: Pubkey,
+}
+
 #[event]
 pub struct WardenUnstaked {
     pub authority: Pubkey,
@@ -522,6 +1373,13 @@ pub struct WardenUnstaked {
     pub stake_token: StakeToken,
 }
 
+#[event]
+pub struct RegistrationAborted {
+    pub authority: Pubkey,
+    pub stake_amount: u64,
+    pub stake_token: StakeToken,
+}
+
 // Custom error codes specific to oracle verification
 #[error_code]
 pub enum OracleError {
@@ -550,25 +1408,580 @@ pub enum OracleError {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::test_warden;
+
+    #[test]
+    fn test_never_used_warden_skips_cooldown() {
+        let mut warden = test_warden();
+        warden.total_bandwidth_served = 0;
+        warden.successful_connections = 0;
+        warden.active_connections = 0;
+        warden.unstake_requested_at = None;
+        let mut region_index = RegionIndex { active_wardens: [0; crate::state::REGION_COUNT] };
+        join_region(&mut warden, Some(&mut region_index)).unwrap();
+
+        // The claim path still wants an unstake request...
+        assert_eq!(
+            ensure_unstake_cooldown_complete(warden.unstake_requested_at, 0).unwrap_err(),
+            ArkhamErrorCode::UnstakeNotRequested.into()
+        );
+
+        // ...but aborting returns the full stake straight away and frees the region slot
+        let aborted = prepare_registration_abort(&mut warden, Some(&mut region_index)).unwrap();
+        assert_eq!((aborted.authority, aborted.stake_amount), (warden.authority, warden.stake_amount));
+        assert_eq!(region_index.active_wardens[warden.region_code as usize], 0);
+        assert!(!warden.counted_in_region);
+    }
+
+    #[test]
+    fn test_used_warden_must_wait_out_cooldown() {
+        let now = 1_700_000_000;
+        for (bandwidth, successes, active) in [(1, 0, 0), (0, 1, 0), (0, 0, 1)] {
+            let mut warden = test_warden();
+            warden.total_bandwidth_served = bandwidth;
+            warden.successful_connections = successes;
+            warden.active_connections = active;
+            let mut region_index = RegionIndex { active_wardens: [0; crate::state::REGION_COUNT] };
+            join_region(&mut warden, Some(&mut region_index)).unwrap();
+
+            // Refused before anything changes: the warden stays counted in its region
+            assert_eq!(
+                prepare_registration_abort(&mut warden, Some(&mut region_index)).map(|_| ()).unwrap_err(),
+                ArkhamErrorCode::WardenAlreadyUsed.into()
+            );
+            assert_eq!(region_index.active_wardens[warden.region_code as usize], 1);
+            assert!(warden.counted_in_region);
+        }
+
+        assert_eq!(
+            ensure_unstake_cooldown_complete(None, now).unwrap_err(),
+            ArkhamErrorCode::UnstakeNotRequested.into()
+        );
+        assert_eq!(
+            ensure_unstake_cooldown_complete(Some(now - UNSTAKE_COOLDOWN_PERIOD + 1), now).unwrap_err(),
+            ArkhamErrorCode::CooldownNotComplete.into()
+        );
+        assert!(ensure_unstake_cooldown_complete(Some(now - UNSTAKE_COOLDOWN_PERIOD), now).is_ok());
+    }
+
+    #[test]
+    fn test_slashing_history_accumulates() {
+        let mut warden = test_warden();
+
+        record_slash(&mut warden, 1_000).unwrap();
+        record_slash(&mut warden, 250).unwrap();
+        record_slash(&mut warden, 0).unwrap();
+        assert_eq!(warden.total_slashed, 1_250);
+        assert_eq!(warden.slash_count, 3);
+
+        // Surfaced through snapshot_warden_earnings
+        let snapshot = crate::instructions::payments::warden_earnings_snapshot(&warden, 10);
+        assert_eq!((snapshot.total_slashed, snapshot.slash_count), (1_250, 3));
+
+        warden.total_slashed = u64::MAX;
+        assert!(record_slash(&mut warden, 1).is_err());
+        assert_eq!(warden.slash_count, 3);
+    }
+
+    #[test]
+    fn test_rotate_warden_authority() {
+        let mut warden = test_warden();
+        warden.stake_amount = 5_000_000_000;
+        warden.pending_claims = 42;
+        warden.reputation_score = 9_100;
+        let old_authority = warden.authority;
+        let new_authority = Pubkey::new_unique();
+
+        let migrated = rotated_warden(&warden, new_authority);
+        assert_eq!(migrated.authority, new_authority);
+        assert_eq!(migrated.stake_amount, warden.stake_amount);
+        assert_eq!(migrated.pending_claims, warden.pending_claims);
+        assert_eq!(migrated.reputation_score, warden.reputation_score);
+
+        // The old key no longer owns the migrated state (has_one = authority fails)
+        assert_ne!(migrated.authority, old_authority);
+
+        // Nor does it derive the migrated PDA
+        let (old_pda, _) = Pubkey::find_program_address(&[b"warden", old_authority.as_ref()], &crate::ID);
+        let (new_pda, _) = Pubkey::find_program_address(&[b"warden", new_authority.as_ref()], &crate::ID);
+        assert_ne!(old_pda, new_pda);
+
+        let other = Pubkey::new_unique();
+        let mut entries = vec![
+            EarnerEntry { warden: other, total_earnings: 10 },
+            EarnerEntry { warden: old_pda, total_earnings: 5 },
+        ];
+        rekey_earner_entry(&mut entries, &old_pda, new_pda);
+        assert_eq!(entries[0].warden, other);
+        assert_eq!(entries[1].warden, new_pda);
+        assert_eq!(entries[1].total_earnings, 5);
+    }
     
+    #[test]
+    fn test_query_tier_matches_manual_computation() {
+        let thresholds = [1_000_000_000, 5_000_000_000, 10_000_000_000]; // $1k, $5k, $10k
+
+        // 40 SOL at $150 = $6,000 -> Silver, $4,000 short of Gold
+        let value = calculate_stake_value_usd(&StakeToken::Sol, 40_000_000_000, 150_000_000).unwrap();
+        assert_eq!(value, 6_000_000_000);
+        assert_eq!(determine_tier(value, &thresholds), Some(Tier::Silver));
+        assert_eq!(next_tier_gap_usd(value, &thresholds), 4_000_000_000);
+
+        // Same stake at $20 = $800 -> below Bronze
+        let value = calculate_stake_value_usd(&StakeToken::Sol, 40_000_000_000, 20_000_000).unwrap();
+        assert_eq!(determine_tier(value, &thresholds), None);
+        assert_eq!(next_tier_gap_usd(value, &thresholds), 200_000_000);
+
+        // Gold has no next tier
+        assert_eq!(next_tier_gap_usd(10_000_000_000, &thresholds), 0);
+    }
+
+    #[test]
+    fn test_fallback_price_for() {
+        let fallback_prices = [150_000_000, 1_000_000, 0]; // SOL $150, USDC $1, USDT unset
+
+        // Oracle disabled: the stake is tiered at the configured fallback
+        let price = fallback_price_for(&fallback_prices, &StakeToken::Sol).unwrap();
+        let value = calculate_stake_value_usd(&StakeToken::Sol, 40_000_000_000, price).unwrap();
+        assert_eq!(value, 6_000_000_000);
+        assert_eq!(fallback_price_for(&fallback_prices, &StakeToken::Usdc).unwrap(), 1_000_000);
+
+        // No fallback configured for the token
+        assert_eq!(
+            fallback_price_for(&fallback_prices, &StakeToken::Usdt).unwrap_err(),
+            ArkhamErrorCode::FallbackPriceNotSet.into()
+        );
+    }
+
+    #[test]
+    fn test_determine_tier_at_exact_boundaries() {
+        let thresholds = [1_000_000_000, 5_000_000_000, 10_000_000_000];
+
+        assert_eq!(determine_tier(999_999_999, &thresholds), None);
+        assert_eq!(determine_tier(1_000_000_000, &thresholds), Some(Tier::Bronze));
+        assert_eq!(determine_tier(4_999_999_999, &thresholds), Some(Tier::Bronze));
+        assert_eq!(determine_tier(5_000_000_000, &thresholds), Some(Tier::Silver));
+        assert_eq!(determine_tier(9_999_999_999, &thresholds), Some(Tier::Silver));
+        assert_eq!(determine_tier(10_000_000_000, &thresholds), Some(Tier::Gold));
+    }
+
+    #[test]
+    fn test_reclassify_tier_after_lowering_gold_threshold() {
+        let mut warden = test_warden();
+        warden.stake_value_usd = 8_000_000_000;
+        warden.tier = Tier::Silver;
+
+        // Unchanged thresholds leave the tier alone
+        let thresholds = [1_000_000_000, 5_000_000_000, 10_000_000_000];
+        assert_eq!(apply_tier_thresholds(&mut warden, &thresholds), None);
+        assert_eq!(warden.tier, Tier::Silver);
+
+        // Governance lowers Gold below the stored stake value
+        let thresholds = [1_000_000_000, 5_000_000_000, 7_500_000_000];
+        assert_eq!(apply_tier_thresholds(&mut warden, &thresholds), Some(Tier::Silver));
+        assert_eq!(warden.tier, Tier::Gold);
+
+        // Running it again is a no-op
+        assert_eq!(apply_tier_thresholds(&mut warden, &thresholds), None);
+
+        // Raising every threshold above the stake floors the warden at Bronze
+        let thresholds = [9_000_000_000, 12_000_000_000, 15_000_000_000];
+        assert_eq!(apply_tier_thresholds(&mut warden, &thresholds), Some(Tier::Gold));
+        assert_eq!(warden.tier, Tier::Bronze);
+    }
+
+    #[test]
+    fn test_compounding_earnings_pushes_silver_warden_to_gold() {
+        let config = crate::state::test_protocol_config();
+        let price = 150_000_000u64; // $150 per SOL
+        let one_sol = 1_000_000_000u64;
+
+        // 40 SOL at $150 is $6,000: Silver
+        let mut warden = test_warden();
+        warden.stake_amount = 40 * one_sol;
+        warden.stake_value_usd = calculate_stake_value_usd(&StakeToken::Sol, warden.stake_amount, price).unwrap();
+        warden.tier = determine_tier(warden.stake_value_usd, &config.tier_thresholds).unwrap();
+        assert_eq!(warden.tier, Tier::Silver);
+
+        // Compounding 30 SOL of earnings lifts the stake to $10,500: Gold
+        warden.pending_claims = 30 * one_sol;
+        let (amount, old_tier) = compound_into_stake(&mut warden, price, &config.tier_thresholds).unwrap();
+        assert_eq!(amount, 30 * one_sol);
+        assert_eq!(old_tier, Some(Tier::Silver));
+        assert_eq!(warden.tier, Tier::Gold);
+        assert_eq!(warden.stake_amount, 70 * one_sol);
+        assert_eq!(warden.stake_value_usd, 10_500_000_000);
+        assert_eq!(warden.pending_claims, 0);
+
+        // Nothing left to compound
+        assert_eq!(
+            compound_into_stake(&mut warden, price, &config.tier_thresholds).unwrap_err(),
+            ArkhamErrorCode::NothingToClaim.into()
+        );
+
+        // Token stakes can't take SOL earnings without a swap
+        let mut token_warden = test_warden();
+        token_warden.stake_token = StakeToken::Usdc;
+        token_warden.pending_claims = one_sol;
+        assert_eq!(
+            compound_into_stake(&mut token_warden, 1_000_000, &config.tier_thresholds).unwrap_err(),
+            ArkhamErrorCode::CompoundRequiresSolStake.into()
+        );
+        assert_eq!(token_warden.pending_claims, one_sol);
+    }
+
+    #[test]
+    fn test_validate_approved_mint() {
+        let approved_usdc = Pubkey::new_unique();
+        let fake_usdc = Pubkey::new_unique();
+
+        assert!(validate_approved_mint(&approved_usdc, &approved_usdc).is_ok());
+        assert_eq!(
+            validate_approved_mint(&fake_usdc, &approved_usdc).unwrap_err(),
+            ArkhamErrorCode::UnapprovedStakeMint.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_stake_source_mint() {
+        let usdc_mint = Pubkey::new_unique();
+        let usdt_mint = Pubkey::new_unique();
+
+        assert!(validate_stake_source_mint(&usdc_mint, &usdc_mint).is_ok());
+
+        // Staking USDT while declaring USDC
+        assert_eq!(
+            validate_stake_source_mint(&usdt_mint, &usdc_mint).unwrap_err(),
+            ArkhamErrorCode::StakeMintMismatch.into()
+        );
+    }
+
+    #[test]
+    fn test_back_to_back_registrations_share_existing_vaults() {
+        use anchor_lang::ToAccountMetas;
+        use anchor_spl::associated_token::get_associated_token_address;
+
+        let (stake_vault, _) = Pubkey::find_program_address(&[b"stake_vault"], &crate::ID);
+        let (stake_authority, _) = Pubkey::find_program_address(&STAKE_AUTHORITY_SEEDS, &crate::ID);
+        let (usdc_mint, usdt_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let usdc_vault = get_associated_token_address(&stake_authority, &usdc_mint);
+        let usdt_vault = get_associated_token_address(&stake_authority, &usdt_mint);
+
+        let registration = |authority: Pubkey| {
+            let (warden, _) = Pubkey::find_program_address(&[b"warden", authority.as_ref()], &crate::ID);
+            crate::accounts::InitializeWarden {
+                warden,
+                authority,
+                protocol_config: Pubkey::new_unique(),
+                treasury: Pubkey::new_unique(),
+                instructions_sysvar: INSTRUCTIONS_SYSVAR_ID,
+                stake_from_account: authority,
+                stake_vault,
+                stake_authority,
+                usdc_vault,
+                usdt_vault,
+                usdc_mint,
+                usdt_mint,
+                system_program: anchor_lang::system_program::ID,
+                token_program: anchor_spl::token::ID,
+                region_index: None,
+            }
+            .to_account_metas(None)
+        };
+
+        // Two wardens registering concurrently reference the same pre-created vaults,
+        // and neither carries the associated token program needed to create them
+        for metas in [registration(Pubkey::new_unique()), registration(Pubkey::new_unique())] {
+            assert!(metas.iter().any(|m| m.pubkey == usdc_vault && m.is_writable));
+            assert!(metas.iter().any(|m| m.pubkey == usdt_vault && m.is_writable));
+            assert!(metas.iter().all(|m| m.pubkey != anchor_spl::associated_token::ID));
+        }
+    }
+
+    #[test]
+    fn test_stablecoin_stake_moves_through_dedicated_stake_authority() {
+        use anchor_lang::ToAccountMetas;
+        use anchor_spl::associated_token::get_associated_token_address;
+
+        let (stake_vault, _) = Pubkey::find_program_address(&[b"stake_vault"], &crate::ID);
+        let (stake_authority, bump) = Pubkey::find_program_address(&STAKE_AUTHORITY_SEEDS, &crate::ID);
+        assert_ne!(stake_authority, stake_vault);
+
+        // The signer seeds used on release re-derive the stake authority
+        assert_eq!(
+            Pubkey::create_program_address(&stake_authority_signer_seeds(&[bump]), &crate::ID).unwrap(),
+            stake_authority
+        );
+
+        let (usdc_mint, usdt_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let usdc_vault = get_associated_token_address(&stake_authority, &usdc_mint);
+        let usdt_vault = get_associated_token_address(&stake_authority, &usdt_mint);
+        assert_ne!(usdc_vault, get_associated_token_address(&stake_vault, &usdc_mint));
+        assert_ne!(usdt_vault, get_associated_token_address(&stake_vault, &usdt_mint));
+
+        let authority = Pubkey::new_unique();
+        let (warden, _) = Pubkey::find_program_address(&[b"warden", authority.as_ref()], &crate::ID);
+
+        // Stake: USDC/USDT land in the stake authority's vaults, SOL still in stake_vault
+        let stake = crate::accounts::InitializeWarden {
+            warden,
+            authority,
+            protocol_config: Pubkey::new_unique(),
+            treasury: Pubkey::new_unique(),
+            instructions_sysvar: INSTRUCTIONS_SYSVAR_ID,
+            stake_from_account: authority,
+            stake_vault,
+            stake_authority,
+            usdc_vault,
+            usdt_vault,
+            usdc_mint,
+            usdt_mint,
+            system_program: anchor_lang::system_program::ID,
+            token_program: anchor_spl::token::ID,
+            region_index: None,
+        }
+        .to_account_metas(None);
+
+        // Unstake: the stake authority signs stablecoin releases by PDA seeds, not as a tx signer
+        let unstake = crate::accounts::ClaimUnstake {
+            warden,
+            authority,
+            stake_vault,
+            stake_authority,
+            usdc_vault,
+            usdt_vault,
+            stake_to_account: Pubkey::new_unique(),
+            system_program: anchor_lang::system_program::ID,
+            token_program: anchor_spl::token::ID,
+            region_index: None,
+        }
+        .to_account_metas(None);
+
+        for metas in [stake, unstake] {
+            let meta = |key: Pubkey| metas.iter().find(|m| m.pubkey == key).unwrap().clone();
+            assert!(!meta(stake_authority).is_signer && !meta(stake_authority).is_writable);
+            assert!(meta(usdc_vault).is_writable && meta(usdt_vault).is_writable);
+            assert!(meta(stake_vault).is_writable);
+        }
+    }
+
+    #[test]
+    fn test_query_warden_summaries_decodes_three_wardens() {
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut data: Vec<Vec<u8>> = (0..3u8)
+            .map(|i| {
+                let mut warden = crate::state::test_warden();
+                warden.tier = [Tier::Bronze, Tier::Silver, Tier::Gold][i as usize].clone();
+                warden.reputation_score = 9_000 + i as u32;
+                warden.region_code = i;
+                warden.uptime_percentage = 9_900 + i as u16;
+                warden.active_connections = i + 1;
+                let mut bytes = Vec::new();
+                warden.try_serialize(&mut bytes).unwrap();
+                bytes.resize(8 + 512, 0);
+                bytes
+            })
+            .collect();
+        let mut lamports = [0u64; 3];
+        let owner = crate::ID;
+        let infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(data.iter_mut())
+            .zip(lamports.iter_mut())
+            .map(|((key, data), lamports)| AccountInfo::new(key, false, false, lamports, data, &owner, false, 0))
+            .collect();
+
+        let return_data = warden_summaries(&infos).unwrap().try_to_vec().unwrap();
+        assert!(return_data.len() <= anchor_lang::solana_program::program::MAX_RETURN_DATA);
+
+        // What the client decodes from get_return_data
+        let summaries = Vec::<WardenSummary>::try_from_slice(&return_data).unwrap();
+        assert_eq!(summaries.len(), 3);
+        for (i, summary) in summaries.iter().enumerate() {
+            assert_eq!(summary.warden, keys[i]);
+            assert_eq!(summary.tier, [Tier::Bronze, Tier::Silver, Tier::Gold][i]);
+            assert_eq!(summary.reputation_score, 9_000 + i as u32);
+            assert_eq!(summary.region_code, i as u8);
+            assert_eq!(summary.uptime_percentage, 9_900 + i as u16);
+            assert_eq!(summary.active_connections, i as u8 + 1);
+        }
+    }
+
+    #[test]
+    fn test_warden_summaries_fit_return_data() {
+        let summary = warden_summary(Pubkey::new_unique(), &crate::state::test_warden());
+        assert_eq!(summary.try_to_vec().unwrap().len(), WardenSummary::SPACE);
+
+        let full = vec![summary; MAX_WARDEN_SUMMARIES].try_to_vec().unwrap();
+        assert!(full.len() <= anchor_lang::solana_program::program::MAX_RETURN_DATA);
+
+        // One more would no longer fit, so the query refuses it before decoding anything
+        assert!(full.len() + WardenSummary::SPACE > anchor_lang::solana_program::program::MAX_RETURN_DATA);
+        let keys: Vec<Pubkey> = (0..=MAX_WARDEN_SUMMARIES).map(|_| Pubkey::new_unique()).collect();
+        let mut lamports = vec![0u64; keys.len()];
+        let mut data = vec![Vec::<u8>::new(); keys.len()];
+        let owner = crate::ID;
+        let infos: Vec<AccountInfo> = keys
+            .iter()
+            .zip(data.iter_mut())
+            .zip(lamports.iter_mut())
+            .map(|((key, data), lamports)| AccountInfo::new(key, false, false, lamports, data, &owner, false, 0))
+            .collect();
+        assert_eq!(
+            warden_summaries(&infos).unwrap_err(),
+            ArkhamErrorCode::TooManyWardenSummaries.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_sol_stake_amount() {
+        let rent_exempt_minimum = Rent::default().minimum_balance(0);
+
+        assert!(validate_sol_stake_amount(rent_exempt_minimum, rent_exempt_minimum).is_ok());
+        assert!(validate_sol_stake_amount(rent_exempt_minimum - 1, rent_exempt_minimum).is_err());
+    }
+
     #[test]
     fn test_create_oracle_message() {
         let price = 150_000_000u64; // $150 in micro-units
         let timestamp = 1234567890i64;
         
-        let message = create_oracle_message(price, timestamp);
-        let message2 = create_oracle_message(price, timestamp);
+        let message = create_oracle_message(price, 6, timestamp);
+        let message2 = create_oracle_message(price, 6, timestamp);
         
         // Messages should be deterministic
         assert_eq!(message, message2);
         assert_eq!(message.len(), 32); // Keccak hash is 32 bytes
         
         // Different price should produce different message
-        let message3 = create_oracle_message(price + 1, timestamp);
+        let message3 = create_oracle_message(price + 1, 6, timestamp);
         assert_ne!(message, message3);
         
         // Different timestamp should produce different message
-        let message4 = create_oracle_message(price, timestamp + 1);
+        let message4 = create_oracle_message(price, 6, timestamp + 1);
         assert_ne!(message, message4);
+
+        // Different decimals should produce different message
+        let message5 = create_oracle_message(price, 9, timestamp);
+        assert_ne!(message, message5);
+    }
+
+    #[test]
+    fn test_oracle_message_versions() {
+        use crate::instructions::bandwidth::{ed25519_ix_data, instructions_sysvar_data};
+
+        let mut config = crate::state::test_protocol_config();
+        let (price, timestamp, signature) = (150_000_000u64, 1_700_000_000i64, [5u8; 64]);
+
+        // v1 is the legacy unversioned layout; v2 hashes different bytes
+        let v1 = create_versioned_oracle_message(ORACLE_MESSAGE_V1, price, 6, timestamp).unwrap();
+        let v2 = create_versioned_oracle_message(ORACLE_MESSAGE_V2, price, 6, timestamp).unwrap();
+        assert_eq!(v1, create_oracle_message(price, 6, timestamp));
+        assert_eq!(v2.len(), 32);
+        assert_ne!(v1, v2);
+        assert_eq!(
+            create_versioned_oracle_message(LATEST_ORACLE_MESSAGE_VERSION + 1, price, 6, timestamp).unwrap_err(),
+            ArkhamErrorCode::UnsupportedOracleMessageVersion.into()
+        );
+
+        let verify = |config: &ProtocolConfig, signed: &[u8], version: u8| {
+            let ed25519_ix = ed25519_ix_data(&signature, &config.oracle_authority, signed);
+            let mut lamports = 0u64;
+            let mut data = instructions_sysvar_data(&[(ed25519_program::ID, ed25519_ix)]);
+            let owner = Pubkey::default();
+            let sysvar = AccountInfo::new(
+                &INSTRUCTIONS_SYSVAR_ID, false, false, &mut lamports, &mut data, &owner, false, 0,
+            );
+            verify_oracle_price(&sysvar, config, &OraclePrice { version, price, decimals: 6, timestamp, signature }, timestamp)
+        };
+
+        // Each message verifies only under the version it was built for
+        assert!(verify(&config, &v1, ORACLE_MESSAGE_V1).is_ok());
+        assert!(verify(&config, &v2, ORACLE_MESSAGE_V2).is_ok());
+        assert!(verify(&config, &v1, ORACLE_MESSAGE_V2).is_err());
+        assert!(verify(&config, &v2, ORACLE_MESSAGE_V1).is_err());
+
+        // Raising the minimum ends v1's grace without affecting v2 signers
+        config.min_oracle_message_version = ORACLE_MESSAGE_V2;
+        assert_eq!(
+            verify(&config, &v1, ORACLE_MESSAGE_V1).unwrap_err(),
+            ArkhamErrorCode::OracleMessageVersionRetired.into()
+        );
+        assert!(verify(&config, &v2, ORACLE_MESSAGE_V2).is_ok());
+    }
+
+    /// Runs verify_oracle_price against a sysvar holding one Ed25519 instruction
+    /// in which the oracle signed `signed_decimals`
+    fn verify_oracle_price_with(signed_decimals: u8, claimed_decimals: u8, price: u64) -> Result<()> {
+        use crate::instructions::bandwidth::{ed25519_ix_data, instructions_sysvar_data};
+
+        let config = crate::state::test_protocol_config();
+        let (timestamp, signature) = (1_700_000_000i64, [5u8; 64]);
+        let signed = create_oracle_message(price, signed_decimals, timestamp);
+        let ed25519_ix = ed25519_ix_data(&signature, &config.oracle_authority, &signed);
+
+        let mut lamports = 0u64;
+        let mut data = instructions_sysvar_data(&[(ed25519_program::ID, ed25519_ix)]);
+        let owner = Pubkey::default();
+        let sysvar = AccountInfo::new(
+            &INSTRUCTIONS_SYSVAR_ID, false, false, &mut lamports, &mut data, &owner, false, 0,
+        );
+        let oracle_price = OraclePrice { version: ORACLE_MESSAGE_V1, price, decimals: claimed_decimals, timestamp, signature };
+        verify_oracle_price(&sysvar, &config, &oracle_price, timestamp)
+    }
+
+    #[test]
+    fn test_registration_failure_paths_fail_before_any_transfer() {
+        let config = crate::state::test_protocol_config();
+        let one_sol = 1_000_000_000u64;
+
+        // Insufficient SOL: the fee plus stake must be covered before anything moves
+        assert!(ensure_registration_funded(one_sol + 1_000, 1_000, one_sol).is_ok());
+        assert_eq!(
+            ensure_registration_funded(one_sol, 1_000, one_sol).unwrap_err(),
+            ArkhamErrorCode::InsufficientFundsForRegistration.into()
+        );
+
+        // Invalid signature: the oracle signed a different price
+        let (timestamp, signature) = (1_700_000_000i64, [5u8; 64]);
+        let signed = create_oracle_message(150_000_000, 6, timestamp);
+        let instructions = [(
+            ed25519_program::ID,
+            crate::instructions::bandwidth::ed25519_ix_data(&signature, &config.oracle_authority, &signed),
+        )];
+        let verify = |price: u64, now: i64| {
+            let mut lamports = 0u64;
+            let mut data = crate::instructions::bandwidth::instructions_sysvar_data(&instructions);
+            let owner = Pubkey::default();
+            let sysvar = AccountInfo::new(
+                &INSTRUCTIONS_SYSVAR_ID, false, false, &mut lamports, &mut data, &owner, false, 0,
+            );
+            verify_oracle_price(&sysvar, &config, &OraclePrice { version: ORACLE_MESSAGE_V1, price, decimals: 6, timestamp, signature }, now)
+        };
+        assert!(verify(150_000_000, timestamp).is_ok());
+        assert!(verify(999_000_000, timestamp).is_err());
+
+        // Stale price: more than five minutes old
+        assert_eq!(verify(150_000_000, timestamp + 301).unwrap_err(), ArkhamErrorCode::StalePrice.into());
+
+        // Below-tier stake: worth less than the Bronze threshold
+        let stake_value_usd = calculate_stake_value_usd(&StakeToken::Sol, one_sol, 150_000_000).unwrap();
+        assert!(stake_value_usd < config.tier_thresholds[0]);
+        assert_eq!(determine_tier(stake_value_usd, &config.tier_thresholds), None);
+    }
+
+    #[test]
+    fn test_oracle_price_decimals() {
+        // $150 per SOL in micro-USD, signed as such
+        assert!(verify_oracle_price_with(6, 6, 150_000_000).is_ok());
+
+        // The same $150 from a 9-decimal feed is rejected before any value is computed
+        assert_eq!(
+            verify_oracle_price_with(9, 9, 150_000_000_000).unwrap_err(),
+            ArkhamErrorCode::OraclePriceDecimalsMismatch.into()
+        );
+
+        // Claiming 6 decimals for a price the oracle signed at 9 fails the signature check
+        assert!(verify_oracle_price_with(9, 6, 150_000_000_000).is_err());
     }
 }
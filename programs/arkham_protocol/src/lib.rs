@@ -4,6 +4,8 @@ declare_id!("B85X9aTrpWAdi1xhLvPmDPuYmfz5YdMd9X8qr7uU4H18");
 
 pub mod state;
 pub mod instructions;
+#[cfg(not(target_os = "solana"))]
+pub mod client;
 
 pub use instructions::*;
 pub use state::*;
@@ -21,6 +23,7 @@ pub mod arkham_protocol {
         tokens_per_5gb: u64,
         geo_premiums: Vec<GeoPremium>,
         oracle_authority: Pubkey,
+        params: InitializeProtocolConfigParams,
     ) -> Result<()> {
         instructions::admin::initialize_protocol_config_handler(
             ctx,
@@ -31,6 +34,7 @@ pub mod arkham_protocol {
             tokens_per_5gb,
             geo_premiums,
             oracle_authority,
+            params,
         )
     }
 
@@ -50,9 +54,7 @@ pub mod arkham_protocol {
         peer_id: String,
         region_code: u8,
         ip_hash: [u8; 32],
-        price: u64,
-        timestamp: i64,
-        signature: [u8; 64],
+        oracle_price: OraclePrice,
     ) -> Result<()> {
         instructions::staking::initialize_warden_handler(
             ctx,
@@ -61,20 +63,61 @@ pub mod arkham_protocol {
             peer_id,
             region_code,
             ip_hash,
-            price,
-            timestamp,
-            signature,
+            oracle_price,
         )
     }
 
+    pub fn query_warden_tier(
+        ctx: Context<QueryWardenTier>,
+        oracle_price: OraclePrice,
+    ) -> Result<TierQualification> {
+        instructions::staking::query_warden_tier_handler(ctx, oracle_price)
+    }
+
+    pub fn query_warden_summaries<'info>(
+        ctx: Context<'_, '_, 'info, 'info, QueryWardenSummaries>,
+    ) -> Result<Vec<WardenSummary>> {
+        instructions::staking::query_warden_summaries_handler(ctx)
+    }
+
+    pub fn reclassify_tier(ctx: Context<ReclassifyTier>) -> Result<()> {
+        instructions::staking::reclassify_tier_handler(ctx)
+    }
+
+    pub fn compound_earnings(
+        ctx: Context<CompoundEarnings>,
+        oracle_price: OraclePrice,
+    ) -> Result<()> {
+        instructions::staking::compound_earnings_handler(ctx, oracle_price)
+    }
+
+    pub fn rotate_warden_authority(ctx: Context<RotateWardenAuthority>) -> Result<()> {
+        instructions::staking::rotate_warden_authority_handler(ctx)
+    }
+
+    pub fn update_warden_metadata(
+        ctx: Context<UpdateWardenMetadata>,
+        params: WardenMetadataUpdate,
+    ) -> Result<()> {
+        instructions::staking::update_warden_metadata_handler(ctx, params)
+    }
+
     pub fn unstake_warden(ctx: Context<UnstakeWarden>) -> Result<()> {
         instructions::staking::unstake_warden_handler(ctx)
     }
 
+    pub fn cancel_unstake(ctx: Context<UnstakeWarden>) -> Result<()> {
+        instructions::staking::cancel_unstake_handler(ctx)
+    }
+
     pub fn claim_unstake(ctx: Context<ClaimUnstake>) -> Result<()> {
         instructions::staking::claim_unstake_handler(ctx)
     }
 
+    pub fn abort_registration(ctx: Context<ClaimUnstake>) -> Result<()> {
+        instructions::staking::abort_registration_handler(ctx)
+    }
+
     // ============================================
     // Payment Instructions
     // ============================================
@@ -87,6 +130,41 @@ pub mod arkham_protocol {
         instructions::payments::deposit_escrow_handler(ctx, amount, use_private)
     }
 
+    pub fn withdraw_escrow(ctx: Context<WithdrawEscrow>, amount: u64) -> Result<()> {
+        instructions::payments::withdraw_escrow_handler(ctx, amount)
+    }
+
+    pub fn set_allowed_wardens(
+        ctx: Context<SetAllowedWardens>,
+        allowed_wardens: Option<Vec<Pubkey>>,
+    ) -> Result<()> {
+        instructions::payments::set_allowed_wardens_handler(ctx, allowed_wardens)
+    }
+
+    pub fn set_private_escrow(ctx: Context<SetPrivateEscrow>, private_escrow: Pubkey) -> Result<()> {
+        instructions::payments::set_private_escrow_handler(ctx, private_escrow)
+    }
+
+    pub fn clear_private_escrow(ctx: Context<SetPrivateEscrow>, private_escrow: Pubkey) -> Result<()> {
+        instructions::payments::clear_private_escrow_handler(ctx, private_escrow)
+    }
+
+    pub fn query_private_escrow(ctx: Context<QueryPrivateEscrow>) -> Result<Option<Pubkey>> {
+        instructions::payments::query_private_escrow_handler(ctx)
+    }
+
+    pub fn expire_premium(ctx: Context<ExpirePremium>) -> Result<()> {
+        instructions::payments::expire_premium_handler(ctx)
+    }
+
+    pub fn stake_idle_escrow(ctx: Context<StakeIdleEscrow>, amount: u64) -> Result<()> {
+        instructions::payments::stake_idle_escrow_handler(ctx, amount)
+    }
+
+    pub fn unwind_staked_escrow(ctx: Context<UnwindStakedEscrow>, amount: u64) -> Result<()> {
+        instructions::payments::unwind_staked_escrow_handler(ctx, amount)
+    }
+
     pub fn start_connection(
         ctx: Context<StartConnection>,
         estimated_mb: u64,
@@ -94,6 +172,20 @@ pub mod arkham_protocol {
         instructions::payments::start_connection_handler(ctx, estimated_mb)
     }
 
+    pub fn estimate_session_cost(
+        ctx: Context<EstimateSessionCost>,
+        estimated_mb: u64,
+    ) -> Result<SessionCostEstimate> {
+        instructions::payments::estimate_session_cost_handler(ctx, estimated_mb)
+    }
+
+    pub fn reduce_connection_escrow(
+        ctx: Context<ReduceConnectionEscrow>,
+        remaining_mb: u64,
+    ) -> Result<()> {
+        instructions::payments::reduce_connection_escrow_handler(ctx, remaining_mb)
+    }
+
     pub fn submit_bandwidth_proof(
         ctx: Context<SubmitBandwidthProof>,
         mb_consumed: u64,
@@ -110,10 +202,68 @@ pub mod arkham_protocol {
         )
     }
 
-    pub fn end_connection(ctx: Context<EndConnection>) -> Result<()> {
+    pub fn authorize_warden_settlement(
+        ctx: Context<AuthorizeWardenSettlement>,
+        max_mb: u64,
+    ) -> Result<()> {
+        instructions::payments::authorize_warden_settlement_handler(ctx, max_mb)
+    }
+
+    pub fn set_warden_spend_limit(
+        ctx: Context<SetWardenSpendLimit>,
+        max_spend: u64,
+    ) -> Result<()> {
+        instructions::payments::set_warden_spend_limit_handler(ctx, max_spend)
+    }
+
+    pub fn sla_refund(ctx: Context<SlaRefund>) -> Result<()> {
+        instructions::payments::sla_refund_handler(ctx)
+    }
+
+    pub fn submit_delegated_bandwidth_proof(
+        ctx: Context<SubmitBandwidthProof>,
+        mb_consumed: u64,
+        timestamp: i64,
+        warden_signature: [u8; 64],
+    ) -> Result<()> {
+        instructions::payments::submit_delegated_bandwidth_proof_handler(
+            ctx,
+            mb_consumed,
+            timestamp,
+            warden_signature,
+        )
+    }
+
+    pub fn end_connection(ctx: Context<EndConnection>) -> Result<ConnectionSummary> {
         instructions::payments::end_connection_handler(ctx)
     }
 
+    pub fn quick_session(
+        ctx: Context<QuickSession>,
+        mb_consumed: u64,
+        timestamp: i64,
+        seeker_signature: [u8; 64],
+        warden_signature: [u8; 64],
+    ) -> Result<ConnectionSummary> {
+        instructions::payments::quick_session_handler(
+            ctx,
+            mb_consumed,
+            timestamp,
+            seeker_signature,
+            warden_signature,
+        )
+    }
+
+    pub fn close_settled_connections_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CloseSettledConnectionsBatch<'info>>,
+    ) -> Result<()> {
+        instructions::payments::close_settled_connections_batch_handler(ctx)
+    }
+
+    pub fn snapshot_warden_earnings(ctx: Context<SnapshotWardenEarnings>) -> Result<()> {
+        instructions::payments::snapshot_warden_earnings_handler(ctx)
+    }
+
     pub fn claim_earnings(
         ctx: Context<ClaimEarnings>,
         use_private: bool,
@@ -125,6 +275,16 @@ pub mod arkham_protocol {
         instructions::payments::claim_arkham_tokens_handler(ctx)
     }
 
+    pub fn query_arkham_minting_headroom(
+        ctx: Context<QueryArkhamMintingHeadroom>,
+    ) -> Result<ArkhamMintingHeadroom> {
+        instructions::payments::query_arkham_minting_headroom_handler(ctx)
+    }
+
+    pub fn claim_subsidized_arkham_tokens(ctx: Context<ClaimSubsidizedArkhamTokens>) -> Result<()> {
+        instructions::payments::claim_subsidized_arkham_tokens_handler(ctx)
+    }
+
     // ============================================
     // Reputation Instructions
     // ============================================
@@ -133,14 +293,32 @@ pub mod arkham_protocol {
         ctx: Context<UpdateReputation>,
         connection_success: bool,
         uptime_report: u16,
+        report_timestamp: i64,
     ) -> Result<()> {
         instructions::reputation::update_reputation_handler(
             ctx,
             connection_success,
             uptime_report,
+            report_timestamp,
         )
     }
 
+    pub fn report_rejected_proof(
+        ctx: Context<ReportRejectedProof>,
+        connection: Pubkey,
+        reason: ProofRejectionReason,
+    ) -> Result<()> {
+        instructions::reputation::report_rejected_proof_handler(ctx, connection, reason)
+    }
+
+    pub fn report_anomaly(ctx: Context<ReportAnomaly>, evidence: AnomalyEvidence) -> Result<()> {
+        instructions::reputation::report_anomaly_handler(ctx, evidence)
+    }
+
+    pub fn check_warden_activity(ctx: Context<CheckWardenActivity>) -> Result<()> {
+        instructions::reputation::check_warden_activity_handler(ctx)
+    }
+
     pub fn update_premium_pool_rankings(
         ctx: Context<UpdatePremiumPoolRankings>,
         top_wardens: Vec<Pubkey>,
@@ -179,22 +357,106 @@ pub mod arkham_protocol {
         )
     }
 
+    pub fn remove_geo_premium_regions(
+        ctx: Context<UpdateProtocolConfig>,
+        region_codes: Vec<u8>,
+        force: bool,
+    ) -> Result<()> {
+        instructions::admin::remove_geo_premium_regions_handler(ctx, region_codes, force)
+    }
+
+    pub fn update_protocol_params(
+        ctx: Context<UpdateProtocolConfig>,
+        params: ProtocolParamsUpdate,
+    ) -> Result<()> {
+        instructions::admin::update_protocol_params_handler(ctx, params)
+    }
+
+    pub fn set_reputation_updater(
+        ctx: Context<UpdateProtocolConfig>,
+        new_reputation_updater: Pubkey,
+    ) -> Result<()> {
+        instructions::admin::set_reputation_updater_handler(ctx, new_reputation_updater)
+    }
+
+    pub fn export_protocol_config(ctx: Context<ExportProtocolConfig>) -> Result<ProtocolConfigExport> {
+        instructions::admin::export_protocol_config_handler(ctx)
+    }
+
+    pub fn initialize_top_earners(ctx: Context<InitializeTopEarners>) -> Result<()> {
+        instructions::admin::initialize_top_earners_handler(ctx)
+    }
+
+    pub fn initialize_region_index(ctx: Context<InitializeRegionIndex>) -> Result<()> {
+        instructions::admin::initialize_region_index_handler(ctx)
+    }
+
+    pub fn initialize_stake_vaults(ctx: Context<InitializeStakeVaults>) -> Result<()> {
+        instructions::admin::initialize_stake_vaults_handler(ctx)
+    }
+
+    pub fn initialize_insurance_fund(ctx: Context<InitializeInsuranceFund>) -> Result<()> {
+        instructions::admin::initialize_insurance_fund_handler(ctx)
+    }
+
+    pub fn initialize_rewards_vault(ctx: Context<InitializeRewardsVault>) -> Result<()> {
+        instructions::admin::initialize_rewards_vault_handler(ctx)
+    }
+
+    pub fn initialize_escrow_yield_vault(ctx: Context<InitializeEscrowYieldVault>) -> Result<()> {
+        instructions::admin::initialize_escrow_yield_vault_handler(ctx)
+    }
+
+    pub fn migrate_stake_vaults(ctx: Context<MigrateStakeVaults>) -> Result<()> {
+        instructions::admin::migrate_stake_vaults_handler(ctx)
+    }
+
+    pub fn sweep_legacy_sol_vault(ctx: Context<SweepLegacySolVault>, to_rewards: u64) -> Result<()> {
+        instructions::admin::sweep_legacy_sol_vault_handler(ctx, to_rewards)
+    }
+
     pub fn initialize_arkham_mint(ctx: Context<InitializeArkhamMint>) -> Result<()> {
         instructions::admin::initialize_arkham_mint_handler(ctx)
     }
 
-    pub fn distribute_subsidies(
-        ctx: Context<DistributeSubsidies>,
+    pub fn renounce_mint_authority(ctx: Context<RenounceMintAuthority>) -> Result<()> {
+        instructions::admin::renounce_mint_authority_handler(ctx)
+    }
+
+    pub fn distribute_subsidies<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DistributeSubsidies<'info>>,
+        subsidy_token: SubsidyToken,
         warden_keys: Vec<Pubkey>,
         subsidy_amounts: Vec<u64>,
     ) -> Result<()> {
         instructions::admin::distribute_subsidies_handler(
             ctx,
+            subsidy_token,
             warden_keys,
             subsidy_amounts,
         )
     }
 
+    pub fn grant_arkham_subsidy(ctx: Context<GrantArkhamSubsidy>, amount: u64) -> Result<()> {
+        instructions::admin::grant_arkham_subsidy_handler(ctx, amount)
+    }
+
+    pub fn adjust_pending_claims(
+        ctx: Context<AdjustPendingClaims>,
+        new_amount: u64,
+        reason: String,
+    ) -> Result<()> {
+        instructions::admin::adjust_pending_claims_handler(ctx, new_amount, reason)
+    }
+
+    pub fn admin_refund_connection(ctx: Context<AdminRefundConnection>) -> Result<()> {
+        instructions::admin::admin_refund_connection_handler(ctx)
+    }
+
+    pub fn admin_refund_legacy_connection(ctx: Context<AdminRefundLegacyConnection>) -> Result<()> {
+        instructions::admin::admin_refund_legacy_connection_handler(ctx)
+    }
+
     pub fn close_protocol_config(ctx: Context<CloseProtocolConfig>) -> Result<()> {
         instructions::admin::close_protocol_config_handler(ctx)
     }
@@ -210,6 +472,15 @@ pub mod arkham_protocol {
     pub fn migrate_warden_peer_id(ctx: Context<MigrateWardenPeerId>) -> Result<()> {
         instructions::admin::migrate_warden_peer_id_handler(ctx)
     }
+
+    pub fn migrate_account(
+        ctx: Context<MigrateAccount>,
+        account_type: MigratableAccount,
+        target_version: u8,
+        params: MigrationParams,
+    ) -> Result<()> {
+        instructions::migration::migrate_account_handler(ctx, account_type, target_version, params)
+    }
 }
 
 #[derive(Accounts)]
@@ -223,6 +494,8 @@ pub enum ArkhamErrorCode {
     // Staking errors
     #[msg("Stake amount is insufficient to qualify for the lowest tier.")]
     InsufficientStake,
+    #[msg("Too many wardens for one summary query - the result must fit in return data.")]
+    TooManyWardenSummaries,
     #[msg("Warden has active connections and cannot unstake.")]
     HasActiveConnections,
     #[msg("Reputation score too low to unstake (must be at least 80%).")]
@@ -231,70 +504,258 @@ pub enum ArkhamErrorCode {
     UnstakeNotRequested,
     #[msg("Cooldown period not complete - must wait 7 days.")]
     CooldownNotComplete,
+    #[msg("Warden has served connections and must unstake through the cooldown.")]
+    WardenAlreadyUsed,
+    #[msg("SOL stake is below the vault's rent-exempt minimum.")]
+    StakeBelowRentExemption,
+    #[msg("Authority cannot cover the registration fee plus SOL stake.")]
+    InsufficientFundsForRegistration,
+    #[msg("New warden authority must differ from the current one.")]
+    InvalidAuthorityRotation,
+    #[msg("Only SOL-staked wardens can compound earnings into their stake.")]
+    CompoundRequiresSolStake,
+    #[msg("Availability schedule must name hours 0-23 and at least one hour in the week.")]
+    InvalidAvailabilitySchedule,
 
     // Oracle errors
     #[msg("The provided oracle price feed is invalid.")]
     InvalidPriceAccount,
     #[msg("The oracle price is too old.")]
     StalePrice,
+    #[msg("The oracle price is not expressed in the expected USD decimals.")]
+    OraclePriceDecimalsMismatch,
     #[msg("The oracle price has too wide of a confidence interval.")]
     InvalidPriceConfidence,
     #[msg("The provided signature is invalid.")]
     InvalidSignature,
     #[msg("The signer of the price data is not the trusted oracle.")]
     InvalidSigner,
+    #[msg("Oracle is not required but no fallback price is configured for this token.")]
+    FallbackPriceNotSet,
+    #[msg("The oracle message version is not one this program can construct.")]
+    UnsupportedOracleMessageVersion,
+    #[msg("The oracle message version is older than min_oracle_message_version.")]
+    OracleMessageVersionRetired,
 
     // Payment errors
     #[msg("Insufficient escrow balance.")]
     InsufficientEscrow,
+    #[msg("Withdrawal would leave the seeker account below its rent-exempt minimum.")]
+    EscrowWithdrawalBelowRentExemption,
     #[msg("Insufficient connection escrow for payment.")]
     InsufficientConnectionEscrow,
+    #[msg("Connection escrow is fully paid out; end the connection and start a new one with more escrow.")]
+    ConnectionFullySettled,
+    #[msg("Connection has settled max_total_proofs; end it and start a new one.")]
+    ProofLimitReached,
+    #[msg("No SLA breach was reported during this connection, or the warden has nothing left to refund.")]
+    NoSlaRefundDue,
+    #[msg("An SLA refund has already been paid for this connection.")]
+    SlaRefundAlreadyClaimed,
+    #[msg("Only the seeker's authority may claim its SLA refund.")]
+    UnauthorizedSlaRefundClaim,
+    #[msg("Payment would exceed the seeker's spend limit for this warden.")]
+    WardenSpendLimitExceeded,
+    #[msg("Active connection escrow would exceed the seeker account's lamports.")]
+    EscrowUndercollateralized,
+    #[msg("Escrow reduction must lower the locked amount and keep paid amounts covered.")]
+    InvalidEscrowReduction,
+    #[msg("Connection still holds unsettled escrow.")]
+    ConnectionNotSettled,
+    #[msg("Connection account does not match the provided seeker or warden.")]
+    ConnectionAccountMismatch,
+    #[msg("Batch accounts must be non-empty (connection, seeker) pairs.")]
+    InvalidBatchAccounts,
     #[msg("Nothing to claim.")]
     NothingToClaim,
+    #[msg("Escrow amount must be greater than zero.")]
+    InvalidEscrowAmount,
+    #[msg("Idle escrow yield is disabled in the protocol config.")]
+    IdleEscrowYieldDisabled,
+    #[msg("Insufficient staked escrow to unwind.")]
+    InsufficientStakedEscrow,
+    #[msg("Seeker and warden cannot share the same authority.")]
+    SelfDealingNotAllowed,
+    #[msg("Computed rate per MB is zero - connection cannot start.")]
+    ZeroRateNotAllowed,
+    #[msg("Warden is not on the seeker's allowlist.")]
+    WardenNotAllowed,
+    #[msg("Warden only accepts seekers with an active premium subscription.")]
+    PremiumSeekersOnly,
+    #[msg("Too many wardens in the seeker's allowlist.")]
+    TooManyAllowedWardens,
+    #[msg("Warden is draining ahead of unstaking and accepts no new connections.")]
+    WardenDraining,
+    #[msg("Warden is suspended for inactivity and accepts no new connections.")]
+    WardenSuspended,
+    #[msg("Warden does not serve at this hour of its weekly availability schedule.")]
+    WardenUnavailable,
+    #[msg("Seeker already holds the maximum number of active connections.")]
+    SeekerAtCapacity,
+    #[msg("Warden's region has fewer active wardens than min_wardens_per_region.")]
+    RegionBelowMinimumWardens,
+    #[msg("Warden's reported uptime is below min_uptime_for_matching.")]
+    WardenUptimeTooLow,
+    #[msg("The region index account is required for this warden or configuration.")]
+    RegionIndexRequired,
+    #[msg("The insurance fund account is required while insurance_bps is set.")]
+    InsuranceFundRequired,
+    #[msg("Seeker has not authorized the warden to settle this connection alone.")]
+    SettlementNotDelegated,
+    #[msg("Delegated settlement would exceed the seeker-authorized MB cap.")]
+    DelegatedSettlementCapExceeded,
 
     // Token errors
+    #[msg("Stake source token account mint does not match the declared stake token.")]
+    StakeMintMismatch,
+    #[msg("Stablecoin mint is not the one approved in the protocol config.")]
+    UnapprovedStakeMint,
     #[msg("Invalid stake token type provided.")]
     InvalidStakeToken,
     #[msg("Token mint not initialized.")]
     TokenMintNotInitialized,
     #[msg("Token minting not yet implemented.")]
     TokenMintingNotImplemented,
+    #[msg("ARKHAM mint authority has been renounced - no further tokens can be minted.")]
+    ArkhamMintingRenounced,
+    #[msg("ARKHAM supply cap reached - no further tokens can be minted.")]
+    ArkhamSupplyExhausted,
+    #[msg("Invalid max ARKHAM supply - must be 0 (uncapped) or at least the amount already minted.")]
+    InvalidMaxArkhamSupply,
 
     // Privacy errors
     #[msg("Private payments not yet implemented.")]
     PrivatePaymentsNotImplemented,
+    #[msg("No private escrow account is registered for this seeker.")]
+    PrivateEscrowNotSet,
+    #[msg("Private escrow account does not match the one registered for this seeker.")]
+    PrivateEscrowMismatch,
+    #[msg("A different private escrow account is already registered; clear it first.")]
+    PrivateEscrowAlreadySet,
 
     // Reputation errors
     #[msg("Unauthorized reputation update attempt.")]
     UnauthorizedReputationUpdate,
+    #[msg("The warden account does not belong to the supplied warden authority.")]
+    WardenAuthorityMismatch,
+    #[msg("Invalid uptime report - must be <= 10000 (100%).")]
+    InvalidUptimeReport,
+    #[msg("Invalid bootstrap reputation cap - must be <= 10000 (100%).")]
+    InvalidBootstrapReputationCap,
+    #[msg("Premium ranking lists more wardens than premium_pool_size.")]
+    PremiumPoolTooLarge,
+    #[msg("Premium ranking list does not match the supplied warden accounts.")]
+    PremiumRankingAccountsMismatch,
+    #[msg("Premium ranking lists the same warden more than once.")]
+    DuplicatePremiumWarden,
+    #[msg("Reputation was updated too recently; wait for min_reputation_update_interval.")]
+    ReputationUpdateTooFrequent,
+    #[msg("Inactivity timeout must not be negative.")]
+    InvalidInactivityTimeout,
+    #[msg("Failure decay window must not be negative.")]
+    InvalidFailureDecayWindow,
+    #[msg("Uptime report is older than max_uptime_report_age or dated in the future.")]
+    StaleUptimeReport,
+    #[msg("Invalid uptime report age - must not be negative.")]
+    InvalidUptimeReportAge,
+    #[msg("Invalid reputation update interval - must not be negative.")]
+    InvalidReputationUpdateInterval,
+    #[msg("Invalid SLA uptime - must be <= 10000 (100%).")]
+    InvalidSlaUptime,
+    #[msg("Invalid proof event interval - must not be negative.")]
+    InvalidProofEventInterval,
+    #[msg("Invalid anomaly bounty - must be <= 10000 (100%).")]
+    InvalidAnomalyBounty,
+    #[msg("Invalid insurance share - must be <= 10000 (100%).")]
+    InvalidInsuranceBps,
+    #[msg("Anomaly reporting is disabled.")]
+    AnomalyReportingDisabled,
+    #[msg("Anomaly evidence must be two different proofs for the same connection and timestamp.")]
+    InvalidAnomalyEvidence,
+    #[msg("Invalid minimum uptime for matching - must be <= 10000 (100%).")]
+    InvalidMinUptimeForMatching,
+    #[msg("Invalid premium pool size - must be at least 1.")]
+    InvalidPremiumPoolSize,
 
     // Admin errors
     #[msg("Unauthorized admin action - caller is not the protocol authority.")]
     UnauthorizedAdminAction,
     #[msg("Invalid fee basis points - must be <= 10000 (100%).")]
     InvalidFeeBps,
+    #[msg("Invalid fee bounds - min_fee_bps must not exceed max_fee_bps, which must not exceed PROTOCOL_FEE_CEILING_BPS.")]
+    InvalidFeeBounds,
+    #[msg("Protocol fee is outside the configured min_fee_bps/max_fee_bps bounds.")]
+    ProtocolFeeOutOfBounds,
     #[msg("Invalid tier thresholds - must be in ascending order.")]
     InvalidTierThresholds,
+    #[msg("Invalid tier thresholds - strict tiers require strictly ascending thresholds; equal thresholds leave a tier unreachable.")]
+    CollapsedTierThresholds,
     #[msg("Invalid tier multiplier - must be <= 50000 (5x).")]
     InvalidTierMultiplier,
+    #[msg("Tier multipliers must be nonzero; a zero multiplier prices connections at zero.")]
+    ZeroTierMultiplier,
+    #[msg("Invalid tier multipliers - must be in ascending order.")]
+    NonMonotonicTierMultipliers,
+    #[msg("Invalid premium reward boost - must be <= 10000 (100%).")]
+    InvalidPremiumRewardBoost,
+    #[msg("Invalid reward rate - reward_denominator must be nonzero.")]
+    InvalidRewardRate,
     #[msg("Invalid geographic premium - must be <= 50000 (500%).")]
     InvalidGeoPremium,
     #[msg("Duplicate region code found in geographic premiums.")]
     DuplicateRegionCode,
+    #[msg("Geographic premium region still has registered wardens - remove it with remove_geo_premium_regions and force.")]
+    GeoPremiumRegionInUse,
     #[msg("ARKHAM token mint is already initialized.")]
     TokenMintAlreadyInitialized,
     #[msg("Invalid subsidy distribution - vectors must have the same length.")]
     InvalidSubsidyDistribution,
+    #[msg("Subsidy batch exceeds MAX_SUBSIDY_BATCH wardens - split it into smaller batches.")]
+    SubsidyBatchTooLarge,
     #[msg("Insufficient treasury balance for subsidy distribution.")]
     InsufficientTreasuryBalance,
+    #[msg("Treasury token account is not owned by the configured treasury or holds the wrong mint.")]
+    InvalidTreasuryAccount,
+    #[msg("Invalid vesting duration - must not be negative.")]
+    InvalidVestingDuration,
+    #[msg("Invalid max connections per seeker - must be at least 1.")]
+    InvalidMaxConnectionsPerSeeker,
+    #[msg("Pending claims increase exceeds the rewards vault's spendable balance.")]
+    ClaimsAdjustmentExceedsVault,
+    #[msg("Adjustment reason exceeds MAX_ADJUSTMENT_REASON_LEN bytes.")]
+    AdjustmentReasonTooLong,
+    #[msg("Rewards share of the legacy sol_vault sweep exceeds the vault's balance.")]
+    LegacySweepExceedsBalance,
     #[msg("The provided Peer ID is invalid.")]
     InvalidPeerId,
     #[msg("Unauthorized warden update attempt.")]
     UnauthorizedWardenUpdate,
 
+    // Migration errors
+    #[msg("No migration is registered for this account type and target version.")]
+    UnknownMigration,
+    #[msg("Account does not match the declared migration account type or address.")]
+    MigrationAccountMismatch,
+    #[msg("Only the authority stored in the account may migrate it.")]
+    UnauthorizedMigration,
+    #[msg("A parameter this migration step requires was not provided.")]
+    MissingMigrationParam,
+
     // General errors
     #[msg("Arithmetic operation resulted in overflow.")]
     ArithmeticOverflow,
+    #[msg("Rate or payment computation overflowed.")]
+    RateOverflow,
+    #[msg("Escrow accounting overflowed.")]
+    EscrowOverflow,
+    #[msg("Warden earnings accounting overflowed.")]
+    RewardOverflow,
+    #[msg("Reputation computation overflowed.")]
+    ReputationOverflow,
+    #[msg("Stablecoin mints must be set and distinct.")]
+    InvalidStakeMints,
+    #[msg("Geographic premium region is not configured.")]
+    GeoPremiumRegionNotFound,
 }
 
 impl From<crate::instructions::staking::OracleError> for ArkhamErrorCode {
@@ -1,5 +1,9 @@
 use anchor_lang::prelude::*;
 
+pub const PROOF_HISTORY_CAPACITY: usize = 10;
+
+/// Connections opened while bandwidth_proofs was a Vec no longer deserialize;
+/// admin_refund_legacy_connection refunds and closes them
 #[account]
 pub struct Connection {
     pub seeker: Pubkey,
@@ -7,17 +11,86 @@ pub struct Connection {
     pub started_at: i64,
     pub last_proof_at: i64,
     pub bandwidth_consumed: u64, // in megabytes
-    pub bandwidth_proofs: Vec<BandwidthProof>,
+    pub bandwidth_proofs: [BandwidthProof; PROOF_HISTORY_CAPACITY], // ring buffer of recent proofs
+    pub proofs_head: u8, // next slot to write
+    pub proofs_count: u8, // slots in use, up to PROOF_HISTORY_CAPACITY
     pub amount_escrowed: u64, // in lamports
     pub amount_paid: u64, // in lamports
     pub rate_per_mb: u64, // in lamports
     pub warden_multiplier: u16, // basis points
+    pub tokens_per_5gb: u64, // ARKHAM reward rate locked at connection start, superseded by the pair below
+    pub delegated_max_mb: u64, // MB the seeker authorized the warden to settle alone; 0 = no delegation
+    pub delegated_mb_settled: u64, // MB settled so far under that delegation
+    pub max_spend: u64, // lamports the seeker trusts this warden to collect; 0 = escrow is the only limit
+    pub uptime_at_start: u16, // warden's uptime (bps) when the connection started
+    pub sla_refunded: bool, // an SLA refund has been paid for this connection
+    pub pending_proof_event: PendingProofEvent, // proofs settled since the last BandwidthProofSubmitted
+    pub connection_id: u64, // derived from seeker + nonce; unique even when the PDA is reused
+    pub total_proofs: u32, // proofs settled over the connection's lifetime, bounded by max_total_proofs
+    pub escrow_buffer: u64, // part of amount_escrowed above the estimated cost, in lamports
+    pub reward_numerator: u64, // ARKHAM reward rate locked at connection start
+    pub reward_denominator: u64, // 0 on connections opened before the pair; tokens_per_5gb applies
+}
+
+/// Zeroed Connection between fresh seeker and warden keys, for unit tests
+#[cfg(test)]
+pub(crate) fn test_connection() -> Connection {
+    Connection {
+        seeker: Pubkey::new_unique(),
+        warden: Pubkey::new_unique(),
+        started_at: 0,
+        last_proof_at: 0,
+        bandwidth_consumed: 0,
+        bandwidth_proofs: [BandwidthProof::EMPTY; PROOF_HISTORY_CAPACITY],
+        proofs_head: 0,
+        proofs_count: 0,
+        amount_escrowed: 0,
+        amount_paid: 0,
+        rate_per_mb: 0,
+        warden_multiplier: 10000,
+        tokens_per_5gb: 0,
+        delegated_max_mb: 0,
+        delegated_mb_settled: 0,
+        max_spend: 0,
+        uptime_at_start: 10000,
+        sla_refunded: false,
+        pending_proof_event: PendingProofEvent::default(),
+        connection_id: 0,
+        total_proofs: 0,
+        escrow_buffer: 0,
+        reward_numerator: 0,
+        reward_denominator: 0,
+    }
+}
+
+/// Proofs settled but not yet reported in a BandwidthProofSubmitted event
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PendingProofEvent {
+    pub proofs: u16,
+    pub mb_consumed: u64,
+    pub payment_amount: u64, // in lamports
+    pub arkham_earned: u64,
+    pub last_emitted_at: i64,
+}
+
+impl PendingProofEvent {
+    pub const SPACE: usize = 2 + 8 + 8 + 8 + 8;
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
 pub struct BandwidthProof {
     pub timestamp: i64,
     pub mb_consumed: u64,
-    pub seeker_signature: [u8; 64],
+    pub seeker_signature: [u8; 64], // zeroed for proofs settled under a warden delegation
     pub warden_signature: [u8; 64],
 }
+
+impl BandwidthProof {
+    /// Unused ring buffer slot
+    pub const EMPTY: BandwidthProof = BandwidthProof {
+        timestamp: 0,
+        mb_consumed: 0,
+        seeker_signature: [0; 64],
+        warden_signature: [0; 64],
+    };
+}
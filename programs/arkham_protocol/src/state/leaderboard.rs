@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+pub const TOP_EARNERS_CAPACITY: usize = 10;
+
+#[account]
+pub struct TopEarners {
+    pub entries: Vec<EarnerEntry>, // sorted by total_earnings, highest first
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EarnerEntry {
+    pub warden: Pubkey,
+    pub total_earnings: u64, // in lamports
+}
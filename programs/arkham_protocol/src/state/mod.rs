@@ -1,12 +1,16 @@
 
     pub mod connection;
+    pub mod leaderboard;
     pub mod protocol;
+    pub mod region;
     pub mod reputation;
     pub mod seeker;
     pub mod warden;
 
     pub use connection::*;
+    pub use leaderboard::*;
     pub use protocol::*;
+    pub use region::*;
     pub use reputation::*;
     pub use seeker::*;
     pub use warden::*;
@@ -1,18 +1,186 @@
 use anchor_lang::prelude::*;
 
+/// Seeds of the ARKHAM mint authority PDA
+/// Shared by every context that derives it and every CPI it signs
+pub const MINT_AUTHORITY_SEEDS: [&[u8]; 3] = [b"arkham", b"mint", b"authority"];
+
+/// Signer seeds for the ARKHAM mint authority PDA with its bump appended
+pub fn mint_authority_signer_seeds(bump: &[u8; 1]) -> [&[u8]; 4] {
+    [MINT_AUTHORITY_SEEDS[0], MINT_AUTHORITY_SEEDS[1], MINT_AUTHORITY_SEEDS[2], bump]
+}
+
+/// Seeds of the stake authority PDA, owner of the USDC/USDT stake vaults
+/// Kept apart from the stake_vault PDA, which only holds SOL stake
+pub const STAKE_AUTHORITY_SEEDS: [&[u8]; 1] = [b"stake_authority"];
+
+/// Signer seeds for the stake authority PDA with its bump appended
+pub fn stake_authority_signer_seeds(bump: &[u8; 1]) -> [&[u8]; 2] {
+    [STAKE_AUTHORITY_SEEDS[0], bump]
+}
+
+/// ProtocolConfig account size, with room for 10 geo premium regions
+/// Configs created before the later fields existed grow to this through migrate_account
+pub const PROTOCOL_CONFIG_SPACE: usize = 8 + // discriminator
+    32 + // authority
+    32 + // treasury
+    32 + // arkham_token_mint
+    32 + // oracle_authority
+    8 +  // base_rate_per_mb
+    2 +  // protocol_fee_bps
+    (8 * 3) + // tier_thresholds
+    (2 * 3) + // tier_multipliers
+    8 +  // tokens_per_5gb
+    4 + (10 * (1 + 2)) + // geo_premiums vec (assume max 10 regions)
+    32 + // reputation_updater
+    1 + // idle_escrow_yield_enabled
+    2 + // premium_reward_boost_bps
+    1 + // rate_unit
+    1 + // arkham_minting_renounced
+    8 + // registration_fee
+    8 + // subsidy_vesting_duration
+    32 + // usdc_mint
+    32 + // usdt_mint
+    1 + // oracle_required
+    (8 * 3) + // fallback_prices
+    1 + // max_connections_per_seeker
+    1 + // require_strict_tiers
+    8 + // max_arkham_reward_per_proof
+    2 + // min_wardens_per_region
+    8 + // min_connections_for_full_reputation
+    4 + // bootstrap_reputation_cap
+    2 + // min_uptime_for_matching
+    2 + // min_fee_bps
+    2 + // max_fee_bps
+    2 + // premium_pool_size
+    8 + // min_reputation_update_interval
+    2 + // sla_uptime_bps
+    2 + // proof_event_every_n
+    8 + // proof_event_interval
+    8 + // anomaly_penalty
+    2 + // anomaly_bounty_bps
+    8 + // reward_numerator
+    8 + // reward_denominator
+    2 + // rate_check_tolerance_bps
+    8 + // max_uptime_report_age
+    8 + // max_arkham_supply
+    8 + // arkham_total_minted
+    1 + // min_oracle_message_version
+    4 + // max_total_proofs
+    8 + // inactivity_timeout
+    1 + // buffer_refund_enabled
+    2 + // insurance_bps
+    8 + // failure_decay_window
+    1; // proof_fee_enabled
+
 #[account]
 pub struct ProtocolConfig {
     pub authority: Pubkey,
     pub treasury: Pubkey,
     pub arkham_token_mint: Pubkey,
     pub oracle_authority: Pubkey, // The authority allowed to sign price data
-    pub base_rate_per_mb: u64, // in lamports
+    pub base_rate_per_mb: u64, // in lamports, per rate_unit
     pub protocol_fee_bps: u16,
-    pub tier_thresholds: [u64; 3], // USD value
+    pub tier_thresholds: [u64; 3], // USD value, inclusive lower bound of Bronze/Silver/Gold
     pub tier_multipliers: [u16; 3], // basis points
-    pub tokens_per_5gb: u64,
+    pub tokens_per_5gb: u64, // legacy view of reward_numerator/reward_denominator, rounded down
     pub geo_premiums: Vec<GeoPremium>,
     pub reputation_updater: Pubkey, // Authority allowed to update reputations
+    pub idle_escrow_yield_enabled: bool, // Seekers may park idle escrow in the yield vault
+    pub premium_reward_boost_bps: u16, // Extra ARKHAM allocation for premium-ranked wardens
+    pub rate_unit: RateUnit, // Unit base_rate_per_mb is expressed in
+    pub arkham_minting_renounced: bool, // Mint authority permanently removed
+    pub registration_fee: u64, // in lamports, one-time warden registration fee paid to treasury
+    pub subsidy_vesting_duration: i64, // seconds over which subsidized ARKHAM vests linearly; 0 = immediate
+    pub usdc_mint: Pubkey, // Approved USDC mint for stablecoin stakes
+    pub usdt_mint: Pubkey, // Approved USDT mint for stablecoin stakes
+    pub oracle_required: bool, // When false, registration tiers stakes at the fallback prices
+    pub fallback_prices: [u64; 3], // micro-USD per SOL/USDC/USDT, used only when oracle_required is false
+    pub max_connections_per_seeker: u8, // concurrent connections a seeker may hold open
+    pub require_strict_tiers: bool, // Reject equal tier thresholds, which leave a tier unreachable
+    pub max_arkham_reward_per_proof: u64, // ARKHAM cap per bandwidth proof; 0 = uncapped
+    pub min_wardens_per_region: u16, // active wardens a region needs before it accepts connections; 0 = off
+    pub min_connections_for_full_reputation: u64, // successful connections before bootstrap_reputation_cap lifts
+    pub bootstrap_reputation_cap: u32, // reputation ceiling (bps) for wardens still bootstrapping
+    pub min_uptime_for_matching: u16, // uptime (bps) a warden needs to accept new connections; 0 = off
+    pub min_fee_bps: u16, // lowest protocol_fee_bps governance may set
+    pub max_fee_bps: u16, // highest protocol_fee_bps governance may set; at most PROTOCOL_FEE_CEILING_BPS
+    pub premium_pool_size: u16, // most wardens a single premium ranking update may list
+    pub min_reputation_update_interval: i64, // seconds between update_reputation calls per warden; 0 = off
+    pub sla_uptime_bps: u16, // uptime (bps) wardens promise seekers; reports below it allow refunds; 0 = off
+    pub proof_event_every_n: u16, // emit BandwidthProofSubmitted once per this many proofs; 0 or 1 = every proof
+    pub proof_event_interval: i64, // or once this many seconds have passed since the last one; 0 = off
+    pub anomaly_penalty: u64, // lamports taken from a warden's pending claims per proven anomaly; 0 = reporting off
+    pub anomaly_bounty_bps: u16, // share of the penalty paid to the reporter; the rest goes to the treasury
+    pub reward_numerator: u64, // ARKHAM per MB is reward_numerator / reward_denominator
+    pub reward_denominator: u64, // 0 on configs predating the pair; tokens_per_5gb / MB_PER_5GB applies
+    pub rate_check_tolerance_bps: u16, // flag settled proofs whose locked rate drifts further than this from config; 0 = off
+    pub max_uptime_report_age: i64, // seconds an uptime measurement stays usable by update_reputation; 0 = any age
+    pub max_arkham_supply: u64, // most ARKHAM claims and subsidy grants may ever mint; 0 = uncapped
+    pub arkham_total_minted: u64, // ARKHAM minted so far by claims and subsidy grants
+    pub min_oracle_message_version: u8, // oldest oracle message format still accepted; raise to end a format's grace
+    pub max_total_proofs: u32, // proofs one connection may settle before it must be reopened; 0 = unlimited
+    pub inactivity_timeout: i64, // seconds without activity before check_warden_activity suspends a warden; 0 = off
+    pub buffer_refund_enabled: bool, // return unneeded escrow buffer after each proof instead of at end_connection
+    pub insurance_bps: u16, // share of the protocol fee on each proof payment routed to the insurance fund
+    pub failure_decay_window: i64, // seconds after which a failed connection counts at reduced weight; 0 = never
+    pub proof_fee_enabled: bool, // take protocol_fee_bps from each proof payment; off, the warden is paid in full
+}
+
+/// Default ProtocolConfig for unit tests
+#[cfg(test)]
+pub(crate) fn test_protocol_config() -> ProtocolConfig {
+    ProtocolConfig {
+        authority: Pubkey::new_unique(),
+        treasury: Pubkey::new_unique(),
+        arkham_token_mint: Pubkey::new_unique(),
+        oracle_authority: Pubkey::new_unique(),
+        base_rate_per_mb: 1_000,
+        protocol_fee_bps: 200,
+        tier_thresholds: [1_000_000_000, 5_000_000_000, 10_000_000_000],
+        tier_multipliers: [10000, 12000, 15000],
+        tokens_per_5gb: 500_000_000,
+        geo_premiums: Vec::new(),
+        reputation_updater: Pubkey::new_unique(),
+        idle_escrow_yield_enabled: false,
+        premium_reward_boost_bps: 0,
+        rate_unit: RateUnit::PerMb,
+        arkham_minting_renounced: false,
+        registration_fee: 0,
+        subsidy_vesting_duration: 0,
+        usdc_mint: Pubkey::new_unique(),
+        usdt_mint: Pubkey::new_unique(),
+        oracle_required: true,
+        fallback_prices: [0; 3],
+        max_connections_per_seeker: 5,
+        require_strict_tiers: false,
+        max_arkham_reward_per_proof: 0,
+        min_wardens_per_region: 0,
+        min_connections_for_full_reputation: 0,
+        bootstrap_reputation_cap: 10000,
+        min_uptime_for_matching: 0,
+        min_fee_bps: 0,
+        max_fee_bps: 1_000,
+        premium_pool_size: 100,
+        min_reputation_update_interval: 0,
+        sla_uptime_bps: 0,
+        proof_event_every_n: 0,
+        proof_event_interval: 0,
+        anomaly_penalty: 0,
+        anomaly_bounty_bps: 0,
+        reward_numerator: 500_000_000,
+        reward_denominator: 5_120,
+        rate_check_tolerance_bps: 0,
+        max_uptime_report_age: 0,
+        max_arkham_supply: 0,
+        arkham_total_minted: 0,
+        min_oracle_message_version: 1,
+        max_total_proofs: 0,
+        inactivity_timeout: 0,
+        buffer_refund_enabled: false,
+        insurance_bps: 0,
+        failure_decay_window: 0,
+        proof_fee_enabled: false,
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
@@ -20,3 +188,17 @@ pub struct GeoPremium {
     pub region_code: u8,
     pub premium_bps: u16,
 }
+
+/// Currency a subsidy distribution is paid in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SubsidyToken {
+    Sol,    // credited to pending_claims, funded into the rewards vault
+    Arkham, // credited to arkham_tokens_subsidized, funded into the subsidy vault
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub enum RateUnit {
+    #[default]
+    PerMb,
+    PerGb,
+}
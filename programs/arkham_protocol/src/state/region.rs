@@ -0,0 +1,9 @@
+use anchor_lang::prelude::*;
+
+/// One slot per possible `region_code`
+pub const REGION_COUNT: usize = 256;
+
+#[account]
+pub struct RegionIndex {
+    pub active_wardens: [u16; REGION_COUNT], // wardens accepting connections, by region_code
+}
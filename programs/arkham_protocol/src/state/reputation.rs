@@ -1,5 +1,17 @@
 use anchor_lang::prelude::*;
 
+/// Marks one connection/timestamp as already reported through report_anomaly
+/// Its existence is what stops the same evidence from being paid out twice
+#[account]
+pub struct AnomalyReport {
+    pub warden: Pubkey,
+    pub connection: Pubkey,
+    pub timestamp: i64, // the proof timestamp both conflicting proofs carry
+    pub reporter: Pubkey,
+    pub penalty: u64, // in lamports
+    pub bounty: u64, // in lamports
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default)]
 pub struct ReputationMetrics {
     pub connection_success_weight: u16, // basis points
@@ -1,5 +1,25 @@
 use anchor_lang::prelude::*;
 
+pub const SEEKER_HISTORY_CAPACITY: usize = 5;
+pub const MAX_ALLOWED_WARDENS: usize = 8;
+/// Seeker account size with a full connection_history ring and warden allowlist
+/// Accounts created before the later fields existed no longer deserialize; their authority
+/// grows them to this with migrate_account (Seeker v2)
+pub const SEEKER_SPACE: usize = 8 + // discriminator
+    32 + // authority
+    8 + // escrow_balance
+    (1 + 32) + // private_escrow
+    8 + // total_bandwidth_consumed
+    8 + // total_spent
+    1 + // active_connections
+    (1 + 8) + // premium_expires_at
+    8 + // staked_escrow
+    4 + (SEEKER_HISTORY_CAPACITY * (32 + 8 + 8 + 8)) + // connection_history
+    1 + // history_head
+    8 + // committed_escrow
+    1 + 4 + (MAX_ALLOWED_WARDENS * 32) + // allowed_wardens
+    8; // connection_nonce
+
 #[account]
 pub struct Seeker {
     pub authority: Pubkey,
@@ -9,4 +29,38 @@ pub struct Seeker {
     pub total_spent: u64, // in lamports
     pub active_connections: u8,
     pub premium_expires_at: Option<i64>,
+    pub staked_escrow: u64, // in lamports, idle escrow parked in the yield vault
+    pub connection_history: Vec<SessionRecord>, // ring buffer of recent sessions
+    pub history_head: u8, // next slot to overwrite once the history is full
+    pub committed_escrow: u64, // in lamports, locked and not yet paid out in active connections
+    pub allowed_wardens: Option<Vec<Pubkey>>, // None or empty means any warden
+    pub connection_nonce: u64, // connections started so far; nonce of the next connection_id
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SessionRecord {
+    pub warden: Pubkey,
+    pub started_at: i64,
+    pub bandwidth_consumed: u64, // in megabytes
+    pub amount_paid: u64, // in lamports
+}
+
+/// Zeroed Seeker with no history, for unit tests
+#[cfg(test)]
+pub(crate) fn test_seeker() -> Seeker {
+    Seeker {
+        authority: Pubkey::new_unique(),
+        escrow_balance: 0,
+        private_escrow: None,
+        total_bandwidth_consumed: 0,
+        total_spent: 0,
+        active_connections: 0,
+        premium_expires_at: None,
+        staked_escrow: 0,
+        connection_history: vec![],
+        history_head: 0,
+        committed_escrow: 0,
+        allowed_wardens: None,
+        connection_nonce: 0,
+    }
 }
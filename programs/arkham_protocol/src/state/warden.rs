@@ -1,5 +1,13 @@
 use anchor_lang::prelude::*;
 
+pub const RECENT_FAILURE_CAPACITY: usize = 8;
+pub const WARDEN_HISTORY_CAPACITY: usize = 5;
+/// Warden account size: the original 512-byte allowance, which also holds the optional
+/// availability schedule (1 + 7 * 4 bytes), plus a full recent_connections ring
+/// Accounts created at 8 + 512 grow to this with migrate_account (Warden v3), paid by the
+/// warden authority; until then they keep no recent_connections
+pub const WARDEN_SPACE: usize = 8 + 512 + 4 + WARDEN_HISTORY_CAPACITY * WardenSessionRecord::SPACE + 1;
+
 #[account]
 pub struct Warden {
     pub authority: Pubkey,
@@ -23,6 +31,91 @@ pub struct Warden {
     pub ip_hash: [u8; 32],
     pub premium_pool_rank: Option<u16>,
     pub active_connections: u8,
+    pub rejected_proof_count: u64,
+    pub arkham_tokens_subsidized: u64, // bootstrap grants, kept apart from organic earnings
+    pub arkham_subsidy_claimed: u64,
+    pub subsidy_vesting_start: i64, // start of the linear vesting of grants not yet carried as vested
+    pub draining: bool, // unstake requested; no new connections accepted
+    pub counted_in_region: bool, // included in the RegionIndex count for region_code
+    pub total_slashed: u64, // in lamports, anomaly penalties taken from pending_claims
+    pub slash_count: u32,
+    pub last_reputation_update: i64, // when update_reputation last applied a report
+    pub last_sla_breach_at: i64, // when update_reputation last reported uptime below sla_uptime_bps
+    pub last_sla_breach_uptime: u16, // the uptime (bps) that report carried
+    pub premium_seekers_only: bool, // only seekers with an active premium subscription may connect
+    pub suspended: bool, // idle past inactivity_timeout; no new connections until active again
+    pub recent_failures: [i64; RECENT_FAILURE_CAPACITY], // ring of the latest failure timestamps; 0 = empty slot
+    pub recent_failures_head: u8, // next slot to overwrite in recent_failures
+    pub recent_connections: Vec<WardenSessionRecord>, // ring buffer of recently ended sessions
+    pub recent_connections_head: u8, // next slot to overwrite once recent_connections is full
+    pub availability: Option<[u32; 7]>, // UTC hours served: bit h of day d (0 = Monday); None = always
+    pub subsidy_vested_carry: u64, // subsidized ARKHAM fully vested before subsidy_vesting_start
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct WardenSessionRecord {
+    pub seeker: Pubkey,
+    pub started_at: i64,
+    pub bandwidth_served: u64, // in megabytes
+    pub amount_earned: u64, // in lamports, the session's payments before the protocol fee
+    pub outcome: SessionOutcome,
+}
+
+impl WardenSessionRecord {
+    pub const SPACE: usize = 32 + 8 + 8 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum SessionOutcome {
+    Served, // delivered bandwidth; counted in successful_connections
+    Unserved, // ended without any bandwidth served
+}
+
+/// Zeroed Warden for unit tests
+#[cfg(test)]
+pub(crate) fn test_warden() -> Warden {
+    Warden {
+        authority: Pubkey::new_unique(),
+        peer_id: String::new(),
+        stake_token: StakeToken::Sol,
+        stake_amount: 0,
+        stake_value_usd: 0,
+        tier: Tier::Bronze,
+        staked_at: 0,
+        unstake_requested_at: None,
+        total_bandwidth_served: 0,
+        total_earnings: 0,
+        pending_claims: 0,
+        arkham_tokens_earned: 0,
+        reputation_score: 10000,
+        successful_connections: 0,
+        failed_connections: 0,
+        uptime_percentage: 10000,
+        last_active: 0,
+        region_code: 0,
+        ip_hash: [0; 32],
+        premium_pool_rank: None,
+        active_connections: 0,
+        rejected_proof_count: 0,
+        arkham_tokens_subsidized: 0,
+        arkham_subsidy_claimed: 0,
+        subsidy_vesting_start: 0,
+        draining: false,
+        counted_in_region: false,
+        total_slashed: 0,
+        slash_count: 0,
+        last_reputation_update: 0,
+        last_sla_breach_at: 0,
+        last_sla_breach_uptime: 0,
+        premium_seekers_only: false,
+        suspended: false,
+        recent_failures: [0; RECENT_FAILURE_CAPACITY],
+        recent_failures_head: 0,
+        recent_connections: vec![],
+        recent_connections_head: 0,
+        availability: None,
+        subsidy_vested_carry: 0,
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
@@ -12,6 +12,9 @@ import * as path from "path";
 const CONFIG_PARAMS = {
   baseRatePerMb: new BN(1500),
   protocolFeeBps: 200,
+  // Hard bounds on future fee updates, fixed at initialization (max is capped at 30%)
+  minFeeBps: 0,
+  maxFeeBps: 1000,
   tierThresholds: [new BN(100), new BN(500), new BN(1000)],
   tierMultipliers: [10000, 12000, 15000],
   tokensPer5gb: new BN(500000000),
@@ -22,6 +25,9 @@ const CONFIG_PARAMS = {
   ],
   // Oracle Authority - this is the public key of our oracle server
   oracleAuthority: new PublicKey("9WE7mxzUNFGJ4df3kuALhAWLmBmFkYvTt8LkMDFVuycC"),
+  // Approved stablecoin mints for warden stakes (mainnet; override for other clusters)
+  usdcMint: new PublicKey(process.env.USDC_MINT ?? "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"),
+  usdtMint: new PublicKey(process.env.USDT_MINT ?? "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"),
 };
 
 // ============================================================================
@@ -149,7 +155,13 @@ async function initializeProtocolConfig(
         CONFIG_PARAMS.tierMultipliers,
         CONFIG_PARAMS.tokensPer5gb,
         CONFIG_PARAMS.geoPremiums,
-        CONFIG_PARAMS.oracleAuthority
+        CONFIG_PARAMS.oracleAuthority,
+        {
+          usdcMint: CONFIG_PARAMS.usdcMint,
+          usdtMint: CONFIG_PARAMS.usdtMint,
+          minFeeBps: CONFIG_PARAMS.minFeeBps,
+          maxFeeBps: CONFIG_PARAMS.maxFeeBps,
+        }
       )
       .accounts({
         protocolConfig: protocolConfigPda,
@@ -194,6 +206,7 @@ async function updateProtocolConfig(
       .accounts({
         protocolConfig: protocolConfigPda,
         authority: authority,
+        regionIndex: null,
       })
       .rpc();
 
@@ -9,18 +9,84 @@ async function setupVaults() {
   
   const program = anchor.workspace.ArkhamProtocol as Program;
 
-  // Derive SOL vault PDA
-  const [solVault] = PublicKey.findProgramAddressSync(
-    [Buffer.from("sol_vault")],
+  // Derive stake vault PDA (stake principal)
+  const [stakeVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("stake_vault")],
     program.programId
   );
 
-  console.log("SOL Vault PDA:", solVault.toString());
-  console.log("✅ SOL vault will be created automatically on first use");
+  // Derive rewards vault PDA (warden earnings)
+  const [rewardsVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("rewards_vault")],
+    program.programId
+  );
+
+  console.log("Stake Vault PDA:", stakeVault.toString());
+  console.log("Rewards Vault PDA:", rewardsVault.toString());
+  console.log("✅ Stake vault will be created automatically on first use");
+
+  // Create the shared USDC/USDT stake vaults once, for the approved mints in the config
+  const [protocolConfig] = PublicKey.findProgramAddressSync(
+    [Buffer.from("protocol_config")],
+    program.programId
+  );
+  // Owned by the dedicated stake authority PDA, not the SOL stake vault
+  const [stakeAuthority] = PublicKey.findProgramAddressSync(
+    [Buffer.from("stake_authority")],
+    program.programId
+  );
+  const config = await program.account.protocolConfig.fetch(protocolConfig);
+  const usdcVault = await getAssociatedTokenAddress(config.usdcMint, stakeAuthority, true);
+  const usdtVault = await getAssociatedTokenAddress(config.usdtMint, stakeAuthority, true);
+
+  await program.methods
+    .initializeStakeVaults()
+    .accounts({
+      protocolConfig,
+      authority: provider.wallet.publicKey,
+      stakeAuthority,
+      usdcVault,
+      usdtVault,
+      usdcMint: config.usdcMint,
+      usdtMint: config.usdtMint,
+      systemProgram: SystemProgram.programId,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    })
+    .rpc();
+
+  console.log("Stake Authority PDA:", stakeAuthority.toString());
+  console.log("USDC Vault:", usdcVault.toString());
+  console.log("USDT Vault:", usdtVault.toString());
+
+  // Fund the rewards vault to rent-exempt before the first proof payment lands in it
+  await program.methods
+    .initializeRewardsVault()
+    .accounts({
+      protocolConfig,
+      rewardsVault,
+      authority: provider.wallet.publicKey,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+  console.log("✅ Rewards vault funded to rent-exempt");
+
+  // Fund the escrow yield vault to rent-exempt before seekers park idle escrow in it
+  const [escrowYieldVault] = PublicKey.findProgramAddressSync(
+    [Buffer.from("escrow_yield_vault")],
+    program.programId
+  );
+  await program.methods
+    .initializeEscrowYieldVault()
+    .accounts({
+      protocolConfig,
+      escrowYieldVault,
+      authority: provider.wallet.publicKey,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+  console.log("Escrow Yield Vault:", escrowYieldVault.toString());
 
-  // TODO: Create USDC/USDT token accounts
-  // This requires knowing the USDC/USDT mint addresses on your network
-  
   console.log("\n🎯 Vaults are ready!");
 }
 
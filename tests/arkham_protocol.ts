@@ -4,14 +4,30 @@ import {
   PublicKey, 
   Keypair, 
   SystemProgram, 
-  LAMPORTS_PER_SOL 
+  LAMPORTS_PER_SOL,
+  Ed25519Program,
+  TransactionInstruction,
 } from "@solana/web3.js";
 import { 
   TOKEN_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  getAccount,
+  createMint,
+  createAssociatedTokenAccount,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
 } from "@solana/spl-token";
+import { keccak_256 } from "@noble/hashes/sha3";
+import { ed25519 } from "@noble/curves/ed25519";
+import { assert } from "chai";
 import { ArkhamProtocol } from "../target/types/arkham_protocol";
 
+const LATEST_ORACLE_MESSAGE_VERSION = 2;
+const USD_DECIMALS = 6;
+const SOL_PRICE = 150_000_000; // $150, in micro-USD
+const PEER_ID = "12D3KooWTestPeerId1234567890";
+
 // Helper to create a new keypair with some SOL
 async function newAccountWithLamports(provider: anchor.AnchorProvider, lamports: number = 1 * LAMPORTS_PER_SOL): Promise<Keypair> {
   const keypair = Keypair.generate();
@@ -20,6 +36,42 @@ async function newAccountWithLamports(provider: anchor.AnchorProvider, lamports:
   return keypair;
 }
 
+// Oracle price message in the latest format (create_versioned_oracle_message)
+function oracleMessage(price: number, timestamp: number): Buffer {
+  const data = Buffer.concat([
+    Buffer.from([LATEST_ORACLE_MESSAGE_VERSION]),
+    new anchor.BN(price).toArrayLike(Buffer, "le", 8),
+    Buffer.from([USD_DECIMALS]),
+    new anchor.BN(timestamp).toArrayLike(Buffer, "le", 8),
+  ]);
+  return Buffer.from(keccak_256(data));
+}
+
+// Ed25519Program instruction in the layout the program parses: header, signature,
+// public key, message (as client::ed25519_instruction_data builds it)
+function ed25519Instruction(signer: Keypair, message: Buffer): { ix: TransactionInstruction; signature: number[] } {
+  const signature = ed25519.sign(message, signer.secretKey.slice(0, 32));
+  const header = Buffer.alloc(16);
+  header.writeUInt8(1, 0); // one signature
+  [16, 0xffff, 80, 0xffff, 112, message.length, 0xffff].forEach((value, i) => header.writeUInt16LE(value, 2 + 2 * i));
+  const data = Buffer.concat([header, Buffer.from(signature), signer.publicKey.toBuffer(), message]);
+  return {
+    ix: new TransactionInstruction({ programId: Ed25519Program.programId, keys: [], data }),
+    signature: Array.from(signature),
+  };
+}
+
+// Awaits `tx` and asserts it failed with the program error `code`
+async function expectProgramError(tx: Promise<unknown>, code: string, context: string = code) {
+  try {
+    await tx;
+  } catch (err) {
+    assert.equal(err?.error?.errorCode?.code, code, `${context}: ${err}`);
+    return;
+  }
+  assert.fail(`${context}: expected ${code}, but the transaction succeeded`);
+}
+
 describe("arkham_protocol", () => {
   // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -37,12 +89,20 @@ describe("arkham_protocol", () => {
   let wardenPDA: PublicKey;
   let seekerPDA: PublicKey;
   let protocolConfigPDA: PublicKey;
-  let solVaultPDA: PublicKey;
+  let stakeVaultPDA: PublicKey;
+  let stakeAuthorityPDA: PublicKey;
+  let rewardsVaultPDA: PublicKey;
   let usdcVaultPDA: PublicKey;
   let usdtVaultPDA: PublicKey;
   let arkhamMintPDA: PublicKey;
   let mintAuthorityPDA: PublicKey;
 
+  // Protocol fixture: set only when this run initialized the config, so the
+  // fixture oracle's signatures are the ones the program accepts
+  let oracle: Keypair;
+  let treasury: PublicKey;
+  let fixtureReady = false;
+
   before(async () => {
     // Create test accounts
     protocolAuthority = await newAccountWithLamports(provider);
@@ -52,7 +112,7 @@ describe("arkham_protocol", () => {
 
     // Derive PDAs
     [protocolConfigPDA] = await PublicKey.findProgramAddress(
-      [Buffer.from("protocol"), Buffer.from("config")],
+      [Buffer.from("protocol_config")],
       program.programId
     );
 
@@ -66,8 +126,18 @@ describe("arkham_protocol", () => {
       program.programId
     );
 
-    [solVaultPDA] = await PublicKey.findProgramAddress(
-      [Buffer.from("sol_vault")],
+    [stakeVaultPDA] = await PublicKey.findProgramAddress(
+      [Buffer.from("stake_vault")],
+      program.programId
+    );
+
+    [stakeAuthorityPDA] = await PublicKey.findProgramAddress(
+      [Buffer.from("stake_authority")],
+      program.programId
+    );
+
+    [rewardsVaultPDA] = await PublicKey.findProgramAddress(
+      [Buffer.from("rewards_vault")],
       program.programId
     );
 
@@ -90,8 +160,135 @@ describe("arkham_protocol", () => {
       [Buffer.from("arkham"), Buffer.from("mint"), Buffer.from("authority")],
       program.programId
     );
+
+    // Initialize the protocol on a fresh validator: $100/$500/$1000 tiers, fixture oracle
+    oracle = Keypair.generate();
+    treasury = (await newAccountWithLamports(provider)).publicKey;
+    if (!(await provider.connection.getAccountInfo(protocolConfigPDA))) {
+      const usdcMint = await createMint(provider.connection, protocolAuthority, protocolAuthority.publicKey, null, 6);
+      const usdtMint = await createMint(provider.connection, protocolAuthority, protocolAuthority.publicKey, null, 6);
+      await program.methods
+        .initializeProtocolConfig(
+          new anchor.BN(1_000), // base rate per MB
+          200, // protocol fee bps
+          [new anchor.BN(100_000_000), new anchor.BN(500_000_000), new anchor.BN(1_000_000_000)],
+          [10000, 12000, 15000],
+          new anchor.BN(500_000_000), // tokens per 5GB
+          [],
+          oracle.publicKey,
+          { usdcMint, usdtMint, minFeeBps: 0, maxFeeBps: 1_000 }
+        )
+        .accounts({
+          protocolConfig: protocolConfigPDA,
+          treasury,
+          authority: protocolAuthority.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([protocolAuthority])
+        .rpc();
+      fixtureReady = true;
+    }
   });
 
+  // Fails the calling test unless the fixture above initialized the protocol
+  function requireFixture() {
+    assert.isTrue(fixtureReady, "protocol config was already initialized; run against a fresh validator");
+  }
+
+  async function updateProtocolParams(params: Record<string, unknown>) {
+    await program.methods
+      .updateProtocolParams(params as any)
+      .accounts({ protocolConfig: protocolConfigPDA, authority: protocolAuthority.publicKey })
+      .signers([protocolAuthority])
+      .rpc();
+  }
+
+  // Creates the shared stablecoin vaults unless an earlier test already did
+  async function stakeVaults(): Promise<{ usdcVault: PublicKey; usdtVault: PublicKey; usdcMint: PublicKey; usdtMint: PublicKey }> {
+    const config = await program.account.protocolConfig.fetch(protocolConfigPDA);
+    const usdcVault = getAssociatedTokenAddressSync(config.usdcMint, stakeAuthorityPDA, true);
+    const usdtVault = getAssociatedTokenAddressSync(config.usdtMint, stakeAuthorityPDA, true);
+    if (!(await provider.connection.getAccountInfo(usdcVault))) {
+      await program.methods
+        .initializeStakeVaults()
+        .accounts({
+          protocolConfig: protocolConfigPDA,
+          authority: protocolAuthority.publicKey,
+          stakeAuthority: stakeAuthorityPDA,
+          usdcVault,
+          usdtVault,
+          usdcMint: config.usdcMint,
+          usdtMint: config.usdtMint,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        })
+        .signers([protocolAuthority])
+        .rpc();
+    }
+    return { usdcVault, usdtVault, usdcMint: config.usdcMint, usdtMint: config.usdtMint };
+  }
+
+  function wardenAddress(authority: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync([Buffer.from("warden"), authority.toBuffer()], program.programId)[0];
+  }
+
+  type OracleQuote = { price: number; timestamp: number; signer?: Keypair; signature?: number[] };
+
+  // Signs `quote` as the fixture oracle (or `quote.signer`): the Ed25519Program
+  // instruction to place first and the matching OraclePrice instruction argument
+  function signedOraclePrice(quote: OracleQuote) {
+    const { ix, signature } = ed25519Instruction(quote.signer ?? oracle, oracleMessage(quote.price, quote.timestamp));
+    const oraclePrice = {
+      version: LATEST_ORACLE_MESSAGE_VERSION,
+      price: new anchor.BN(quote.price),
+      decimals: USD_DECIMALS,
+      timestamp: new anchor.BN(quote.timestamp),
+      signature: quote.signature ?? signature,
+    };
+    return { ix, oraclePrice };
+  }
+
+  // Registers a SOL-staked warden priced by a fresh fixture-oracle signature
+  async function registerSolWarden(
+    authority: Keypair,
+    stakeLamports: number,
+    oracleQuote: OracleQuote = { price: SOL_PRICE, timestamp: Math.floor(Date.now() / 1000) }
+  ) {
+    return registerWarden(authority, { sol: {} }, stakeLamports, authority.publicKey, oracleQuote);
+  }
+
+  // Registers a warden staking `stakeAmount` of `stakeToken` from `stakeFromAccount`
+  async function registerWarden(
+    authority: Keypair,
+    stakeToken: Record<string, {}>,
+    stakeAmount: number,
+    stakeFromAccount: PublicKey,
+    oracleQuote: OracleQuote
+  ) {
+    const vaults = await stakeVaults();
+    const { ix, oraclePrice } = signedOraclePrice(oracleQuote);
+    return program.methods
+      .initializeWarden(stakeToken as any, new anchor.BN(stakeAmount), PEER_ID, 0, Array(32).fill(0), oraclePrice)
+      .accounts({
+        warden: wardenAddress(authority.publicKey),
+        authority: authority.publicKey,
+        protocolConfig: protocolConfigPDA,
+        treasury,
+        instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+        stakeFromAccount,
+        stakeVault: stakeVaultPDA,
+        stakeAuthority: stakeAuthorityPDA,
+        ...vaults,
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        regionIndex: null,
+      })
+      .preInstructions([ix])
+      .signers([authority])
+      .rpc();
+  }
+
   describe("Basic Functionality Tests", () => {
     it("Should verify program is accessible", async () => {
       // Just test that the program is accessible
@@ -133,27 +330,32 @@ describe("arkham_protocol", () => {
       const ipHash = new Array(32).fill(0); // Mock IP hash
 
       try {
+        const vaults = await stakeVaults();
+        const { ix, oraclePrice } = signedOraclePrice({ price: SOL_PRICE, timestamp: Math.floor(Date.now() / 1000) });
         const tx = await program.methods
           .initializeWarden(
             { sol: {} }, // StakeToken::Sol
             stakeAmount,
             peerId,
             regionCode,
-            Uint8Array.from(ipHash)
+            ipHash,
+            oraclePrice
           )
           .accounts({
             warden: wardenPDA,
             authority: wardenAuthority.publicKey,
             protocolConfig: protocolConfigPDA,
-            stakeFromAccount: wardenAuthority.publicKey, // In a real test, this would be a token account
-            solVault: solVaultPDA,
-            usdcVault: usdcVaultPDA,
-            usdtVault: usdtVaultPDA,
-            solUsdPriceFeed: PublicKey.unique(), // Mock Pyth feed
-            usdtUsdPriceFeed: PublicKey.unique(), // Mock Pyth feed
+            treasury,
+            instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            stakeFromAccount: wardenAuthority.publicKey,
+            stakeVault: stakeVaultPDA,
+            stakeAuthority: stakeAuthorityPDA,
+            ...vaults,
             systemProgram: SystemProgram.programId,
             tokenProgram: TOKEN_PROGRAM_ID,
+            regionIndex: null,
           })
+          .preInstructions([ix])
           .signers([wardenAuthority])
           .rpc();
 
@@ -163,10 +365,112 @@ describe("arkham_protocol", () => {
       }
     });
 
+    it("Should register two wardens back-to-back against the shared stablecoin vaults", async () => {
+      requireFixture();
+      const config = await program.account.protocolConfig.fetch(protocolConfigPDA);
+      // Created once up front; registrations only reference them
+      const { usdcVault, usdtVault } = await stakeVaults();
+
+      const wardens = [await newAccountWithLamports(provider, 2 * LAMPORTS_PER_SOL), await newAccountWithLamports(provider, 2 * LAMPORTS_PER_SOL)];
+      const registrations = wardens.map((authority) => {
+        const [warden] = PublicKey.findProgramAddressSync(
+          [Buffer.from("warden"), authority.publicKey.toBuffer()],
+          program.programId
+        );
+        const { ix, oraclePrice } = signedOraclePrice({ price: SOL_PRICE, timestamp: Math.floor(Date.now() / 1000) });
+        return program.methods
+          .initializeWarden({ sol: {} }, new anchor.BN(LAMPORTS_PER_SOL), PEER_ID, 0, Array(32).fill(0), oraclePrice)
+          .accounts({
+            warden,
+            authority: authority.publicKey,
+            protocolConfig: protocolConfigPDA,
+            treasury: config.treasury,
+            instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            stakeFromAccount: authority.publicKey,
+            stakeVault: stakeVaultPDA,
+            stakeAuthority: stakeAuthorityPDA,
+            usdcVault,
+            usdtVault,
+            usdcMint: config.usdcMint,
+            usdtMint: config.usdtMint,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            regionIndex: null,
+          })
+          .preInstructions([ix])
+          .signers([authority])
+          .rpc();
+      });
+
+      // Both land without contending over vault creation
+      await Promise.all(registrations);
+
+      // The stablecoin vaults belong to the dedicated stake authority, not the SOL stake vault
+      for (const vault of [usdcVault, usdtVault]) {
+        const account = await getAccount(provider.connection, vault);
+        assert.isTrue(account.owner.equals(stakeAuthorityPDA));
+        assert.isFalse(account.owner.equals(stakeVaultPDA));
+      }
+    });
+
+    it("Should leave no lamports moved and no warden account behind when registration fails", async () => {
+      requireFixture();
+      const now = Math.floor(Date.now() / 1000);
+
+      // Each case must fail with its own error; the provider wallet pays the fees so
+      // the authority's balance must come out exactly as it went in
+      const cases = [
+        { name: "insufficient SOL for stake", funding: LAMPORTS_PER_SOL / 2, stake: 100 * LAMPORTS_PER_SOL, quote: { price: SOL_PRICE, timestamp: now }, code: "InsufficientFundsForRegistration" },
+        { name: "invalid signature", funding: 2 * LAMPORTS_PER_SOL, stake: LAMPORTS_PER_SOL, quote: { price: SOL_PRICE, timestamp: now, signer: Keypair.generate() }, code: "PublicKeyMismatch" },
+        { name: "stale price", funding: 2 * LAMPORTS_PER_SOL, stake: LAMPORTS_PER_SOL, quote: { price: SOL_PRICE, timestamp: now - 3600 }, code: "StalePrice" },
+        { name: "below-tier stake", funding: 2 * LAMPORTS_PER_SOL, stake: 1_000_000, quote: { price: SOL_PRICE, timestamp: now }, code: "InsufficientStake" },
+      ];
+
+      for (const c of cases) {
+        const authority = await newAccountWithLamports(provider, c.funding);
+        const authorityBefore = await provider.connection.getBalance(authority.publicKey);
+        const treasuryBefore = await provider.connection.getBalance(treasury);
+        const vaultBefore = await provider.connection.getBalance(stakeVaultPDA);
+
+        await expectProgramError(registerSolWarden(authority, c.stake, c.quote), c.code, c.name);
+
+        assert.equal(await provider.connection.getBalance(authority.publicKey), authorityBefore, `${c.name}: authority balance`);
+        assert.equal(await provider.connection.getBalance(treasury), treasuryBefore, `${c.name}: treasury balance`);
+        assert.equal(await provider.connection.getBalance(stakeVaultPDA), vaultBefore, `${c.name}: stake vault balance`);
+        assert.isNull(await provider.connection.getAccountInfo(wardenAddress(authority.publicKey)), `${c.name}: no warden account`);
+      }
+    });
+
+    it("Should charge the registration fee into the treasury and refuse underfunded registrations", async () => {
+      requireFixture();
+      const fee = LAMPORTS_PER_SOL / 10;
+
+      // No fee configured: the treasury is untouched
+      let treasuryBefore = await provider.connection.getBalance(treasury);
+      await registerSolWarden(await newAccountWithLamports(provider, 2 * LAMPORTS_PER_SOL), LAMPORTS_PER_SOL);
+      assert.equal(await provider.connection.getBalance(treasury), treasuryBefore);
+
+      await updateProtocolParams({ registrationFee: new anchor.BN(fee) });
+      try {
+        treasuryBefore = await provider.connection.getBalance(treasury);
+        await registerSolWarden(await newAccountWithLamports(provider, 2 * LAMPORTS_PER_SOL), LAMPORTS_PER_SOL);
+        assert.equal(await provider.connection.getBalance(treasury), treasuryBefore + fee);
+
+        // Enough for the stake alone, but not the stake plus the fee
+        const underfunded = await newAccountWithLamports(provider, LAMPORTS_PER_SOL + fee / 2);
+        treasuryBefore = await provider.connection.getBalance(treasury);
+        await expectProgramError(registerSolWarden(underfunded, LAMPORTS_PER_SOL), "InsufficientFundsForRegistration");
+        assert.equal(await provider.connection.getBalance(treasury), treasuryBefore);
+        assert.isNull(await provider.connection.getAccountInfo(wardenAddress(underfunded.publicKey)));
+      } finally {
+        await updateProtocolParams({ registrationFee: new anchor.BN(0) });
+      }
+    });
+
     it("Should handle reputation updates", async () => {
       try {
         const tx = await program.methods
-          .updateReputation(true, 9950) // success = true, uptime = 99.5%
+          .updateReputation(true, 9950, new anchor.BN(Math.floor(Date.now() / 1000))) // success = true, uptime = 99.5%, measured now
           .accounts({
             warden: wardenPDA,
             wardenAuthority: wardenAuthority.publicKey,
@@ -210,6 +514,8 @@ describe("arkham_protocol", () => {
             seeker: seekerPDA,
             protocolConfig: protocolConfigPDA,
             instructionsSysvar: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+            rewardsVault: rewardsVaultPDA,
+            treasury,
             submitter: wardenAuthority.publicKey,
           })
           .signers([wardenAuthority])
@@ -222,6 +528,109 @@ describe("arkham_protocol", () => {
     });
   });
 
+  describe("Stake Vault Tests", () => {
+    const STABLECOIN_PRICE = 1_000_000; // $1, in micro-USD
+
+    async function tokenBalance(account: PublicKey): Promise<number> {
+      return Number((await getAccount(provider.connection, account)).amount);
+    }
+
+    it("Should stake and return USDC and USDT through the dedicated stake authority", async () => {
+      requireFixture();
+      const { usdcVault, usdtVault, usdcMint, usdtMint } = await stakeVaults();
+      const stake = 150_000_000; // $150 at $1, above the $100 tier
+
+      for (const [name, stakeToken, mint, vault] of [
+        ["USDC", { usdc: {} }, usdcMint, usdcVault],
+        ["USDT", { usdt: {} }, usdtMint, usdtVault],
+      ] as const) {
+        const authority = await newAccountWithLamports(provider, 2 * LAMPORTS_PER_SOL);
+        const source = await createAssociatedTokenAccount(provider.connection, authority, mint, authority.publicKey);
+        await mintTo(provider.connection, protocolAuthority, mint, source, protocolAuthority, stake);
+        const vaultBefore = await tokenBalance(vault);
+        const solVaultBefore = await provider.connection.getBalance(stakeVaultPDA);
+
+        await registerWarden(authority, stakeToken, stake, source, {
+          price: STABLECOIN_PRICE,
+          timestamp: Math.floor(Date.now() / 1000),
+        });
+        assert.equal(await tokenBalance(vault), vaultBefore + stake, `${name}: vault after staking`);
+        assert.equal(await tokenBalance(source), 0, `${name}: source after staking`);
+
+        // The stake authority signs the release back to the warden
+        await program.methods
+          .abortRegistration()
+          .accounts({
+            warden: wardenAddress(authority.publicKey),
+            authority: authority.publicKey,
+            stakeVault: stakeVaultPDA,
+            stakeAuthority: stakeAuthorityPDA,
+            usdcVault,
+            usdtVault,
+            stakeToAccount: source,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+            regionIndex: null,
+          })
+          .signers([authority])
+          .rpc();
+        assert.equal(await tokenBalance(vault), vaultBefore, `${name}: vault after release`);
+        assert.equal(await tokenBalance(source), stake, `${name}: source after release`);
+        assert.isNull(await provider.connection.getAccountInfo(wardenAddress(authority.publicKey)), `${name}: warden closed`);
+
+        // SOL stake principal never moves for a stablecoin stake
+        assert.equal(await provider.connection.getBalance(stakeVaultPDA), solVaultBefore, `${name}: SOL stake vault`);
+      }
+
+      // SOL staking still lands in the stake vault alongside the stablecoin vaults
+      const solWarden = await newAccountWithLamports(provider, 2 * LAMPORTS_PER_SOL);
+      const solVaultBefore = await provider.connection.getBalance(stakeVaultPDA);
+      await registerSolWarden(solWarden, LAMPORTS_PER_SOL);
+      assert.equal(await provider.connection.getBalance(stakeVaultPDA), solVaultBefore + LAMPORTS_PER_SOL);
+    });
+
+    it("Should move the legacy stablecoin vaults' balances to the stake authority", async () => {
+      requireFixture();
+      const { usdcVault, usdtVault, usdcMint, usdtMint } = await stakeVaults();
+
+      // Stablecoin vaults as they existed before the stake authority: owned by the stake vault
+      const legacy: PublicKey[] = [];
+      for (const [mint, amount] of [[usdcMint, 5_000_000], [usdtMint, 7_000_000]] as const) {
+        const account = await getOrCreateAssociatedTokenAccount(provider.connection, protocolAuthority, mint, stakeVaultPDA, true);
+        await mintTo(provider.connection, protocolAuthority, mint, account.address, protocolAuthority, amount);
+        legacy.push(account.address);
+      }
+      const [legacyUsdcVault, legacyUsdtVault] = legacy;
+      const legacyUsdc = await tokenBalance(legacyUsdcVault);
+      const legacyUsdt = await tokenBalance(legacyUsdtVault);
+      const usdcBefore = await tokenBalance(usdcVault);
+      const usdtBefore = await tokenBalance(usdtVault);
+
+      await program.methods
+        .migrateStakeVaults()
+        .accounts({
+          protocolConfig: protocolConfigPDA,
+          authority: protocolAuthority.publicKey,
+          stakeVault: stakeVaultPDA,
+          stakeAuthority: stakeAuthorityPDA,
+          legacyUsdcVault,
+          legacyUsdtVault,
+          usdcVault,
+          usdtVault,
+          usdcMint,
+          usdtMint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([protocolAuthority])
+        .rpc();
+
+      assert.equal(await tokenBalance(legacyUsdcVault), 0);
+      assert.equal(await tokenBalance(legacyUsdtVault), 0);
+      assert.equal(await tokenBalance(usdcVault), usdcBefore + legacyUsdc);
+      assert.equal(await tokenBalance(usdtVault), usdtBefore + legacyUsdt);
+    });
+  });
+
   describe("User Journey Tests", () => {
     it("Warden basic flow test", async () => {
       // This would be the complete flow once all PDAs are properly set up